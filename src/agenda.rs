@@ -0,0 +1,198 @@
+//! `later agenda`: every dated item from every list as one schedule,
+//! grouped into overdue, today, tomorrow, the rest of the week and beyond,
+//! with hard deadlines called out above them all.
+
+use crate::i18n::Lang;
+use crate::table::fit;
+use crate::{
+    all_items, display_width, formats, DateMaybeTime, ListItem, RenderOptions,
+    TodoList, DEADLINE_LEAD_DAYS,
+};
+use ansi_term::{Color, Style};
+use chrono::prelude::*;
+use chrono::Duration;
+use std::collections::HashMap;
+use std::io::prelude::*;
+
+const GAP: &str = "  ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Group {
+    /// Hard deadlines, whichever day they fall on.
+    Deadline,
+    Overdue,
+    Today,
+    Tomorrow,
+    ThisWeek,
+    Later,
+}
+
+impl Group {
+    fn of(date: NaiveDate, today: NaiveDate) -> Group {
+        match (date - today).num_days() {
+            n if n < 0 => Group::Overdue,
+            0 => Group::Today,
+            1 => Group::Tomorrow,
+            n if n < 7 => Group::ThisWeek,
+            _ => Group::Later,
+        }
+    }
+
+    fn heading(self, lang: Lang) -> String {
+        match self {
+            Group::Deadline => lang.deadlines().to_string(),
+            Group::Overdue => {
+                let overdue = lang.overdue();
+                let mut chars = overdue.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            }
+            Group::Today => lang.today().to_string(),
+            Group::Tomorrow => lang.tomorrow().to_string(),
+            Group::ThisWeek => lang.this_week().to_string(),
+            Group::Later => lang.further_ahead().to_string(),
+        }
+    }
+}
+
+/// A dated item, with where to find it.
+pub struct Entry<'a> {
+    pub list: &'a str,
+    pub index: Vec<usize>,
+    pub item: &'a ListItem,
+    pub date: DateMaybeTime,
+}
+
+/// Every unfinished item due before `days` days from `today` is up,
+/// overdue ones included, soonest first. Hard deadlines are included from
+/// when they show as urgent, however few `days` are asked for.
+pub fn collect(
+    lists: &HashMap<String, TodoList>,
+    today: NaiveDate,
+    days: i64,
+) -> Vec<Entry<'_>> {
+    let end = today + Duration::days(days);
+    let deadline_end = today + Duration::days(days.max(DEADLINE_LEAD_DAYS));
+    let mut entries: Vec<Entry> = all_items(lists)
+        .into_iter()
+        .filter(|(_, _, item)| !item.is_done())
+        .filter_map(|(list, index, item)| {
+            let date = item.date()?;
+            let end = if item.is_deadline() {
+                deadline_end
+            } else {
+                end
+            };
+            (day_of(&date) < end).then_some(Entry {
+                list,
+                index,
+                item,
+                date,
+            })
+        })
+        .collect();
+    // all_items is already in list order, and the sort is stable
+    entries.sort_by_key(|entry| entry.date.start());
+    entries
+}
+
+fn day_of(date: &DateMaybeTime) -> NaiveDate {
+    date.start().date()
+}
+
+// what goes in the first column: enough to place the item within its group
+fn when(
+    date: &DateMaybeTime,
+    group: Group,
+    today: NaiveDate,
+    lang: Lang,
+) -> String {
+    let time = match date {
+        DateMaybeTime::Date(_) => None,
+        DateMaybeTime::DateTime(datetime) => {
+            Some(formats::time(datetime.time()))
+        }
+    };
+    let day = day_of(date);
+    let day = match group {
+        // away from the days, a deadline's day needs naming in full
+        Group::Deadline => Some(match Group::of(day, today) {
+            Group::Today => lang.today().to_string(),
+            Group::Tomorrow => lang.tomorrow().to_string(),
+            Group::ThisWeek => lang.weekday(day.weekday()).to_string(),
+            _ => lang.month_day(day, day.year() != today.year()),
+        }),
+        Group::Today | Group::Tomorrow => None,
+        Group::ThisWeek => Some(lang.weekday(day.weekday()).to_string()),
+        Group::Overdue | Group::Later => {
+            Some(lang.month_day(day, day.year() != today.year()))
+        }
+    };
+    match (day, time) {
+        (Some(day), Some(time)) => format!("{} {}", day, time),
+        (Some(day), None) => day,
+        (None, Some(time)) => time,
+        (None, None) => String::new(),
+    }
+}
+
+/// Writes the hard deadlines under a heading of their own, then the other
+/// entries under a heading for each group, each line with when it's due,
+/// its title and the list and index it's at.
+pub fn write_agenda(
+    out: &mut impl Write,
+    entries: &[Entry],
+    today: NaiveDate,
+    opts: &RenderOptions,
+) -> std::io::Result<()> {
+    let lang = Lang::current();
+    if entries.is_empty() {
+        return writeln!(out, "{}", lang.nothing_due());
+    }
+    let (deadlines, rest): (Vec<&Entry>, Vec<&Entry>) =
+        entries.iter().partition(|entry| entry.item.is_deadline());
+    let lines: Vec<(Group, String, &Entry)> = deadlines
+        .into_iter()
+        .chain(rest)
+        .map(|entry| {
+            let group = if entry.item.is_deadline() {
+                Group::Deadline
+            } else {
+                Group::of(day_of(&entry.date), today)
+            };
+            (group, when(&entry.date, group, today, lang), entry)
+        })
+        .collect();
+    let when_width = lines
+        .iter()
+        .map(|(_, when, _)| display_width(when))
+        .max()
+        .unwrap_or(0);
+    let heading = opts.style(Style::new().bold());
+    let location = opts.style(Color::Cyan.normal());
+    let mut current = None;
+    for (group, when, entry) in &lines {
+        if current != Some(*group) {
+            if current.is_some() {
+                writeln!(out)?;
+            }
+            writeln!(out, "{}", heading.paint(group.heading(lang)))?;
+            current = Some(*group);
+        }
+        let index: Vec<String> =
+            entry.index.iter().map(|i| i.to_string()).collect();
+        writeln!(
+            out,
+            "{}{}{}{}{}",
+            GAP,
+            opts.date_style(&entry.date, entry.item.is_deadline(), false)
+                .paint(fit(when, when_width)),
+            GAP,
+            entry.item.title(),
+            location.paint(format!("  {}: {}", entry.list, index.join(",")))
+        )?;
+    }
+    Ok(())
+}
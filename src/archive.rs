@@ -0,0 +1,196 @@
+//! The archive: items taken out of the lists with `later remove --archive`,
+//! or once they've been done for a while, kept one per line in
+//! `archive.jsonl` next to the lists, so they can be looked up, put back or
+//! purged for good later.
+
+use crate::{clock, crypt, ItemPath, ListItem, TodoList};
+use anyhow::{Context, Result};
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// Why an item was archived.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Reason {
+    Removed,
+    Completed,
+}
+
+impl Reason {
+    pub fn name(self) -> &'static str {
+        match self {
+            Reason::Removed => "removed",
+            Reason::Completed => "completed",
+        }
+    }
+}
+
+/// One archived item, with where it was.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Archived {
+    /// Numbers count up from 1, and an item keeps its number when others
+    /// are restored or purged.
+    pub id: u64,
+    pub list: String,
+    pub index: Vec<usize>,
+    pub reason: Reason,
+    pub at: DateTime<Local>,
+    pub item: ListItem,
+}
+
+impl Archived {
+    /// An item taken out just now; it gets its number once it's written.
+    pub fn new(
+        list: &str,
+        index: &[usize],
+        reason: Reason,
+        item: ListItem,
+    ) -> Archived {
+        Archived {
+            id: 0,
+            list: String::from(list),
+            index: index.to_vec(),
+            reason,
+            at: clock::now(),
+            item,
+        }
+    }
+}
+
+/// `archive.jsonl` in the folder holding the lists.
+pub fn path(todo_folder: &Path) -> PathBuf {
+    todo_folder.join("archive.jsonl")
+}
+
+/// Every archived item, oldest first; no file means none.
+pub fn read(path: &Path) -> Result<Vec<Archived>> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(Vec::new())
+        }
+        Err(e) => {
+            return Err(e).with_context(|| {
+                format!("Couldn't read archive ({})", path.display())
+            })
+        }
+    };
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            serde_json::from_str(&crypt::open(line)?).with_context(|| {
+                format!(
+                    "Couldn't parse line {} of the archive ({})",
+                    n + 1,
+                    path.display()
+                )
+            })
+        })
+        .collect()
+}
+
+/// Numbers the items and adds them to the end of the archive, returning
+/// the numbers given.
+pub fn append(path: &Path, mut items: Vec<Archived>) -> Result<Vec<u64>> {
+    if items.is_empty() {
+        return Ok(Vec::new());
+    }
+    let last = read(path)?.iter().map(|a| a.id).max().unwrap_or(0);
+    let mut lines = String::new();
+    for (n, archived) in items.iter_mut().enumerate() {
+        archived.id = last + n as u64 + 1;
+        lines.push_str(&crypt::seal(&serde_json::to_string(archived)?)?);
+        lines.push('\n');
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(lines.as_bytes()))
+        .with_context(|| {
+            format!("Couldn't write archive ({})", path.display())
+        })?;
+    Ok(items.iter().map(|a| a.id).collect())
+}
+
+/// Replaces the archive with `items`, for taking some out of it.
+pub fn rewrite(path: &Path, items: &[Archived]) -> Result<()> {
+    let mut text = String::new();
+    for archived in items {
+        text.push_str(&crypt::seal(&serde_json::to_string(archived)?)?);
+        text.push('\n');
+    }
+    // written aside first, so a failure can't cut the archive short
+    let temp = path.with_extension("jsonl.tmp");
+    std::fs::write(&temp, text)
+        .and_then(|_| std::fs::rename(&temp, path))
+        .with_context(|| format!("Couldn't write archive ({})", path.display()))
+}
+
+/// Takes every item done before `cutoff` out of the lists, along with
+/// where it was. Done sublists go whole.
+pub fn take_completed(
+    lists: &mut HashMap<String, TodoList>,
+    cutoff: DateTime<Local>,
+) -> Vec<Archived> {
+    let mut taken = Vec::new();
+    let mut names: Vec<String> = lists.keys().cloned().collect();
+    names.sort();
+    for name in names {
+        let list = lists.get_mut(&name).unwrap();
+        let mut paths: Vec<Vec<usize>> = Vec::new();
+        for (path, item) in list.items_with_paths() {
+            let old = item.completed_at().is_some_and(|done| done < cutoff);
+            // anything inside a sublist already going goes with it
+            let inside = paths.iter().any(|taken| path.starts_with(taken));
+            if old && !inside {
+                paths.push(path);
+            }
+        }
+        // last first, so taking one out doesn't move the others
+        for path in paths.into_iter().rev() {
+            if let Ok(item) = list.remove(&ItemPath::from(path.clone())) {
+                taken.push(Archived::new(
+                    &name,
+                    &path,
+                    Reason::Completed,
+                    item,
+                ));
+            }
+        }
+    }
+    taken
+}
+
+/// Writes one line per item: its number, when and why it was archived,
+/// where it was and its title.
+pub fn write_archived(
+    out: &mut impl Write,
+    items: &[&Archived],
+) -> std::io::Result<()> {
+    let width = items
+        .iter()
+        .map(|a| a.id.to_string().len())
+        .max()
+        .unwrap_or(0);
+    for archived in items {
+        let index: Vec<String> =
+            archived.index.iter().map(|i| i.to_string()).collect();
+        writeln!(
+            out,
+            "#{:<width$} {} {:<9} {} ({} {})",
+            archived.id,
+            archived.at.format("%Y-%m-%d"),
+            archived.reason.name(),
+            archived.item.title(),
+            archived.list,
+            index.join(","),
+            width = width,
+        )?;
+    }
+    Ok(())
+}
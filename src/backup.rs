@@ -0,0 +1,89 @@
+//! Rotating copies of `later.json`: each save first moves the previous
+//! version to `later.json.1`, that one to `later.json.2`, and so on, so a
+//! bad change or a damaged file can be undone with `later restore`.
+
+use crate::error::{Error, Result};
+use crate::{crypt, migrate, TodoList};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// `later.json.n`, next to the file.
+pub fn path(todo_file: &Path, n: usize) -> PathBuf {
+    let mut name = todo_file.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", n));
+    todo_file.with_file_name(name)
+}
+
+/// Shifts every backup up a number, dropping any past `keep`, and copies
+/// the file as it is now to `.1`. Does nothing if the file is missing or
+/// empty, as it is before the first save, or if no backups are kept.
+pub fn rotate(todo_file: &Path, keep: usize) -> Result<()> {
+    match std::fs::metadata(todo_file) {
+        Ok(metadata) if metadata.len() > 0 && keep > 0 => {}
+        _ => return Ok(()),
+    }
+    for n in (1..keep).rev() {
+        let from = path(todo_file, n);
+        if from.exists() {
+            std::fs::rename(&from, path(todo_file, n + 1)).map_err(
+                |source| Error::Write {
+                    what: "backup",
+                    path: from.clone(),
+                    source,
+                },
+            )?;
+        }
+    }
+    let to = path(todo_file, 1);
+    std::fs::copy(todo_file, &to).map_err(|source| Error::Write {
+        what: "backup",
+        path: to.clone(),
+        source,
+    })?;
+    Ok(())
+}
+
+/// The lists in backup `n`, or in the most recent of the `keep` backups
+/// that can be read if `n` is `None`, along with its number.
+pub fn read(
+    todo_file: &Path,
+    n: Option<usize>,
+    keep: usize,
+) -> Result<(usize, HashMap<String, TodoList>)> {
+    let parse = |n: usize| -> Result<HashMap<String, TodoList>> {
+        let backup = path(todo_file, n);
+        let json =
+            std::fs::read_to_string(&backup).map_err(|source| Error::Read {
+                what: "backup",
+                path: backup.clone(),
+                source,
+            })?;
+        migrate::parse(&crypt::open(&json)?).map_err(|e| match e {
+            migrate::ParseError::Json(source) => Error::Parse {
+                what: "backup",
+                path: backup,
+                source,
+            },
+            migrate::ParseError::TooNew { version } => Error::TooNew {
+                path: backup,
+                version,
+            },
+        })
+    };
+    if let Some(n) = n {
+        return Ok((n, parse(n)?));
+    }
+    match (1..=keep).find_map(|n| Some((n, parse(n).ok()?))) {
+        Some(found) => Ok(found),
+        None => Err(Error::NoBackups {
+            path: todo_file.to_path_buf(),
+        }),
+    }
+}
+
+/// The numbers of the `keep` backups that exist, newest first.
+pub fn list(todo_file: &Path, keep: usize) -> Vec<usize> {
+    (1..=keep)
+        .filter(|&n| path(todo_file, n).exists())
+        .collect()
+}
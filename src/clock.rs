@@ -0,0 +1,24 @@
+//! The moment dates are shown relative to: normally the real time, but it
+//! can be pinned, e.g. to see a list as it will look on some other day.
+
+use chrono::prelude::*;
+use std::sync::RwLock;
+
+static PINNED: RwLock<Option<DateTime<Local>>> = RwLock::new(None);
+
+pub fn now() -> DateTime<Local> {
+    match *PINNED.read().unwrap() {
+        Some(pinned) => pinned,
+        None => Local::now(),
+    }
+}
+
+pub fn today() -> NaiveDate {
+    now().date().naive_local()
+}
+
+/// Makes `now` return `time` from here on, or the real time again after
+/// `pin(None)`.
+pub fn pin(time: Option<DateTime<Local>>) {
+    *PINNED.write().unwrap() = time;
+}
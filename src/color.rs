@@ -0,0 +1,27 @@
+//! Whether output is styled at all. It isn't with `--plain`, or when the
+//! `NO_COLOR` environment variable is set (see no-color.org), so output can
+//! be piped into grep, fzf or scripts without escape codes in the way.
+
+use ansi_term::Style;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turns styling off from here on, as `--plain` does.
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    !DISABLED.load(Ordering::Relaxed)
+        && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+}
+
+/// `style` if styling is on, otherwise no style, which paints text as is.
+pub fn style(style: Style) -> Style {
+    if enabled() {
+        style
+    } else {
+        Style::new()
+    }
+}
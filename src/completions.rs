@@ -0,0 +1,95 @@
+//! Shell completion scripts: clap's static completions for every command
+//! and flag, plus, for bash, zsh and fish, list names and tags read from
+//! the data file as you type (through `later complete lists|tags`).
+
+use crate::{all_items, TodoList};
+use clap_complete::Shell;
+use std::collections::{BTreeSet, HashMap};
+use std::io::prelude::*;
+
+// both wrap clap's `_later`, offering tags after the tag options and list
+// names as the first word
+const BASH: &str = r#"
+_later_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}"
+    case "$prev" in
+        --tag|-t|--untag)
+            COMPREPLY=($(compgen -W "$(later complete tags 2>/dev/null)" -- "$cur"))
+            return 0
+            ;;
+    esac
+    _later "$@"
+    if [[ $COMP_CWORD -eq 1 ]]; then
+        COMPREPLY+=($(compgen -W "$(later complete lists 2>/dev/null)" -- "$cur"))
+    fi
+}
+complete -F _later_dynamic -o bashdefault -o default later
+"#;
+
+const ZSH: &str = r#"
+_later_dynamic() {
+    case "${words[CURRENT-1]}" in
+        --tag|-t|--untag)
+            compadd -- ${(f)"$(later complete tags 2>/dev/null)"}
+            return
+            ;;
+    esac
+    if (( CURRENT == 2 )); then
+        compadd -- ${(f)"$(later complete lists 2>/dev/null)"}
+    fi
+    _later "$@"
+}
+# called when autoloaded from $fpath as `_later`, registered when sourced
+if [ "$funcstack[1]" = "_later" ]; then
+    _later_dynamic "$@"
+else
+    compdef _later_dynamic later
+fi
+"#;
+
+const FISH: &str = r#"
+complete -c later -n "__fish_is_first_arg" -f -a "(later complete lists 2>/dev/null)"
+complete -c later -l tag -s t -f -a "(later complete tags 2>/dev/null)"
+complete -c later -l untag -f -a "(later complete tags 2>/dev/null)"
+"#;
+
+/// Writes the completion script for `shell`.
+pub fn write_script(
+    shell: Shell,
+    cmd: &mut clap::Command,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, cmd, "later", &mut script);
+    let mut script = String::from_utf8_lossy(&script).into_owned();
+    let dynamic = match shell {
+        Shell::Bash => BASH,
+        Shell::Zsh => {
+            // clap's script ends by calling `_later`, which ours does instead
+            if let Some(end) = script.rfind("_later \"$@\"") {
+                script.truncate(end);
+            }
+            ZSH
+        }
+        Shell::Fish => FISH,
+        _ => "",
+    };
+    out.write_all(script.as_bytes())?;
+    out.write_all(dynamic.as_bytes())
+}
+
+/// Every list name, in order.
+pub fn list_names(lists: &HashMap<String, TodoList>) -> Vec<&str> {
+    let mut names: Vec<&str> = lists.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    names
+}
+
+/// Every tag used anywhere, once each, in order.
+pub fn tags(lists: &HashMap<String, TodoList>) -> Vec<&str> {
+    let tags: BTreeSet<&str> = all_items(lists)
+        .into_iter()
+        .flat_map(|(_, _, item)| item.tags().iter().map(String::as_str))
+        .collect();
+    tags.into_iter().collect()
+}
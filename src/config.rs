@@ -0,0 +1,441 @@
+use crate::timings::Phase;
+use crate::{natural, DateMaybeTime};
+use ansi_term::{Color, Style};
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// User preferences, read from `config.toml` in the later config directory.
+/// Every setting is optional, so a missing file means all defaults.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub general: GeneralConfig,
+    pub display: DisplayConfig,
+    pub theme: ThemeConfig,
+    pub capture: CaptureConfig,
+    pub notify: NotifyConfig,
+    pub sync: SyncConfig,
+    pub sort: SortConfig,
+    pub escalate: EscalateConfig,
+    pub archive: ArchiveConfig,
+    pub budgets: BudgetConfig,
+    /// Caps on the number of top-level items in a list, by list name, e.g.
+    /// `inbox = { max = 50, move_to = "backlog" }`.
+    pub limits: BTreeMap<String, Limit>,
+    /// Dates that `later add` gives new items in a list when none is given,
+    /// by list name, e.g. `groceries = "next saturday"`.
+    pub default_dates: BTreeMap<String, String>,
+    pub webhooks: Vec<Webhook>,
+    /// Named title templates for `later add --template`, e.g.
+    /// `weekly = "Review {week} metrics"`.
+    pub templates: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct GeneralConfig {
+    /// Folder holding `later.json` and friends, instead of the standard
+    /// data directory. A leading `~` means the home directory.
+    pub data_dir: Option<PathBuf>,
+    /// List used when none is named. Defaults to `to-do`.
+    pub default_list: Option<String>,
+    /// Whether removing things asks first.
+    pub confirm: bool,
+    /// How many changes `later undo` can take back; 0 keeps no history.
+    pub undo_depth: usize,
+    /// How many old versions of the lists file are kept for `later restore`.
+    pub backups: usize,
+}
+
+impl Default for GeneralConfig {
+    fn default() -> Self {
+        GeneralConfig {
+            data_dir: None,
+            default_list: None,
+            confirm: true,
+            undo_depth: 20,
+            backups: 5,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayConfig {
+    /// Bullet characters by nesting depth, e.g. `["•", "◦", "▪"]`; the last
+    /// one repeats for deeper levels. Empty keeps the numbered markers.
+    pub bullets: Vec<String>,
+    /// Marker colors by nesting depth, as color names, 256-color numbers or
+    /// `#rrggbb` values; the last one repeats for deeper levels.
+    pub bullet_colors: Vec<String>,
+    /// Lists without a date of their own show, and sort by, the earliest
+    /// date among their unfinished items.
+    pub roll_up_dates: bool,
+    /// Colors for dates and priorities under the default theme: `default`,
+    /// or `colorblind` for hues that stay apart with color blindness; its
+    /// urgency symbols go with any theme.
+    pub palette: crate::Palette,
+    /// Start each listing with how many items are overdue or due today,
+    /// across all lists.
+    pub summary: bool,
+    /// How dates are written and read, e.g. `"dd/mm/yyyy"` or a strftime
+    /// pattern such as `"%d %B %Y"`; see [`crate::formats`].
+    pub date_format: Option<String>,
+    /// `"24h"`, `"12h"` or a strftime pattern such as `"%H.%M"`.
+    pub time_format: Option<String>,
+}
+
+/// Styles, each as words such as `"bold red"`, `"208 underline"` or
+/// `"italic #d33682"`, in place of those of the built-in theme; see
+/// [`parse_style`].
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    /// The built-in theme to start from: `default`, `solarized` or `mono`.
+    pub name: crate::theme::Builtin,
+    pub overdue: Option<String>,
+    /// Dates within the next day.
+    pub today: Option<String>,
+    pub future: Option<String>,
+    pub entry_marker: Option<String>,
+    pub list_marker: Option<String>,
+    pub entry_title: Option<String>,
+    pub list_title: Option<String>,
+    /// Added to a title's style once the item is done.
+    pub done_title: Option<String>,
+    pub tag: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CaptureConfig {
+    /// List that `later now` appends to, created on first use. Defaults to
+    /// the default list.
+    pub inbox: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotifyConfig {
+    /// How many minutes ahead of its due time an item is announced.
+    pub ahead_minutes: i64,
+    /// Where `later notify` sends alerts. Empty means a desktop
+    /// notification.
+    pub channels: Vec<Channel>,
+    /// A shell command that `later notify --daemon` runs the moment a timed
+    /// item falls due, e.g. to play a sound.
+    pub on_due: Option<String>,
+    /// Commands to run instead of `on_due` for items with these tags.
+    pub on_due_tags: BTreeMap<String, String>,
+}
+
+impl NotifyConfig {
+    /// The command for an item with `tags` falling due: the one for the
+    /// first of them that has one, or else `on_due`.
+    pub fn on_due_command(&self, tags: &[&str]) -> Option<&str> {
+        tags.iter()
+            .find_map(|tag| self.on_due_tags.get(*tag))
+            .or(self.on_due.as_ref())
+            .map(String::as_str)
+    }
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        NotifyConfig {
+            ahead_minutes: 15,
+            channels: Vec::new(),
+            on_due: None,
+            on_due_tags: BTreeMap::new(),
+        }
+    }
+}
+
+/// A destination for due alerts, e.g. `{ type = "webhook", url = "..." }`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Channel {
+    /// The desktop's notification daemon (`notify-send` or `osascript`).
+    Desktop,
+    /// A shell command, given the message on stdin and in the
+    /// `LATER_TITLE` and `LATER_MESSAGE` environment variables.
+    Command { command: String },
+    /// An HTTP POST of the message: as plain text (ntfy.sh and the like)
+    /// or, with `json = true`, as `{"text": ...}` (Slack, Mattermost).
+    Webhook {
+        url: String,
+        #[serde(default)]
+        json: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SortConfig {
+    /// How `later sort` orders items that share a date: `original`,
+    /// `title` or `created`.
+    pub tie_break: crate::TieBreak,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct EscalateConfig {
+    /// Whether the rules are applied every time the lists are loaded, not
+    /// just by `later escalate`.
+    pub on_load: bool,
+    pub rules: Vec<EscalationRule>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArchiveConfig {
+    /// Days after being marked done that items move to the archive, as
+    /// they're loaded; never if left out.
+    pub after_days: Option<i64>,
+}
+
+/// Milliseconds each phase of a command may take before `later -v` warns
+/// about it, e.g. `parse = 100`; see [`Phase`](crate::timings::Phase) for
+/// the defaults.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct BudgetConfig {
+    pub load: Option<u64>,
+    pub parse: Option<u64>,
+    pub sort: Option<u64>,
+    pub render: Option<u64>,
+    pub save: Option<u64>,
+}
+
+impl BudgetConfig {
+    pub fn budget(&self, phase: Phase) -> std::time::Duration {
+        let millis = match phase {
+            Phase::Load => self.load,
+            Phase::Parse => self.parse,
+            Phase::Sort => self.sort,
+            Phase::Render => self.render,
+            Phase::Save => self.save,
+        };
+        millis.map_or_else(
+            || phase.default_budget(),
+            std::time::Duration::from_millis,
+        )
+    }
+}
+
+/// What happens to an unfinished item once it is `overdue_days` days past
+/// its date, e.g. `{ overdue_days = 3, priority = "A", tag = "late" }`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct EscalationRule {
+    pub overdue_days: i64,
+    /// Priority to raise the item to; a higher one is kept.
+    pub priority: Option<crate::Priority>,
+    /// Tag to give the item, with or without the `#`.
+    pub tag: Option<String>,
+}
+
+/// Where to post changes as they're saved, e.g.
+/// `{ url = "https://example.com/hook", events = ["done"] }`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Webhook {
+    pub url: String,
+    /// Which changes to send: `add`, `done` and `remove`; all of them if
+    /// left out.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<crate::webhook::Action>,
+}
+
+/// How many items a list may hold, and what happens past that.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Limit {
+    pub max: usize,
+    /// List that the oldest items move to once there are too many; without
+    /// one, saving just warns.
+    pub move_to: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyncConfig {
+    /// Where `later sync` keeps the shared copy of the lists.
+    pub remote: Option<Remote>,
+    /// Commit `later.json` to a git repository in the data directory after
+    /// every change, making one if need be.
+    pub auto_commit: bool,
+}
+
+/// A sync remote, e.g. `{ type = "directory", path = "~/Dropbox/later" }`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Remote {
+    /// A directory shared some other way (a network drive, Syncthing, ...).
+    Directory { path: PathBuf },
+    /// An S3-compatible bucket, e.g. `{ type = "s3", bucket = "me-later",
+    /// prefix = "lists" }`; `endpoint` is for services other than AWS, e.g.
+    /// `"http://localhost:9000"` for MinIO. Credentials come from the
+    /// environment.
+    S3 {
+        bucket: String,
+        prefix: Option<String>,
+        region: Option<String>,
+        endpoint: Option<String>,
+    },
+    /// A branch of a git repository, e.g. `{ type = "git", url =
+    /// "git@example.com:me/lists.git" }`, reached through a repository in
+    /// the data directory. `branch` is `main` unless given.
+    Git { url: String, branch: Option<String> },
+}
+
+impl Config {
+    pub fn default_list(&self) -> &str {
+        self.general
+            .default_list
+            .as_deref()
+            .unwrap_or(crate::DEFAULT_LIST)
+    }
+
+    /// List that `later now` appends to.
+    pub fn inbox(&self) -> &str {
+        self.capture.inbox.as_deref().unwrap_or(self.default_list())
+    }
+
+    /// The date a new item in `list` gets when none is given, if the list
+    /// has one set.
+    pub fn default_date(
+        &self,
+        list: &str,
+        today: NaiveDate,
+    ) -> Result<Option<DateMaybeTime>> {
+        let text = match self.default_dates.get(list) {
+            Some(text) => text,
+            None => return Ok(None),
+        };
+        match natural::parse(text, today) {
+            Some(date) => Ok(Some(date)),
+            None => bail!(
+                "Invalid default date '{}' for list '{}' (e.g. {})",
+                text,
+                list,
+                natural::EXAMPLES
+            ),
+        }
+    }
+
+    /// Where the lists are kept, e.g. `~/.local/share/later`.
+    pub fn data_dir(&self) -> Result<PathBuf> {
+        match (&self.general.data_dir, dirs::data_local_dir()) {
+            (Some(path), _) => Ok(expand_home(path)),
+            (None, Some(path)) => Ok(path.join("later")),
+            // should never happen on common operating systems
+            (None, None) => {
+                bail!("Could not find standard local data directory.")
+            }
+        }
+    }
+
+    /// Writes the config file, creating its folder if need be.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(folder) = path.parent() {
+            std::fs::create_dir_all(folder).with_context(|| {
+                format!("Couldn't create {}", folder.display())
+            })?;
+        }
+        std::fs::write(path, toml::to_string(self)?).with_context(|| {
+            format!("Couldn't write config file ({})", path.display())
+        })
+    }
+
+    /// Where the config file lives, e.g. `~/.config/later/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("later").join("config.toml"))
+    }
+
+    /// Reads the config file, falling back to defaults if it doesn't exist.
+    pub fn load(path: &Path) -> Result<Config> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Config::default())
+            }
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Couldn't read config file ({})", path.display())
+                })
+            }
+        };
+        toml::from_str(&text).with_context(|| {
+            format!("Couldn't parse config file ({})", path.display())
+        })
+    }
+}
+
+/// `path` with a leading `~` replaced by the home directory.
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Parses a style: any of `bold`, `dimmed`, `italic`, `underline` and
+/// `strikethrough`, and a color as [`parse_color`] reads it, with `on` and
+/// another color for the background, e.g. `"bold white on #dc322f"`.
+/// `plain` is no style at all.
+pub fn parse_style(s: &str) -> Result<Style> {
+    let mut style = Style::new();
+    let mut words = s.split_whitespace();
+    while let Some(word) = words.next() {
+        match word.to_lowercase().as_str() {
+            "plain" => {}
+            "bold" => style = style.bold(),
+            "dimmed" | "dim" => style = style.dimmed(),
+            "italic" => style = style.italic(),
+            "underline" => style = style.underline(),
+            "strikethrough" => style = style.strikethrough(),
+            "on" => match words.next() {
+                Some(color) => style = style.on(parse_color(color)?),
+                None => bail!("Missing color after 'on' in '{}'", s),
+            },
+            _ => style = style.fg(parse_color(word)?),
+        }
+    }
+    Ok(style)
+}
+
+/// Parses a color name (`red`, `cyan`, ...), a 256-color palette number, or
+/// a `#rrggbb` truecolor value.
+pub fn parse_color(s: &str) -> Result<Color> {
+    let s = s.trim();
+    if let Some(hex) = s.strip_prefix('#') {
+        if hex.len() == 6 {
+            if let Ok(rgb) = u32::from_str_radix(hex, 16) {
+                return Ok(Color::RGB(
+                    (rgb >> 16) as u8,
+                    (rgb >> 8) as u8,
+                    rgb as u8,
+                ));
+            }
+        }
+        bail!("Invalid color '{}' (expected #rrggbb)", s);
+    }
+    if let Ok(n) = s.parse::<u8>() {
+        return Ok(Color::Fixed(n));
+    }
+    Ok(match s.to_lowercase().as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "purple" | "magenta" => Color::Purple,
+        "cyan" => Color::Cyan,
+        "white" => Color::White,
+        _ => bail!("Unknown color '{}'", s),
+    })
+}
@@ -0,0 +1,134 @@
+//! `later countdown`: the time left until one item falls due, kept up to
+//! date on a single line, or in large digits, until it passes.
+
+use crate::{clock, date_text, DateMaybeTime, ListItem, RenderOptions};
+use ansi_term::Style;
+use anyhow::{bail, Result};
+use chrono::prelude::*;
+use chrono::Duration;
+use std::io::{prelude::*, IsTerminal};
+
+// five rows per character, for `--big`
+const GLYPHS: &[(char, [&str; 5])] = &[
+    ('0', ["███", "█ █", "█ █", "█ █", "███"]),
+    ('1', ["  █", "  █", "  █", "  █", "  █"]),
+    ('2', ["███", "  █", "███", "█  ", "███"]),
+    ('3', ["███", "  █", "███", "  █", "███"]),
+    ('4', ["█ █", "█ █", "███", "  █", "  █"]),
+    ('5', ["███", "█  ", "███", "  █", "███"]),
+    ('6', ["███", "█  ", "███", "█ █", "███"]),
+    ('7', ["███", "  █", "  █", "  █", "  █"]),
+    ('8', ["███", "█ █", "███", "█ █", "███"]),
+    ('9', ["███", "█ █", "███", "  █", "███"]),
+    ('d', ["  █", "  █", "███", "█ █", "███"]),
+    (':', [" ", "█", " ", "█", " "]),
+    (' ', [" ", " ", " ", " ", " "]),
+];
+
+/// The moment `date` is past: the given time, or the end of a plain date,
+/// which is when it starts to show as overdue.
+pub fn deadline(date: &DateMaybeTime) -> DateTime<Local> {
+    match date {
+        DateMaybeTime::DateTime(moment) => *moment,
+        DateMaybeTime::Date(date) => {
+            let midnight = date.succ().and_hms(0, 0, 0);
+            Local
+                .from_local_datetime(&midnight)
+                .earliest()
+                .unwrap_or_else(|| Local.from_utc_datetime(&midnight))
+        }
+    }
+}
+
+/// Time left as a clock, e.g. "03:04:05", with whole days in front when
+/// there are any, as in "2d 03:04:05". Part seconds count as a whole one,
+/// so it reads 00:00:00 only once the time is up.
+pub fn clock_text(left: Duration) -> String {
+    let millis = left.num_milliseconds().max(0);
+    let seconds = (millis + 999) / 1000;
+    let (days, rest) = (seconds / 86400, seconds % 86400);
+    let time = format!(
+        "{:02}:{:02}:{:02}",
+        rest / 3600,
+        rest % 3600 / 60,
+        rest % 60
+    );
+    match days {
+        0 => time,
+        days => format!("{}d {}", days, time),
+    }
+}
+
+fn big_text(text: &str) -> String {
+    let mut rows = vec![String::new(); 5];
+    for c in text.chars() {
+        if let Some((_, glyph)) = GLYPHS.iter().find(|(g, _)| *g == c) {
+            for (row, part) in rows.iter_mut().zip(glyph) {
+                row.push_str(part);
+                row.push(' ');
+            }
+        }
+    }
+    rows.iter()
+        .map(|row| format!("{}\n", row.trim_end()))
+        .collect()
+}
+
+/// Counts down to `item`'s date, redrawing every second until it passes,
+/// or until interrupted. When output isn't a terminal the time left is
+/// written just once.
+pub fn run(item: &ListItem, big: bool, opts: &RenderOptions) -> Result<()> {
+    let date = match item.date() {
+        Some(date) => date,
+        None => bail!("'{}' has no date to count down to", item.title()),
+    };
+    let due = deadline(&date);
+    let done = item.is_done();
+    let title = opts
+        .title_style(Style::new().bold(), done)
+        .paint(item.title());
+    let mut stdout = std::io::stdout();
+    let live = stdout.is_terminal();
+    loop {
+        let now = clock::now();
+        let left = due.signed_duration_since(now);
+        let passed = left <= Duration::zero();
+        let hard = item.is_deadline();
+        let style = opts.date_style(&date, hard, done);
+        let when = date_text(&date, item.zone(), hard, done, opts.palette);
+        let mut screen = Vec::new();
+        if big {
+            if live {
+                // clear the screen and go home
+                screen.extend_from_slice(b"\x1b[2J\x1b[H");
+            }
+            let digits = big_text(&clock_text(left));
+            write!(screen, "{}", style.paint(digits))?;
+            writeln!(screen, "{} ({})", title, style.paint(when))?;
+        } else {
+            if live {
+                // back to the start of the line and clear it
+                screen.extend_from_slice(b"\r\x1b[2K");
+            }
+            write!(
+                screen,
+                "{}  {} ({})",
+                style.paint(clock_text(left)),
+                title,
+                style.paint(when)
+            )?;
+            if passed || !live {
+                writeln!(screen)?;
+            }
+        }
+        stdout.write_all(&screen)?;
+        stdout.flush()?;
+        if passed || !live {
+            return Ok(());
+        }
+        // wake on the next whole second of what's left
+        let wait = left.num_milliseconds() % 1000;
+        let wait = if wait == 0 { 1000 } else { wait };
+        std::thread::sleep(std::time::Duration::from_millis(wait as u64));
+    }
+}
@@ -0,0 +1,244 @@
+//! Encryption at rest, turned on with `later init --encrypt`. The lists
+//! file (and so its backups, and copies pushed by `later sync`) is then a
+//! JSON envelope holding the usual file sealed with ChaCha20-Poly1305,
+//! under a key derived from a passphrase with Argon2id. The passphrase
+//! comes from `LATER_PASSPHRASE`, or is asked for once per run.
+//!
+//! The undo history, the archive (a line at a time) and the sync state hold
+//! copies of the lists, so they're sealed the same way. Everything goes
+//! through [`open`] and [`seal`], which pass plain text through: the first
+//! file opened (the lists file) decides whether everything saved after it is
+//! sealed, and with what.
+
+use crate::error::{Error, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ring::aead::{self, Aad, LessSafeKey, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::io::IsTerminal;
+use std::sync::Mutex;
+
+/// Where the passphrase is read from before asking for it.
+pub const PASSPHRASE_VAR: &str = "LATER_PASSPHRASE";
+
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    encrypted: Sealed,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Sealed {
+    cipher: String,
+    kdf: Kdf,
+    salt: String,
+    nonce: String,
+    data: String,
+}
+
+/// Argon2id's cost, kept in the file so that later defaults don't lock
+/// anyone out.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+struct Kdf {
+    /// Memory, in KiB.
+    m: u32,
+    t: u32,
+    p: u32,
+}
+
+impl Default for Kdf {
+    fn default() -> Kdf {
+        Kdf {
+            m: argon2::Params::DEFAULT_M_COST,
+            t: argon2::Params::DEFAULT_T_COST,
+            p: argon2::Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+struct Keys {
+    passphrase: Option<String>,
+    // every key derived so far, by what it was derived with
+    derived: Vec<(Kdf, Vec<u8>, [u8; KEY_LEN])>,
+    // what new files are sealed with, if they are
+    sealing: Option<(Kdf, Vec<u8>)>,
+    // whether `sealing` has been decided yet
+    settled: bool,
+}
+
+static KEYS: Mutex<Keys> = Mutex::new(Keys {
+    passphrase: None,
+    derived: Vec::new(),
+    sealing: None,
+    settled: false,
+});
+
+fn fail(reason: impl Into<String>) -> Error {
+    Error::Decrypt {
+        reason: reason.into(),
+    }
+}
+
+/// Whether `text` is a sealed file rather than a plain one.
+pub fn is_sealed(text: &str) -> bool {
+    // in a plain file, "encrypted" could only be the name of a list, which
+    // has none of the fields an envelope does
+    serde_json::from_str::<Envelope>(text).is_ok()
+}
+
+/// Whether saves are sealed.
+pub fn is_on() -> bool {
+    KEYS.lock().unwrap().sealing.is_some()
+}
+
+fn passphrase(keys: &mut Keys) -> Result<String> {
+    if let Some(passphrase) = &keys.passphrase {
+        return Ok(passphrase.clone());
+    }
+    let passphrase = match std::env::var(PASSPHRASE_VAR) {
+        Ok(passphrase) => passphrase,
+        Err(_) if std::io::stdin().is_terminal() => {
+            rpassword::prompt_password("Passphrase for the lists: ")
+                .map_err(|e| fail(e.to_string()))?
+        }
+        Err(_) => return Err(Error::Locked),
+    };
+    keys.passphrase = Some(passphrase.clone());
+    Ok(passphrase)
+}
+
+fn key(keys: &mut Keys, kdf: Kdf, salt: &[u8]) -> Result<[u8; KEY_LEN]> {
+    let found = keys
+        .derived
+        .iter()
+        .find(|(k, s, _)| *k == kdf && s == salt)
+        .map(|(_, _, key)| *key);
+    if let Some(key) = found {
+        return Ok(key);
+    }
+    let passphrase = passphrase(keys)?;
+    let params = argon2::Params::new(kdf.m, kdf.t, kdf.p, Some(KEY_LEN))
+        .map_err(|e| fail(e.to_string()))?;
+    let argon2 = argon2::Argon2::new(
+        argon2::Algorithm::Argon2id,
+        argon2::Version::V0x13,
+        params,
+    );
+    let mut key = [0; KEY_LEN];
+    argon2
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| fail(e.to_string()))?;
+    keys.derived.push((kdf, salt.to_vec(), key));
+    Ok(key)
+}
+
+fn cipher(key: &[u8; KEY_LEN]) -> LessSafeKey {
+    LessSafeKey::new(UnboundKey::new(&aead::CHACHA20_POLY1305, key).unwrap())
+}
+
+/// The plain file inside `text`, asking for the passphrase if it's sealed
+/// and none has been given yet; a plain file comes back as it is.
+pub fn open(text: &str) -> Result<String> {
+    let sealed = match serde_json::from_str::<Envelope>(text) {
+        Ok(envelope) => envelope.encrypted,
+        Err(_) => {
+            KEYS.lock().unwrap().settled = true;
+            return Ok(String::from(text));
+        }
+    };
+    if sealed.cipher != "chacha20-poly1305" {
+        return Err(fail(format!("unknown cipher '{}'", sealed.cipher)));
+    }
+    let decode = |field: &str| {
+        BASE64
+            .decode(field)
+            .map_err(|_| fail("the file is damaged"))
+    };
+    let (salt, nonce, mut data) = (
+        decode(&sealed.salt)?,
+        decode(&sealed.nonce)?,
+        decode(&sealed.data)?,
+    );
+    let nonce = Nonce::try_assume_unique_for_key(&nonce)
+        .map_err(|_| fail("the file is damaged"))?;
+    let mut keys = KEYS.lock().unwrap();
+    let key = key(&mut keys, sealed.kdf, &salt)?;
+    let plain = cipher(&key)
+        .open_in_place(nonce, Aad::empty(), &mut data)
+        .map_err(|_| {
+            // ask again next time rather than keep trying this one
+            keys.passphrase = None;
+            keys.derived
+                .retain(|(k, s, _)| (*k, s) != (sealed.kdf, &salt));
+            fail("wrong passphrase, or the file has been changed")
+        })?;
+    let plain = String::from_utf8(plain.to_vec())
+        .map_err(|_| fail("the file is damaged"))?;
+    if !keys.settled {
+        keys.sealing = Some((sealed.kdf, salt));
+        keys.settled = true;
+    }
+    Ok(plain)
+}
+
+/// `text` sealed, if saves are; otherwise as it is.
+pub fn seal(text: &str) -> Result<String> {
+    let mut keys = KEYS.lock().unwrap();
+    let (kdf, salt) = match keys.sealing.clone() {
+        Some(sealing) => sealing,
+        None => return Ok(String::from(text)),
+    };
+    let encrypt_error = |reason: &str| Error::Encrypt {
+        reason: String::from(reason),
+    };
+    let key = key(&mut keys, kdf, &salt)?;
+    // a fresh nonce for every save, as the key stays the same
+    let mut nonce = [0; aead::NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| encrypt_error("no random numbers"))?;
+    let mut data = text.as_bytes().to_vec();
+    cipher(&key)
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut data,
+        )
+        .map_err(|_| encrypt_error("the file is too big"))?;
+    let envelope = Envelope {
+        encrypted: Sealed {
+            cipher: String::from("chacha20-poly1305"),
+            kdf,
+            salt: BASE64.encode(salt),
+            nonce: BASE64.encode(nonce),
+            data: BASE64.encode(data),
+        },
+    };
+    // on one line, as the archive has one item to a line
+    serde_json::to_string(&envelope).map_err(|e| encrypt_error(&e.to_string()))
+}
+
+/// Seals everything saved from now on with `passphrase`, under a new salt.
+pub fn enable(passphrase: &str) -> Result<()> {
+    let mut salt = [0; SALT_LEN];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| Error::Encrypt {
+            reason: String::from("no random numbers"),
+        })?;
+    let mut keys = KEYS.lock().unwrap();
+    keys.passphrase = Some(String::from(passphrase));
+    keys.sealing = Some((Kdf::default(), salt.to_vec()));
+    keys.settled = true;
+    Ok(())
+}
+
+/// Saves everything from now on as plain files again.
+pub fn disable() {
+    let mut keys = KEYS.lock().unwrap();
+    keys.sealing = None;
+    keys.settled = true;
+}
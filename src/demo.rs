@@ -0,0 +1,128 @@
+use crate::{DateMaybeTime, ListItem, TodoEntry, TodoList};
+use chrono::{prelude::*, Duration};
+
+const TASKS: &[&str] = &[
+    "Call the dentist",
+    "Email the landlord about the boiler",
+    "Buy groceries",
+    "Fix the bike tyre",
+    "Review the quarterly report",
+    "Plan Jo's birthday party",
+    "Book train tickets",
+    "Clean out the garage",
+    "Write the blog post draft",
+    "Return library books",
+    "Renew passport",
+    "Schedule the team sync",
+    "Pay the electricity bill",
+    "Update resume",
+    "Read that paper Sam sent",
+    "Water the plants",
+    "Back up the laptop",
+    "Cancel the gym membership",
+    "Pick up dry cleaning",
+    "Reply to the recruiter",
+    "Order printer ink",
+    "Submit expense report",
+    "Defrost the freezer",
+    "Send thank-you cards",
+];
+const PROJECTS: &[&str] = &[
+    "Home",
+    "Work",
+    "Garden",
+    "Holiday",
+    "Side project",
+    "Errands",
+    "Finances",
+    "Health",
+    "Reading list",
+    "Move house",
+];
+
+/// Small xorshift generator so demo data doesn't need a dependency, and a
+/// given seed always produces the same list.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // xorshift gets stuck on zero
+        Rng(seed.max(1))
+    }
+
+    pub fn from_time() -> Rng {
+        Rng::new(Local::now().timestamp_nanos() as u64)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `0..n`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n.max(1)
+    }
+
+    pub fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<'a>(&mut self, words: &[&'a str]) -> &'a str {
+        words[self.below(words.len() as u64) as usize]
+    }
+}
+
+fn random_date(rng: &mut Rng) -> Option<DateMaybeTime> {
+    if !rng.chance(60) {
+        return None;
+    }
+    let date =
+        crate::clock::today() + Duration::days(rng.below(120) as i64 - 30);
+    if rng.chance(30) {
+        let time = NaiveTime::from_hms(8 + rng.below(12) as u32, 0, 0);
+        Local
+            .from_local_datetime(&date.and_time(time))
+            .single()
+            .map(DateMaybeTime::DateTime)
+    } else {
+        Some(DateMaybeTime::Date(date))
+    }
+}
+
+// fills `list` with `count` items in total, nesting at most `depth` levels
+fn fill(list: &mut TodoList, rng: &mut Rng, count: usize, depth: usize) {
+    let mut remaining = count;
+    while remaining > 0 {
+        remaining -= 1;
+        if depth > 1 && remaining >= 2 && rng.chance(25) {
+            let children = 2 + rng.below(remaining.min(8) as u64 - 1) as usize;
+            remaining -= children;
+            let mut sublist =
+                TodoList::from_info(rng.pick(PROJECTS).to_string(), None);
+            fill(&mut sublist, rng, children, depth - 1);
+            list.list.push(ListItem::List(sublist));
+        } else {
+            let title = rng.pick(TASKS).to_string();
+            list.list.push(ListItem::Entry(TodoEntry::from_info(
+                title,
+                random_date(rng),
+            )));
+        }
+    }
+}
+
+/// Builds a list of `items` random but plausible items (sublists included)
+/// nested up to `depth` levels deep.
+pub fn generate(
+    title: String,
+    items: usize,
+    depth: usize,
+    rng: &mut Rng,
+) -> TodoList {
+    let mut list = TodoList::from_info(title, None);
+    fill(&mut list, rng, items, depth.max(1));
+    list
+}
@@ -0,0 +1,100 @@
+use crate::color;
+use ansi_term::Color;
+use std::io::prelude::*;
+
+const CONTEXT: usize = 2;
+
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+// longest-common-subsequence line diff; the files involved are small enough
+// that the quadratic table is not a concern
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            lines.push(Line::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(Line::Removed(old[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(new[j]));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|l| Line::Removed(l)));
+    lines.extend(new[j..].iter().map(|l| Line::Added(l)));
+    lines
+}
+
+/// Writes a unified-style diff of `old` and `new`, showing a couple of lines
+/// of context around each change. Returns whether anything changed.
+pub fn write_diff(
+    out: &mut impl Write,
+    old: &str,
+    new: &str,
+) -> std::io::Result<bool> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let lines = diff_lines(&old, &new);
+
+    let changed: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| !matches!(l, Line::Same(_)))
+        .map(|(i, _)| i)
+        .collect();
+    if changed.is_empty() {
+        return Ok(false);
+    }
+
+    let near_change = |i: usize| {
+        changed
+            .iter()
+            .any(|&c| c.saturating_sub(CONTEXT) <= i && i <= c + CONTEXT)
+    };
+    let mut skipped = false;
+    for (i, line) in lines.iter().enumerate() {
+        if !near_change(i) {
+            skipped = true;
+            continue;
+        }
+        if skipped {
+            writeln!(
+                out,
+                "{}",
+                color::style(Color::Cyan.normal()).paint("@@")
+            )?;
+            skipped = false;
+        }
+        match line {
+            Line::Same(l) => writeln!(out, " {}", l)?,
+            Line::Removed(l) => {
+                let removed = color::style(Color::Red.normal());
+                writeln!(out, "{}", removed.paint(format!("-{}", l)))?
+            }
+            Line::Added(l) => {
+                let added = color::style(Color::Green.normal());
+                writeln!(out, "{}", added.paint(format!("+{}", l)))?
+            }
+        }
+    }
+    Ok(true)
+}
@@ -0,0 +1,374 @@
+//! `later doctor`: a plain-text account of where later keeps things and
+//! what it can see of its surroundings, to paste into bug reports.
+
+use crate::config::{self, Channel, Config, Remote};
+use crate::theme::Theme;
+use crate::{
+    backup, crypt, formats, lock, migrate, sync, template, url_scheme, TodoList,
+};
+use std::collections::HashMap;
+use std::io::prelude::*;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+
+/// Everything the report looks at. The config and lists are results, so a
+/// broken file is reported rather than stopping the report.
+pub struct Environment<'a> {
+    pub config_path: Option<&'a Path>,
+    pub config: &'a anyhow::Result<Config>,
+    pub todo_file: &'a Path,
+}
+
+#[derive(Default)]
+struct Report {
+    lines: Vec<(&'static str, String)>,
+    problems: Vec<String>,
+}
+
+impl Report {
+    fn line(&mut self, label: &'static str, value: impl Into<String>) {
+        self.lines.push((label, value.into()));
+    }
+}
+
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| dir.join(program).is_file())
+    })
+}
+
+// "4.2 KiB", "180 B"
+fn size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        _ => format!("{:.1} KiB", bytes as f64 / 1024.0),
+    }
+}
+
+fn writable(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|m| !m.permissions().readonly())
+}
+
+/// Writes the report, ending with any problems found.
+pub fn write_report(
+    out: &mut impl Write,
+    env: &Environment,
+) -> std::io::Result<()> {
+    let mut report = Report::default();
+    report.line("version", env!("CARGO_PKG_VERSION"));
+
+    let default_config = Config::default();
+    let config = match (env.config_path, env.config) {
+        (None, _) => {
+            report.line("config", "no config directory");
+            &default_config
+        }
+        (Some(path), Ok(config)) => {
+            let state = if path.exists() { "" } else { " (not created)" };
+            report.line("config", format!("{}{}", path.display(), state));
+            config
+        }
+        (Some(path), Err(e)) => {
+            report.line("config", format!("{} (unreadable)", path.display()));
+            report.problems.push(format!("{:#}", e));
+            &default_config
+        }
+    };
+    check_config(&mut report, config);
+
+    let lists = check_data(&mut report, env.todo_file, config.general.backups);
+    if let Some(lists) = &lists {
+        if !lists.contains_key(config.default_list()) {
+            report.problems.push(format!(
+                "The default list '{}' doesn't exist yet; it will be \
+                 created empty on first use.",
+                config.default_list()
+            ));
+        }
+    }
+    check_terminal(&mut report);
+    check_integrations(&mut report, config, env.todo_file);
+
+    let label_width = report.lines.iter().map(|(l, _)| l.len()).max();
+    for (label, value) in &report.lines {
+        let width = label_width.unwrap_or(0);
+        writeln!(out, "{:<width$}  {}", label, value, width = width)?;
+    }
+    writeln!(out)?;
+    if report.problems.is_empty() {
+        return writeln!(out, "No problems found.");
+    }
+    writeln!(out, "Problems:")?;
+    for problem in &report.problems {
+        writeln!(out, "- {}", problem.trim_end().replace('\n', "\n  "))?;
+    }
+    Ok(())
+}
+
+fn check_config(report: &mut Report, config: &Config) {
+    for color in &config.display.bullet_colors {
+        if let Err(e) = config::parse_color(color) {
+            report
+                .problems
+                .push(format!("display.bullet_colors: {}", e));
+        }
+    }
+    if let Err(e) = Theme::from_config(&config.theme, config.display.palette) {
+        report.problems.push(format!("{:#}", e));
+    }
+    if let Err(e) = formats::Formats::from_config(&config.display) {
+        report.problems.push(format!("{:#}", e));
+    }
+    for (name, text) in &config.templates {
+        if let Err(e) = template::expand(text, crate::clock::today()) {
+            report.problems.push(format!("templates.{}: {}", name, e));
+        }
+    }
+}
+
+fn check_data(
+    report: &mut Report,
+    todo_file: &Path,
+    backups: usize,
+) -> Option<HashMap<String, TodoList>> {
+    let metadata = match std::fs::metadata(todo_file) {
+        Ok(metadata) => metadata,
+        Err(_) => {
+            report.line(
+                "data file",
+                format!("{} (not created)", todo_file.display()),
+            );
+            return None;
+        }
+    };
+    report.line(
+        "data file",
+        format!("{} ({})", todo_file.display(), size(metadata.len())),
+    );
+    let folder = todo_file.parent().unwrap_or(Path::new("."));
+    let access = match (writable(todo_file), writable(folder)) {
+        (true, true) => String::from("read-write"),
+        (false, _) => String::from("read-only file"),
+        (true, false) => String::from("read-only folder"),
+    };
+    if access != "read-write" {
+        report.problems.push(format!(
+            "{} can't be saved ({}).",
+            todo_file.display(),
+            access
+        ));
+    }
+    report.line("permissions", access);
+    report.line(
+        "backups",
+        format!(
+            "{} of {} kept",
+            backup::list(todo_file, backups).len(),
+            backups
+        ),
+    );
+    report.line(
+        "lock",
+        format!(
+            "{} ({})",
+            if lock::is_held(todo_file) {
+                "held by another later"
+            } else {
+                "free"
+            },
+            lock::path(todo_file).display()
+        ),
+    );
+
+    let text = match std::fs::read_to_string(todo_file) {
+        Ok(text) => text,
+        Err(e) => {
+            report
+                .problems
+                .push(format!("Couldn't read data file: {}", e));
+            return None;
+        }
+    };
+    let sealed = crypt::is_sealed(&text);
+    report.line("encryption", if sealed { "on" } else { "off" });
+    let text = match crypt::open(&text) {
+        Ok(text) => text,
+        Err(e) => {
+            report.problems.push(format!("{}.", e));
+            return None;
+        }
+    };
+    if let Ok(version) = migrate::version_of(&text) {
+        report.line(
+            "schema",
+            match version {
+                v if v == migrate::VERSION => format!("{} (current)", v),
+                v if v < migrate::VERSION => format!(
+                    "{} (upgraded to {} on the next save)",
+                    v,
+                    migrate::VERSION
+                ),
+                v => format!("{} (newer than this release)", v),
+            },
+        );
+    }
+    match migrate::parse(&text) {
+        Ok(lists) => {
+            let items: usize =
+                lists.values().map(|l| l.items_with_paths().len()).sum();
+            report.line(
+                "lists",
+                format!("{} lists, {} items", lists.len(), items),
+            );
+            Some(lists)
+        }
+        Err(e) => {
+            report
+                .problems
+                .push(format!("Couldn't parse data file: {}", e));
+            None
+        }
+    }
+}
+
+fn check_terminal(report: &mut Report) {
+    let stdout = std::io::stdout();
+    let var = |name| std::env::var(name).unwrap_or_default();
+    let color = if std::env::var_os("NO_COLOR").is_some() {
+        String::from("off (NO_COLOR)")
+    } else if var("COLORTERM") == "truecolor" || var("COLORTERM") == "24bit" {
+        String::from("truecolor")
+    } else if var("TERM").contains("256color") {
+        String::from("256 colors")
+    } else if var("TERM") == "dumb" {
+        String::from("none (TERM=dumb)")
+    } else {
+        String::from("16 colors")
+    };
+    report.line("color", color);
+    let width = terminal_size::terminal_size_of(&stdout)
+        .map(|(terminal_size::Width(w), _)| format!("{} columns", w));
+    let width = match std::env::var("COLUMNS") {
+        Ok(columns) => format!("{} columns (COLUMNS)", columns),
+        Err(_) => width.unwrap_or_else(|| String::from("unknown")),
+    };
+    report.line("width", width);
+    report.line(
+        "terminal",
+        match (std::io::stdin().is_terminal(), stdout.is_terminal()) {
+            (true, true) => "interactive",
+            (false, true) => "input piped",
+            (true, false) => "output piped",
+            (false, false) => "input and output piped",
+        },
+    );
+}
+
+fn check_integrations(report: &mut Report, config: &Config, todo_file: &Path) {
+    let channels: Vec<String> = config
+        .notify
+        .channels
+        .iter()
+        .map(|channel| match channel {
+            Channel::Desktop => String::from("desktop"),
+            Channel::Command { command } => format!("command '{}'", command),
+            Channel::Webhook { url, .. } => format!("webhook {}", url),
+        })
+        .collect();
+    let desktop = config.notify.channels.is_empty()
+        || config.notify.channels.contains(&Channel::Desktop);
+    let notifier = if cfg!(target_os = "macos") {
+        "osascript"
+    } else {
+        "notify-send"
+    };
+    if desktop && !on_path(notifier) {
+        report.problems.push(format!(
+            "Desktop notifications need {}, which isn't on PATH.",
+            notifier
+        ));
+    }
+    report.line(
+        "notify",
+        if channels.is_empty() {
+            String::from("desktop (default)")
+        } else {
+            channels.join(", ")
+        },
+    );
+
+    let folder = todo_file.parent().unwrap_or(Path::new("."));
+    match &config.sync.remote {
+        Some(remote) => {
+            report.line("sync", sync::backend(remote, folder).describe());
+            match remote {
+                Remote::Directory { path } => {
+                    let path: PathBuf = config::expand_home(path);
+                    if !path.is_dir() {
+                        report.problems.push(format!(
+                            "The sync directory {} doesn't exist yet.",
+                            path.display()
+                        ));
+                    }
+                }
+                Remote::S3 { .. } => {
+                    let missing: Vec<&str> =
+                        ["AWS_ACCESS_KEY_ID", "AWS_SECRET_ACCESS_KEY"]
+                            .into_iter()
+                            .filter(|var| {
+                                std::env::var(var)
+                                    .map_or(true, |value| value.is_empty())
+                            })
+                            .collect();
+                    if !missing.is_empty() {
+                        report.problems.push(format!(
+                            "S3 sync needs {} set.",
+                            missing.join(" and ")
+                        ));
+                    }
+                }
+                Remote::Git { .. } => {}
+            }
+        }
+        None => report.line("sync", "off"),
+    }
+    if config.sync.auto_commit {
+        report.line(
+            "auto-commit",
+            if folder.join(".git").exists() {
+                "on"
+            } else {
+                "on (the repository is made on the first change)"
+            },
+        );
+    }
+    if let Ok(state) = sync::State::load(&folder.join("sync.json")) {
+        if !state.conflicts.is_empty() {
+            report.problems.push(format!(
+                "{} sync conflicts are waiting for `later sync resolve`.",
+                state.conflicts.len()
+            ));
+        }
+    }
+
+    let handler = url_scheme::desktop_entry_path()
+        .filter(|path| path.exists())
+        .map_or("not installed", |_| "installed");
+    report.line("url handler", handler);
+    report.line(
+        "templates",
+        format!("{} configured", config.templates.len()),
+    );
+    report.line(
+        "escalation",
+        format!(
+            "{} rules{}",
+            config.escalate.rules.len(),
+            if config.escalate.on_load {
+                ", on load"
+            } else {
+                ""
+            }
+        ),
+    );
+}
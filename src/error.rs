@@ -0,0 +1,162 @@
+//! What can go wrong in the library, as values to match on rather than
+//! messages to show. The `later` binary adds to them where it knows more,
+//! e.g. by drawing the items around an index that's out of range. Modules
+//! for single commands still report with `anyhow`.
+
+use crate::{did_you_mean, natural, suggest_names, ItemPath};
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// An index with no positions in it, where an item was needed.
+    #[error("Invalid index! (it's empty)")]
+    EmptyIndex,
+    /// A title-based index with nothing between two slashes.
+    #[error("Invalid index '{index}': a part is empty")]
+    EmptyIndexPart { index: String },
+    /// A segment of a numeric index that isn't a number. `segment` counts
+    /// from 0 for the top level, as it does below.
+    #[error(
+        "Invalid index '{index}': segment {} ('{text}') is not a \
+         non-negative whole number",
+        .segment + 1
+    )]
+    BadIndexSegment {
+        index: String,
+        segment: usize,
+        text: String,
+    },
+    /// A position past the end of the list at that level, which has `len`
+    /// items.
+    #[error(
+        "Invalid index! (segment {} is {position}, but there are only {len} \
+         items there)",
+        .segment + 1
+    )]
+    IndexOutOfBounds {
+        segment: usize,
+        position: usize,
+        len: usize,
+    },
+    /// A position under an entry, which has no items of its own.
+    #[error(
+        "Invalid index! (segment {} is under an entry, not a sublist)",
+        .segment + 1
+    )]
+    SubindexOnEntry { segment: usize },
+    /// Nothing at `index`, when only whether there is matters.
+    #[error("Invalid index! (there's nothing at {index})")]
+    NoItem { index: ItemPath },
+    #[error("Can't swap an item with one inside it")]
+    SwapNested,
+    /// A title to look for under `at`, which isn't a sublist.
+    #[error("No sublist at {at} to look for '{title}' in")]
+    NoSublist { at: ItemPath, title: String },
+    #[error("No item in '{list}' matches '{title}'")]
+    NoMatch { list: String, title: String },
+    /// A title that matches several items equally well, given as
+    /// `position) title`.
+    #[error(
+        "'{title}' could be any of: {}; use more of the title or the index",
+        .candidates.join(", ")
+    )]
+    AmbiguousTitle {
+        title: String,
+        candidates: Vec<String>,
+    },
+    /// With the closest list names, see [`Error::list_not_found`].
+    #[error("List '{name}' not found!{}", hint(.suggestions))]
+    ListNotFound {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    #[error(
+        "Invalid date '{text}' (e.g. {}, friday 9:40 JST)",
+        natural::EXAMPLES
+    )]
+    ParseDate { text: String },
+    /// `what` is e.g. "to-do list file" or "backup".
+    #[error("Couldn't read {what} ({})", .path.display())]
+    Read {
+        what: &'static str,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Couldn't write {what} ({})", .path.display())]
+    Write {
+        what: &'static str,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Couldn't parse {what} ({})", .path.display())]
+    Parse {
+        what: &'static str,
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    /// The lists file itself doesn't parse, which a backup can fix.
+    #[error(
+        "Couldn't parse to-do list file ({}); `later restore` brings back \
+         the last good backup",
+        .path.display()
+    )]
+    Corrupt {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    /// The file, or a backup, is in a layout from a newer release; see
+    /// [`migrate`](crate::migrate).
+    #[error(
+        "{} was written by a newer version of later (data version \
+         {version}; this one reads up to {})",
+        .path.display(),
+        crate::migrate::VERSION
+    )]
+    TooNew { path: PathBuf, version: u64 },
+    /// The lists are encrypted, and there's no passphrase to hand; see
+    /// [`crypt`](crate::crypt).
+    #[error(
+        "The lists are encrypted: set {} or run later in a terminal to be \
+         asked for the passphrase",
+        crate::crypt::PASSPHRASE_VAR
+    )]
+    Locked,
+    #[error("Couldn't decrypt the lists ({reason})")]
+    Decrypt { reason: String },
+    #[error("Couldn't encrypt the lists ({reason})")]
+    Encrypt { reason: String },
+    #[error("Couldn't generate to-do list file ({})", .path.display())]
+    Serialize {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("No readable backups of {}", .path.display())]
+    NoBackups { path: PathBuf },
+    /// Reading a title or date at a prompt failed, or was interrupted.
+    #[error(transparent)]
+    Prompt(#[from] rustyline::error::ReadlineError),
+}
+
+impl Error {
+    /// No list called `name`, suggesting the closest of `names`.
+    pub fn list_not_found<'a>(
+        name: &str,
+        names: impl IntoIterator<Item = &'a String>,
+    ) -> Error {
+        Error::ListNotFound {
+            name: String::from(name),
+            suggestions: suggest_names(name, names)
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        }
+    }
+}
+
+fn hint(suggestions: &[String]) -> String {
+    let suggestions: Vec<&str> =
+        suggestions.iter().map(String::as_str).collect();
+    did_you_mean(&suggestions)
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
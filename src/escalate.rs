@@ -0,0 +1,66 @@
+//! Overdue escalation: rules such as "after 3 days overdue, raise the
+//! priority to A and tag #late", so neglected items climb the ranking on
+//! their own.
+
+use crate::config::EscalationRule;
+use crate::{DateMaybeTime, ListItem, TodoList};
+use chrono::prelude::*;
+use std::collections::HashMap;
+
+/// Applies every rule to the unfinished items of every list, returning how
+/// many items changed. Applying the rules twice changes nothing more.
+pub fn escalate(
+    lists: &mut HashMap<String, TodoList>,
+    rules: &[EscalationRule],
+    now: DateTime<Local>,
+) -> usize {
+    let mut changed = 0;
+    for list in lists.values_mut() {
+        let paths: Vec<Vec<usize>> = list
+            .items_with_paths()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        for path in paths {
+            let item = list.get_mut(&path).unwrap();
+            if escalate_item(item, rules, now) {
+                changed += 1;
+            }
+        }
+    }
+    changed
+}
+
+fn escalate_item(
+    item: &mut ListItem,
+    rules: &[EscalationRule],
+    now: DateTime<Local>,
+) -> bool {
+    // a plain date is overdue once its day is over
+    let due = match item.date() {
+        Some(_) if item.is_done() => return false,
+        Some(DateMaybeTime::Date(date)) => date.succ().and_hms(0, 0, 0),
+        Some(DateMaybeTime::DateTime(datetime)) => datetime.naive_local(),
+        None => return false,
+    };
+    if now.naive_local() <= due {
+        return false;
+    }
+    let overdue = (now.naive_local() - due).num_days();
+    let mut changed = false;
+    for rule in rules.iter().filter(|rule| overdue >= rule.overdue_days) {
+        // only ever raised: A is the highest
+        if let Some(priority) = rule.priority {
+            if item.priority().is_none_or(|p| priority < p) {
+                item.set_priority(Some(priority));
+                changed = true;
+            }
+        }
+        if let Some(tag) = &rule.tag {
+            if item.add_tag(tag.trim_start_matches('#')) {
+                changed = true;
+            }
+        }
+    }
+    changed
+}
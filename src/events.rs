@@ -0,0 +1,162 @@
+//! `later events`: changes to the lists as a stream of JSON lines, one per
+//! item added, edited, marked done or reopened, or removed, worked out by
+//! comparing the data file before and after each save. Status bars, loggers
+//! and the like can follow it without knowing the file's layout.
+
+use crate::{clock, load, ListItem, TodoList};
+use anyhow::Result;
+use chrono::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::prelude::*;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// What happened to an item.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Add,
+    Edit,
+    Done,
+    Reopen,
+    Remove,
+}
+
+/// One change: `{"event": "done", "list": "to-do", "index": [2], "item":
+/// {...}, "at": "..."}`, in the same shape as the webhooks send, with the
+/// item as it's stored in the file (as it was, for removals).
+#[derive(Serialize, Debug, Clone)]
+pub struct Event {
+    pub event: Kind,
+    pub list: String,
+    pub index: Vec<usize>,
+    pub item: serde_json::Value,
+    pub at: DateTime<Local>,
+}
+
+impl Event {
+    fn new(kind: Kind, list: &str, index: &[usize], item: &ListItem) -> Event {
+        Event {
+            event: kind,
+            list: String::from(list),
+            index: index.to_vec(),
+            item: serde_json::to_value(item).unwrap_or_default(),
+            at: clock::now(),
+        }
+    }
+}
+
+// items have no ids, so they're told apart by when they were added, or by
+// title for items from before that was kept
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Key {
+    Created(String, DateTime<Local>),
+    Title(String, String),
+}
+
+fn key(list: &str, item: &ListItem) -> Key {
+    match item.created_at() {
+        Some(at) => Key::Created(String::from(list), at),
+        None => Key::Title(String::from(list), item.title().to_string()),
+    }
+}
+
+// the item without the items inside it, which have events of their own
+fn own_fields(item: &ListItem) -> serde_json::Value {
+    let mut value = serde_json::to_value(item).unwrap_or_default();
+    if let Some(list) = value.get_mut("List").and_then(|v| v.as_object_mut()) {
+        list.remove("list");
+    }
+    value
+}
+
+type Located<'a> = (&'a str, Vec<usize>, &'a ListItem);
+
+fn by_key(
+    lists: &HashMap<String, TodoList>,
+) -> BTreeMap<Key, VecDeque<Located<'_>>> {
+    let mut keyed: BTreeMap<Key, VecDeque<Located<'_>>> = BTreeMap::new();
+    let mut names: Vec<&String> = lists.keys().collect();
+    names.sort();
+    for name in names {
+        for (index, item) in lists[name].items_with_paths() {
+            keyed
+                .entry(key(name, item))
+                .or_default()
+                .push_back((name, index, item));
+        }
+    }
+    keyed
+}
+
+/// The changes that turn `before` into `after`: items that are only in
+/// `after` were added and those only in `before` removed, while those in
+/// both were marked done, reopened or otherwise edited if they differ.
+/// Items that only moved make no event.
+pub fn diff(
+    before: &HashMap<String, TodoList>,
+    after: &HashMap<String, TodoList>,
+) -> Vec<Event> {
+    let mut old = by_key(before);
+    let mut events = Vec::new();
+    for (key, items) in by_key(after) {
+        for (list, index, item) in items {
+            let was = old.get_mut(&key).and_then(|items| items.pop_front());
+            let kind = match was {
+                None => Some(Kind::Add),
+                Some((_, _, was)) => match (was.is_done(), item.is_done()) {
+                    (false, true) => Some(Kind::Done),
+                    (true, false) => Some(Kind::Reopen),
+                    _ if own_fields(was) != own_fields(item) => {
+                        Some(Kind::Edit)
+                    }
+                    _ => None,
+                },
+            };
+            if let Some(kind) = kind {
+                events.push(Event::new(kind, list, &index, item));
+            }
+        }
+    }
+    for (list, index, item) in old.into_values().flatten() {
+        events.push(Event::new(Kind::Remove, list, &index, item));
+    }
+    events
+}
+
+/// Writes each event as a line of JSON.
+pub fn write_events(
+    out: &mut impl Write,
+    events: &[Event],
+) -> std::io::Result<()> {
+    for event in events {
+        serde_json::to_writer(&mut *out, event)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Writes an event for every change to the lists in `todo_file`, checking
+/// for them every `interval`, until interrupted.
+pub fn follow(todo_file: &Path, interval: Duration) -> Result<()> {
+    let mut last = modified(todo_file);
+    let mut lists = load(todo_file)?;
+    let mut stdout = std::io::stdout();
+    loop {
+        std::thread::sleep(interval);
+        let now = modified(todo_file);
+        if now == last {
+            continue;
+        }
+        last = now;
+        let fresh = load(todo_file)?;
+        write_events(&mut stdout, &diff(&lists, &fresh))?;
+        stdout.flush()?;
+        lists = fresh;
+    }
+}
@@ -0,0 +1,71 @@
+//! Converters from later lists into other formats.
+
+pub mod ansi_html;
+pub mod markdown;
+pub mod todotxt;
+
+use crate::{ics, RenderOptions, TodoList};
+use std::io::prelude::*;
+
+/// Formats that `later export` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Todotxt,
+    /// Dated items as calendar events.
+    Ics,
+    /// Dated items as calendar to-dos.
+    IcsTodo,
+    /// The list as drawn in the terminal, colors and all, in HTML.
+    AnsiHtml,
+}
+
+impl Format {
+    pub const NAMES: &'static [&'static str] =
+        &["markdown", "todotxt", "ics", "ics-todo", "ansi-html"];
+
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "markdown" | "md" => Some(Format::Markdown),
+            "todotxt" => Some(Format::Todotxt),
+            "ics" | "ical" => Some(Format::Ics),
+            "ics-todo" => Some(Format::IcsTodo),
+            "ansi-html" => Some(Format::AnsiHtml),
+            _ => None,
+        }
+    }
+
+    /// Whether the format can hold several lists at once.
+    pub fn is_calendar(self) -> bool {
+        matches!(self, Format::Ics | Format::IcsTodo)
+    }
+}
+
+/// Writes `list`, and everything in it, in the given format. `opts` are
+/// only used by formats that draw the list as the terminal does.
+pub fn export(
+    format: Format,
+    list: &TodoList,
+    opts: &RenderOptions,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    match format {
+        Format::AnsiHtml => ansi_html::write_list(list, opts, out),
+        Format::Markdown => markdown::write_list(list, out),
+        Format::Todotxt => todotxt::write_list(list, out),
+        Format::Ics | Format::IcsTodo => write_calendar(format, &[list], out),
+    }
+}
+
+/// Writes the dated items of every list in one calendar.
+pub fn write_calendar(
+    format: Format,
+    lists: &[&TodoList],
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    let component = match format {
+        Format::IcsTodo => ics::Component::Todo,
+        _ => ics::Component::Event,
+    };
+    ics::write_calendar(out, lists, component)
+}
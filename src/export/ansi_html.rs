@@ -0,0 +1,248 @@
+//! Lists as HTML that looks like later's colored output: the list is drawn
+//! just as it is in a terminal, then its escape codes are turned into spans
+//! with inline styles, inside a `<pre>` with a dark background. Inline
+//! styles survive being pasted into wikis and emails, where stylesheets
+//! tend to be dropped.
+
+use crate::{RenderOptions, TodoList};
+use std::io::prelude::*;
+
+const BACKGROUND: &str = "#1e1e1e";
+const FOREGROUND: &str = "#d4d4d4";
+
+// black, red, green, yellow, blue, magenta, cyan and white, as xterm has
+// them, then their bright versions
+const BASIC: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Pen {
+    bold: bool,
+    dimmed: bool,
+    italic: bool,
+    underline: bool,
+    strikethrough: bool,
+    reverse: bool,
+    hidden: bool,
+    foreground: Option<(u8, u8, u8)>,
+    background: Option<(u8, u8, u8)>,
+}
+
+impl Pen {
+    fn css(&self) -> String {
+        let hex =
+            |(r, g, b): (u8, u8, u8)| format!("#{:02x}{:02x}{:02x}", r, g, b);
+        let (mut foreground, mut background) =
+            (self.foreground.map(hex), self.background.map(hex));
+        if self.reverse {
+            (foreground, background) = (
+                Some(background.unwrap_or_else(|| String::from(BACKGROUND))),
+                Some(foreground.unwrap_or_else(|| String::from(FOREGROUND))),
+            );
+        }
+        let mut css = Vec::new();
+        if let Some(color) = foreground {
+            css.push(format!("color:{}", color));
+        }
+        if let Some(color) = background {
+            css.push(format!("background-color:{}", color));
+        }
+        if self.bold {
+            css.push(String::from("font-weight:bold"));
+        }
+        if self.dimmed {
+            css.push(String::from("opacity:0.6"));
+        }
+        if self.italic {
+            css.push(String::from("font-style:italic"));
+        }
+        let lines: Vec<&str> = [
+            (self.underline, "underline"),
+            (self.strikethrough, "line-through"),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, line)| *line)
+        .collect();
+        if !lines.is_empty() {
+            css.push(format!("text-decoration:{}", lines.join(" ")));
+        }
+        if self.hidden {
+            css.push(String::from("visibility:hidden"));
+        }
+        css.join(";")
+    }
+
+    // a color given as `38;5;N` or `38;2;R;G;B`, taking its numbers from
+    // `codes`
+    fn extended(codes: &mut impl Iterator<Item = u8>) -> Option<(u8, u8, u8)> {
+        match codes.next()? {
+            5 => Some(fixed(codes.next()?)),
+            2 => Some((codes.next()?, codes.next()?, codes.next()?)),
+            _ => None,
+        }
+    }
+
+    // applies the numbers of one `ESC [ ... m` sequence
+    fn apply(&mut self, codes: &[u8]) {
+        let mut codes = codes.iter().copied();
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = Pen::default(),
+                1 => self.bold = true,
+                2 => self.dimmed = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                7 => self.reverse = true,
+                8 => self.hidden = true,
+                9 => self.strikethrough = true,
+                22 => (self.bold, self.dimmed) = (false, false),
+                23 => self.italic = false,
+                24 => self.underline = false,
+                27 => self.reverse = false,
+                28 => self.hidden = false,
+                29 => self.strikethrough = false,
+                30..=37 => self.foreground = Some(BASIC[code as usize - 30]),
+                38 => self.foreground = Pen::extended(&mut codes),
+                39 => self.foreground = None,
+                40..=47 => self.background = Some(BASIC[code as usize - 40]),
+                48 => self.background = Pen::extended(&mut codes),
+                49 => self.background = None,
+                90..=97 => {
+                    self.foreground = Some(BASIC[code as usize - 90 + 8])
+                }
+                100..=107 => {
+                    self.background = Some(BASIC[code as usize - 100 + 8])
+                }
+                // blinking and the like have no place in a snapshot
+                _ => {}
+            }
+        }
+    }
+}
+
+// one of the 256 colors of `38;5;N`: the 16 basic ones, a 6x6x6 cube,
+// then 24 grays
+fn fixed(n: u8) -> (u8, u8, u8) {
+    match n {
+        0..=15 => BASIC[n as usize],
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let n = n - 16;
+            (level(n / 36), level(n / 6 % 6), level(n % 6))
+        }
+        _ => {
+            let gray = 8 + (n - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+/// Appends `text` to `html`, with the characters HTML gives meaning to
+/// escaped.
+pub(crate) fn escape(text: &str, html: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            c => html.push(c),
+        }
+    }
+}
+
+/// `ansi`, text with SGR escape codes in it, as HTML spans with the same
+/// styles. Any other escape sequence is left out.
+pub fn to_html(ansi: &str) -> String {
+    let mut html = String::with_capacity(ansi.len());
+    let mut pen = Pen::default();
+    let mut open = false;
+    let mut rest = ansi;
+    while !rest.is_empty() {
+        let (text, after) = match rest.find('\x1b') {
+            Some(at) => rest.split_at(at),
+            None => (rest, ""),
+        };
+        if !text.is_empty() {
+            if !open && pen != Pen::default() {
+                html.push_str(&format!("<span style=\"{}\">", pen.css()));
+                open = true;
+            }
+            escape(text, &mut html);
+        }
+        if after.is_empty() {
+            break;
+        }
+        // the sequence runs from `ESC [` to its final letter
+        let body = after[1..].strip_prefix('[').unwrap_or(&after[1..]);
+        let end = match body.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(end) => end,
+            // cut off partway through
+            None => break,
+        };
+        rest = &body[end + 1..];
+        if !body[end..].starts_with('m') {
+            continue;
+        }
+        let codes: Vec<u8> = match body[..end]
+            .split(';')
+            .map(|code| if code.is_empty() { Ok(0) } else { code.parse() })
+            .collect()
+        {
+            Ok(codes) => codes,
+            Err(_) => continue,
+        };
+        let before = pen.clone();
+        pen.apply(&codes);
+        if open && pen != before {
+            html.push_str("</span>");
+            open = false;
+        }
+    }
+    if open {
+        html.push_str("</span>");
+    }
+    html
+}
+
+/// Writes `list` as it would be drawn in the terminal with `opts`, but in
+/// HTML. It's drawn in color whether or not the terminal has it.
+pub fn write_list(
+    list: &TodoList,
+    opts: &RenderOptions,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    let opts = RenderOptions {
+        color: true,
+        hints: false,
+        ..opts.clone()
+    };
+    let mut drawn = Vec::new();
+    list.write_focus(&mut drawn, &opts)?;
+    list.write_to(&mut drawn, 0, &opts)?;
+    writeln!(
+        out,
+        "<pre style=\"background-color:{};color:{};padding:1em;\
+         font-family:monospace\">{}</pre>",
+        BACKGROUND,
+        FOREGROUND,
+        to_html(&String::from_utf8_lossy(&drawn))
+    )
+}
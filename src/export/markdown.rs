@@ -0,0 +1,70 @@
+//! Lists as Markdown: a heading for the list, then nested task list items
+//! (`- [ ]` and `- [x]`) with dates in parentheses, as GitHub and most
+//! notes apps render them.
+
+use crate::{DateMaybeTime, ListItem, TodoList};
+use std::io::prelude::*;
+
+// dates are written out in full, since "tomorrow" wouldn't stay true
+fn date(date: &DateMaybeTime) -> String {
+    match date {
+        DateMaybeTime::Date(date) => date.format("%Y-%m-%d").to_string(),
+        DateMaybeTime::DateTime(datetime) => {
+            datetime.format("%Y-%m-%d %H:%M").to_string()
+        }
+    }
+}
+
+// keeps a title from being read as Markdown syntax
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn titled(title: &str, when: Option<DateMaybeTime>) -> String {
+    match when {
+        Some(when) => format!("{} ({})", escape(title), date(&when)),
+        None => escape(title),
+    }
+}
+
+pub fn write_list(
+    list: &TodoList,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    writeln!(out, "# {}", titled(&list.title, list.date))?;
+    writeln!(out)?;
+    write_items(list, 0, out)
+}
+
+fn write_items(
+    list: &TodoList,
+    depth: usize,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    let indent = "  ".repeat(depth);
+    for item in &list.list {
+        let check = if item.is_done() { "x" } else { " " };
+        writeln!(
+            out,
+            "{}- [{}] {}",
+            indent,
+            check,
+            titled(item.title(), item.date())
+        )?;
+        // continuation lines line up with the text after "- "
+        for line in item.notes().into_iter().flat_map(str::lines) {
+            writeln!(out, "{}  {}", indent, line)?;
+        }
+        if let ListItem::List(sublist) = item {
+            write_items(sublist, depth + 1, out)?;
+        }
+    }
+    Ok(())
+}
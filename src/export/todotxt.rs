@@ -0,0 +1,52 @@
+//! Lists as todo.txt lines, the reverse of `later import --format todotxt`.
+//! todo.txt has no nesting, so sublists are flattened, each followed by
+//! its items.
+
+use crate::{DateMaybeTime, ListItem, TodoList};
+use std::io::prelude::*;
+
+pub fn write_list(
+    list: &TodoList,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    for (_, item) in list.items_with_paths() {
+        writeln!(out, "{}", line(item))?;
+    }
+    Ok(())
+}
+
+fn line(item: &ListItem) -> String {
+    let mut words = Vec::new();
+    if let Some(completed) = item.completed_at() {
+        words.push(String::from("x"));
+        words.push(completed.format("%Y-%m-%d").to_string());
+    }
+    let notes = item.notes().unwrap_or("");
+    // other tags were kept in the notes on import
+    let tags: Vec<(&str, &str)> = notes
+        .lines()
+        .filter_map(|line| line.split_once(": "))
+        .filter(|(key, value)| {
+            key.chars().all(|c| c.is_ascii_alphanumeric())
+                && !value.contains(char::is_whitespace)
+        })
+        .collect();
+    if let (None, Some(priority)) = (item.completed_at(), item.priority()) {
+        words.push(format!("({})", priority));
+    }
+    if let Some(created) = item.created_at() {
+        words.push(created.format("%Y-%m-%d").to_string());
+    }
+    words.push(item.title().replace('\n', " "));
+    if let Some(date) = item.date() {
+        let day = match date {
+            DateMaybeTime::Date(date) => date,
+            DateMaybeTime::DateTime(datetime) => datetime.date().naive_local(),
+        };
+        words.push(format!("due:{}", day.format("%Y-%m-%d")));
+    }
+    for (key, value) in &tags {
+        words.push(format!("{}:{}", key, value));
+    }
+    words.join(" ")
+}
@@ -0,0 +1,240 @@
+//! How dates and times are written out and read back in. Unless
+//! `display.date_format` and `display.time_format` say otherwise, that's
+//! `yyyy/mm/dd` and a 24-hour clock; they take patterns such as
+//! `"dd/mm/yyyy"` and `"12h"`, or strftime patterns such as `"%A %d %B"`,
+//! whose day and month names are shown in the display language.
+
+use crate::config::DisplayConfig;
+use crate::i18n::Lang;
+use anyhow::{bail, Context, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Datelike, NaiveDate, NaiveTime};
+use std::sync::OnceLock;
+
+const DEFAULT_DATE: &str = "%Y/%m/%d";
+const DEFAULT_TIME: &str = "%H:%M";
+
+static FORMATS: OnceLock<Formats> = OnceLock::new();
+
+/// The configured formats, as strftime patterns.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Formats {
+    date: Option<String>,
+    time: Option<String>,
+}
+
+impl Formats {
+    /// The formats `config` gives, checked to write dates and times that
+    /// read back the same.
+    pub fn from_config(config: &DisplayConfig) -> Result<Formats> {
+        let date = config
+            .date_format
+            .as_deref()
+            .map(date_pattern)
+            .transpose()
+            .context("Invalid display.date_format in config")?;
+        let time = config
+            .time_format
+            .as_deref()
+            .map(time_pattern)
+            .transpose()
+            .context("Invalid display.time_format in config")?;
+        Ok(Formats { date, time })
+    }
+}
+
+/// Uses `formats` from here on. Only the first call counts.
+pub fn set(formats: Formats) {
+    let _ = FORMATS.set(formats);
+}
+
+fn configured() -> &'static Formats {
+    static NONE: Formats = Formats {
+        date: None,
+        time: None,
+    };
+    FORMATS.get().unwrap_or(&NONE)
+}
+
+fn is_valid(pattern: &str) -> bool {
+    !StrftimeItems::new(pattern).any(|item| item == Item::Error)
+}
+
+// "dd/mm/yyyy" and the like as strftime, which passes through as it is
+fn date_pattern(format: &str) -> Result<String> {
+    let pattern = if format.contains('%') {
+        String::from(format)
+    } else {
+        format
+            .replace("yyyy", "%Y")
+            .replace("yy", "%y")
+            .replace("mm", "%m")
+            .replace("dd", "%d")
+    };
+    let sample = NaiveDate::from_ymd(2009, 10, 23);
+    if !is_valid(&pattern)
+        || NaiveDate::parse_from_str(
+            &sample.format(&pattern).to_string(),
+            &pattern,
+        )
+        .ok()
+            != Some(sample)
+    {
+        bail!(
+            "{:?} needs a day, month and year, as in \"dd/mm/yyyy\"",
+            format
+        );
+    }
+    Ok(pattern)
+}
+
+fn time_pattern(format: &str) -> Result<String> {
+    let pattern = match format {
+        "24h" => String::from(DEFAULT_TIME),
+        "12h" => String::from("%-I:%M%P"),
+        format if format.contains('%') => String::from(format),
+        _ => bail!("{:?} isn't \"12h\", \"24h\" or a strftime pattern", format),
+    };
+    let sample = NaiveTime::from_hms(17, 45, 0);
+    if !is_valid(&pattern)
+        || NaiveTime::parse_from_str(
+            &sample.format(&pattern).to_string(),
+            &pattern,
+        )
+        .ok()
+            != Some(sample)
+    {
+        bail!("{:?} needs the hour and minute", format);
+    }
+    Ok(pattern)
+}
+
+// `pattern` with its day and month names put in, in the display language,
+// as chrono only knows English ones
+fn localize(pattern: &str, date: NaiveDate) -> String {
+    let lang = Lang::current();
+    let short = |name: &str| name.chars().take(3).collect::<String>();
+    let mut localized = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            localized.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('A') => localized.push_str(lang.weekday(date.weekday())),
+            Some('a') => {
+                localized.push_str(&short(lang.weekday(date.weekday())))
+            }
+            Some('B') => localized.push_str(lang.month(date.month())),
+            Some('b') => localized.push_str(&short(lang.month(date.month()))),
+            Some(other) => {
+                localized.push('%');
+                localized.push(other);
+            }
+            None => localized.push('%'),
+        }
+    }
+    localized
+}
+
+/// `date` in the configured format, `yyyy/mm/dd` by default.
+pub fn date(date: NaiveDate) -> String {
+    let pattern = configured().date.as_deref().unwrap_or(DEFAULT_DATE);
+    date.format(&localize(pattern, date)).to_string()
+}
+
+/// `time` in the configured format, `hh:mm` by default.
+pub fn time(time: NaiveTime) -> String {
+    let pattern = configured().time.as_deref().unwrap_or(DEFAULT_TIME);
+    time.format(pattern).to_string()
+}
+
+/// `date` in the configured format, if one is configured; listings show
+/// far-off dates as a month and day otherwise.
+pub fn custom_date(date: NaiveDate) -> Option<String> {
+    configured().date.as_ref().map(|_| self::date(date))
+}
+
+/// `time` in the configured format, if one is configured.
+pub fn custom_time(time: NaiveTime) -> Option<String> {
+    configured().time.as_ref().map(|_| self::time(time))
+}
+
+/// Reads a date in the configured format, or as `yyyy/mm/dd`, which is
+/// always understood. Day and month names are read in English.
+pub fn parse_date(text: &str) -> Option<NaiveDate> {
+    configured()
+        .date
+        .iter()
+        .map(String::as_str)
+        .chain([DEFAULT_DATE])
+        .find_map(|pattern| NaiveDate::parse_from_str(text, pattern).ok())
+}
+
+/// Reads a time in the configured format, or as `hh:mm`.
+pub fn parse_time(text: &str) -> Option<NaiveTime> {
+    configured()
+        .time
+        .iter()
+        .map(String::as_str)
+        .chain([DEFAULT_TIME])
+        .find_map(|pattern| NaiveTime::parse_from_str(text, pattern).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn dates_read_back(date in testing::day()) {
+            prop_assert_eq!(parse_date(&self::date(date)), Some(date));
+        }
+
+        #[test]
+        fn times_read_back(hour in 0..24u32, minute in 0..60u32) {
+            let time = NaiveTime::from_hms(hour, minute, 0);
+            prop_assert_eq!(parse_time(&self::time(time)), Some(time));
+        }
+
+        #[test]
+        fn patterns_read_back(
+            date in testing::day(),
+            format in prop_oneof![
+                Just("dd/mm/yyyy"),
+                Just("mm-dd-yyyy"),
+                Just("yyyy.mm.dd"),
+                Just("dd.mm.yy"),
+                Just("%d %B %Y"),
+            ],
+            hour in 0..24u32,
+            minute in 0..60u32,
+            clock in prop_oneof![Just("12h"), Just("24h")],
+        ) {
+            let pattern = date_pattern(format).unwrap();
+            let written = date.format(&pattern).to_string();
+            prop_assert_eq!(
+                NaiveDate::parse_from_str(&written, &pattern).ok(),
+                Some(date)
+            );
+            let pattern = time_pattern(clock).unwrap();
+            let time = NaiveTime::from_hms(hour, minute, 0);
+            let written = time.format(&pattern).to_string();
+            prop_assert_eq!(
+                NaiveTime::parse_from_str(&written, &pattern).ok(),
+                Some(time)
+            );
+        }
+    }
+
+    #[test]
+    fn incomplete_patterns_are_refused() {
+        assert!(date_pattern("dd/mm").is_err());
+        assert!(date_pattern("%Q").is_err());
+        assert!(time_pattern("%H").is_err());
+        assert!(time_pattern("noon").is_err());
+    }
+}
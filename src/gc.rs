@@ -0,0 +1,157 @@
+//! `later gc --suggest`: items that are likely just clutter by now, namely
+//! those done long ago, those with no date that nobody has touched in
+//! months, and sublists with nothing left in them, offered for archiving
+//! or deleting a chosen few at a time.
+
+use crate::i18n::Lang;
+use crate::{humanize, ItemPath, ListItem, TodoList};
+use anyhow::{bail, Result};
+use chrono::prelude::*;
+use chrono::Duration;
+use std::collections::HashMap;
+use std::io::prelude::*;
+
+/// Why an item is suggested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    /// Done this long ago.
+    Done(Duration),
+    /// Not done and with no date, and added this long ago.
+    Untouched(Duration),
+    EmptySublist,
+}
+
+impl Reason {
+    fn text(self, lang: Lang) -> String {
+        match self {
+            Reason::Done(age) => {
+                format!("done {}", humanize::relative(lang, -age))
+            }
+            Reason::Untouched(age) => format!(
+                "untouched for {}",
+                humanize::amount(lang, age).unwrap_or_default()
+            ),
+            Reason::EmptySublist => String::from("empty sublist"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub list: String,
+    pub index: ItemPath,
+    pub title: String,
+    pub reason: Reason,
+}
+
+/// How old is old enough.
+#[derive(Debug, Clone, Copy)]
+pub struct Thresholds {
+    pub done: Duration,
+    pub untouched: Duration,
+}
+
+fn reason(
+    item: &ListItem,
+    now: DateTime<Local>,
+    thresholds: Thresholds,
+) -> Option<Reason> {
+    if let Some(done) = item.completed_at() {
+        return (now - done >= thresholds.done)
+            .then_some(Reason::Done(now - done));
+    }
+    match item {
+        ListItem::List(list) if list.items().is_empty() => {
+            Some(Reason::EmptySublist)
+        }
+        // a sublist's items speak for it
+        ListItem::List(_) => None,
+        ListItem::Entry(_) if item.is_done() || item.date().is_some() => None,
+        ListItem::Entry(_) => {
+            // items from before this was kept can't be aged
+            let age = now - item.created_at()?;
+            (age >= thresholds.untouched).then_some(Reason::Untouched(age))
+        }
+    }
+}
+
+/// Every item worth clearing out, by list name and then position. Items
+/// inside a sublist that's suggested are left out, since they'd go with it.
+pub fn suggest(
+    lists: &HashMap<String, TodoList>,
+    now: DateTime<Local>,
+    thresholds: Thresholds,
+) -> Vec<Candidate> {
+    let mut names: Vec<&String> = lists.keys().collect();
+    names.sort();
+    let mut found: Vec<Candidate> = Vec::new();
+    for name in names {
+        for (index, item) in lists[name].items_with_paths() {
+            let inside = found
+                .iter()
+                .any(|c| &c.list == name && index.starts_with(&c.index));
+            if inside {
+                continue;
+            }
+            if let Some(reason) = reason(item, now, thresholds) {
+                found.push(Candidate {
+                    list: name.clone(),
+                    index: ItemPath::from(index),
+                    title: item.title().to_string(),
+                    reason,
+                });
+            }
+        }
+    }
+    found
+}
+
+/// Reads a choice of candidates such as "1,3-5" or "all", numbered from 1,
+/// as positions in the `count` offered. Blank chooses none.
+pub fn parse_picks(text: &str, count: usize) -> Result<Vec<usize>> {
+    let text = text.trim();
+    if text.eq_ignore_ascii_case("all") {
+        return Ok((0..count).collect());
+    }
+    let mut picks = Vec::new();
+    for part in text.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (first, last) = part.split_once('-').unwrap_or((part, part));
+        let (first, last): (usize, usize) =
+            match (first.trim().parse(), last.trim().parse()) {
+                (Ok(first), Ok(last)) if first >= 1 && first <= last => {
+                    (first, last)
+                }
+                _ => bail!("Invalid choice '{}' (e.g. 1,3-5 or all)", part),
+            };
+        if last > count {
+            bail!("There's no suggestion {} (there are {})", last, count);
+        }
+        picks.extend(first - 1..last);
+    }
+    picks.sort_unstable();
+    picks.dedup();
+    Ok(picks)
+}
+
+/// Writes one numbered line per candidate: where it is, its title and why
+/// it's there.
+pub fn write_candidates(
+    out: &mut impl Write,
+    candidates: &[Candidate],
+) -> std::io::Result<()> {
+    let lang = Lang::current();
+    let width = candidates.len().to_string().len();
+    for (n, candidate) in candidates.iter().enumerate() {
+        writeln!(
+            out,
+            "{:>width$}. {} ({}: {}, {})",
+            n + 1,
+            candidate.title,
+            candidate.list,
+            candidate.index,
+            candidate.reason.text(lang),
+            width = width,
+        )?;
+    }
+    Ok(())
+}
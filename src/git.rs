@@ -0,0 +1,166 @@
+//! `later.json` kept in a git repository in the data directory: committed
+//! after every change with `sync.auto_commit`, and synced through a remote
+//! of `{ type = "git", url = "..." }`. Git only carries the file; the lists
+//! are merged by `later sync` like any other remote's, so pushes record the
+//! remote's history as merged in rather than asking git to merge the JSON.
+
+use crate::sync::Backend;
+use anyhow::{bail, Context, Result};
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const FILE: &str = "later.json";
+
+// runs git in `dir`, failing with what it printed if it fails
+fn git(dir: &Path, args: &[&str]) -> Result<Output> {
+    let output = run(dir, args)?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output)
+}
+
+// runs git in `dir`, leaving its exit status to the caller
+fn run(dir: &Path, args: &[&str]) -> Result<Output> {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(dir);
+    // commits need a name, which a machine only syncing lists may not have
+    // set up
+    for (key, fallback) in [("user.name", "later"), ("user.email", "later@")] {
+        let set = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["config", key])
+            .output()
+            .is_ok_and(|output| output.status.success());
+        if !set {
+            command.arg("-c").arg(format!("{}={}", key, fallback));
+        }
+    }
+    command
+        .args(args)
+        .output()
+        .context("Couldn't run git (is it installed?)")
+}
+
+/// Makes `dir` a repository if it isn't one yet, one that ignores
+/// everything but the lists file (backups, history and so on are kept per
+/// machine).
+pub fn ensure_repo(dir: &Path) -> Result<()> {
+    if !dir.join(".git").exists() {
+        git(dir, &["init", "-q"])?;
+        let exclude = dir.join(".git").join("info").join("exclude");
+        std::fs::create_dir_all(exclude.parent().unwrap())
+            .and_then(|_| std::fs::write(&exclude, format!("*\n!{}\n", FILE)))
+            .with_context(|| format!("Couldn't write {}", exclude.display()))?;
+    }
+    Ok(())
+}
+
+/// Commits the lists file in `dir` with `message`, returning whether there
+/// was anything to commit. Nothing else in the folder is added.
+pub fn commit(dir: &Path, message: &str) -> Result<bool> {
+    ensure_repo(dir)?;
+    git(dir, &["add", "--", FILE])?;
+    // exits with 1 if there are staged changes
+    let unchanged = run(dir, &["diff", "--cached", "--quiet", "--", FILE])?
+        .status
+        .success();
+    if unchanged {
+        return Ok(false);
+    }
+    git(dir, &["commit", "-q", "-m", message, "--", FILE])?;
+    Ok(true)
+}
+
+/// A branch of a git repository, reached from the one in the data
+/// directory.
+pub struct Git {
+    dir: PathBuf,
+    url: String,
+    branch: String,
+    // whether FETCH_HEAD is the remote branch as of this sync
+    fetched: Cell<bool>,
+}
+
+impl Git {
+    pub fn new(dir: &Path, url: &str, branch: &str) -> Git {
+        Git {
+            dir: dir.to_path_buf(),
+            url: String::from(url),
+            branch: String::from(branch),
+            fetched: Cell::new(false),
+        }
+    }
+
+    fn refspec(&self) -> String {
+        format!("refs/heads/{}", self.branch)
+    }
+}
+
+impl Backend for Git {
+    fn describe(&self) -> String {
+        format!("git {} ({})", self.url, self.branch)
+    }
+
+    fn fetch(&self) -> Result<Option<String>> {
+        ensure_repo(&self.dir)?;
+        let heads = git(
+            &self.dir,
+            &["ls-remote", "--heads", &self.url, &self.refspec()],
+        )?;
+        // nothing pushed to the branch yet
+        if heads.stdout.is_empty() {
+            return Ok(None);
+        }
+        git(&self.dir, &["fetch", "-q", &self.url, &self.refspec()])?;
+        self.fetched.set(true);
+        let shown = run(&self.dir, &["show", &format!("FETCH_HEAD:{}", FILE)])?;
+        if !shown.status.success() {
+            return Ok(None);
+        }
+        String::from_utf8(shown.stdout)
+            .map(Some)
+            .context("The remote copy isn't valid UTF-8")
+    }
+
+    fn push(&self, data: &str) -> Result<()> {
+        std::fs::write(self.dir.join(FILE), data).with_context(|| {
+            format!("Couldn't write {}", self.dir.join(FILE).display())
+        })?;
+        commit(&self.dir, "later sync")?;
+        if self.fetched.get() {
+            // the file already holds the merged lists, so the remote's
+            // commits only need joining into the history
+            let behind = !run(
+                &self.dir,
+                &["merge-base", "--is-ancestor", "FETCH_HEAD", "HEAD"],
+            )?
+            .status
+            .success();
+            if behind {
+                git(
+                    &self.dir,
+                    &[
+                        "merge",
+                        "-q",
+                        "-s",
+                        "ours",
+                        "--no-edit",
+                        // a machine's first sync starts a history of its own
+                        "--allow-unrelated-histories",
+                        "FETCH_HEAD",
+                    ],
+                )?;
+            }
+        }
+        let to = format!("HEAD:{}", self.refspec());
+        git(&self.dir, &["push", "-q", &self.url, &to])?;
+        Ok(())
+    }
+}
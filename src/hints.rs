@@ -0,0 +1,79 @@
+//! Two-letter codes for items, like a browser's link hints: `later --hints`
+//! labels every item in the list it shows with `aa`, `ab`, ..., and the
+//! next command accepts the code wherever it takes an index, so deep paths
+//! like `2,11,0,4` needn't be typed out.
+
+use crate::TodoList;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+/// The code for the `n`th item shown, counting depth first as the tree is
+/// drawn; items after the 676th get none.
+pub fn code(n: usize) -> Option<String> {
+    let (first, second) = (n / LETTERS.len(), n % LETTERS.len());
+    let first = *LETTERS.get(first)?;
+    Some(String::from_utf8(vec![first, LETTERS[second]]).unwrap())
+}
+
+/// The codes from the last render with hints, kept in `hints.json`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Hints {
+    /// The list the codes were drawn for; they mean nothing in others.
+    pub list: String,
+    pub codes: BTreeMap<String, Vec<usize>>,
+}
+
+impl Hints {
+    /// Codes for `list` in the order the tree draws its items.
+    pub fn for_list(name: &str, list: &TodoList) -> Hints {
+        let codes = list
+            .items_with_paths()
+            .into_iter()
+            .enumerate()
+            .filter_map(|(n, (path, _))| Some((code(n)?, path)))
+            .collect();
+        Hints {
+            list: String::from(name),
+            codes,
+        }
+    }
+
+    /// The codes last drawn for `list`, or none if the last hints were for
+    /// another list or there haven't been any.
+    pub fn load(path: &Path, list: &str) -> Result<Hints> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map(|hints: Hints| {
+                    if hints.list == list {
+                        hints
+                    } else {
+                        Hints::default()
+                    }
+                })
+                .with_context(|| {
+                    format!("Couldn't parse hints ({})", path.display())
+                }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(Hints::default())
+            }
+            Err(e) => Err(e).with_context(|| {
+                format!("Couldn't read hints ({})", path.display())
+            }),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string(self)?).with_context(|| {
+            format!("Couldn't write hints ({})", path.display())
+        })
+    }
+
+    /// The path that `text` stands for, if it's one of the codes.
+    pub fn resolve(&self, text: &str) -> Option<Vec<usize>> {
+        self.codes.get(text.trim()).cloned()
+    }
+}
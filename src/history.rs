@@ -0,0 +1,92 @@
+//! The undo journal: the lists as they were before each change, kept in
+//! `history.json` next to the lists, so `later undo` can put them back and
+//! `later redo` can take them forward again.
+
+use crate::crypt;
+use crate::sync::Snapshot;
+use anyhow::{Context, Result};
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// One change, with the lists as they stood on the other side of it.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Step {
+    /// The command that made the change, e.g. `add`.
+    pub command: String,
+    pub at: DateTime<Local>,
+    pub lists: Snapshot,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct History {
+    /// Oldest first; each holds the lists from before its change.
+    pub undo: Vec<Step>,
+    /// Most recently undone last; each holds the lists from after its
+    /// change.
+    pub redo: Vec<Step>,
+}
+
+impl History {
+    pub fn load(path: &Path) -> Result<History> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&crypt::open(&text)?)
+                .with_context(|| {
+                    format!("Couldn't parse undo history ({})", path.display())
+                }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(History::default())
+            }
+            Err(e) => Err(e).with_context(|| {
+                format!("Couldn't read undo history ({})", path.display())
+            }),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = crypt::seal(&serde_json::to_string(self)?)?;
+        std::fs::write(path, text).with_context(|| {
+            format!("Couldn't write undo history ({})", path.display())
+        })
+    }
+
+    /// Remembers the lists from before a change, keeping at most `depth`
+    /// changes. A new change can't be redone past, so it clears the redo
+    /// steps.
+    pub fn record(&mut self, command: &str, before: Snapshot, depth: usize) {
+        self.undo.push(Step {
+            command: String::from(command),
+            at: crate::clock::now(),
+            lists: before,
+        });
+        let excess = self.undo.len().saturating_sub(depth);
+        self.undo.drain(..excess);
+        self.redo.clear();
+    }
+
+    /// Takes back the latest change, given the lists as they are now, and
+    /// returns the step holding the lists to restore.
+    pub fn undo(&mut self, current: Snapshot) -> Option<Step> {
+        let step = self.undo.pop()?;
+        self.redo.push(Step {
+            command: step.command.clone(),
+            at: step.at,
+            lists: current,
+        });
+        Some(step)
+    }
+
+    /// Reapplies the latest undone change, the reverse of [`undo`].
+    ///
+    /// [`undo`]: History::undo
+    pub fn redo(&mut self, current: Snapshot) -> Option<Step> {
+        let step = self.redo.pop()?;
+        self.undo.push(Step {
+            command: step.command.clone(),
+            at: step.at,
+            lists: current,
+        });
+        Some(step)
+    }
+}
@@ -0,0 +1,71 @@
+use crate::i18n::{Lang, Unit};
+use chrono::Duration;
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 60 * MINUTE;
+const DAY: i64 = 24 * HOUR;
+const WEEK: i64 = 7 * DAY;
+// calendar-agnostic approximations, close enough for "in 3 months"
+const MONTH: i64 = 30 * DAY;
+const YEAR: i64 = 365 * DAY;
+
+const UNITS: [(Unit, i64); 6] = [
+    (Unit::Year, YEAR),
+    (Unit::Month, MONTH),
+    (Unit::Week, WEEK),
+    (Unit::Day, DAY),
+    (Unit::Hour, HOUR),
+    (Unit::Minute, MINUTE),
+];
+
+/// Breaks a duration into at most two adjacent units, e.g. 9 days becomes
+/// `[(1, Week), (2, Day)]`. The smaller unit is dropped once the larger one
+/// reaches 3, since "3 months 1 week" is more precision than anyone reads.
+pub fn parts(duration: Duration) -> Vec<(i64, Unit)> {
+    let seconds = duration.num_seconds().abs();
+    let first = match UNITS.iter().position(|(_, size)| seconds >= *size) {
+        Some(i) => i,
+        None => return Vec::new(),
+    };
+    let (unit, size) = UNITS[first];
+    let count = seconds / size;
+    let mut parts = vec![(count, unit)];
+    if count < 3 {
+        if let Some((next_unit, next_size)) = UNITS.get(first + 1) {
+            let rest = (seconds % size) / next_size;
+            // months don't divide into whole weeks, so skip the noise
+            if rest > 0 && !(unit == Unit::Month && rest >= 4) {
+                parts.push((rest, *next_unit));
+            }
+        }
+    }
+    parts
+}
+
+/// Phrases the length of a duration, e.g. "1 week 2 days", or `None` if
+/// it's under a minute.
+pub fn amount(lang: Lang, duration: Duration) -> Option<String> {
+    let parts = parts(duration);
+    if parts.is_empty() {
+        return None;
+    }
+    let quantities: Vec<String> = parts
+        .iter()
+        .map(|(count, unit)| lang.quantity(*count, *unit))
+        .collect();
+    Some(lang.join(&quantities))
+}
+
+/// Phrases a signed duration relative to now, e.g. "in 1 week 2 days" or
+/// "1 hour ago".
+pub fn relative(lang: Lang, duration: Duration) -> String {
+    let amount = match amount(lang, duration) {
+        Some(amount) => amount,
+        None => return String::from(lang.now()),
+    };
+    if duration < Duration::zero() {
+        lang.in_past(&amount)
+    } else {
+        lang.in_future(&amount)
+    }
+}
@@ -0,0 +1,401 @@
+use chrono::{NaiveDate, Weekday};
+use std::sync::OnceLock;
+
+/// Languages with a translation of the user-facing date vocabulary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    De,
+    Fr,
+    Es,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+    /// Not of time: items in a list, for counts such as "3 items".
+    Item,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Plural {
+    One,
+    Other,
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+impl Lang {
+    /// The language picked from `LATER_LANG`, falling back to the usual
+    /// locale variables (`LC_ALL`, `LC_MESSAGES`, `LANG`) and then English.
+    pub fn current() -> Lang {
+        *LANG.get_or_init(|| {
+            ["LATER_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|var| std::env::var(var).ok())
+                .find(|value| !value.is_empty())
+                .and_then(|value| Lang::from_tag(&value))
+                .unwrap_or(Lang::En)
+        })
+    }
+
+    /// Parses a language tag such as `de`, `fr_FR.UTF-8` or `es-MX`.
+    pub fn from_tag(tag: &str) -> Option<Lang> {
+        let code = tag.split(['_', '-', '.']).next()?.to_lowercase();
+        match code.as_str() {
+            "en" | "c" | "posix" => Some(Lang::En),
+            "de" => Some(Lang::De),
+            "fr" => Some(Lang::Fr),
+            "es" => Some(Lang::Es),
+            _ => None,
+        }
+    }
+
+    fn plural(self, n: i64) -> Plural {
+        match self {
+            // French treats zero as singular
+            Lang::Fr if n == 0 || n == 1 => Plural::One,
+            _ if n == 1 => Plural::One,
+            _ => Plural::Other,
+        }
+    }
+
+    pub fn yesterday(self) -> &'static str {
+        match self {
+            Lang::En => "Yesterday",
+            Lang::De => "Gestern",
+            Lang::Fr => "Hier",
+            Lang::Es => "Ayer",
+        }
+    }
+
+    pub fn today(self) -> &'static str {
+        match self {
+            Lang::En => "Today",
+            Lang::De => "Heute",
+            Lang::Fr => "Aujourd'hui",
+            Lang::Es => "Hoy",
+        }
+    }
+
+    pub fn tomorrow(self) -> &'static str {
+        match self {
+            Lang::En => "Tomorrow",
+            Lang::De => "Morgen",
+            Lang::Fr => "Demain",
+            Lang::Es => "Mañana",
+        }
+    }
+
+    /// Heading for the days after tomorrow in the coming week.
+    pub fn this_week(self) -> &'static str {
+        match self {
+            Lang::En => "This week",
+            Lang::De => "Diese Woche",
+            Lang::Fr => "Cette semaine",
+            Lang::Es => "Esta semana",
+        }
+    }
+
+    /// Heading for anything further off than a week.
+    pub fn further_ahead(self) -> &'static str {
+        match self {
+            Lang::En => "Later",
+            Lang::De => "Später",
+            Lang::Fr => "Plus tard",
+            Lang::Es => "Más adelante",
+        }
+    }
+
+    pub fn overdue(self) -> &'static str {
+        match self {
+            Lang::En => "overdue",
+            Lang::De => "überfällig",
+            Lang::Fr => "en retard",
+            Lang::Es => "atrasado",
+        }
+    }
+
+    /// Marks a hard deadline's date.
+    pub fn deadline(self) -> &'static str {
+        match self {
+            Lang::En => "deadline",
+            Lang::De => "Frist",
+            Lang::Fr => "échéance",
+            Lang::Es => "fecha límite",
+        }
+    }
+
+    /// Heading for hard deadlines, listed apart from the other dates.
+    pub fn deadlines(self) -> &'static str {
+        match self {
+            Lang::En => "Deadlines",
+            Lang::De => "Fristen",
+            Lang::Fr => "Échéances",
+            Lang::Es => "Fechas límite",
+        }
+    }
+
+    pub fn nothing_due(self) -> &'static str {
+        match self {
+            Lang::En => "Nothing due.",
+            Lang::De => "Nichts fällig.",
+            Lang::Fr => "Rien à faire.",
+            Lang::Es => "Nada pendiente.",
+        }
+    }
+
+    /// e.g. "upcoming Friday"
+    pub fn upcoming(self, weekday: Weekday) -> String {
+        let day = self.weekday(weekday);
+        match self {
+            Lang::En => format!("upcoming {}", day),
+            Lang::De => format!("kommenden {}", day),
+            Lang::Fr => format!("{} prochain", day),
+            Lang::Es => format!("el próximo {}", day),
+        }
+    }
+
+    /// e.g. "recent Monday"
+    pub fn recent(self, weekday: Weekday) -> String {
+        let day = self.weekday(weekday);
+        match self {
+            Lang::En => format!("recent {}", day),
+            Lang::De => format!("letzten {}", day),
+            Lang::Fr => format!("{} dernier", day),
+            Lang::Es => format!("el pasado {}", day),
+        }
+    }
+
+    /// A count of some unit, e.g. "3 weeks" or "1 day".
+    pub fn quantity(self, n: i64, unit: Unit) -> String {
+        format!("{} {}", n, self.unit(unit, self.plural(n)))
+    }
+
+    /// Joins quantities into one duration, e.g. "1 week 2 days".
+    pub fn join(self, quantities: &[String]) -> String {
+        let separator = match self {
+            Lang::En => " ",
+            Lang::De => " und ",
+            Lang::Fr => " et ",
+            Lang::Es => " y ",
+        };
+        quantities.join(separator)
+    }
+
+    /// A moment too close to the present to measure.
+    pub fn now(self) -> &'static str {
+        match self {
+            Lang::En => "now",
+            Lang::De => "jetzt",
+            Lang::Fr => "maintenant",
+            Lang::Es => "ahora",
+        }
+    }
+
+    /// A duration in the future, e.g. "in 3 weeks".
+    pub fn in_future(self, amount: &str) -> String {
+        match self {
+            Lang::En | Lang::De => format!("in {}", amount),
+            Lang::Fr => format!("dans {}", amount),
+            Lang::Es => format!("en {}", amount),
+        }
+    }
+
+    /// A duration in the past, e.g. "3 weeks ago".
+    pub fn in_past(self, amount: &str) -> String {
+        match self {
+            Lang::En => format!("{} ago", amount),
+            Lang::De => format!("vor {}", amount),
+            Lang::Fr => format!("il y a {}", amount),
+            Lang::Es => format!("hace {}", amount),
+        }
+    }
+
+    /// Month and day, with the year only when `with_year` is set.
+    pub fn month_day(self, date: NaiveDate, with_year: bool) -> String {
+        use chrono::Datelike;
+        let month = self.month(date.month());
+        let (day, year) = (date.day(), date.year());
+        match (self, with_year) {
+            (Lang::En, false) => format!("{} {:02}", month, day),
+            (Lang::En, true) => format!("{} {:02} {}", month, day, year),
+            (Lang::De, false) => format!("{:02}. {}", day, month),
+            (Lang::De, true) => format!("{:02}. {} {}", day, month, year),
+            (Lang::Fr, false) => format!("{:02} {}", day, month),
+            (Lang::Fr, true) => format!("{:02} {} {}", day, month, year),
+            (Lang::Es, false) => format!("{:02} de {}", day, month),
+            (Lang::Es, true) => {
+                format!("{:02} de {} de {}", day, month, year)
+            }
+        }
+    }
+
+    pub fn weekday(self, weekday: Weekday) -> &'static str {
+        let names = match self {
+            Lang::En => [
+                "Monday",
+                "Tuesday",
+                "Wednesday",
+                "Thursday",
+                "Friday",
+                "Saturday",
+                "Sunday",
+            ],
+            Lang::De => [
+                "Montag",
+                "Dienstag",
+                "Mittwoch",
+                "Donnerstag",
+                "Freitag",
+                "Samstag",
+                "Sonntag",
+            ],
+            Lang::Fr => [
+                "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi",
+                "dimanche",
+            ],
+            Lang::Es => [
+                "lunes",
+                "martes",
+                "miércoles",
+                "jueves",
+                "viernes",
+                "sábado",
+                "domingo",
+            ],
+        };
+        names[weekday.num_days_from_monday() as usize]
+    }
+
+    /// Name of a month, numbered from 1.
+    pub fn month(self, month: u32) -> &'static str {
+        let names = match self {
+            Lang::En => [
+                "January",
+                "February",
+                "March",
+                "April",
+                "May",
+                "June",
+                "July",
+                "August",
+                "September",
+                "October",
+                "November",
+                "December",
+            ],
+            Lang::De => [
+                "Januar",
+                "Februar",
+                "März",
+                "April",
+                "Mai",
+                "Juni",
+                "Juli",
+                "August",
+                "September",
+                "Oktober",
+                "November",
+                "Dezember",
+            ],
+            Lang::Fr => [
+                "janvier",
+                "février",
+                "mars",
+                "avril",
+                "mai",
+                "juin",
+                "juillet",
+                "août",
+                "septembre",
+                "octobre",
+                "novembre",
+                "décembre",
+            ],
+            Lang::Es => [
+                "enero",
+                "febrero",
+                "marzo",
+                "abril",
+                "mayo",
+                "junio",
+                "julio",
+                "agosto",
+                "septiembre",
+                "octubre",
+                "noviembre",
+                "diciembre",
+            ],
+        };
+        names[(month as usize - 1) % 12]
+    }
+
+    fn unit(self, unit: Unit, plural: Plural) -> &'static str {
+        use Plural::*;
+        use Unit::*;
+        match (self, unit, plural) {
+            (Lang::En, Minute, One) => "minute",
+            (Lang::En, Minute, Other) => "minutes",
+            (Lang::En, Hour, One) => "hour",
+            (Lang::En, Hour, Other) => "hours",
+            (Lang::En, Day, One) => "day",
+            (Lang::En, Day, Other) => "days",
+            (Lang::En, Week, One) => "week",
+            (Lang::En, Week, Other) => "weeks",
+            (Lang::En, Month, One) => "month",
+            (Lang::En, Month, Other) => "months",
+            (Lang::En, Year, One) => "year",
+            (Lang::En, Year, Other) => "years",
+            (Lang::En, Item, One) => "item",
+            (Lang::En, Item, Other) => "items",
+            // German durations follow "in"/"vor", so take the dative
+            (Lang::De, Minute, One) => "Minute",
+            (Lang::De, Minute, Other) => "Minuten",
+            (Lang::De, Hour, One) => "Stunde",
+            (Lang::De, Hour, Other) => "Stunden",
+            (Lang::De, Day, One) => "Tag",
+            (Lang::De, Day, Other) => "Tagen",
+            (Lang::De, Week, One) => "Woche",
+            (Lang::De, Week, Other) => "Wochen",
+            (Lang::De, Month, One) => "Monat",
+            (Lang::De, Month, Other) => "Monaten",
+            (Lang::De, Year, One) => "Jahr",
+            (Lang::De, Year, Other) => "Jahren",
+            (Lang::De, Item, One) => "Eintrag",
+            (Lang::De, Item, Other) => "Einträge",
+            (Lang::Fr, Minute, One) => "minute",
+            (Lang::Fr, Minute, Other) => "minutes",
+            (Lang::Fr, Hour, One) => "heure",
+            (Lang::Fr, Hour, Other) => "heures",
+            (Lang::Fr, Day, One) => "jour",
+            (Lang::Fr, Day, Other) => "jours",
+            (Lang::Fr, Week, One) => "semaine",
+            (Lang::Fr, Week, Other) => "semaines",
+            (Lang::Fr, Month, _) => "mois",
+            (Lang::Fr, Year, One) => "an",
+            (Lang::Fr, Year, Other) => "ans",
+            (Lang::Fr, Item, One) => "élément",
+            (Lang::Fr, Item, Other) => "éléments",
+            (Lang::Es, Minute, One) => "minuto",
+            (Lang::Es, Minute, Other) => "minutos",
+            (Lang::Es, Hour, One) => "hora",
+            (Lang::Es, Hour, Other) => "horas",
+            (Lang::Es, Day, One) => "día",
+            (Lang::Es, Day, Other) => "días",
+            (Lang::Es, Week, One) => "semana",
+            (Lang::Es, Week, Other) => "semanas",
+            (Lang::Es, Month, One) => "mes",
+            (Lang::Es, Month, Other) => "meses",
+            (Lang::Es, Year, One) => "año",
+            (Lang::Es, Year, Other) => "años",
+            (Lang::Es, Item, One) => "elemento",
+            (Lang::Es, Item, Other) => "elementos",
+        }
+    }
+}
@@ -0,0 +1,298 @@
+//! iCalendar (RFC 5545) output for dated items.
+
+use crate::{DateMaybeTime, ListItem, TodoList};
+use chrono::prelude::*;
+use ring::digest;
+use std::collections::HashMap;
+use std::io::prelude::*;
+
+// content lines are folded at 75 octets
+const LINE_LIMIT: usize = 75;
+
+/// One unfolded content line, e.g. `DTSTART;TZID=Europe/Paris:2024...`.
+#[derive(Debug, Clone)]
+pub struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl Property {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The value as a date, or a date and time. UTC times are converted to
+    /// local time; times with a `TZID` or no zone at all are taken to be
+    /// local already.
+    pub fn date(&self) -> Option<DateMaybeTime> {
+        parse_date(&self.value, self.param("VALUE") == Some("DATE"))
+    }
+}
+
+/// Splits calendar text into properties, joining folded lines back up.
+/// Lines that aren't `NAME[;PARAM=...]:VALUE` are skipped.
+pub fn parse(text: &str) -> Vec<Property> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+        .iter()
+        .filter_map(|line| parse_property(line))
+        .collect()
+}
+
+fn parse_property(line: &str) -> Option<Property> {
+    // split on ';' and ':' outside of quoted parameter values
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut value_start = None;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                fields.push(&line[start..i]);
+                start = i + 1;
+            }
+            ':' if !quoted => {
+                fields.push(&line[start..i]);
+                value_start = Some(i + 1);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let value = &line[value_start?..];
+    let (name, params) = fields.split_first()?;
+    if name.is_empty() {
+        return None;
+    }
+    let params = params
+        .iter()
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((key.to_uppercase(), value.trim_matches('"').to_string()))
+        })
+        .collect();
+    Some(Property {
+        name: name.to_uppercase(),
+        params,
+        value: value.to_string(),
+    })
+}
+
+/// Reverses [`escape`].
+pub fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Parses `20240131`, `20240131T090000` or `20240131T090000Z`.
+pub fn parse_date(value: &str, date_only: bool) -> Option<DateMaybeTime> {
+    let value = value.trim();
+    if date_only || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d");
+        return date.ok().map(DateMaybeTime::Date);
+    }
+    let (value, utc) = match value.strip_suffix(['Z', 'z']) {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let datetime =
+        NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let local = if utc {
+        Utc.from_utc_datetime(&datetime).with_timezone(&Local)
+    } else {
+        Local.from_local_datetime(&datetime).earliest()?
+    };
+    Some(DateMaybeTime::DateTime(local))
+}
+
+/// Escapes TEXT values: backslashes, separators and newlines.
+pub fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Writes one content line, folding it onto continuation lines as needed
+/// without splitting UTF-8 sequences.
+pub fn write_line(out: &mut impl Write, line: &str) -> std::io::Result<()> {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > LINE_LIMIT {
+            out.write_all(b"\r\n ")?;
+            // the leading space counts towards the next line
+            width = 1;
+        }
+        write!(out, "{}", c)?;
+        width += c.len_utf8();
+    }
+    out.write_all(b"\r\n")
+}
+
+/// Formats a date as a `DTSTART`/`DUE`-style property: all-day dates as
+/// `VALUE=DATE`, timed ones in UTC.
+pub fn date_property(name: &str, date: DateMaybeTime) -> String {
+    match date {
+        DateMaybeTime::Date(date) => {
+            format!("{};VALUE=DATE:{}", name, date.format("%Y%m%d"))
+        }
+        DateMaybeTime::DateTime(datetime) => format!(
+            "{}:{}",
+            name,
+            datetime.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ")
+        ),
+    }
+}
+
+/// What each dated item becomes: an event, for calendar apps, or a to-do
+/// with a due date and status, for task apps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    Event,
+    Todo,
+}
+
+impl Component {
+    fn name(self) -> &'static str {
+        match self {
+            Component::Event => "VEVENT",
+            Component::Todo => "VTODO",
+        }
+    }
+}
+
+// from when the item was made and its title, so it stays the same as the
+// item moves around and calendar apps update it rather than add another
+fn uid(item: &ListItem) -> String {
+    let created = item.created_at().map(|at| at.to_rfc3339());
+    let key = format!("{}\n{}", created.unwrap_or_default(), item.title());
+    let hash = digest::digest(&digest::SHA256, key.as_bytes());
+    hash.as_ref()[..16]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Writes every dated item in `lists` as a `VEVENT` or `VTODO` in one
+/// calendar, with the list name and the item's tags as its categories.
+pub fn write_calendar(
+    out: &mut impl Write,
+    lists: &[&TodoList],
+    component: Component,
+) -> std::io::Result<()> {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    write_line(out, "BEGIN:VCALENDAR")?;
+    write_line(out, "VERSION:2.0")?;
+    write_line(out, "PRODID:-//later//later to-do lists//EN")?;
+    write_line(out, "X-WR-CALNAME:later")?;
+
+    let mut lists = lists.to_vec();
+    lists.sort_by(|a, b| a.title.cmp(&b.title));
+    // copies of an item share its UID, so later ones are numbered
+    let mut uids: HashMap<String, usize> = HashMap::new();
+    for list in lists {
+        let name = &list.title;
+        for (path, item) in list.items_with_paths() {
+            let date = match item.date() {
+                Some(date) => date,
+                None => continue,
+            };
+            let inherited = list.inherited_tags(&path);
+            let mut uid = uid(item);
+            let copies = uids.entry(uid.clone()).or_default();
+            *copies += 1;
+            if *copies > 1 {
+                uid = format!("{}-{}", uid, copies);
+            }
+            write_line(out, &format!("BEGIN:{}", component.name()))?;
+            write_line(out, &format!("UID:{}@later", uid))?;
+            write_line(out, &format!("DTSTAMP:{}", stamp))?;
+            match component {
+                Component::Event => {
+                    write_line(out, &date_property("DTSTART", date))?
+                }
+                Component::Todo => {
+                    write_line(out, &date_property("DUE", date))?;
+                    write_todo_status(out, item)?;
+                }
+            }
+            write_line(out, &format!("SUMMARY:{}", escape(item.title())))?;
+            let categories: Vec<String> = std::iter::once(name.as_str())
+                .chain(inherited.iter().copied())
+                .chain(
+                    item.tags()
+                        .iter()
+                        .map(String::as_str)
+                        .filter(|tag| !inherited.contains(tag)),
+                )
+                .map(escape)
+                .collect();
+            write_line(out, &format!("CATEGORIES:{}", categories.join(",")))?;
+            match (item.notes(), item) {
+                (Some(notes), _) => {
+                    write_line(out, &format!("DESCRIPTION:{}", escape(notes)))?
+                }
+                (None, ListItem::List(_)) => {
+                    write_line(out, "DESCRIPTION:Sublist")?
+                }
+                (None, ListItem::Entry(_)) => {}
+            }
+            write_line(out, &format!("END:{}", component.name()))?;
+        }
+    }
+    write_line(out, "END:VCALENDAR")
+}
+
+// whether it's done, and how much it matters: iCalendar priorities run
+// from 1 (most) to 9, and high, medium and low are usually 1, 5 and 9
+fn write_todo_status(
+    out: &mut impl Write,
+    item: &ListItem,
+) -> std::io::Result<()> {
+    match item.completed_at() {
+        Some(completed) => {
+            write_line(out, "STATUS:COMPLETED")?;
+            write_line(
+                out,
+                &format!(
+                    "COMPLETED:{}",
+                    completed.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ")
+                ),
+            )?;
+        }
+        None => write_line(out, "STATUS:NEEDS-ACTION")?,
+    }
+    if let Some(priority) = item.priority() {
+        let level = match priority.letter() {
+            'A' => 1,
+            'B' => 5,
+            _ => 9,
+        };
+        write_line(out, &format!("PRIORITY:{}", level))?;
+    }
+    Ok(())
+}
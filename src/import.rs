@@ -0,0 +1,55 @@
+//! Converters from other to-do and calendar formats into later items.
+
+pub mod eml;
+pub mod ics;
+pub mod json;
+pub mod todotxt;
+
+use crate::ListItem;
+use anyhow::{bail, Result};
+use std::path::Path;
+
+/// Formats that `later import` understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Eml,
+    Ics,
+    /// Any JSON, described by a mapping file.
+    Json,
+    Todotxt,
+}
+
+impl Format {
+    pub const NAMES: &'static [&'static str] =
+        &["eml", "ics", "json", "todotxt"];
+
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "eml" => Some(Format::Eml),
+            "ics" => Some(Format::Ics),
+            "json" => Some(Format::Json),
+            "todotxt" => Some(Format::Todotxt),
+            _ => None,
+        }
+    }
+}
+
+/// Reads `path` in the given format and returns the items it describes.
+/// Formats without a fixed layout need a `mapping` file to explain it.
+pub fn import(
+    format: Format,
+    path: &Path,
+    mapping: Option<&Path>,
+) -> Result<Vec<ListItem>> {
+    match (format, mapping) {
+        (Format::Eml, _) => eml::import(path),
+        (Format::Ics, _) => ics::import(path),
+        (Format::Todotxt, _) => todotxt::import(path),
+        (Format::Json, Some(mapping)) => {
+            json::import(path, &json::Mapping::load(mapping)?)
+        }
+        (Format::Json, None) => {
+            bail!("Importing JSON needs a mapping file (see --map)")
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! Emails (single `.eml` files or maildirs) as entries: the subject becomes
+//! the title, the body the notes, and a "by Friday"-style deadline in the
+//! subject or body, or failing that the Date header, becomes the due date.
+
+use crate::{DateMaybeTime, ListItem, TodoEntry};
+use anyhow::{Context, Result};
+use chrono::{prelude::*, Duration};
+use std::path::{Path, PathBuf};
+
+/// The parts of an email that matter for turning it into a task.
+#[derive(Debug, Default)]
+pub struct Email {
+    pub subject: String,
+    pub date: Option<NaiveDate>,
+    pub body: String,
+}
+
+pub fn import(path: &Path) -> Result<Vec<ListItem>> {
+    let files = if path.is_dir() {
+        maildir_files(path)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+    let today = crate::clock::today();
+    files
+        .iter()
+        .map(|file| {
+            let raw = std::fs::read(file).with_context(|| {
+                format!("Couldn't read email ({})", file.display())
+            })?;
+            let email = parse(&String::from_utf8_lossy(&raw));
+            let due = find_deadline(&email.subject, today)
+                .or_else(|| find_deadline(&email.body, today))
+                .or(email.date);
+            let title = if email.subject.is_empty() {
+                String::from("(no subject)")
+            } else {
+                email.subject
+            };
+            let mut entry =
+                TodoEntry::from_info(title, due.map(DateMaybeTime::Date));
+            let body = email.body.trim();
+            if !body.is_empty() {
+                entry.notes = Some(String::from(body));
+            }
+            Ok(ListItem::Entry(entry))
+        })
+        .collect()
+}
+
+// messages in a maildir's cur/ and new/, or else every file in the directory
+fn maildir_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let subdirs: Vec<PathBuf> = ["cur", "new"]
+        .iter()
+        .map(|sub| dir.join(sub))
+        .filter(|sub| sub.is_dir())
+        .collect();
+    let dirs = if subdirs.is_empty() {
+        vec![dir.to_path_buf()]
+    } else {
+        subdirs
+    };
+    let mut files = Vec::new();
+    for dir in dirs {
+        for entry in std::fs::read_dir(&dir)
+            .with_context(|| format!("Couldn't read {}", dir.display()))?
+        {
+            let path = entry?.path();
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Parses the headers and plain-text body of an RFC 5322 message.
+pub fn parse(raw: &str) -> Email {
+    let raw = raw.replace("\r\n", "\n");
+    let (head, body) = raw.split_once("\n\n").unwrap_or((&raw, ""));
+    let headers = parse_headers(head);
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    };
+    Email {
+        subject: header("Subject").map(decode_words).unwrap_or_default(),
+        date: header("Date")
+            .and_then(|d| DateTime::parse_from_rfc2822(d.trim()).ok())
+            .map(|d| d.with_timezone(&Local).date().naive_local()),
+        body: text_body(&headers, body),
+    }
+}
+
+// unfolds continuation lines into (name, value) pairs
+fn parse_headers(head: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in head.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+fn header_param(value: &str, param: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|part| {
+        let (k, v) = part.trim().split_once('=')?;
+        k.eq_ignore_ascii_case(param)
+            .then(|| v.trim_matches('"').to_string())
+    })
+}
+
+// the first text/plain part of the body, transfer-decoded
+fn text_body(headers: &[(String, String)], body: &str) -> String {
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+            .unwrap_or("")
+    };
+    let content_type = header("Content-Type").to_lowercase();
+    if content_type.starts_with("multipart/") {
+        let boundary = match header_param(header("Content-Type"), "boundary") {
+            Some(b) => format!("--{}", b),
+            None => return String::new(),
+        };
+        return body
+            .split(&boundary)
+            .skip(1)
+            .filter_map(|part| {
+                let part = part.strip_prefix('\n').unwrap_or(part);
+                let (head, body) = part.split_once("\n\n")?;
+                let part_headers = parse_headers(head);
+                let text = text_body(&part_headers, body);
+                (!text.is_empty()).then_some(text)
+            })
+            .next()
+            .unwrap_or_default();
+    }
+    if !(content_type.is_empty() || content_type.starts_with("text/plain")) {
+        return String::new();
+    }
+    let encoding = header("Content-Transfer-Encoding").to_lowercase();
+    let bytes = match encoding.trim() {
+        "base64" => base64_decode(body),
+        "quoted-printable" => quoted_printable_decode(body),
+        _ => body.as_bytes().to_vec(),
+    };
+    String::from_utf8_lossy(&bytes).trim().to_string()
+}
+
+// RFC 2047 encoded words, e.g. =?UTF-8?B?...?= or =?UTF-8?Q?...?=
+fn decode_words(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("=?") {
+        let encoded = &rest[start + 2..];
+        let parts: Vec<&str> = encoded.splitn(4, '?').collect();
+        let end = match (parts.as_slice(), encoded.find("?=")) {
+            ([_, _, _, tail], Some(_)) if tail.starts_with('=') => {
+                parts[0].len() + parts[1].len() + parts[2].len() + 3
+            }
+            _ => {
+                out.push_str(&rest[..start + 2]);
+                rest = &rest[start + 2..];
+                continue;
+            }
+        };
+        let before = &rest[..start];
+        // whitespace between adjacent encoded words is dropped
+        if out.is_empty() || !before.trim().is_empty() {
+            out.push_str(before);
+        }
+        let text = parts[2];
+        let bytes = if parts[1].eq_ignore_ascii_case("b") {
+            base64_decode(text)
+        } else {
+            quoted_printable_decode(&text.replace('_', " "))
+        };
+        out.push_str(&String::from_utf8_lossy(&bytes));
+        rest = &encoded[end + 1..];
+    }
+    out.push_str(rest);
+    out.trim().to_string()
+}
+
+fn base64_decode(s: &str) -> Vec<u8> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    };
+    let mut out = Vec::new();
+    let (mut buffer, mut bits) = (0u32, 0);
+    for v in s.bytes().filter_map(value) {
+        buffer = (buffer << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    out
+}
+
+fn quoted_printable_decode(s: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'=' {
+            // soft line break
+            if bytes.get(i + 1) == Some(&b'\n') {
+                i += 2;
+                continue;
+            }
+            let escaped = bytes
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if let Some(b) = escaped {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+/// Finds a deadline phrase like "by Friday", "by tomorrow" or
+/// "by 2022/07/01" in free text, relative to `today`.
+pub fn find_deadline(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| c.is_whitespace() || ",.;:!?()".contains(c))
+        .filter(|w| !w.is_empty())
+        .collect();
+    words.windows(2).find_map(|pair| match pair {
+        ["by" | "before" | "due", when] => parse_when(when, today),
+        _ => None,
+    })
+}
+
+fn parse_when(word: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match word {
+        "today" | "tonight" | "eod" => return Some(today),
+        "tomorrow" => return Some(today + Duration::days(1)),
+        _ => {}
+    }
+    let weekdays = [
+        "monday",
+        "tuesday",
+        "wednesday",
+        "thursday",
+        "friday",
+        "saturday",
+        "sunday",
+    ];
+    if let Some(n) = weekdays
+        .iter()
+        .position(|d| word == *d || (word.len() == 3 && d.starts_with(word)))
+    {
+        let today_n = today.weekday().num_days_from_monday() as i64;
+        let ahead = (n as i64 - today_n).rem_euclid(7);
+        return Some(today + Duration::days(ahead));
+    }
+    NaiveDate::parse_from_str(word, "%Y/%m/%d")
+        .or_else(|_| NaiveDate::parse_from_str(word, "%Y-%m-%d"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use base64::engine::general_purpose::STANDARD as BASE64;
+    use base64::Engine;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn headers_and_deadline(
+            subject in testing::title(),
+            sent in testing::day(),
+            due in testing::day(),
+            today in testing::day(),
+            dashes in any::<bool>(),
+        ) {
+            let date = Local
+                .from_local_datetime(&sent.and_hms(12, 0, 0))
+                .earliest()
+                .unwrap();
+            let format = if dashes { "%Y-%m-%d" } else { "%Y/%m/%d" };
+            // the subject folded onto a second line
+            let raw = format!(
+                "Subject: {}\r\nDate: {}\r\n\r\nCould you do it by {}?\r\n",
+                subject.replacen(' ', "\r\n ", 1),
+                date.to_rfc2822(),
+                due.format(format),
+            );
+            let email = parse(&raw);
+            prop_assert_eq!(&email.subject, &subject);
+            prop_assert_eq!(email.date, Some(sent));
+            prop_assert_eq!(find_deadline(&email.body, today), Some(due));
+        }
+
+        #[test]
+        fn encoded_subjects(subject in "\\PC{1,30}", q in any::<bool>()) {
+            let encoded = if q {
+                let hex: String = subject
+                    .bytes()
+                    .map(|byte| format!("={:02X}", byte))
+                    .collect();
+                format!("=?UTF-8?Q?{}?=", hex)
+            } else {
+                format!("=?UTF-8?B?{}?=", BASE64.encode(subject.as_bytes()))
+            };
+            let email = parse(&format!("Subject: {}\n\nbody", encoded));
+            prop_assert_eq!(email.subject, subject.trim());
+        }
+
+        #[test]
+        fn weekday_deadlines_are_this_week(
+            today in testing::day(),
+            n in 0..7u32,
+        ) {
+            let day = today + Duration::days(n as i64);
+            let name = day.format("%A").to_string();
+            let found = find_deadline(&format!("due {}", name), today);
+            prop_assert_eq!(found, Some(day));
+        }
+    }
+}
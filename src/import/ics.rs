@@ -0,0 +1,377 @@
+//! iCalendar files (class timetables, meeting series, exported task lists)
+//! as entries: each `VEVENT` and open `VTODO` becomes an entry due at its
+//! start (or, for tasks, its `DUE` date) with its `DESCRIPTION` as notes,
+//! and recurring events become one entry per occurrence.
+
+use crate::ics::{self, Property};
+use crate::{DateMaybeTime, ListItem, TodoEntry};
+use anyhow::{Context, Result};
+use chrono::{prelude::*, Duration};
+use std::path::Path;
+
+// series without a COUNT or UNTIL are only expanded this far past today
+const OPEN_ENDED_DAYS: i64 = 365;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Event,
+    Todo,
+}
+
+#[derive(Debug)]
+struct Component {
+    kind: Kind,
+    properties: Vec<Property>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The subset of an `RRULE` that is worth expanding into separate entries.
+#[derive(Debug)]
+struct Recurrence {
+    frequency: Frequency,
+    interval: u32,
+    count: Option<usize>,
+    until: Option<NaiveDate>,
+    weekdays: Vec<Weekday>,
+}
+
+pub fn import(path: &Path) -> Result<Vec<ListItem>> {
+    let text = std::fs::read_to_string(path).with_context(|| {
+        format!("Couldn't read calendar ({})", path.display())
+    })?;
+    let today = crate::clock::today();
+    Ok(components(ics::parse(&text))
+        .iter()
+        .flat_map(|component| component.entries(today))
+        .map(ListItem::Entry)
+        .collect())
+}
+
+// top-level events and tasks; properties of nested components such as
+// VALARM are left out
+fn components(properties: Vec<Property>) -> Vec<Component> {
+    let mut components = Vec::new();
+    let mut current: Option<Component> = None;
+    let mut nesting = 0;
+    for property in properties {
+        let value = property.value.to_uppercase();
+        match (property.name.as_str(), &mut current) {
+            ("BEGIN", None) => {
+                let kind = match value.as_str() {
+                    "VEVENT" => Kind::Event,
+                    "VTODO" => Kind::Todo,
+                    _ => continue,
+                };
+                current = Some(Component {
+                    kind,
+                    properties: Vec::new(),
+                });
+            }
+            ("BEGIN", Some(_)) => nesting += 1,
+            ("END", Some(_)) if nesting > 0 => nesting -= 1,
+            ("END", Some(_)) => components.extend(current.take()),
+            (_, Some(component)) if nesting == 0 => {
+                component.properties.push(property)
+            }
+            _ => {}
+        }
+    }
+    components
+}
+
+impl Component {
+    fn get(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+
+    fn entries(&self, today: NaiveDate) -> Vec<TodoEntry> {
+        let status = self.get("STATUS").map(|p| p.value.to_uppercase());
+        if matches!(status.as_deref(), Some("COMPLETED" | "CANCELLED")) {
+            return Vec::new();
+        }
+        let title = match self.get("SUMMARY") {
+            Some(summary) if !summary.value.trim().is_empty() => {
+                ics::unescape(summary.value.trim())
+            }
+            _ => String::from("(untitled)"),
+        };
+        let start = match self.kind {
+            Kind::Event => self.get("DTSTART"),
+            Kind::Todo => self.get("DUE").or_else(|| self.get("DTSTART")),
+        }
+        .and_then(Property::date);
+
+        let recurrence = self
+            .get("RRULE")
+            .and_then(|rule| Recurrence::parse(&rule.value));
+        let dates = match (start, recurrence) {
+            (Some(start), Some(recurrence)) => {
+                let excluded: Vec<NaiveDate> = self
+                    .properties
+                    .iter()
+                    .filter(|p| p.name == "EXDATE")
+                    .flat_map(|p| p.value.split(','))
+                    .filter_map(|value| ics::parse_date(value, false))
+                    .map(|date| day_of(&date))
+                    .collect();
+                recurrence
+                    .occurrences(day_of(&start), today)
+                    .into_iter()
+                    .filter(|day| !excluded.contains(day))
+                    .filter_map(|day| move_to(&start, day))
+                    .map(Some)
+                    .collect()
+            }
+            (start, _) => vec![start],
+        };
+        let notes = self
+            .get("DESCRIPTION")
+            .map(|description| ics::unescape(description.value.trim()))
+            .filter(|notes| !notes.is_empty());
+        dates
+            .into_iter()
+            .map(|date| {
+                let mut entry = TodoEntry::from_info(title.clone(), date);
+                entry.notes = notes.clone();
+                entry
+            })
+            .collect()
+    }
+}
+
+fn day_of(date: &DateMaybeTime) -> NaiveDate {
+    match date {
+        DateMaybeTime::Date(date) => *date,
+        DateMaybeTime::DateTime(datetime) => datetime.naive_local().date(),
+    }
+}
+
+// the same time of day as `start`, on another day
+fn move_to(start: &DateMaybeTime, day: NaiveDate) -> Option<DateMaybeTime> {
+    match start {
+        DateMaybeTime::Date(_) => Some(DateMaybeTime::Date(day)),
+        DateMaybeTime::DateTime(datetime) => Local
+            .from_local_datetime(&day.and_time(datetime.time()))
+            .earliest()
+            .map(DateMaybeTime::DateTime),
+    }
+}
+
+impl Recurrence {
+    /// Parses an `RRULE` value, returning `None` for frequencies finer than
+    /// a day.
+    fn parse(rule: &str) -> Option<Recurrence> {
+        let mut recurrence = Recurrence {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            weekdays: Vec::new(),
+        };
+        let mut frequency = None;
+        for part in rule.split(';') {
+            let (key, value) = match part.split_once('=') {
+                Some(pair) => pair,
+                None => continue,
+            };
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = match value.to_uppercase().as_str() {
+                        "DAILY" => Some(Frequency::Daily),
+                        "WEEKLY" => Some(Frequency::Weekly),
+                        "MONTHLY" => Some(Frequency::Monthly),
+                        "YEARLY" => Some(Frequency::Yearly),
+                        _ => return None,
+                    }
+                }
+                "INTERVAL" => {
+                    recurrence.interval = value.parse().ok()?;
+                }
+                "COUNT" => recurrence.count = value.parse().ok(),
+                "UNTIL" => {
+                    recurrence.until =
+                        ics::parse_date(value, false).map(|date| day_of(&date));
+                }
+                "BYDAY" => {
+                    recurrence.weekdays =
+                        value.split(',').filter_map(weekday).collect();
+                }
+                _ => {}
+            }
+        }
+        recurrence.frequency = frequency?;
+        recurrence.interval = recurrence.interval.max(1);
+        Some(recurrence)
+    }
+
+    /// Days on which the series starting at `start` happens, in order.
+    fn occurrences(
+        &self,
+        start: NaiveDate,
+        today: NaiveDate,
+    ) -> Vec<NaiveDate> {
+        let last = match (self.until, self.count) {
+            (Some(until), _) => until,
+            (None, Some(_)) => chrono::naive::MAX_DATE,
+            (None, None) => start.max(today) + Duration::days(OPEN_ENDED_DAYS),
+        };
+        let interval = self.interval as i64;
+        let mut days = Vec::new();
+        for step in 0.. {
+            let period: Vec<NaiveDate> = match self.frequency {
+                Frequency::Daily => {
+                    vec![start + Duration::days(step * interval)]
+                }
+                Frequency::Weekly => {
+                    let monday = start
+                        - Duration::days(
+                            start.weekday().num_days_from_monday() as i64,
+                        )
+                        + Duration::weeks(step * interval);
+                    let mut weekdays = self.weekdays.clone();
+                    if weekdays.is_empty() {
+                        weekdays.push(start.weekday());
+                    }
+                    weekdays.sort_by_key(|day| day.num_days_from_monday());
+                    weekdays.dedup();
+                    weekdays
+                        .iter()
+                        .map(|day| {
+                            monday
+                                + Duration::days(
+                                    day.num_days_from_monday() as i64
+                                )
+                        })
+                        .filter(|day| *day >= start)
+                        .collect()
+                }
+                Frequency::Monthly => {
+                    let months = start.month0() as i64 + step * interval;
+                    let year = start.year() as i64 + months / 12;
+                    // months without the start's day (e.g. the 31st) are
+                    // skipped, as RFC 5545 asks
+                    NaiveDate::from_ymd_opt(
+                        year as i32,
+                        (months % 12) as u32 + 1,
+                        start.day(),
+                    )
+                    .into_iter()
+                    .collect()
+                }
+                Frequency::Yearly => NaiveDate::from_ymd_opt(
+                    start.year() + (step * interval) as i32,
+                    start.month(),
+                    start.day(),
+                )
+                .into_iter()
+                .collect(),
+            };
+            for day in period {
+                let full = self.count.is_some_and(|n| days.len() >= n);
+                if day > last || full {
+                    return days;
+                }
+                days.push(day);
+            }
+        }
+        days
+    }
+}
+
+fn weekday(code: &str) -> Option<Weekday> {
+    // ordinal prefixes such as "-1SU" only make sense for monthly rules,
+    // which are expanded by day of the month instead
+    let code = code.trim_start_matches(|c: char| {
+        c.is_ascii_digit() || c == '-' || c == '+'
+    });
+    match code.to_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing, TodoList};
+    use proptest::prelude::*;
+
+    fn import(list: &TodoList, component: ics::Component) -> Vec<TodoEntry> {
+        let mut out = Vec::new();
+        ics::write_calendar(&mut out, &[list], component).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let today = crate::clock::today();
+        components(ics::parse(&text))
+            .iter()
+            .flat_map(|component| component.entries(today))
+            .collect()
+    }
+
+    fn dated(
+        list: &TodoList,
+        keep: impl Fn(&ListItem) -> bool,
+    ) -> Vec<(&str, Option<DateMaybeTime>)> {
+        list.items_with_paths()
+            .into_iter()
+            .filter(|(_, item)| item.date().is_some() && keep(item))
+            .map(|(_, item)| (item.title(), item.date()))
+            .collect()
+    }
+
+    fn read(entries: &[TodoEntry]) -> Vec<(&str, Option<DateMaybeTime>)> {
+        entries
+            .iter()
+            .map(|entry| (entry.title.as_str(), entry.date))
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn events_round_trip(list in testing::list()) {
+            let entries = import(&list, ics::Component::Event);
+            prop_assert_eq!(read(&entries), dated(&list, |_| true));
+        }
+
+        #[test]
+        fn done_tasks_are_left_out(list in testing::list()) {
+            let entries = import(&list, ics::Component::Todo);
+            let open = dated(&list, |item| item.completed_at().is_none());
+            prop_assert_eq!(read(&entries), open);
+        }
+
+        #[test]
+        fn series_keep_their_count(
+            start in testing::date(),
+            count in 1..30usize,
+            interval in 1..4u32,
+            frequency in prop_oneof![
+                Just("DAILY"),
+                Just("WEEKLY"),
+                Just("YEARLY"),
+            ],
+        ) {
+            let rule = format!(
+                "FREQ={};INTERVAL={};COUNT={}",
+                frequency, interval, count
+            );
+            let recurrence = Recurrence::parse(&rule).unwrap();
+            let days = recurrence.occurrences(day_of(&start), day_of(&start));
+            prop_assert_eq!(days.len(), count);
+            prop_assert!(days.windows(2).all(|pair| pair[0] < pair[1]));
+            prop_assert_eq!(days.first(), Some(&day_of(&start)));
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Arbitrary JSON exports, read with the help of a mapping file that says
+//! where the items are and which of their fields hold what, e.g.
+//!
+//! ```toml
+//! items = "data.tasks"    # where the top-level items are (default: root)
+//! title = "name"
+//! date = "due.date"
+//! notes = "description"
+//! children = "subtasks"   # nested items, mapped the same way
+//! date_format = "%d.%m.%Y" # if dates aren't ISO 8601 or Unix times
+//! ```
+//!
+//! Field paths are either dotted (`due.date`, `tags.0`) or JSON pointers
+//! (`/due/date`).
+
+use crate::{DateMaybeTime, ListItem, TodoEntry, TodoList};
+use anyhow::{bail, Context, Result};
+use chrono::prelude::*;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+/// Where to find each part of an item in the JSON document.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Mapping {
+    pub items: Option<String>,
+    pub title: String,
+    pub date: Option<String>,
+    pub notes: Option<String>,
+    pub children: Option<String>,
+    /// A `strftime`-style format for dates that aren't ISO 8601 or Unix
+    /// timestamps; with no time fields, dates are all-day.
+    pub date_format: Option<String>,
+}
+
+impl Mapping {
+    pub fn load(path: &Path) -> Result<Mapping> {
+        let text = std::fs::read_to_string(path).with_context(|| {
+            format!("Couldn't read mapping file ({})", path.display())
+        })?;
+        toml::from_str(&text).with_context(|| {
+            format!("Couldn't parse mapping file ({})", path.display())
+        })
+    }
+}
+
+pub fn import(path: &Path, mapping: &Mapping) -> Result<Vec<ListItem>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Couldn't read {}", path.display()))?;
+    let document: Value = serde_json::from_str(&text)
+        .with_context(|| format!("Couldn't parse {}", path.display()))?;
+    let items = match &mapping.items {
+        Some(field) => match lookup(&document, field) {
+            Some(items) => items,
+            None => bail!("No '{}' in {}", field, path.display()),
+        },
+        None => &document,
+    };
+    match items {
+        Value::Array(items) => {
+            items.iter().map(|item| convert(item, mapping)).collect()
+        }
+        Value::Object(_) => Ok(vec![convert(items, mapping)?]),
+        _ => bail!(
+            "Expected an array of items at '{}'",
+            mapping.items.as_deref().unwrap_or("(root)")
+        ),
+    }
+}
+
+// `due.date`, `tags.0` or `/due/date`
+fn lookup<'a>(value: &'a Value, field: &str) -> Option<&'a Value> {
+    if field.starts_with('/') {
+        return value.pointer(field);
+    }
+    field.split('.').try_fold(value, |value, key| match value {
+        Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+        _ => value.get(key),
+    })
+}
+
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn convert(item: &Value, mapping: &Mapping) -> Result<ListItem> {
+    let field = |name: &Option<String>| {
+        name.as_deref()
+            .and_then(|name| lookup(item, name))
+            .filter(|value| !value.is_null())
+    };
+    let title = lookup(item, &mapping.title)
+        .and_then(text)
+        .filter(|title| !title.trim().is_empty())
+        .unwrap_or_else(|| String::from("(untitled)"));
+    let date = match field(&mapping.date) {
+        Some(value) => Some(
+            parse_date(value, mapping.date_format.as_deref())
+                .with_context(|| format!("Invalid date for '{}'", title))?,
+        ),
+        None => None,
+    };
+    let children = match field(&mapping.children) {
+        Some(Value::Array(children)) => children
+            .iter()
+            .map(|child| convert(child, mapping))
+            .collect::<Result<Vec<_>>>()?,
+        _ => Vec::new(),
+    };
+
+    let notes = field(&mapping.notes).and_then(text);
+    if children.is_empty() {
+        let mut entry = TodoEntry::from_info(title, date);
+        entry.notes = notes;
+        return Ok(ListItem::Entry(entry));
+    }
+    let mut list = TodoList::from_info(title, date);
+    list.notes = notes;
+    list.list = children;
+    Ok(ListItem::List(list))
+}
+
+fn parse_date(value: &Value, format: Option<&str>) -> Result<DateMaybeTime> {
+    if let Some(seconds) = value.as_i64() {
+        // milliseconds, as JavaScript apps tend to write them
+        let seconds = if seconds.abs() > 100_000_000_000 {
+            seconds / 1000
+        } else {
+            seconds
+        };
+        let time = Utc.timestamp_opt(seconds, 0).single();
+        return match time {
+            Some(time) => Ok(DateMaybeTime::DateTime(time.into())),
+            None => bail!("'{}' is out of range", seconds),
+        };
+    }
+    let s = match value.as_str() {
+        Some(s) => s.trim(),
+        None => bail!("Expected a string or number, found {}", value),
+    };
+    if let Some(format) = format {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(s, format) {
+            return local(datetime);
+        }
+        return NaiveDate::parse_from_str(s, format)
+            .map(DateMaybeTime::Date)
+            .with_context(|| format!("'{}' doesn't match '{}'", s, format));
+    }
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        return Ok(DateMaybeTime::DateTime(datetime.into()));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(s, format) {
+            return local(datetime);
+        }
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(s, "%Y/%m/%d"))
+        .map(DateMaybeTime::Date)
+        .with_context(|| format!("Unrecognised date '{}'", s))
+}
+
+fn local(datetime: NaiveDateTime) -> Result<DateMaybeTime> {
+    match Local.from_local_datetime(&datetime).earliest() {
+        Some(time) => Ok(DateMaybeTime::DateTime(time)),
+        None => bail!("{} doesn't exist in the local time zone", datetime),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use proptest::prelude::*;
+    use serde_json::json;
+
+    type Shape = (Vec<usize>, String, Option<DateMaybeTime>, bool);
+
+    // as a to-do app might export it, with the date nested in a field
+    fn export(item: &ListItem) -> Value {
+        let due = item.date().map(|date| match date {
+            DateMaybeTime::Date(date) => date.format("%Y-%m-%d").to_string(),
+            DateMaybeTime::DateTime(at) => at.to_rfc3339(),
+        });
+        let subtasks: Vec<Value> = match item {
+            ListItem::List(list) => list.list.iter().map(export).collect(),
+            ListItem::Entry(_) => Vec::new(),
+        };
+        json!({
+            "name": item.title(),
+            "due": { "date": due },
+            "description": item.notes(),
+            "subtasks": subtasks,
+        })
+    }
+
+    fn shape(items: Vec<ListItem>) -> Vec<Shape> {
+        let mut list = TodoList::from_info(String::from("l"), None);
+        list.list = items;
+        list.items_with_paths()
+            .into_iter()
+            .map(|(path, item)| {
+                let is_list = matches!(item, ListItem::List(_));
+                (path, item.title().to_string(), item.date(), is_list)
+            })
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn nested_items_round_trip(list in testing::list()) {
+            let tasks: Vec<Value> = list.list.iter().map(export).collect();
+            let document = json!({ "data": { "tasks": tasks } });
+            let mapping = Mapping {
+                items: Some(String::from("data.tasks")),
+                title: String::from("name"),
+                date: Some(String::from("/due/date")),
+                notes: Some(String::from("description")),
+                children: Some(String::from("subtasks")),
+                date_format: None,
+            };
+            let imported = lookup(&document, "data.tasks")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .map(|item| convert(item, &mapping))
+                .collect::<Result<Vec<_>>>()
+                .map_err(|e| TestCaseError::fail(format!("{:#}", e)))?;
+            prop_assert_eq!(shape(imported), shape(list.list));
+        }
+
+        #[test]
+        fn formatted_dates(date in testing::date()) {
+            let (text, format) = match date {
+                DateMaybeTime::Date(date) => {
+                    (date.format("%d.%m.%Y").to_string(), "%d.%m.%Y")
+                }
+                DateMaybeTime::DateTime(at) => {
+                    let format = "%d.%m.%Y %H:%M";
+                    (at.format(format).to_string(), format)
+                }
+            };
+            let parsed = parse_date(&json!(text), Some(format))
+                .map_err(|e| TestCaseError::fail(format!("{:#}", e)))?;
+            prop_assert_eq!(parsed, date);
+        }
+    }
+
+    #[test]
+    fn unix_times() {
+        let seconds = parse_date(&json!(1_700_000_000), None).unwrap();
+        let millis = parse_date(&json!(1_700_000_000_000i64), None).unwrap();
+        assert_eq!(seconds, millis);
+    }
+}
@@ -0,0 +1,142 @@
+//! todo.txt files (<https://github.com/todotxt/todo.txt>), one task per
+//! line: `x 2024-03-02 2024-02-20 (A) Call Mom +family @phone due:2024-03-01`.
+//!
+//! Completion marks and dates, priorities, creation dates and `due:` become
+//! the entry's own fields. Projects and contexts stay in the title, where
+//! they read naturally, and any other `key:value` tags are kept in the notes
+//! so nothing is lost.
+
+use crate::{DateMaybeTime, ListItem, Priority, TodoEntry};
+use anyhow::{Context, Result};
+use chrono::prelude::*;
+use std::path::Path;
+
+pub fn import(path: &Path) -> Result<Vec<ListItem>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Couldn't read {}", path.display()))?;
+    Ok(text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| ListItem::Entry(parse(line)))
+        .collect())
+}
+
+fn date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()
+}
+
+fn midnight(date: NaiveDate) -> Option<DateTime<Local>> {
+    Local.from_local_datetime(&date.and_hms(0, 0, 0)).earliest()
+}
+
+// "(A)"
+fn priority(word: &str) -> Option<Priority> {
+    match word.as_bytes() {
+        [b'(', p, b')'] if p.is_ascii_uppercase() => Some(Priority(*p as char)),
+        _ => None,
+    }
+}
+
+/// Reads one line of a todo.txt file.
+pub fn parse(line: &str) -> TodoEntry {
+    let mut words = line.split_whitespace().peekable();
+    let done = words.next_if_eq(&"x").is_some();
+    let priority = words.next_if(|w| priority(w).is_some()).and_then(priority);
+    // a completed task has its completion date first, then its creation
+    // date; an open one only the creation date
+    let first = words.next_if(|w| date(w).is_some()).and_then(date);
+    let second = words.next_if(|w| date(w).is_some()).and_then(date);
+    let (completed, created) = match (done, first, second) {
+        (true, Some(completed), created) => (Some(completed), created),
+        (_, created, _) => (None, created),
+    };
+
+    let mut title = Vec::new();
+    let mut notes = Vec::new();
+    let mut due = None;
+    for word in words {
+        match word.split_once(':') {
+            Some(("due", value)) if date(value).is_some() => {
+                due = date(value).map(DateMaybeTime::Date)
+            }
+            // URLs have colons too, but no key on its own
+            Some((key, value))
+                if !key.is_empty()
+                    && !value.is_empty()
+                    && !value.starts_with("//")
+                    && key.chars().all(|c| c.is_ascii_alphanumeric()) =>
+            {
+                notes.push(format!("{}: {}", key, value))
+            }
+            _ => title.push(word),
+        }
+    }
+
+    let title = match title.join(" ") {
+        title if title.is_empty() => String::from("(untitled)"),
+        title => title,
+    };
+    let mut entry = TodoEntry::from_info(title, due);
+    entry.priority = priority;
+    if created.is_some() {
+        entry.created_at = created.and_then(midnight);
+    }
+    if done {
+        entry.completed_at = completed
+            .and_then(midnight)
+            .or_else(|| Some(crate::clock::now()));
+    }
+    if !notes.is_empty() {
+        entry.notes = Some(notes.join("\n"));
+    }
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{export, testing, TodoList};
+    use proptest::prelude::*;
+
+    fn render(entry: TodoEntry) -> String {
+        let mut list = TodoList::from_info(String::from("l"), None);
+        list.list = vec![ListItem::Entry(entry)];
+        let mut out = Vec::new();
+        export::todotxt::write_list(&list, &mut out).unwrap();
+        String::from_utf8(out).unwrap().trim_end().to_string()
+    }
+
+    proptest! {
+        #[test]
+        fn parse_render_parse(
+            mut entry in testing::entry(),
+            priority in proptest::option::of("[A-Z]"),
+        ) {
+            entry.priority = priority.and_then(|p| Priority::from_name(&p));
+            let line = render(entry.clone());
+            let parsed = parse(&line);
+            prop_assert_eq!(render(parsed.clone()), line);
+
+            let item = ListItem::Entry(entry);
+            let day = |date: DateMaybeTime| match date {
+                DateMaybeTime::Date(date) => date,
+                DateMaybeTime::DateTime(at) => at.date().naive_local(),
+            };
+            let done = item.completed_at().is_some();
+            prop_assert_eq!(&parsed.title, item.title());
+            prop_assert_eq!(parsed.date.map(day), item.date().map(day));
+            prop_assert_eq!(parsed.completed_at.is_some(), done);
+            // todo.txt drops the priority of done tasks
+            let priority = item.priority().filter(|_| !done);
+            prop_assert_eq!(parsed.priority, priority);
+        }
+    }
+
+    #[test]
+    fn other_tags_go_in_the_notes() {
+        let entry = parse("(B) 2024-02-20 call +family http://a.org id:42");
+        assert_eq!(entry.title, "call +family http://a.org");
+        assert_eq!(entry.notes.as_deref(), Some("id: 42"));
+        assert_eq!(entry.priority, Priority::from_name("B"));
+    }
+}
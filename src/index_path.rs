@@ -0,0 +1,171 @@
+//! Item addresses as typed on the command line: positions such as `1,3,2`
+//! or `1.3.2`, or titles such as `groceries/milk`, where each part picks
+//! the item at that level whose title is the part, starts with it, or
+//! failing that has its letters in order.
+
+use crate::error::{Error, Result};
+use crate::{ItemPath, ListItem, TodoList};
+use std::str::FromStr;
+
+/// One step down the tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Position(usize),
+    Title(String),
+}
+
+/// An address that may still need looking up in a list, see
+/// [`resolve`](IndexPath::resolve).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexPath(Vec<Segment>);
+
+impl IndexPath {
+    /// Reads an address. Parts are split on `/`, or on `,` or `.` when
+    /// they're all numbers; anything else is a single title, so titles with
+    /// dots in them still work.
+    pub fn parse(text: &str) -> Result<IndexPath> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(Error::EmptyIndex);
+        }
+        if text.contains('/') {
+            return text
+                .split('/')
+                .map(|part| match part.trim() {
+                    "" => Err(Error::EmptyIndexPart {
+                        index: String::from(text),
+                    }),
+                    part => Ok(segment(part)),
+                })
+                .collect::<Result<_>>()
+                .map(IndexPath);
+        }
+        let parts: Vec<&str> = text.split([',', '.']).collect();
+        if text.contains(',') || parts.iter().all(|part| is_number(part)) {
+            let positions = crate::parse_index(&parts)?;
+            return Ok(IndexPath(
+                positions.into_iter().map(Segment::Position).collect(),
+            ));
+        }
+        Ok(IndexPath(vec![Segment::Title(String::from(text))]))
+    }
+
+    /// The positions, if the address is made of nothing else.
+    pub fn positions(&self) -> Option<Vec<usize>> {
+        self.0
+            .iter()
+            .map(|segment| match segment {
+                Segment::Position(i) => Some(*i),
+                Segment::Title(_) => None,
+            })
+            .collect()
+    }
+
+    /// The positions of the item the address leads to in `list`. Positions
+    /// aren't checked, so callers can explain a bad one in their own way;
+    /// titles that match nothing, or more than one item equally well, are
+    /// errors.
+    pub fn resolve(&self, list: &TodoList) -> Result<ItemPath> {
+        let mut positions = Vec::new();
+        let mut level = Some(list);
+        for segment in &self.0 {
+            let i = match segment {
+                Segment::Position(i) => *i,
+                Segment::Title(title) => match level {
+                    Some(level) => find(level, title)?,
+                    None => {
+                        return Err(Error::NoSublist {
+                            at: ItemPath::from(positions),
+                            title: title.clone(),
+                        })
+                    }
+                },
+            };
+            level = match level.and_then(|level| level.list.get(i)) {
+                Some(ListItem::List(sublist)) => Some(sublist),
+                _ => None,
+            };
+            positions.push(i);
+        }
+        Ok(ItemPath::from(positions))
+    }
+}
+
+impl FromStr for IndexPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<IndexPath> {
+        IndexPath::parse(s)
+    }
+}
+
+fn is_number(part: &str) -> bool {
+    let part = part.trim();
+    !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn segment(part: &str) -> Segment {
+    match part.parse() {
+        Ok(i) if is_number(part) => Segment::Position(i),
+        _ => Segment::Title(String::from(part)),
+    }
+}
+
+// how well a title matches what was typed, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Match {
+    Exact,
+    Prefix,
+    Letters,
+}
+
+fn matches(title: &str, typed: &str) -> Option<Match> {
+    let (title, typed) = (title.to_lowercase(), typed.to_lowercase());
+    if title == typed {
+        return Some(Match::Exact);
+    }
+    if title.starts_with(&typed) {
+        return Some(Match::Prefix);
+    }
+    let mut letters = title.chars();
+    typed
+        .chars()
+        .all(|c| letters.any(|t| t == c))
+        .then_some(Match::Letters)
+}
+
+// the position of the one item in `list` that matches `typed` best
+fn find(list: &TodoList, typed: &str) -> Result<usize> {
+    let found: Vec<(Match, usize, &str)> = list
+        .list
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| {
+            matches(item.title(), typed).map(|how| (how, i, item.title()))
+        })
+        .collect();
+    let best = match found.iter().map(|(how, _, _)| *how).min() {
+        Some(best) => best,
+        None => {
+            return Err(Error::NoMatch {
+                list: list.title.clone(),
+                title: String::from(typed),
+            })
+        }
+    };
+    let best: Vec<&(Match, usize, &str)> =
+        found.iter().filter(|(how, _, _)| *how == best).collect();
+    match best.as_slice() {
+        [(_, i, _)] => Ok(*i),
+        _ => {
+            let candidates: Vec<String> = best
+                .iter()
+                .map(|(_, i, title)| format!("{}) {}", i, title))
+                .collect();
+            Err(Error::AmbiguousTitle {
+                title: String::from(typed),
+                candidates,
+            })
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Where an item sits in a list: its position at each level, from the top
+//! down. Unlike [`IndexPath`](crate::index_path::IndexPath), which is what
+//! was typed and may still need looking up, an `ItemPath` is only positions,
+//! and the [`TodoList`](crate::TodoList) methods taking one report a bad one
+//! as an [`Error`](crate::Error) rather than panicking.
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+/// Positions from the top level down, e.g. `[1, 3, 2]` for the third item
+/// of the fourth item of the second. The empty path is the list itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ItemPath(Vec<usize>);
+
+impl ItemPath {
+    /// The list itself, e.g. to append to its top level.
+    pub fn root() -> ItemPath {
+        ItemPath(Vec::new())
+    }
+
+    /// The path of the sublist holding the item, and the item's position
+    /// in it; nothing for the root.
+    pub fn split_last(&self) -> Option<(ItemPath, usize)> {
+        let (&last, parent) = self.0.split_last()?;
+        Some((ItemPath(parent.to_vec()), last))
+    }
+
+    pub fn push(&mut self, position: usize) {
+        self.0.push(position);
+    }
+
+    pub fn into_vec(self) -> Vec<usize> {
+        self.0
+    }
+
+    /// Where the item at this path ends up once the one at `removed` is
+    /// taken out.
+    pub fn after_removal(mut self, removed: &[usize]) -> ItemPath {
+        let depth = removed.len() - 1;
+        if self.len() > depth
+            && self[..depth] == removed[..depth]
+            && self[depth] > removed[depth]
+        {
+            self[depth] -= 1;
+        }
+        self
+    }
+}
+
+impl From<Vec<usize>> for ItemPath {
+    fn from(positions: Vec<usize>) -> ItemPath {
+        ItemPath(positions)
+    }
+}
+
+impl From<&[usize]> for ItemPath {
+    fn from(positions: &[usize]) -> ItemPath {
+        ItemPath(positions.to_vec())
+    }
+}
+
+impl Deref for ItemPath {
+    type Target = [usize];
+
+    fn deref(&self) -> &[usize] {
+        &self.0
+    }
+}
+
+impl DerefMut for ItemPath {
+    fn deref_mut(&mut self) -> &mut [usize] {
+        &mut self.0
+    }
+}
+
+/// Written as on the command line, e.g. `1,3,2`.
+impl fmt::Display for ItemPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.0.iter().map(|i| i.to_string()).collect();
+        write!(f, "{}", parts.join(","))
+    }
+}
@@ -1,31 +1,389 @@
 use ansi_term::{Color, Style};
-use anyhow::{bail, Context, Result};
+use anyhow::Context;
 use chrono::{prelude::*, Duration};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, io::prelude::*, path::Path};
+use std::{
+    cell::Cell, collections::HashMap, fmt, io::prelude::*, path::Path,
+    time::Instant,
+};
+use tracing::{debug, instrument};
+use unicode_width::UnicodeWidthStr;
+
+pub mod agenda;
+pub mod archive;
+pub mod backup;
+pub mod clock;
+pub mod color;
+pub mod completions;
+pub mod config;
+pub mod countdown;
+pub mod crypt;
+pub mod demo;
+pub mod diff;
+pub mod doctor;
+pub mod error;
+pub mod escalate;
+pub mod events;
+pub mod export;
+pub mod formats;
+pub mod gc;
+pub mod git;
+pub mod hints;
+pub mod history;
+pub mod humanize;
+pub mod i18n;
+pub mod ics;
+pub mod import;
+pub mod index_path;
+pub mod item_path;
+pub mod limits;
+pub mod lock;
+pub mod migrate;
+pub mod motd;
+pub mod native_host;
+pub mod natural;
+pub mod notify;
+pub mod picker;
+pub mod report;
+pub mod s3;
+pub mod scratch;
+pub mod search;
+pub mod server;
+pub mod setup;
+pub mod show;
+pub mod split;
+pub mod status;
+pub mod store;
+pub mod sync;
+pub mod table;
+pub mod template;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod theme;
+pub mod tiers;
+pub mod timings;
+pub mod tracking;
+pub mod triage;
+pub mod url_scheme;
+pub mod watch;
+pub mod webhook;
+pub mod zone;
+
+pub use error::Error;
+use error::Result;
+use i18n::Lang;
+pub use item_path::ItemPath;
 
 pub const DEFAULT_LIST: &str = "to-do";
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+/// The name of the read-only list holding every list as a sublist, in name
+/// order; see [`all_lists`].
+pub const ALL_LISTS: &str = "@all";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Hash)]
 pub enum DateMaybeTime {
     Date(NaiveDate),
     DateTime(DateTime<Local>),
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Hash)]
 pub struct TodoEntry {
     pub title: String,
     pub date: Option<DateMaybeTime>,
+    /// When the item was added; unknown for items from older files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Local>>,
+    /// When the item was last added, edited or moved; unknown for items
+    /// from older files that haven't changed since.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<DateTime<Local>>,
+    /// Free-form details beyond the title.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// When the item was marked done; done items stay in the list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Local>>,
+    /// Every change to the date, once it had one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rescheduled: Vec<Reschedule>,
+    /// The date is a hard deadline rather than a soft target: it shows as
+    /// urgent [`DEADLINE_LEAD_DAYS`] days early, `later reset --every`
+    /// leaves it where it is, and `later agenda` and `later motd` list it on
+    /// its own.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deadline: bool,
+    /// Pinned to the focus section above the list.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub focused: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    /// Labels such as `work`, shown after the title as `#work`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Where the date's time of day was given, if not here, e.g. for a
+    /// flight; it's shown in that zone as well as locally.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<Tz>,
+    /// Time spent on the item, from `later start` and `later stop`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tracked: Vec<tracking::Interval>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Hash)]
 pub struct TodoList {
     pub title: String,
     pub date: Option<DateMaybeTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<DateTime<Local>>,
+    /// Free-form details beyond the title.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Local>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rescheduled: Vec<Reschedule>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deadline: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub focused: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<Tz>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tracked: Vec<tracking::Interval>,
     list: Vec<ListItem>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// How much an item matters, from `A` (most) to `Z`, as in todo.txt.
+/// `high`, `medium` and `low` stand for `A`, `B` and `C`.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+)]
+#[serde(try_from = "String", into = "String")]
+pub struct Priority(char);
+
+impl Priority {
+    pub fn from_name(name: &str) -> Option<Priority> {
+        let letter = match name.to_lowercase().as_str() {
+            "high" => 'A',
+            "medium" => 'B',
+            "low" => 'C',
+            _ => match name.chars().collect::<Vec<_>>().as_slice() {
+                [c] if c.is_ascii_alphabetic() => c.to_ascii_uppercase(),
+                _ => return None,
+            },
+        };
+        Some(Priority(letter))
+    }
+
+    pub fn letter(self) -> char {
+        self.0
+    }
+
+    fn style(self, palette: Palette) -> Style {
+        match (self.0, palette) {
+            ('A', Palette::Default) => Color::Red.bold(),
+            ('B', Palette::Default) => Color::Yellow.bold(),
+            ('C', Palette::Default) => Color::Cyan.bold(),
+            ('A', Palette::Colorblind) => Color::RGB(213, 94, 0).bold(),
+            ('B', Palette::Colorblind) => Color::RGB(240, 228, 66).bold(),
+            ('C', Palette::Colorblind) => Color::RGB(86, 180, 233).bold(),
+            _ => Style::new().dimmed(),
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<String> for Priority {
+    type Error = String;
+
+    fn try_from(s: String) -> std::result::Result<Priority, String> {
+        Priority::from_name(&s)
+            .ok_or_else(|| format!("Invalid priority '{}' (A-Z)", s))
+    }
+}
+
+impl From<Priority> for String {
+    fn from(priority: Priority) -> String {
+        priority.to_string()
+    }
+}
+
+/// A change to an item's date.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Hash)]
+pub struct Reschedule {
+    pub at: DateTime<Local>,
+    pub from: DateMaybeTime,
+    pub to: Option<DateMaybeTime>,
+}
+
+impl Reschedule {
+    /// How much later the item fell due, if it was pushed back rather than
+    /// brought forward or undated.
+    pub fn slip(&self) -> Option<Duration> {
+        let slip = self.to?.start() - self.from.start();
+        Some(slip).filter(|slip| *slip > Duration::zero())
+    }
+}
+
+// records the change from `from` to `to`, if there is one
+fn reschedule(
+    history: &mut Vec<Reschedule>,
+    from: Option<DateMaybeTime>,
+    to: Option<DateMaybeTime>,
+) {
+    if let Some(from) = from.filter(|from| Some(*from) != to) {
+        history.push(Reschedule {
+            at: clock::now(),
+            from,
+            to,
+        });
+    }
+}
+
+// cells in a progress bar at most; longer lists fill them proportionally
+const PROGRESS_CELLS: usize = 10;
+
+// e.g. " [3/7] ▓▓▓░░░░" for a list with three of its seven entries done,
+// styled and plain; nothing for a list with no entries
+fn progress_marker(
+    (done, total): (usize, usize),
+    opts: &RenderOptions,
+) -> (String, String) {
+    if total == 0 {
+        return (String::new(), String::new());
+    }
+    let cells = total.min(PROGRESS_CELLS);
+    // rounded down, so the bar is only full once everything is done
+    let filled = done * cells / total;
+    let count = format!(" [{}/{}] ", done, total);
+    let (full, empty) = ("▓".repeat(filled), "░".repeat(cells - filled));
+    // empty runs are left out, rather than painted as bare escape codes
+    let paint = |style: Style, text: &str| match text {
+        "" => String::new(),
+        text => opts.style(style).paint(text).to_string(),
+    };
+    let styled = format!(
+        "{}{}{}",
+        paint(Style::new().dimmed(), &count),
+        paint(Color::Green.normal(), &full),
+        paint(Style::new().dimmed(), &empty)
+    );
+    (styled, format!("{}{}{}", count, full, empty))
+}
+
+// e.g. " ↻3" for an item pushed back three times
+fn slip_marker(history: &[Reschedule]) -> String {
+    match history.iter().filter(|r| r.slip().is_some()).count() {
+        0 => String::new(),
+        n => format!(" ↻{}", n),
+    }
+}
+
+/// A tag as it's stored: without the leading `#`, which is optional when
+/// typing one. `None` if it's empty or has spaces in it.
+pub fn tag_name(text: &str) -> Option<String> {
+    let name = text.trim().trim_start_matches('#');
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        None
+    } else {
+        Some(String::from(name))
+    }
+}
+
+// everything drawn around a title apart from its date
+struct Decorations<'a> {
+    priority: Option<Priority>,
+    tags: &'a [String],
+    rescheduled: &'a [Reschedule],
+    has_notes: bool,
+    /// Done and total entries, for a sublist.
+    progress: Option<(usize, usize)>,
+}
+
+// the title with its priority badge, tags, notes indicator and slip marker,
+// styled and plain (for measuring)
+fn decorate_title(
+    title: &str,
+    style: Style,
+    done: bool,
+    decorations: Decorations,
+    opts: &RenderOptions,
+) -> (String, String) {
+    let Decorations {
+        priority,
+        tags,
+        rescheduled,
+        has_notes,
+        progress,
+    } = decorations;
+    let (badge, painted_badge) = match priority {
+        Some(priority) => {
+            let badge = format!("({}) ", priority);
+            let style = opts.style(opts.theme.priority(priority));
+            let painted = style.paint(&badge).to_string();
+            (badge, painted)
+        }
+        None => (String::new(), String::new()),
+    };
+    let suffix: String = tags.iter().map(|tag| format!(" #{}", tag)).collect();
+    let tag_style = if done {
+        Style::new().dimmed()
+    } else {
+        opts.theme.tag
+    };
+    let painted_tags = if suffix.is_empty() {
+        String::new()
+    } else {
+        opts.style(tag_style).paint(&suffix).to_string()
+    };
+    let mut marker = slip_marker(rescheduled);
+    if has_notes {
+        marker.insert_str(0, " …");
+    }
+    let dimmed = if marker.is_empty() {
+        String::new()
+    } else {
+        opts.style(Style::new().dimmed()).paint(&marker).to_string()
+    };
+    let (painted_progress, bar) = progress
+        .map(|progress| progress_marker(progress, opts))
+        .unwrap_or_default();
+    let styled = format!(
+        "{}{}{}{}{}",
+        painted_badge,
+        opts.title_style(style, done).paint(title),
+        painted_progress,
+        painted_tags,
+        dimmed
+    );
+    (
+        styled,
+        format!("{}{}{}{}{}", badge, title, bar, suffix, marker),
+    )
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Hash)]
 pub enum ListItem {
     Entry(TodoEntry),
     List(TodoList),
@@ -42,7 +400,7 @@ impl DateMaybeTime {
             _ => {
                 let datetime = match date {
                     Some(d) => d,
-                    None => Local::today().naive_local(),
+                    None => clock::today(),
                 }
                 .and_time(time.unwrap());
                 let local_time = Local.from_local_datetime(&datetime).unwrap();
@@ -51,432 +409,1846 @@ impl DateMaybeTime {
         }
     }
 
+    /// The same date `by` later, at the same time of day.
+    pub fn shifted(self, by: Duration) -> DateMaybeTime {
+        match self {
+            DateMaybeTime::Date(date) => DateMaybeTime::Date(date + by),
+            DateMaybeTime::DateTime(datetime) => {
+                let moved = datetime.naive_local() + by;
+                DateMaybeTime::DateTime(
+                    Local
+                        .from_local_datetime(&moved)
+                        .earliest()
+                        .unwrap_or(datetime + by),
+                )
+            }
+        }
+    }
+
+    /// When the item falls due; plain dates at the start of the day.
+    pub fn start(&self) -> NaiveDateTime {
+        match self {
+            DateMaybeTime::Date(date) => date.and_hms(0, 0, 0),
+            DateMaybeTime::DateTime(datetime) => datetime.naive_local(),
+        }
+    }
+
     fn date_string(&self) -> String {
         let date = match self {
             DateMaybeTime::Date(date) => *date,
             DateMaybeTime::DateTime(datetime) => datetime.date().naive_local(),
         };
-        date.format("%Y/%m/%d").to_string()
+        formats::date(date)
     }
 
     fn time_string(&self) -> String {
         match self {
             DateMaybeTime::Date(_) => String::new(),
-            DateMaybeTime::DateTime(datetime) => {
-                datetime.time().format("%H:%M").to_string()
-            }
+            DateMaybeTime::DateTime(datetime) => formats::time(datetime.time()),
         }
     }
 
-    fn to_string(&self) -> String {
-        let (date, time) = match self {
-            DateMaybeTime::Date(date) => (*date, None),
-            DateMaybeTime::DateTime(datetime) => {
-                (datetime.naive_local().date(), Some(datetime.time()))
-            }
-        };
-
-        let today = Local::today().naive_local();
-        let duration = date.signed_duration_since(today);
-        let days = duration.num_days();
-        let weeks = duration.num_weeks();
-        let (date_string, days_till) = match days {
-            -1 => (String::from("Yesterday"), String::new()),
-            0 => (String::from("Today"), String::new()),
-            1 => (String::from("Tomorrow"), String::new()),
-            _ => {
-                let (timescale, difference) = if 14 <= days.abs() {
-                    (String::from("weeks"), weeks)
-                } else {
-                    (String::from("days"), days)
-                };
-                (
-                    if (1..=7).contains(&days) {
-                        format!("upcoming {}", date.format("%A"))
-                    } else if (-7..=-1).contains(&days) {
-                        format!("recent {}", date.format("%A"))
-                    } else if date.year() == today.year() {
-                        date.format("%B %d").to_string()
-                    } else {
-                        date.format("%B %d %Y").to_string()
-                    },
-                    if days < 0 {
-                        format!("; {} {} ago", difference.abs(), timescale)
-                    } else {
-                        format!("; in {} {}", difference, timescale)
-                    },
-                )
-            }
-        };
-        let datetime_string = match time {
-            Some(t) => {
-                format!("{}, {}{}", date_string, t.format("%R%P"), days_till)
-            }
-            None => format!("{}{}", date_string, days_till),
-        };
-        datetime_string
-    }
-
-    fn get_color(&self) -> Color {
+    /// How soon the date comes, as of now.
+    pub fn urgency(&self) -> Urgency {
         let remaining = match self {
             DateMaybeTime::Date(date) => {
-                date.signed_duration_since(Local::today().naive_local())
+                date.signed_duration_since(clock::today())
             }
             DateMaybeTime::DateTime(datetime) => {
-                datetime.signed_duration_since(Local::now())
+                datetime.signed_duration_since(clock::now())
             }
         };
         if remaining.lt(&Duration::days(0)) {
-            Color::Red
+            Urgency::Overdue
         } else if remaining.lt(&Duration::days(1)) {
-            Color::Yellow
+            Urgency::Soon
         } else {
-            Color::Green
+            Urgency::Later
         }
     }
-}
 
-impl TodoEntry {
-    fn write_to(&self, out: &mut impl Write) -> std::io::Result<()> {
-        if let Some(datemaybe) = self.date {
-            let date_string = format!("({})", datemaybe.to_string());
-            write!(
-                out,
-                "{} {}",
-                self.title,
-                datemaybe.get_color().paint(date_string)
-            )
+    /// How soon the date comes as a hard deadline, which counts as overdue
+    /// from [`DEADLINE_LEAD_DAYS`] days before it, while there's still time.
+    pub fn deadline_urgency(&self) -> Urgency {
+        let days = (self.start().date() - clock::today()).num_days();
+        if days < DEADLINE_LEAD_DAYS {
+            Urgency::Overdue
+        } else {
+            self.urgency()
+        }
+    }
+
+    /// [`deadline_urgency`](Self::deadline_urgency) if `hard`, otherwise
+    /// [`urgency`](Self::urgency).
+    pub fn urgency_as(&self, hard: bool) -> Urgency {
+        if hard {
+            self.deadline_urgency()
         } else {
-            write!(out, "{}", self.title)
+            self.urgency()
         }
     }
 }
 
-impl TodoList {
-    // create default list
-    pub fn default() -> TodoList {
-        TodoList {
-            title: String::from(DEFAULT_LIST),
-            date: None,
-            list: vec![ListItem::Entry(TodoEntry {
-                title: String::from("Hello, world!"),
-                date: Some(DateMaybeTime::DateTime(Local::now())),
-            })],
+/// How many days before a hard deadline it's shown as urgent as an overdue
+/// date.
+pub const DEADLINE_LEAD_DAYS: i64 = 3;
+
+/// How soon an item is due.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Urgency {
+    Overdue,
+    /// Within the next day.
+    Soon,
+    Later,
+}
+
+/// The colors, and symbols, that show how urgent dates and priorities are.
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Palette {
+    /// Red, yellow and green.
+    #[default]
+    Default,
+    /// Vermillion, yellow and sky blue from the Okabe-Ito set, which stay
+    /// apart with the common kinds of color blindness, plus a `!` before
+    /// overdue dates and a `•` before those due within a day.
+    Colorblind,
+}
+
+impl Palette {
+    pub const NAMES: &'static [&'static str] = &["default", "colorblind"];
+
+    fn urgency_style(self, urgency: Urgency) -> Style {
+        match (self, urgency) {
+            (Palette::Default, Urgency::Overdue) => Color::Red.normal(),
+            (Palette::Default, Urgency::Soon) => Color::Yellow.normal(),
+            (Palette::Default, Urgency::Later) => Color::Green.normal(),
+            (Palette::Colorblind, Urgency::Overdue) => {
+                Color::RGB(213, 94, 0).bold()
+            }
+            (Palette::Colorblind, Urgency::Soon) => {
+                Color::RGB(240, 228, 66).normal()
+            }
+            (Palette::Colorblind, Urgency::Later) => {
+                Color::RGB(86, 180, 233).normal()
+            }
         }
     }
 
-    pub fn from_info(title: String, date: Option<DateMaybeTime>) -> TodoList {
-        TodoList {
+    fn tag_style(self) -> Style {
+        match self {
+            Palette::Default => Color::Purple.normal(),
+            Palette::Colorblind => Color::RGB(204, 121, 167).normal(),
+        }
+    }
+
+    fn symbol(self, urgency: Urgency) -> &'static str {
+        match (self, urgency) {
+            (Palette::Default, _) | (Palette::Colorblind, Urgency::Later) => "",
+            (Palette::Colorblind, Urgency::Overdue) => "! ",
+            (Palette::Colorblind, Urgency::Soon) => "• ",
+        }
+    }
+}
+
+// the date as shown, with the palette's urgency symbol if it has one
+pub(crate) fn date_text(
+    date: &DateMaybeTime,
+    zone: Option<Tz>,
+    hard: bool,
+    done: bool,
+    palette: Palette,
+) -> String {
+    let symbol = if done {
+        ""
+    } else {
+        palette.symbol(date.urgency_as(hard))
+    };
+    let hard = if hard {
+        format!(" · {}", Lang::current().deadline())
+    } else {
+        String::new()
+    };
+    let there = match (date, zone) {
+        (DateMaybeTime::DateTime(moment), Some(zone)) => {
+            zone::label(*moment, zone)
+        }
+        _ => None,
+    };
+    match there {
+        Some(there) => format!("{}{} · {}{}", symbol, date, there, hard),
+        None => format!("{}{}{}", symbol, date, hard),
+    }
+}
+
+impl fmt::Display for DateMaybeTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (date, time) = match self {
+            DateMaybeTime::Date(date) => (*date, None),
+            DateMaybeTime::DateTime(datetime) => {
+                (datetime.naive_local().date(), Some(datetime.time()))
+            }
+        };
+
+        let lang = Lang::current();
+        let today = clock::today();
+        let days = date.signed_duration_since(today).num_days();
+        let date_string = match days {
+            -1 => String::from(lang.yesterday()),
+            0 => String::from(lang.today()),
+            1 => String::from(lang.tomorrow()),
+            2..=7 => lang.upcoming(date.weekday()),
+            -7..=-2 => lang.recent(date.weekday()),
+            _ => formats::custom_date(date).unwrap_or_else(|| {
+                lang.month_day(date, date.year() != today.year())
+            }),
+        };
+        // timed items count down to the minute, plain dates only by the day
+        let remaining = match self {
+            DateMaybeTime::Date(_) if (-1..=1).contains(&days) => None,
+            DateMaybeTime::Date(_) => Some(Duration::days(days)),
+            DateMaybeTime::DateTime(datetime) => {
+                Some(datetime.signed_duration_since(clock::now()))
+            }
+        };
+        let days_till = match remaining {
+            Some(duration) => {
+                format!("; {}", humanize::relative(lang, duration))
+            }
+            None => String::new(),
+        };
+        match time {
+            Some(t) => {
+                let time = formats::custom_time(t)
+                    .unwrap_or_else(|| t.format("%R%P").to_string());
+                write!(f, "{}, {}{}", date_string, time, days_till)
+            }
+            None => write!(f, "{}{}", date_string, days_till),
+        }
+    }
+}
+
+impl TodoEntry {
+    pub fn from_info(title: String, date: Option<DateMaybeTime>) -> TodoEntry {
+        let now = clock::now();
+        TodoEntry {
             title,
             date,
-            list: Vec::new(),
+            created_at: Some(now),
+            modified_at: Some(now),
+            notes: None,
+            completed_at: None,
+            rescheduled: Vec::new(),
+            deadline: false,
+            focused: false,
+            priority: None,
+            tags: Vec::new(),
+            zone: None,
+            tracked: Vec::new(),
         }
     }
 
-    pub fn write_to(
+    /// Changes the date, remembering the old one.
+    pub fn reschedule(&mut self, date: Option<DateMaybeTime>) {
+        reschedule(&mut self.rescheduled, self.date, date);
+        self.date = date;
+        self.modified_at = Some(clock::now());
+    }
+
+    fn write_to(
         &self,
         out: &mut impl Write,
-        indent: usize,
+        column: usize,
+        opts: &RenderOptions,
     ) -> std::io::Result<()> {
-        let title = Style::new().underline().paint(self.title.as_str());
-        let date_string = if let Some(datemaybe) = self.date {
-            datemaybe
-                .get_color()
-                .paint(format!("({})", datemaybe.to_string()))
+        let done = self.completed_at.is_some();
+        let (title, plain_title) = decorate_title(
+            &self.title,
+            opts.theme.entry_title,
+            done,
+            Decorations {
+                priority: self.priority,
+                tags: &self.tags,
+                rescheduled: &self.rescheduled,
+                has_notes: self.notes.is_some(),
+                progress: None,
+            },
+            opts,
+        );
+        let date = self.date.map(|date| (date, self.zone, self.deadline));
+        write_titled(out, &title, &plain_title, date, done, column, opts)
+    }
+}
+
+/// Settings for rendering lists to the terminal.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Terminal width in columns. Items that would overflow it are stacked,
+    /// with the date on its own line below the title; `None` never stacks.
+    pub width: Option<usize>,
+    /// Bullets to draw before the index at each depth. When empty, entries
+    /// get `0)` and sublists `0--->` markers.
+    pub bullets: Vec<String>,
+    /// Marker colors at each depth, overriding the cyan/blue defaults.
+    pub bullet_colors: Vec<Color>,
+    /// Show undated lists with the earliest date among their items.
+    pub roll_up_dates: bool,
+    /// Label items with [`hints`] codes instead of their indices.
+    pub hints: bool,
+    pub palette: Palette,
+    /// Colors and styles of dates, markers, titles and tags; see [`theme`].
+    pub theme: theme::Theme,
+    /// Style output at all; off with `--plain` or `NO_COLOR`, see [`color`].
+    pub color: bool,
+    /// Only draw items with this tag, along with the sublists they're in
+    /// and everything in sublists that have it.
+    pub tag: Option<String>,
+    // how many items have been labelled so far in this render
+    next_hint: Cell<usize>,
+    // whether the items being drawn are inside a list with `tag`
+    in_tagged: Cell<bool>,
+}
+
+impl RenderOptions {
+    /// Options suited to stdout: the width comes from `COLUMNS` if set, or
+    /// the terminal itself, and is left unset when output is piped.
+    pub fn for_terminal(
+        config: &config::Config,
+    ) -> anyhow::Result<RenderOptions> {
+        let width = std::env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.parse().ok())
+            .or_else(|| {
+                terminal_size::terminal_size_of(std::io::stdout())
+                    .map(|(terminal_size::Width(w), _)| w as usize)
+            });
+        let bullet_colors = config
+            .display
+            .bullet_colors
+            .iter()
+            .map(|c| config::parse_color(c))
+            .collect::<anyhow::Result<_>>()
+            .context("Invalid display.bullet_colors in config")?;
+        Ok(RenderOptions {
+            width,
+            bullets: config.display.bullets.clone(),
+            bullet_colors,
+            roll_up_dates: config.display.roll_up_dates,
+            hints: false,
+            palette: config.display.palette,
+            theme: theme::Theme::from_config(
+                &config.theme,
+                config.display.palette,
+            )?,
+            color: color::enabled(),
+            tag: None,
+            next_hint: Cell::new(0),
+            in_tagged: Cell::new(false),
+        })
+    }
+
+    /// `style`, or no style at all when color is off.
+    pub fn style(&self, style: Style) -> Style {
+        if self.color {
+            style
         } else {
-            ansi_term::ANSIGenericString::from("")
-        };
-        write!(
-            out,
-            "{}{} {}\n",
-            if indent == 0 { "   " } else { "" },
-            title,
-            date_string
-        )
-        .and(
-            self.list
-                .iter()
-                .enumerate()
-                .map(|(i, item)| {
-                    let marker = match item {
-                        ListItem::Entry(_) => {
-                            Color::Cyan.paint(format!("{})", i))
-                        }
-                        ListItem::List(_) => {
-                            Color::Blue.paint(format!("{}--->", i))
-                        }
-                    };
-                    write!(out, "{}", String::from("   ").repeat(indent))
-                        .and(write!(out, "{} ", marker))
-                        .and(item.write_to(out, indent + 1))
-                        .and(if i != self.list.len() - 1 || indent == 0 {
-                            write!(out, "\n")
-                        } else {
-                            write!(out, "")
-                        })
-                })
-                .collect(),
-        )
+            Style::new()
+        }
     }
 
-    pub fn write_header(&self, out: &mut impl Write) -> std::io::Result<()> {
-        let title = self.title.as_str();
-        let date_string = if let Some(datemaybe) = self.date {
-            datemaybe
-                .get_color()
-                .paint(format!("({})", datemaybe.to_string()))
+    // done items are dimmed rather than colored by how soon they're due
+    pub(crate) fn date_style(
+        &self,
+        date: &DateMaybeTime,
+        hard: bool,
+        done: bool,
+    ) -> Style {
+        self.style(if done {
+            Style::new().dimmed()
         } else {
-            ansi_term::ANSIGenericString::from("")
-        };
-        write!(
-            out,
-            "{} {} {}\n",
-            Color::Blue.paint("->"),
-            title,
-            date_string
-        )
+            self.theme.urgency(date.urgency_as(hard))
+        })
     }
 
-    pub fn add_item(
-        &mut self,
-        item: ListItem,
-        index: &mut std::slice::IterMut<'_, usize>,
-    ) -> anyhow::Result<()> {
-        if index.len() == 0 {
-            self.list.push(item);
-            Ok(())
+    // done items get the theme's done style, struck through by default
+    pub(crate) fn title_style(&self, style: Style, done: bool) -> Style {
+        self.style(if done { self.theme.done(style) } else { style })
+    }
+
+    // the depth-th entry of a per-depth setting, repeating the last one
+    fn by_depth<T>(values: &[T], depth: usize) -> Option<&T> {
+        values.get(depth).or_else(|| values.last())
+    }
+
+    fn marker(&self, item: &ListItem, i: usize, depth: usize) -> String {
+        let label = if self.hints {
+            let n = self.next_hint.replace(self.next_hint.get() + 1);
+            hints::code(n).unwrap_or_else(|| i.to_string())
         } else {
-            let i = *index.next().unwrap();
-            if i < self.list.len() {
-                match self.list.get_mut(i).unwrap() {
-                    ListItem::List(l) => l.add_item(item, index),
-                    ListItem::Entry(_) => {
-                        if index.len() == 0 {
-                            if let ListItem::Entry(entry) = self.list.remove(i)
-                            {
-                                self.list.insert(
-                                    i,
-                                    ListItem::List(TodoList::from_info(
-                                        entry.title,
-                                        entry.date,
-                                    )),
-                                );
-                                if let ListItem::List(new_list) =
-                                    self.list.get_mut(i).unwrap()
-                                {
-                                    new_list.add_item(item, index)?;
-                                };
-                            };
-                            Ok(())
-                        } else {
-                            bail!("Invalid index! (sub-indexing a non-list)")
-                        }
-                    }
-                }
-            } else {
-                bail!("Invalid index! (too big)")
-            }
+            i.to_string()
+        };
+        match (Self::by_depth(&self.bullets, depth), item) {
+            (Some(bullet), _) => format!("{} {}", bullet, label),
+            (None, ListItem::Entry(_)) => format!("{})", label),
+            (None, ListItem::List(_)) => format!("{}--->", label),
         }
     }
 
-    pub fn remove_item(
-        &mut self,
-        index: &mut std::slice::IterMut<'_, usize>,
-    ) -> anyhow::Result<ListItem> {
-        let i = *index.next().unwrap();
-        if index.len() == 0 {
-            if i < self.list.len() {
-                Ok(self.list.remove(i))
-            } else {
-                bail!("Invalid index! (too big)");
-            }
-        } else {
-            if i < self.list.len() {
-                let (removed_item, empty) = match self.list.get_mut(i).unwrap()
-                {
-                    ListItem::List(l) => {
-                        (l.remove_item(index)?, l.list.len() == 0)
-                    }
-                    ListItem::Entry(_) => {
-                        bail!("Invalid index! (sub-indexing a non-list)");
-                    }
-                };
-                if empty {
-                    if let ListItem::List(old_list) = self.list.remove(i) {
-                        self.list.insert(
-                            i,
-                            ListItem::Entry(TodoEntry {
-                                title: old_list.title,
-                                date: old_list.date,
-                            }),
-                        )
-                    }
-                };
-                Ok(removed_item)
-            } else {
-                bail!("Invalid index! (too big)")
-            }
+    pub(crate) fn shows(&self, item: &ListItem) -> bool {
+        self.tag
+            .as_ref()
+            .is_none_or(|tag| self.in_tagged.get() || item.has_tag_within(tag))
+    }
+
+    // runs `draw` for the items of `list`, all of which are shown if the
+    // list has the tag being filtered on, as they inherit it
+    pub(crate) fn within<R>(
+        &self,
+        list: &TodoList,
+        draw: impl FnOnce() -> R,
+    ) -> R {
+        let tagged =
+            self.tag.as_ref().is_some_and(|tag| list.tags.contains(tag));
+        let outer = self.in_tagged.replace(self.in_tagged.get() || tagged);
+        let result = draw();
+        self.in_tagged.set(outer);
+        result
+    }
+
+    // a hidden item still uses up hint codes, for itself and everything
+    // under it, so the codes drawn match those saved for the whole list
+    fn skip_hints(&self, item: &ListItem) {
+        let skipped = match item {
+            ListItem::Entry(_) => 1,
+            ListItem::List(list) => 1 + list.items_with_paths().len(),
+        };
+        self.next_hint.set(self.next_hint.get() + skipped);
+    }
+
+    fn marker_style(&self, item: &ListItem, depth: usize) -> Style {
+        match (Self::by_depth(&self.bullet_colors, depth), item) {
+            (Some(color), _) => color.normal(),
+            (None, ListItem::Entry(_)) => self.theme.entry_marker,
+            (None, ListItem::List(_)) => self.theme.list_marker,
         }
     }
+}
 
-    pub fn insert_item(
-        &mut self,
-        item: ListItem,
-        index: &mut std::slice::IterMut<'_, usize>,
-    ) -> anyhow::Result<()> {
-        let i = *index.next().unwrap();
-        if index.len() == 0 {
-            if i <= self.list.len() {
-                Ok(self.list.insert(i, item))
-            } else {
-                bail!("Invalid index! (too big)");
-            }
-        } else {
-            if i < self.list.len() {
-                match self.list.get_mut(i).unwrap() {
-                    ListItem::List(l) => l.insert_item(item, index),
-                    ListItem::Entry(_) => {
-                        if index.len() == 1 {
-                            if let ListItem::Entry(entry) = self.list.remove(i)
-                            {
-                                self.list.insert(
-                                    i,
-                                    ListItem::List(TodoList::from_info(
-                                        entry.title,
-                                        entry.date,
-                                    )),
-                                );
-                                if let ListItem::List(new_list) =
-                                    self.list.get_mut(i).unwrap()
-                                {
-                                    new_list.insert_item(item, index)?;
-                                };
-                            };
-                            Ok(())
-                        } else {
-                            bail!("Invalid index! (sub-indexing a non-list)")
-                        }
-                    }
-                }
-            } else {
-                bail!("Invalid index! (too big)")
+/// Number of terminal columns `s` occupies, counting emoji and CJK
+/// characters as two columns wide.
+pub fn display_width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+// writes a title followed by its date, dropping the date onto an indented
+// line of its own when both won't fit on one line
+fn write_titled(
+    out: &mut impl Write,
+    title: &dyn fmt::Display,
+    plain_title: &str,
+    date: Option<(DateMaybeTime, Option<Tz>, bool)>,
+    done: bool,
+    column: usize,
+    opts: &RenderOptions,
+) -> std::io::Result<()> {
+    write!(out, "{}", title)?;
+    if let Some((datemaybe, zone, hard)) = date {
+        let date_string = format!(
+            "({})",
+            date_text(&datemaybe, zone, hard, done, opts.palette)
+        );
+        let line_width = column
+            + display_width(plain_title)
+            + 1
+            + display_width(&date_string);
+        match opts.width {
+            Some(width) if line_width > width => {
+                write!(out, "\n{}", " ".repeat(column + 2))?
             }
+            _ => write!(out, " ")?,
         }
+        let style = opts.date_style(&datemaybe, hard, done);
+        write!(out, "{}", style.paint(date_string))?;
     }
+    Ok(())
+}
 
-    pub fn sort(&mut self) {
-        for item in self.list.iter_mut() {
-            if let ListItem::List(sublist) = item {
-                sublist.sort()
-            }
+impl Default for TodoList {
+    // create default list
+    fn default() -> TodoList {
+        let mut list = TodoList::from_info(String::from(DEFAULT_LIST), None);
+        list.list.push(ListItem::Entry(TodoEntry::from_info(
+            String::from("Hello, world!"),
+            Some(DateMaybeTime::DateTime(clock::now())),
+        )));
+        list
+    }
+}
+
+impl TodoList {
+    pub fn from_info(title: String, date: Option<DateMaybeTime>) -> TodoList {
+        let now = clock::now();
+        TodoList {
+            title,
+            date,
+            created_at: Some(now),
+            modified_at: Some(now),
+            notes: None,
+            completed_at: None,
+            rescheduled: Vec::new(),
+            deadline: false,
+            focused: false,
+            priority: None,
+            tags: Vec::new(),
+            zone: None,
+            tracked: Vec::new(),
+            list: Vec::new(),
         }
-        self.list.sort_by_cached_key(|item| {
-            let opt_date = match item {
-                ListItem::List(l) => l.date,
-                ListItem::Entry(e) => e.date,
-            };
-            let date_maybe = opt_date
-                .unwrap_or(DateMaybeTime::Date(chrono::naive::MAX_DATE));
-            match date_maybe {
-                DateMaybeTime::Date(date) => (date, None),
-                DateMaybeTime::DateTime(datetime) => {
-                    (datetime.naive_local().date(), Some(datetime.time()))
+    }
+
+    /// Changes the date, remembering the old one.
+    pub fn reschedule(&mut self, date: Option<DateMaybeTime>) {
+        reschedule(&mut self.rescheduled, self.date, date);
+        self.date = date;
+        self.modified_at = Some(clock::now());
+    }
+
+    /// How many of the entries in the list, at any depth, are done, and how
+    /// many there are. Sublists themselves aren't counted, only what's in
+    /// them.
+    pub fn progress(&self) -> (usize, usize) {
+        self.list
+            .iter()
+            .fold((0, 0), |(done, total), item| match item {
+                ListItem::Entry(entry) => (
+                    done + usize::from(entry.completed_at.is_some()),
+                    total + 1,
+                ),
+                ListItem::List(list) => {
+                    let (sub_done, sub_total) = list.progress();
+                    (done + sub_done, total + sub_total)
                 }
-            }
-        });
+            })
     }
-}
 
-impl ListItem {
-    fn write_to(
+    /// The list's own date or, failing that, the earliest date among its
+    /// unfinished items, looking inside undated sublists the same way.
+    pub fn rolled_up_date(&self) -> Option<DateMaybeTime> {
+        self.date.or_else(|| {
+            self.list
+                .iter()
+                .filter(|item| !item.is_done())
+                .filter_map(|item| item.rolled_up_date())
+                .min_by_key(DateMaybeTime::start)
+        })
+    }
+
+    pub fn write_to(
         &self,
         out: &mut impl Write,
         indent: usize,
+        opts: &RenderOptions,
     ) -> std::io::Result<()> {
-        match self {
-            ListItem::Entry(entry) => entry.write_to(out),
-            ListItem::List(list) => list.write_to(out, indent),
+        let column = if indent == 0 { 3 } else { 0 };
+        if indent == 0 {
+            opts.next_hint.set(0);
         }
+        self.write_tree(out, indent, column, opts)
     }
-}
 
-pub fn prompt_for_info(
-    existing: Option<&ListItem>,
-) -> Result<(String, Option<DateMaybeTime>)> {
-    let mut rl = rustyline::Editor::<()>::new();
-    let (prev_title, prev_date) = if let Some(listitem) = existing {
-        match listitem {
-            ListItem::Entry(entry) => {
-                (Some(entry.title.clone()), Some(entry.date))
+    // `column` is where the title starts on screen, after any marker
+    fn write_tree(
+        &self,
+        out: &mut impl Write,
+        indent: usize,
+        column: usize,
+        opts: &RenderOptions,
+    ) -> std::io::Result<()> {
+        let done = self.completed_at.is_some();
+        // the top-level list's header keeps its own date
+        let date = if opts.roll_up_dates && indent > 0 {
+            self.rolled_up_date()
+        } else {
+            self.date
+        };
+        // a date rolled up from an item isn't this list's deadline
+        let hard = self.deadline && date == self.date;
+        let (title, plain_title) = decorate_title(
+            &self.title,
+            opts.theme.list_title,
+            done,
+            Decorations {
+                priority: self.priority,
+                tags: &self.tags,
+                rescheduled: &self.rescheduled,
+                // a top-level list's notes are shown in full instead
+                has_notes: self.notes.is_some() && indent > 0,
+                // the top-level list is what's being looked at, not a
+                // checklist within it
+                progress: (indent > 0).then(|| self.progress()),
+            },
+            opts,
+        );
+        write!(out, "{}", if indent == 0 { "   " } else { "" })
+            .and(write_titled(
+                out,
+                &title,
+                &plain_title,
+                date.map(|date| (date, self.zone, hard)),
+                done,
+                column,
+                opts,
+            ))
+            .and(writeln!(out))?;
+        if let (0, Some(notes)) = (indent, &self.notes) {
+            let dimmed = opts.style(Style::new().dimmed());
+            for line in notes.trim_end().lines() {
+                writeln!(out, "   {}", dimmed.paint(line))?;
             }
-            ListItem::List(list) => (Some(list.title.clone()), Some(list.date)),
+            writeln!(out)?;
         }
-    } else {
-        (None, None)
+        opts.within(self, || self.write_items(out, indent, opts))
+    }
+
+    fn write_items(
+        &self,
+        out: &mut impl Write,
+        indent: usize,
+        opts: &RenderOptions,
+    ) -> std::io::Result<()> {
+        let last_shown = self.list.iter().rposition(|item| opts.shows(item));
+        self.list.iter().enumerate().try_for_each(|(i, item)| {
+            if !opts.shows(item) {
+                opts.skip_hints(item);
+                return Ok(());
+            }
+            let marker = opts.marker(item, i, indent);
+            let column = 3 * indent + display_width(&marker) + 1;
+            let marker =
+                opts.style(opts.marker_style(item, indent)).paint(marker);
+            write!(out, "{}", String::from("   ").repeat(indent))
+                .and(write!(out, "{} ", marker))
+                .and(item.write_to(out, indent + 1, column, opts))
+                .and(if Some(i) != last_shown || indent == 0 {
+                    writeln!(out)
+                } else {
+                    write!(out, "")
+                })
+        })
+    }
+
+    /// Writes the pinned items, each with its index, under a "Focus"
+    /// heading; writes nothing if none are pinned.
+    pub fn write_focus(
+        &self,
+        out: &mut impl Write,
+        opts: &RenderOptions,
+    ) -> std::io::Result<()> {
+        let focused: Vec<(Vec<usize>, &ListItem)> = self
+            .items_with_paths()
+            .into_iter()
+            .filter(|(path, item)| {
+                item.is_focused()
+                    && (opts.shows(item)
+                        || opts.tag.as_ref().is_some_and(|tag| {
+                            self.inherited_tags(path).contains(&tag.as_str())
+                        }))
+            })
+            .collect();
+        if focused.is_empty() {
+            return Ok(());
+        }
+        writeln!(out, "   {}", opts.style(Style::new().bold()).paint("Focus"))?;
+        for (index, item) in focused {
+            let index: Vec<String> =
+                index.iter().map(|i| i.to_string()).collect();
+            let marker = format!("{})", index.join(","));
+            let done = item.is_done();
+            let title =
+                opts.title_style(Style::new(), done).paint(item.title());
+            let painted = opts.style(Color::Yellow.normal()).paint(&marker);
+            write!(out, "{} ", painted)?;
+            let column = display_width(&marker) + 1;
+            write_titled(
+                out,
+                &title,
+                item.title(),
+                item.date()
+                    .map(|date| (date, item.zone(), item.is_deadline())),
+                done,
+                column,
+                opts,
+            )?;
+            writeln!(out)?;
+        }
+        writeln!(out)
+    }
+
+    pub fn write_header(
+        &self,
+        out: &mut impl Write,
+        opts: &RenderOptions,
+    ) -> std::io::Result<()> {
+        let done = self.completed_at.is_some();
+        let (bar, plain_bar) = progress_marker(self.progress(), opts);
+        let title = format!(
+            "{}{}",
+            opts.title_style(Style::new(), done)
+                .paint(self.title.as_str()),
+            bar
+        );
+        let plain_title = format!("{}{}", self.title, plain_bar);
+        write!(out, "{} ", opts.style(opts.theme.list_marker).paint("->"))
+            .and(write_titled(
+                out,
+                &title,
+                &plain_title,
+                self.date.map(|date| (date, self.zone, self.deadline)),
+                done,
+                3,
+                opts,
+            ))
+            .and(writeln!(out))
+    }
+
+    /// Explains why `index` doesn't address an item in this list: which
+    /// segment failed, the valid range at that level, and a rendering of the
+    /// sublist it was indexing into. With `allow_new`, the last segment may
+    /// point just past the end, or at an entry (which becomes a sublist), as
+    /// when adding or inserting. Returns `None` if the index looks valid.
+    pub fn explain_index(
+        &self,
+        index: &[usize],
+        allow_new: bool,
+        opts: &RenderOptions,
+    ) -> Option<String> {
+        let path = |n: usize| {
+            index[..n]
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let mut list = self;
+        for (n, &i) in index.iter().enumerate() {
+            let last = n + 1 == index.len();
+            // adding under an entry turns it into a sublist
+            let becomes_list = allow_new && n + 2 == index.len();
+            let len = list.list.len();
+            let problem = if i < len || (allow_new && last && i == len) {
+                match &list.list.get(i) {
+                    Some(ListItem::List(sublist)) => {
+                        list = sublist;
+                        continue;
+                    }
+                    Some(ListItem::Entry(entry)) if !(last || becomes_list) => {
+                        format!(
+                            "'{}' at {} is an entry, not a sublist, so it \
+                             can't be indexed into",
+                            entry.title,
+                            path(n + 1)
+                        )
+                    }
+                    _ => return None,
+                }
+            } else {
+                let max = if allow_new && last {
+                    len
+                } else {
+                    len.max(1) - 1
+                };
+                let range = if len == 0 && !(allow_new && last) {
+                    String::from("it has no items")
+                } else {
+                    format!("valid indices are 0 to {}", max)
+                };
+                format!(
+                    "segment {} of index {} is {}, but '{}' only has {} \
+                     item{} ({})",
+                    n + 1,
+                    path(index.len()),
+                    i,
+                    list.title,
+                    len,
+                    if len == 1 { "" } else { "s" },
+                    range
+                )
+            };
+            let mut rendering = Vec::new();
+            list.write_to(&mut rendering, 0, opts).ok()?;
+            return Some(format!(
+                "Invalid index! {}\n{}",
+                problem,
+                String::from_utf8_lossy(&rendering).trim_end()
+            ));
+        }
+        None
+    }
+
+    // the sublist at `path`, turning an entry at its end into one when
+    // `promote` is set, as adding under an entry does
+    fn sublist_mut(
+        &mut self,
+        path: &[usize],
+        promote: bool,
+    ) -> Result<&mut TodoList> {
+        let mut list = self;
+        for (segment, &i) in path.iter().enumerate() {
+            let len = list.list.len();
+            if i >= len {
+                return Err(Error::IndexOutOfBounds {
+                    segment,
+                    position: i,
+                    len,
+                });
+            }
+            let last = segment + 1 == path.len();
+            if let ListItem::Entry(_) = list.list[i] {
+                if !(promote && last) {
+                    return Err(Error::SubindexOnEntry { segment });
+                }
+                let entry = list.list.remove(i);
+                list.list.insert(i, ListItem::List(entry.promote_to_list()));
+            }
+            list = match &mut list.list[i] {
+                ListItem::List(sublist) => sublist,
+                ListItem::Entry(_) => unreachable!(),
+            };
+        }
+        Ok(list)
+    }
+
+    /// Adds `item` to the end of the sublist at `under`, or of the list
+    /// itself for the root; an entry there becomes a sublist to hold it.
+    pub fn append(&mut self, under: &ItemPath, item: ListItem) -> Result<()> {
+        debug!(list = %self.title, index = %under, "append");
+        self.sublist_mut(under, true)?.list.push(item);
+        Ok(())
+    }
+
+    /// Puts `item` at `path`, moving the items from there on down one; the
+    /// position may be just past the end. An entry holding the position
+    /// becomes a sublist.
+    pub fn insert(&mut self, path: &ItemPath, item: ListItem) -> Result<()> {
+        debug!(list = %self.title, index = %path, "insert");
+        let (parent, i) = path.split_last().ok_or(Error::EmptyIndex)?;
+        let list = self.sublist_mut(&parent, true)?;
+        if i > list.list.len() {
+            return Err(Error::IndexOutOfBounds {
+                segment: parent.len(),
+                position: i,
+                len: list.list.len(),
+            });
+        }
+        list.list.insert(i, item);
+        Ok(())
+    }
+
+    /// Takes out the item at `path`. A sublist left empty goes back to
+    /// being an entry.
+    pub fn remove(&mut self, path: &ItemPath) -> Result<ListItem> {
+        debug!(list = %self.title, index = %path, "remove");
+        let (parent, i) = path.split_last().ok_or(Error::EmptyIndex)?;
+        let list = self.sublist_mut(&parent, false)?;
+        if i >= list.list.len() {
+            return Err(Error::IndexOutOfBounds {
+                segment: parent.len(),
+                position: i,
+                len: list.list.len(),
+            });
+        }
+        let item = list.list.remove(i);
+        if let Some((grandparent, j)) = parent.split_last() {
+            let holder = self.sublist_mut(&grandparent, false)?;
+            if matches!(&holder.list[j], ListItem::List(l) if l.list.is_empty())
+            {
+                let emptied = holder.list.remove(j);
+                holder.list.insert(j, emptied.collapse_to_entry());
+            }
+        }
+        Ok(item)
+    }
+
+    /// The top-level items, in order.
+    pub fn items(&self) -> &[ListItem] {
+        &self.list
+    }
+
+    /// The item at an index path, if there is one.
+    pub fn get(&self, index: &[usize]) -> Option<&ListItem> {
+        let (&first, rest) = index.split_first()?;
+        match (self.list.get(first)?, rest) {
+            (item, []) => Some(item),
+            (ListItem::List(sublist), rest) => sublist.get(rest),
+            (ListItem::Entry(_), _) => None,
+        }
+    }
+
+    /// The item at an index path, for changing in place.
+    pub fn get_mut(&mut self, index: &[usize]) -> Option<&mut ListItem> {
+        let (&first, rest) = index.split_first()?;
+        match (self.list.get_mut(first)?, rest) {
+            (item, []) => Some(item),
+            (ListItem::List(sublist), rest) => sublist.get_mut(rest),
+            (ListItem::Entry(_), _) => None,
+        }
+    }
+
+    /// Exchanges the items at two index paths, neither of which may be
+    /// inside the other.
+    pub fn swap_items(&mut self, a: &[usize], b: &[usize]) -> Result<()> {
+        if a.starts_with(b) || b.starts_with(a) {
+            return Err(Error::SwapNested);
+        }
+        for index in [a, b] {
+            if self.get(index).is_none() {
+                return Err(Error::NoItem {
+                    index: ItemPath::from(index),
+                });
+            }
+        }
+        let placeholder =
+            ListItem::Entry(TodoEntry::from_info(String::new(), None));
+        let first = std::mem::replace(self.get_mut(a).unwrap(), placeholder);
+        let second = std::mem::replace(self.get_mut(b).unwrap(), first);
+        *self.get_mut(a).unwrap() = second;
+        self.get_mut(a).unwrap().touch();
+        self.get_mut(b).unwrap().touch();
+        Ok(())
+    }
+
+    /// Unpins every item.
+    pub fn clear_focus(&mut self) {
+        for item in &mut self.list {
+            if item.is_focused() {
+                item.set_focused(false);
+            }
+            if let ListItem::List(sublist) = item {
+                sublist.clear_focus();
+            }
+        }
+    }
+
+    /// The tags that the item at `path` inherits: those of this list and
+    /// of every sublist it's inside, each once.
+    pub fn inherited_tags(&self, path: &[usize]) -> Vec<&str> {
+        let mut tags: Vec<&str> = Vec::new();
+        let mut list = self;
+        for (depth, &i) in path.iter().enumerate() {
+            for tag in &list.tags {
+                if !tags.contains(&tag.as_str()) {
+                    tags.push(tag);
+                }
+            }
+            match list.list.get(i) {
+                Some(ListItem::List(sublist)) if depth + 1 < path.len() => {
+                    list = sublist
+                }
+                _ => break,
+            }
+        }
+        tags
+    }
+
+    /// Marks the list and everything in it not done again, for checklists
+    /// that get gone through over and over, returning how many items were
+    /// done. With `every`, dates before `today` move on by that much at a
+    /// time until they're today or later; hard deadlines stay put.
+    pub fn reset(
+        &mut self,
+        every: Option<Duration>,
+        today: NaiveDate,
+    ) -> usize {
+        let mut reopened = 0;
+        self.completed_at = None;
+        if let (Some(every), Some(date), false) =
+            (every, self.date, self.deadline)
+        {
+            self.date = Some(next_occurrence(date, every, today));
+        }
+        for item in self.list.iter_mut() {
+            match item {
+                ListItem::Entry(entry) => {
+                    if entry.completed_at.take().is_some() {
+                        reopened += 1;
+                    }
+                    if let (Some(every), Some(date), false) =
+                        (every, entry.date, entry.deadline)
+                    {
+                        entry.date = Some(next_occurrence(date, every, today));
+                    }
+                }
+                ListItem::List(sublist) => {
+                    if sublist.completed_at.is_some() {
+                        reopened += 1;
+                    }
+                    reopened += sublist.reset(every, today);
+                }
+            }
+        }
+        reopened
+    }
+
+    /// Takes out every item, at any depth, that `pick` picks, returning them
+    /// with the index paths they had, in the order the tree drew them. A
+    /// sublist that's picked goes with everything in it.
+    pub fn take_where(
+        &mut self,
+        pick: &impl Fn(&ListItem) -> bool,
+    ) -> Vec<(Vec<usize>, ListItem)> {
+        let mut taken = Vec::new();
+        for (i, mut item) in
+            std::mem::take(&mut self.list).into_iter().enumerate()
+        {
+            if pick(&item) {
+                taken.push((vec![i], item));
+                continue;
+            }
+            if let ListItem::List(sublist) = &mut item {
+                for (mut path, inner) in sublist.take_where(pick) {
+                    path.insert(0, i);
+                    taken.push((path, inner));
+                }
+            }
+            self.list.push(item);
+        }
+        taken
+    }
+
+    /// Every item in the tree, depth first, alongside its index path.
+    pub fn items_with_paths(&self) -> Vec<(Vec<usize>, &ListItem)> {
+        self.flatten().collect()
+    }
+
+    /// Every item in the list, at any depth, with its index path, in the
+    /// order the tree draws them: each sublist comes just before what's in
+    /// it.
+    pub fn flatten(&self) -> Flatten<'_> {
+        Flatten {
+            stack: vec![self.list.iter().enumerate()],
+            path: Vec::new(),
+        }
+    }
+
+    /// Sorts by date, soonest first, with undated items last, by priority
+    /// and then date, with items without a priority last, or by when items
+    /// were added, oldest first, with those from before that was recorded
+    /// counting as oldest. Items that tie are ordered by `tie_break`, and
+    /// keep their current order when that ties too, so sorting an already
+    /// sorted list changes nothing. With `roll_up`, undated sublists sort by
+    /// their [`rolled_up_date`](TodoList::rolled_up_date).
+    pub fn sort(&mut self, by: SortBy, tie_break: TieBreak, roll_up: bool) {
+        for item in self.list.iter_mut() {
+            if let ListItem::List(sublist) = item {
+                sublist.sort(by, tie_break, roll_up)
+            }
+        }
+        // sort_by_cached_key is stable
+        self.list.sort_by_cached_key(|item| {
+            let (age, priority) = match by {
+                SortBy::Date => (None, None),
+                // unprioritised items after Z
+                SortBy::Priority => {
+                    (None, Some(item.priority().map_or('[', |p| p.0)))
+                }
+                SortBy::Created => (Some(item.created_at()), None),
+            };
+            let date = if roll_up {
+                item.rolled_up_date()
+            } else {
+                item.date()
+            };
+            let date_maybe =
+                date.unwrap_or(DateMaybeTime::Date(chrono::naive::MAX_DATE));
+            let (date, time) = match date_maybe {
+                DateMaybeTime::Date(date) => (date, None),
+                DateMaybeTime::DateTime(datetime) => {
+                    (datetime.naive_local().date(), Some(datetime.time()))
+                }
+            };
+            let (title, created_at) = match tie_break {
+                TieBreak::Original => (None, None),
+                TieBreak::Title => (Some(item.title().to_lowercase()), None),
+                TieBreak::Created => (None, item.created_at()),
+            };
+            (age, priority, date, time, title, created_at)
+        });
+    }
+}
+
+/// What `sort` orders items by first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    Date,
+    Priority,
+    Created,
+}
+
+impl SortBy {
+    pub const NAMES: &'static [&'static str] = &["date", "priority", "created"];
+
+    pub fn from_name(name: &str) -> Option<SortBy> {
+        match name {
+            "date" => Some(SortBy::Date),
+            "priority" => Some(SortBy::Priority),
+            "created" => Some(SortBy::Created),
+            _ => None,
+        }
+    }
+}
+
+/// How `sort` orders items with the same date (or no date).
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TieBreak {
+    /// Keep them in the order they were in.
+    #[default]
+    Original,
+    /// Alphabetically, ignoring case.
+    Title,
+    /// Oldest first; items from before creation times were recorded count
+    /// as oldest.
+    Created,
+}
+
+impl TieBreak {
+    pub const NAMES: &'static [&'static str] =
+        &["original", "title", "created"];
+
+    pub fn from_name(name: &str) -> Option<TieBreak> {
+        match name {
+            "original" => Some(TieBreak::Original),
+            "title" => Some(TieBreak::Title),
+            "created" => Some(TieBreak::Created),
+            _ => None,
+        }
+    }
+}
+
+impl ListItem {
+    pub fn title(&self) -> &str {
+        match self {
+            ListItem::Entry(entry) => &entry.title,
+            ListItem::List(list) => &list.title,
+        }
+    }
+
+    pub fn date(&self) -> Option<DateMaybeTime> {
+        match self {
+            ListItem::Entry(entry) => entry.date,
+            ListItem::List(list) => list.date,
+        }
+    }
+
+    pub fn zone(&self) -> Option<Tz> {
+        match self {
+            ListItem::Entry(entry) => entry.zone,
+            ListItem::List(list) => list.zone,
+        }
+    }
+
+    pub fn tracked(&self) -> &[tracking::Interval] {
+        match self {
+            ListItem::Entry(entry) => &entry.tracked,
+            ListItem::List(list) => &list.tracked,
+        }
+    }
+
+    pub fn tracked_mut(&mut self) -> &mut Vec<tracking::Interval> {
+        match self {
+            ListItem::Entry(entry) => &mut entry.tracked,
+            ListItem::List(list) => &mut list.tracked,
+        }
+    }
+
+    /// The item as a sublist, keeping everything about it; an entry becomes
+    /// a sublist with no items yet.
+    pub fn promote_to_list(self) -> TodoList {
+        match self {
+            ListItem::Entry(TodoEntry {
+                title,
+                date,
+                created_at,
+                modified_at,
+                notes,
+                completed_at,
+                rescheduled,
+                deadline,
+                focused,
+                priority,
+                tags,
+                zone,
+                tracked,
+            }) => TodoList {
+                title,
+                date,
+                created_at,
+                modified_at,
+                notes,
+                completed_at,
+                rescheduled,
+                deadline,
+                focused,
+                priority,
+                tags,
+                zone,
+                tracked,
+                list: Vec::new(),
+            },
+            ListItem::List(list) => list,
+        }
+    }
+
+    /// A sublist with no items back as the entry it stands for, keeping
+    /// everything about it; anything else is returned as it is.
+    pub fn collapse_to_entry(self) -> ListItem {
+        match self {
+            ListItem::List(TodoList {
+                title,
+                date,
+                created_at,
+                modified_at,
+                notes,
+                completed_at,
+                rescheduled,
+                deadline,
+                focused,
+                priority,
+                tags,
+                zone,
+                tracked,
+                list,
+            }) if list.is_empty() => ListItem::Entry(TodoEntry {
+                title,
+                date,
+                created_at,
+                modified_at,
+                notes,
+                completed_at,
+                rescheduled,
+                deadline,
+                focused,
+                priority,
+                tags,
+                zone,
+                tracked,
+            }),
+            item => item,
+        }
+    }
+
+    pub fn set_title(&mut self, title: String) {
+        match self {
+            ListItem::Entry(entry) => entry.title = title,
+            ListItem::List(list) => list.title = title,
+        }
+        self.touch();
+    }
+
+    pub fn priority(&self) -> Option<Priority> {
+        match self {
+            ListItem::Entry(entry) => entry.priority,
+            ListItem::List(list) => list.priority,
+        }
+    }
+
+    pub fn set_priority(&mut self, priority: Option<Priority>) {
+        match self {
+            ListItem::Entry(entry) => entry.priority = priority,
+            ListItem::List(list) => list.priority = priority,
+        }
+        self.touch();
+    }
+
+    pub fn tags(&self) -> &[String] {
+        match self {
+            ListItem::Entry(entry) => &entry.tags,
+            ListItem::List(list) => &list.tags,
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags().iter().any(|t| t == tag)
+    }
+
+    /// Whether the item, or anything inside it, has `tag`.
+    pub fn has_tag_within(&self, tag: &str) -> bool {
+        self.has_tag(tag)
+            || match self {
+                ListItem::Entry(_) => false,
+                ListItem::List(list) => {
+                    list.list.iter().any(|item| item.has_tag_within(tag))
+                }
+            }
+    }
+
+    /// Adds `tag` unless the item already has it, returning whether it was
+    /// added.
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        if self.has_tag(tag) {
+            return false;
+        }
+        let tags = match self {
+            ListItem::Entry(entry) => &mut entry.tags,
+            ListItem::List(list) => &mut list.tags,
+        };
+        tags.push(String::from(tag));
+        self.touch();
+        true
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) {
+        if !self.has_tag(tag) {
+            return;
+        }
+        match self {
+            ListItem::Entry(entry) => entry.tags.retain(|t| t != tag),
+            ListItem::List(list) => list.tags.retain(|t| t != tag),
+        }
+        self.touch();
+    }
+
+    pub fn set_notes(&mut self, notes: Option<String>) {
+        match self {
+            ListItem::Entry(entry) => entry.notes = notes,
+            ListItem::List(list) => list.notes = notes,
+        }
+        self.touch();
+    }
+
+    pub fn notes(&self) -> Option<&str> {
+        match self {
+            ListItem::Entry(entry) => entry.notes.as_deref(),
+            ListItem::List(list) => list.notes.as_deref(),
+        }
+    }
+
+    pub fn created_at(&self) -> Option<DateTime<Local>> {
+        match self {
+            ListItem::Entry(entry) => entry.created_at,
+            ListItem::List(list) => list.created_at,
+        }
+    }
+
+    pub fn modified_at(&self) -> Option<DateTime<Local>> {
+        match self {
+            ListItem::Entry(entry) => entry.modified_at,
+            ListItem::List(list) => list.modified_at,
+        }
+    }
+
+    /// Notes that the item changed just now. The setters here do this
+    /// themselves; it's for changes they don't cover, such as moving it.
+    pub fn touch(&mut self) {
+        let now = Some(clock::now());
+        match self {
+            ListItem::Entry(entry) => entry.modified_at = now,
+            ListItem::List(list) => list.modified_at = now,
+        }
+    }
+
+    pub fn completed_at(&self) -> Option<DateTime<Local>> {
+        match self {
+            ListItem::Entry(entry) => entry.completed_at,
+            ListItem::List(list) => list.completed_at,
+        }
+    }
+
+    pub fn rescheduled(&self) -> &[Reschedule] {
+        match self {
+            ListItem::Entry(entry) => &entry.rescheduled,
+            ListItem::List(list) => &list.rescheduled,
+        }
+    }
+
+    /// Whether the item's date is a hard deadline.
+    pub fn is_deadline(&self) -> bool {
+        match self {
+            ListItem::Entry(entry) => entry.deadline,
+            ListItem::List(list) => list.deadline,
+        }
+    }
+
+    pub fn set_deadline(&mut self, deadline: bool) {
+        match self {
+            ListItem::Entry(entry) => entry.deadline = deadline,
+            ListItem::List(list) => list.deadline = deadline,
+        }
+        self.touch();
+    }
+
+    /// How soon the item's date comes, as a deadline if it is one.
+    pub fn urgency(&self) -> Option<Urgency> {
+        self.date().map(|date| date.urgency_as(self.is_deadline()))
+    }
+
+    pub fn is_focused(&self) -> bool {
+        match self {
+            ListItem::Entry(entry) => entry.focused,
+            ListItem::List(list) => list.focused,
+        }
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        match self {
+            ListItem::Entry(entry) => entry.focused = focused,
+            ListItem::List(list) => list.focused = focused,
+        }
+        self.touch();
+    }
+
+    /// The item's date, or for an undated list its
+    /// [`rolled_up_date`](TodoList::rolled_up_date).
+    pub fn rolled_up_date(&self) -> Option<DateMaybeTime> {
+        match self {
+            ListItem::Entry(entry) => entry.date,
+            ListItem::List(list) => list.rolled_up_date(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.completed_at().is_some()
+    }
+
+    /// Marks the item, and everything under it, done at `when`, or not
+    /// done if `when` is `None`.
+    pub fn set_completed(&mut self, when: Option<DateTime<Local>>) {
+        match self {
+            ListItem::Entry(entry) => entry.completed_at = when,
+            ListItem::List(list) => {
+                list.completed_at = when;
+                for item in &mut list.list {
+                    item.set_completed(when);
+                }
+            }
+        }
+        self.touch();
+    }
+
+    /// Changes the date, remembering the old one, and the zone its time of
+    /// day was given in.
+    pub fn reschedule(
+        &mut self,
+        date: Option<DateMaybeTime>,
+        zone: Option<Tz>,
+    ) {
+        match self {
+            ListItem::Entry(entry) => {
+                entry.reschedule(date);
+                entry.zone = zone;
+            }
+            ListItem::List(list) => {
+                list.reschedule(date);
+                list.zone = zone;
+            }
+        }
+    }
+
+    fn write_to(
+        &self,
+        out: &mut impl Write,
+        indent: usize,
+        column: usize,
+        opts: &RenderOptions,
+    ) -> std::io::Result<()> {
+        match self {
+            ListItem::Entry(entry) => entry.write_to(out, column, opts),
+            ListItem::List(list) => list.write_tree(out, indent, column, opts),
+        }
+    }
+}
+
+// Levenshtein distance, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// The items of a list and its sublists, depth first; see
+/// [`TodoList::flatten`].
+pub struct Flatten<'a> {
+    // the items still to come at each depth
+    stack: Vec<std::iter::Enumerate<std::slice::Iter<'a, ListItem>>>,
+    // the path of the item last returned
+    path: Vec<usize>,
+}
+
+impl<'a> Iterator for Flatten<'a> {
+    type Item = (Vec<usize>, &'a ListItem);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some((i, item)) => {
+                    self.path.truncate(self.stack.len() - 1);
+                    self.path.push(i);
+                    if let ListItem::List(sublist) = item {
+                        self.stack.push(sublist.list.iter().enumerate());
+                    }
+                    return Some((self.path.clone(), item));
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Every item in every list, with the list's name and the item's index
+/// path: lists in name order, and the items of each as its tree draws them.
+pub fn all_items(
+    lists: &HashMap<String, TodoList>,
+) -> Vec<(&str, Vec<usize>, &ListItem)> {
+    let mut names: Vec<&String> = lists.keys().collect();
+    names.sort();
+    names
+        .into_iter()
+        .flat_map(|name| {
+            lists[name]
+                .items_with_paths()
+                .into_iter()
+                .map(move |(path, item)| (name.as_str(), path, item))
+        })
+        .collect()
+}
+
+/// A copy of every list as a sublist of one called [`ALL_LISTS`], in name
+/// order, so that index `2,0` is the first item of the third list. Changes
+/// to it go nowhere; it's for showing, searching and exporting everything
+/// at once.
+pub fn all_lists(lists: &HashMap<String, TodoList>) -> TodoList {
+    let mut names: Vec<&String> = lists.keys().collect();
+    names.sort();
+    TodoList {
+        created_at: None,
+        modified_at: None,
+        list: names
+            .into_iter()
+            .map(|name| ListItem::List(lists[name].clone()))
+            .collect(),
+        ..TodoList::from_info(String::from(ALL_LISTS), None)
+    }
+}
+
+/// The names closest to a mistyped `name`, best first, for "did you mean"
+/// hints. Only reasonably close names are included.
+pub fn suggest_names<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a String>,
+) -> Vec<&'a str> {
+    let name = name.to_lowercase();
+    let max_distance = (name.chars().count() / 3).max(2);
+    let mut close: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .map(|c| (edit_distance(&name, &c.to_lowercase()), c.as_str()))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+    close.sort();
+    close.into_iter().take(3).map(|(_, c)| c).collect()
+}
+
+/// Formats suggestions as a hint to append to an error message.
+pub fn did_you_mean(suggestions: &[&str]) -> String {
+    match suggestions {
+        [] => String::new(),
+        [one] => format!(" Did you mean '{}'?", one),
+        _ => format!(
+            " Did you mean one of: {}?",
+            suggestions
+                .iter()
+                .map(|s| format!("'{}'", s))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Splits a trailing `yyyy/mm/dd` date, optionally followed by an `hh:mm`
+/// time, off the end of a line of text, e.g. `fix bug 2022/07/01 09:30`.
+/// Dates and times in the configured formats are split off too.
+pub fn split_inline_date(line: &str) -> (String, Option<DateMaybeTime>) {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let parse_date = formats::parse_date;
+    let parse_time = formats::parse_time;
+    let (rest, date, time) = match words.as_slice() {
+        [rest @ .., d, t]
+            if parse_date(d).is_some() && parse_time(t).is_some() =>
+        {
+            (rest, parse_date(d), parse_time(t))
+        }
+        [rest @ .., d] if parse_date(d).is_some() => {
+            (rest, parse_date(d), None)
+        }
+        _ => (words.as_slice(), None, None),
     };
-    let title = loop {
+    if rest.is_empty() {
+        // a bare date is a title, not a dated nothing
+        return (line.trim().to_string(), None);
+    }
+    (rest.join(" "), DateMaybeTime::from_parts(date, time))
+}
+
+// `date` moved on by whole steps of `every` until it's no longer before
+// `today`
+fn next_occurrence(
+    date: DateMaybeTime,
+    every: Duration,
+    today: NaiveDate,
+) -> DateMaybeTime {
+    let mut date = date;
+    if every > Duration::zero() {
+        while date.start().date() < today {
+            date = date.shifted(every);
+        }
+    }
+    date
+}
+
+/// The date `by` after `date`, or `by` from `now` for an item without one,
+/// as for `later snooze`: a plain date if `by` is whole days and a time
+/// otherwise. A plain date can only move by whole days, so it gets nothing
+/// for anything else.
+pub fn snoozed(
+    date: Option<DateMaybeTime>,
+    by: Duration,
+    now: DateTime<Local>,
+) -> Option<DateMaybeTime> {
+    let whole_days = by.num_seconds() % 86400 == 0;
+    match date {
+        Some(DateMaybeTime::Date(_)) if !whole_days => None,
+        Some(date) => Some(date.shifted(by)),
+        None if whole_days => {
+            Some(DateMaybeTime::Date(now.date().naive_local() + by))
+        }
+        None => Some(DateMaybeTime::DateTime(now + by)),
+    }
+}
+
+/// Parses the comma-separated segments of an index such as `1,3,2`, naming
+/// the offending segment if one isn't a number.
+pub fn parse_index<S: AsRef<str>>(segments: &[S]) -> Result<Vec<usize>> {
+    segments
+        .iter()
+        .enumerate()
+        .map(|(n, segment)| {
+            let segment = segment.as_ref().trim();
+            segment.parse().map_err(|_| {
+                let whole: Vec<&str> =
+                    segments.iter().map(|s| s.as_ref()).collect();
+                Error::BadIndexSegment {
+                    index: whole.join(","),
+                    segment: n,
+                    text: String::from(segment),
+                }
+            })
+        })
+        .collect()
+}
+
+/// Asks for just a title, e.g. when the date was given some other way.
+pub fn prompt_for_title() -> Result<String> {
+    read_title(&mut rustyline::Editor::<()>::new(), None)
+}
+
+fn read_title(
+    rl: &mut rustyline::Editor<()>,
+    prev_title: Option<&str>,
+) -> Result<String> {
+    loop {
         let title = match prev_title {
-            Some(ref t) => rl.readline_with_initial("title: ", (&t, ""))?,
+            Some(t) => rl.readline_with_initial("title: ", (t, ""))?,
             None => rl.readline("title: ")?,
         };
-        if title.len() == 0 {
+        if title.is_empty() {
             eprintln!("Please give the new list a title.",);
         } else {
-            break title;
+            return Ok(title);
+        }
+    }
+}
+
+pub fn prompt_for_info(
+    existing: Option<&ListItem>,
+) -> Result<(String, Option<DateMaybeTime>)> {
+    let mut rl = rustyline::Editor::<()>::new();
+    let (prev_title, prev_date) = if let Some(listitem) = existing {
+        match listitem {
+            ListItem::Entry(entry) => {
+                (Some(entry.title.clone()), Some(entry.date))
+            }
+            ListItem::List(list) => (Some(list.title.clone()), Some(list.date)),
         }
+    } else {
+        (None, None)
     };
-    let date = loop {
+    let title = read_title(&mut rl, prev_title.as_deref())?;
+    // a time typed along with the date, as in "friday 5pm", skips the
+    // time prompt
+    let (date, typed_time) = loop {
         let date = match prev_date {
-            Some(d) => match d {
-                Some(datemaybe) => rl.readline_with_initial(
-                    "date (?): ",
-                    (&datemaybe.date_string(), ""),
-                )?,
-                None => rl.readline("date (?): ")?,
-            },
-            None => rl.readline("date (?): ")?,
+            Some(Some(datemaybe)) => rl.readline_with_initial(
+                "date (?): ",
+                (&datemaybe.date_string(), ""),
+            )?,
+            _ => rl.readline("date (?): ")?,
         };
-        if date.len() == 0 {
-            break None;
+        if date.is_empty() {
+            break (None, None);
         } else {
-            match NaiveDate::parse_from_str(&date, "%Y/%m/%d") {
-                Ok(date) => break Some(date),
-                Err(_) => eprintln!("Error parsing date (format: yyyy/mm/dd)"),
+            match natural::parse_parts(&date, clock::today()) {
+                Some((date, time)) => break (Some(date), time),
+                None => {
+                    eprintln!("Error parsing date (e.g. {})", natural::EXAMPLES)
+                }
             }
         }
     };
+    if typed_time.is_some() {
+        return Ok((title, DateMaybeTime::from_parts(date, typed_time)));
+    }
     let time = loop {
         let time = match prev_date {
-            Some(d) => match d {
-                Some(datemaybe) => rl.readline_with_initial(
-                    "time (?): ",
-                    (&datemaybe.time_string(), ""),
-                )?,
-                None => rl.readline("time (?): ")?,
-            },
-            None => rl.readline("time (?): ")?,
+            Some(Some(datemaybe)) => rl.readline_with_initial(
+                "time (?): ",
+                (&datemaybe.time_string(), ""),
+            )?,
+            _ => rl.readline("time (?): ")?,
         };
-        if time.len() == 0 {
+        if time.is_empty() {
             break None;
         } else {
-            match NaiveTime::parse_from_str(&time, "%H:%M") {
-                Ok(time) => break Some(time),
-                Err(_) => eprintln!("Error parsing time (format: hh:mm)"),
+            match formats::parse_time(time.trim()) {
+                Some(time) => break Some(time),
+                None => eprintln!(
+                    "Error parsing time (e.g. {})",
+                    formats::time(NaiveTime::from_hms(17, 30, 0))
+                ),
             }
         }
     };
     Ok((title, DateMaybeTime::from_parts(date, time)))
 }
 
-pub fn save(todo_file: &Path, lists: &HashMap<String, TodoList>) -> Result<()> {
-    let json = serde_json::to_string_pretty(lists).with_context(|| {
-        format!(
-            "Couldn't generate to-do list file ({})",
-            todo_file.display()
-        )
-    })?;
-    std::fs::write(todo_file, json).with_context(|| {
-        format!("Couldn't write to-do list file ({})", todo_file.display())
-    })?;
+/// Reads the lists, creating the file with a default list if it's missing
+/// or empty.
+///
+/// Files in an older layout are upgraded as they're read, and saved in the
+/// current one the next time anything changes; see [`migrate`].
+#[instrument(skip_all, fields(file = %todo_file.display()))]
+pub fn load(todo_file: &Path) -> Result<HashMap<String, TodoList>> {
+    // make the file and parent folders if they don't exist
+    let read_error = |source| Error::Read {
+        what: "to-do list file",
+        path: todo_file.to_path_buf(),
+        source,
+    };
+    if let Some(todo_folder) = todo_file.parent() {
+        std::fs::DirBuilder::new()
+            .recursive(true)
+            .create(todo_folder)
+            .map_err(|source| Error::Write {
+                what: "to-do list folder",
+                path: todo_folder.to_path_buf(),
+                source,
+            })?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .read(true)
+        .create(true)
+        .truncate(false)
+        .open(todo_file)
+        .map_err(read_error)?;
+
+    let start = Instant::now();
+    let mut json = String::new();
+    file.read_to_string(&mut json).map_err(read_error)?;
+    debug!(bytes = json.len(), elapsed = ?start.elapsed(), "read file");
+    timings::record(timings::Phase::Load, start.elapsed());
+
+    // load existing lists or make a new one
+    if json.is_empty() {
+        let mut m = HashMap::new();
+        m.insert(String::from(DEFAULT_LIST), TodoList::default());
+        eprintln!("Generating new storage file in {}", todo_file.display());
+        // there's nothing to back up yet
+        save(todo_file, &m, 0)?;
+        Ok(m)
+    } else {
+        let start = Instant::now();
+        let json = crypt::open(&json)?;
+        let lists = migrate::parse(&json).map_err(|e| match e {
+            migrate::ParseError::Json(source) => Error::Corrupt {
+                path: todo_file.to_path_buf(),
+                source,
+            },
+            migrate::ParseError::TooNew { version } => Error::TooNew {
+                path: todo_file.to_path_buf(),
+                version,
+            },
+        })?;
+        debug!(lists = lists.len(), elapsed = ?start.elapsed(), "parsed");
+        timings::record(timings::Phase::Parse, start.elapsed());
+        Ok(lists)
+    }
+}
+
+/// Writes the lists, first keeping the file as it was among the last
+/// `backups` versions; see [`backup`].
+#[instrument(skip_all, fields(file = %todo_file.display()))]
+pub fn save(
+    todo_file: &Path,
+    lists: &HashMap<String, TodoList>,
+    backups: usize,
+) -> Result<()> {
+    let start = Instant::now();
+    let json =
+        migrate::to_string(lists).map_err(|source| Error::Serialize {
+            path: todo_file.to_path_buf(),
+            source,
+        })?;
+    let json = crypt::seal(&json)?;
+    let serialized = start.elapsed();
+    debug!(bytes = json.len(), elapsed = ?serialized, "serialized");
+    let start = Instant::now();
+    backup::rotate(todo_file, backups)?;
+    // write next to the file and rename over it, so a crash part way
+    // through leaves the old version rather than half of the new one
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(todo_file.file_name().unwrap_or_default());
+    temp_name.push(".tmp");
+    let temp = todo_file.with_file_name(temp_name);
+    std::fs::File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(json.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&temp, todo_file))
+        .map_err(|source| Error::Write {
+            what: "to-do list file",
+            path: todo_file.to_path_buf(),
+            source,
+        })?;
+    debug!(elapsed = ?start.elapsed(), "wrote file");
+    timings::record(timings::Phase::Save, serialized + start.elapsed());
     Ok(())
 }
@@ -0,0 +1,56 @@
+//! Caps on how many items a list holds, so capture lists like an inbox
+//! don't silently grow forever: past the cap, either a warning or the
+//! oldest items moving to another list.
+
+use crate::config::Limit;
+use crate::{ItemPath, TodoList};
+use std::collections::{BTreeMap, HashMap};
+
+/// Applies every limit, moving items where the limit says to, and returns
+/// a line to show for each list that was over.
+pub fn enforce(
+    lists: &mut HashMap<String, TodoList>,
+    limits: &BTreeMap<String, Limit>,
+) -> Vec<String> {
+    let mut notices = Vec::new();
+    for (name, limit) in limits {
+        let count = lists.get(name).map_or(0, |list| list.items().len());
+        if count <= limit.max {
+            continue;
+        }
+        let excess = count - limit.max;
+        let to = match &limit.move_to {
+            Some(to) if to != name => to,
+            _ => {
+                notices.push(format!(
+                    "'{}' has {} items, {} over its limit of {}",
+                    name, count, excess, limit.max
+                ));
+                continue;
+            }
+        };
+        let list = lists.get_mut(name).unwrap();
+        // oldest first; items from before creation times were recorded
+        // count as oldest, and ties keep their order
+        let mut oldest: Vec<usize> = (0..count).collect();
+        oldest.sort_by_key(|&i| list.items()[i].created_at());
+        let mut moving = oldest[..excess].to_vec();
+        moving.sort_unstable();
+        let moved: Vec<_> = moving
+            .iter()
+            .rev()
+            .map(|&i| list.remove(&ItemPath::from(vec![i])).unwrap())
+            .collect();
+        let target = lists
+            .entry(to.clone())
+            .or_insert_with(|| TodoList::from_info(to.clone(), None));
+        for item in moved.into_iter().rev() {
+            target.append(&ItemPath::root(), item).unwrap();
+        }
+        notices.push(format!(
+            "moved the {} oldest items from '{}' to '{}' (limit {})",
+            excess, name, to, limit.max
+        ));
+    }
+    notices
+}
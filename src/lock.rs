@@ -0,0 +1,76 @@
+//! The lock that keeps two copies of later, such as the command line and
+//! `later serve`, from changing the lists at the same time. Each takes it
+//! before reading the lists file and lets go once it's done writing, so
+//! neither saves over a change it never saw. It's a file of its own next to
+//! the lists, as saving puts a new lists file in place rather than writing
+//! into the old one.
+
+use crate::error::{Error, Result};
+use std::fs::{File, TryLockError};
+use std::path::{Path, PathBuf};
+
+/// Held until dropped.
+pub struct Lock {
+    _file: File,
+}
+
+/// The lock file for the lists in `todo_file`, e.g. `.later.json.lock`.
+pub fn path(todo_file: &Path) -> PathBuf {
+    let mut name = std::ffi::OsString::from(".");
+    name.push(todo_file.file_name().unwrap_or_default());
+    name.push(".lock");
+    todo_file.with_file_name(name)
+}
+
+fn open(todo_file: &Path) -> Result<File> {
+    let path = path(todo_file);
+    let write_error = |source| Error::Write {
+        what: "lock file",
+        path: path.clone(),
+        source,
+    };
+    if let Some(folder) = path.parent() {
+        std::fs::create_dir_all(folder).map_err(write_error)?;
+    }
+    File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)
+        .map_err(write_error)
+}
+
+/// The lock on the lists in `todo_file`, if nothing else has it.
+pub fn try_acquire(todo_file: &Path) -> Result<Option<Lock>> {
+    let file = open(todo_file)?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(Lock { _file: file })),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(source)) => Err(Error::Write {
+            what: "lock file",
+            path: path(todo_file),
+            source,
+        }),
+    }
+}
+
+/// The lock on the lists in `todo_file`, waiting for it (and saying so) if
+/// another copy of later has it.
+pub fn acquire(todo_file: &Path) -> Result<Lock> {
+    if let Some(lock) = try_acquire(todo_file)? {
+        return Ok(lock);
+    }
+    eprintln!("Waiting for another later to finish with the lists...");
+    let file = open(todo_file)?;
+    file.lock().map_err(|source| Error::Write {
+        what: "lock file",
+        path: path(todo_file),
+        source,
+    })?;
+    Ok(Lock { _file: file })
+}
+
+/// Whether another copy of later has the lock right now.
+pub fn is_held(todo_file: &Path) -> bool {
+    matches!(try_acquire(todo_file), Ok(None))
+}
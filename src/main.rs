@@ -1,16 +1,283 @@
+use ansi_term::Style;
 use anyhow::{bail, Context};
-use clap::{Arg, ArgGroup, Command};
+use chrono::{Datelike, TimeZone};
+use clap::{Arg, ArgGroup, ArgMatches, Command};
+use later::index_path::IndexPath;
 use later::*;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
-use std::io::Read;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use tracing::debug;
+use tracing_subscriber::EnvFilter;
 
 fn main() -> anyhow::Result<()> {
-    let args = Command::new("later")
+    // e.g. `LATER_LOG=debug later add ...`
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_env("LATER_LOG")
+                .unwrap_or_else(|_| EnvFilter::new("off")),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
+    let mut argv: Vec<String> = std::env::args().collect();
+    // browsers run the manifest's path directly, with their own arguments
+    if native_host::is_browser_launch(&argv[1..]) {
+        argv.truncate(1);
+        argv.push(String::from("native-host"));
+    }
+
+    let args = cli().get_matches_from(argv);
+    if args.is_present("plain") {
+        color::disable();
+    }
+    if let Some(("completions", completions_args)) = args.subcommand() {
+        let shell = completions_args.value_of_t_or_exit("shell");
+        completions::write_script(shell, &mut cli(), &mut std::io::stdout())?;
+        return Ok(());
+    }
+
+    let config_path = config::Config::default_path();
+    let loaded = match &config_path {
+        Some(path) => config::Config::load(path),
+        None => Ok(config::Config::default()),
+    };
+
+    // before anything can fail on a broken config or data file
+    if let Some(("doctor", _)) = args.subcommand() {
+        let data_dir = match &loaded {
+            Ok(config) => config.data_dir()?,
+            Err(_) => config::Config::default().data_dir()?,
+        };
+        let env = doctor::Environment {
+            config_path: config_path.as_deref(),
+            config: &loaded,
+            todo_file: &data_dir.join("later.json"),
+        };
+        doctor::write_report(&mut std::io::stdout(), &env)?;
+        return Ok(());
+    }
+    let mut config = loaded?;
+    formats::set(formats::Formats::from_config(&config.display)?);
+
+    // quietly, as it runs on every tab press
+    if let Some(("complete", complete_args)) = args.subcommand() {
+        let todo_file = config.data_dir()?.join("later.json");
+        let lists: HashMap<String, TodoList> =
+            match std::fs::read_to_string(&todo_file) {
+                // without asking for a passphrase on every tab press
+                Ok(text)
+                    if crypt::is_sealed(&text)
+                        && std::env::var_os(crypt::PASSPHRASE_VAR)
+                            .is_none() =>
+                {
+                    HashMap::new()
+                }
+                Ok(text) => crypt::open(&text)
+                    .ok()
+                    .and_then(|text| migrate::parse(&text).ok())
+                    .unwrap_or_default(),
+                Err(_) => HashMap::new(),
+            };
+        let words = match complete_args.value_of("what") {
+            Some("tags") => completions::tags(&lists),
+            _ => completions::list_names(&lists),
+        };
+        for word in words {
+            println!("{}", word);
+        }
+        return Ok(());
+    }
+
+    // find folder + file name
+    let mut todo_folder = config.data_dir()?;
+    let mut todo_file = todo_folder.join("later.json");
+
+    if let Some(config_path) = &config_path {
+        // browsers and URL handlers have nobody to answer questions
+        let unattended = !std::io::stdin().is_terminal()
+            || matches!(
+                args.subcommand_name(),
+                Some("native-host" | "handle-url" | "serve" | "notify")
+            );
+        let asked = args.subcommand_name() == Some("setup");
+        if asked
+            || (!unattended && setup::is_first_run(config_path, &todo_file))
+        {
+            let default_dir = config::Config::default().data_dir()?;
+            config = setup::run(&config, &default_dir)?;
+            config.save(config_path)?;
+            println!("Saved settings to {}", config_path.display());
+            todo_folder = config.data_dir()?;
+            todo_file = todo_folder.join("later.json");
+            if !todo_file.exists() {
+                let name = String::from(config.default_list());
+                let mut lists = HashMap::new();
+                lists.insert(name.clone(), TodoList::from_info(name, None));
+                std::fs::create_dir_all(&todo_folder).with_context(|| {
+                    format!("Couldn't create {}", todo_folder.display())
+                })?;
+                save(&todo_file, &lists, config.general.backups)?;
+            }
+            if asked {
+                return Ok(());
+            }
+        }
+    }
+
+    // before loading, which fails if the file is damaged
+    if let Some(("open-data", open_args)) = args.subcommand() {
+        println!("{}", todo_file.display());
+        if open_args.is_present("edit") {
+            run_editor(&todo_file)?;
+        } else if open_args.is_present("folder") {
+            open_folder(&todo_folder)?;
+        }
+        return Ok(());
+    }
+    // held until this run is done with the lists; commands that keep
+    // running only read them, or lock for each change they make
+    let _lock = match args.subcommand() {
+        Some(("serve" | "watch" | "countdown" | "native-host", _)) => None,
+        Some(("notify", notify_args)) if notify_args.is_present("daemon") => {
+            None
+        }
+        _ => Some(lock::acquire(&todo_file)?),
+    };
+    if let Some(("edit-raw", _)) = args.subcommand() {
+        return edit_raw(
+            &todo_file,
+            args.is_present("dry-run"),
+            config.general.backups,
+        );
+    }
+    if let Some(("restore", restore_args)) = args.subcommand() {
+        let n = match restore_args.value_of("backup") {
+            Some(n) => {
+                Some(n.parse().ok().filter(|&n| n > 0).with_context(|| {
+                    format!(
+                        "Invalid backup number '{}' (expected 1-{})",
+                        n, config.general.backups
+                    )
+                })?)
+            }
+            None => None,
+        };
+        let (n, lists) = backup::read(&todo_file, n, config.general.backups)?;
+        let from = backup::path(&todo_file, n);
+        if args.is_present("dry-run") {
+            println!(
+                "would restore {} from {}",
+                todo_file.display(),
+                from.display()
+            );
+            eprintln!("Dry run, nothing was saved.");
+            return Ok(());
+        }
+        save(&todo_file, &lists, config.general.backups)?;
+        println!(
+            "restored {} lists from {} (the replaced version is now backup 1)",
+            lists.len(),
+            from.display()
+        );
+        return Ok(());
+    }
+
+    let mut lists: HashMap<String, TodoList> = load(&todo_file)?;
+    let snapshot = if args.is_present("dry-run") {
+        Some(serde_json::to_string_pretty(&lists)?)
+    } else {
+        None
+    };
+    // `now` is for getting a thought down before it's gone, so it goes
+    // straight to saving, without an undo step or the sweeps below
+    let quick = args.subcommand_name() == Some("now");
+    // what `later undo` goes back to, unless nothing will be saved
+    let before =
+        if snapshot.is_none() && !quick && config.general.undo_depth > 0 {
+            Some(sync::snapshot(&lists)?)
+        } else {
+            None
+        };
+    let store = store::Store::new(&todo_file, &config);
+    let session = Session {
+        store: &store,
+        snapshot: snapshot.as_deref(),
+        deferred: false,
+        command: args.subcommand_name().unwrap_or("later"),
+        before: Cell::new(before),
+        events: RefCell::new(Vec::new()),
+        archived: RefCell::new(Vec::new()),
+    };
+    if config.escalate.on_load && !quick {
+        let changed = escalate::escalate(
+            &mut lists,
+            &config.escalate.rules,
+            clock::now(),
+        );
+        // on a dry run, the changes show up in the command's own diff
+        if changed > 0 && snapshot.is_none() {
+            session.commit(&mut lists)?;
+        }
+    }
+    if let Some(days) = config.archive.after_days.filter(|_| !quick) {
+        let cutoff = clock::now() - chrono::Duration::days(days);
+        let taken = archive::take_completed(&mut lists, cutoff);
+        if !taken.is_empty() && snapshot.is_none() {
+            session.archived.borrow_mut().extend(taken);
+            session.commit(&mut lists)?;
+        }
+    }
+    let result = run(&args, &mut lists, &config, &session);
+    if args.is_present("verbose") {
+        timings::write_report(&mut std::io::stderr(), &config.budgets)?;
+    }
+    result
+}
+
+fn cli() -> Command<'static> {
+    Command::new("later")
         .about("Autumn's to-do list program")
-        .long_about("This program allows nested lists. The index of a nested list should be given as a comma-separated list of integers starting with the top-level list index. e.g. `later add 1,3,1,2`")
+        .long_about("This program allows nested lists. The index of a nested list should be given as a comma- or dot-separated list of integers starting with the top-level list index, e.g. `later add 1,3,1,2` or `later add 1.3.1.2`. Items can also be picked by title, a level at a time: `later remove groceries/milk` finds the item titled, or starting with, \"milk\" in the sublist \"groceries\".")
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .global(true)
+                .help("show what would change without saving"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .global(true)
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(["tree", "table"])
+                .default_value("tree")
+                .help("how to display the list"),
+        )
+        .arg(
+            Arg::new("plain")
+                .long("plain")
+                .global(true)
+                .help("print without colors or other styling, as when NO_COLOR is set"),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .global(true)
+                .help("report how long loading, parsing, sorting, drawing and saving took, warning about any over budget"),
+        )
+        .arg(
+            Arg::new("hints")
+                .long("hints")
+                .global(true)
+                .help("label items with two-letter codes, which the next command accepts in place of an index"),
+        )
         .arg(
             Arg::new("list-name")
-                .help("name of to-do list")
+                .help("name of to-do list, or @all to show every list at once")
                 .takes_value(true)
                 .value_name("LIST NAME"),
         )
@@ -30,6 +297,85 @@ fn main() -> anyhow::Result<()> {
                     Arg::new("name")
                         .help("name of item to add")
                         .takes_value(true),
+                )
+                .arg(
+                    Arg::new("title")
+                        .long("title")
+                        .help("name of item to add, so the index can't be mistaken for one")
+                        .takes_value(true)
+                        .value_name("TITLE")
+                        .forbid_empty_values(true)
+                        .conflicts_with_all(&["name", "stdin", "template"]),
+                )
+                .arg(
+                    Arg::new("stdin")
+                        .long("stdin")
+                        .help("add each line of stdin as an entry, to LIST if given (a trailing yyyy/mm/dd [hh:mm] becomes its date)")
+                        .takes_value(true)
+                        .value_name("LIST")
+                        .min_values(0)
+                        .multiple_values(false)
+                        .conflicts_with("name"),
+                )
+                .arg(
+                    Arg::new("template")
+                        .long("template")
+                        .help("title with {date}, {week}, {month}... filled in, or the name of one from the config")
+                        .takes_value(true)
+                        .value_name("TEMPLATE")
+                        .conflicts_with_all(&["name", "stdin"]),
+                )
+                .arg(
+                    Arg::new("date")
+                        .long("date")
+                        .help("date to give the item, e.g. tomorrow, next friday 9am, in 3 days, jan 15; a zone after the time, as in friday 9:40 JST, keeps it in that zone's time")
+                        .takes_value(true)
+                        .value_name("DATE")
+                        .conflicts_with("stdin"),
+                )
+                .arg(
+                    Arg::new("time")
+                        .long("time")
+                        .help("time of day to give the item, e.g. 17:30 or 5pm; without --date, on the list's default date or today")
+                        .takes_value(true)
+                        .value_name("TIME")
+                        .conflicts_with("stdin"),
+                )
+                .arg(
+                    Arg::new("no-date")
+                        .long("no-date")
+                        .help("leave the item undated, even if the list has a default date")
+                        .conflicts_with_all(&["date", "time", "stdin"]),
+                )
+                .arg(
+                    Arg::new("deadline")
+                        .long("deadline")
+                        .help("make the date a hard deadline: urgent 3 days early, never moved on by reset --every, and listed apart by agenda and motd")
+                        .conflicts_with_all(&["no-date", "stdin"]),
+                )
+                .arg(
+                    Arg::new("priority")
+                        .long("priority")
+                        .short('p')
+                        .help("priority to give the item: A-Z, or high, medium, low")
+                        .takes_value(true)
+                        .value_name("PRIORITY")
+                        .conflicts_with("stdin"),
+                )
+                .arg(
+                    Arg::new("tag")
+                        .long("tag")
+                        .short('t')
+                        .help("tag to give the item, e.g. work; repeat for more")
+                        .takes_value(true)
+                        .value_name("TAG")
+                        .multiple_occurrences(true)
+                        .conflicts_with("stdin"),
+                )
+                .arg(
+                    Arg::new("no-prompt")
+                        .long("no-prompt")
+                        .help("fail rather than ask for a title that wasn't given, as happens anyway when stdin isn't a terminal"),
                 ),
             Command::new("remove")
                 .short_flag('r')
@@ -37,11 +383,60 @@ fn main() -> anyhow::Result<()> {
                 .arg(
                     Arg::new("index")
                         .help("index of item to remove")
-                        .required(true)
+                        .required_unless_present("pick")
+                        .takes_value(true)
+                        .forbid_empty_values(true)
+                        .use_value_delimiter(true)
+                        .require_value_delimiter(true),
+                )
+                .arg(
+                    Arg::new("archive")
+                        .long("archive")
+                        .help("keep it in the archive instead of deleting it for good"),
+                )
+                .arg(
+                    Arg::new("pick")
+                        .long("pick")
+                        .help("choose the item by typing part of its title instead")
+                        .conflicts_with("index"),
+                ),
+            Command::new("done")
+                .short_flag('d')
+                .about("mark an item, and everything under it, as done")
+                .arg(
+                    Arg::new("index")
+                        .help("index of item to mark")
+                        .required_unless_present("pick")
                         .takes_value(true)
                         .forbid_empty_values(true)
                         .use_value_delimiter(true)
                         .require_value_delimiter(true),
+                )
+                .arg(
+                    Arg::new("reopen")
+                        .long("reopen")
+                        .help("mark it as not done again"),
+                )
+                .arg(
+                    Arg::new("pick")
+                        .long("pick")
+                        .help("choose the item by typing part of its title instead")
+                        .conflicts_with("index"),
+                ),
+            Command::new("focus")
+                .about("pin items to a focus section above the list")
+                .arg(
+                    Arg::new("index")
+                        .help("indices of items to pin, e.g. 3 7,0")
+                        .takes_value(true)
+                        .multiple_values(true)
+                        .required_unless_present("clear"),
+                )
+                .arg(
+                    Arg::new("clear")
+                        .long("clear")
+                        .help("unpin every item")
+                        .conflicts_with("index"),
                 ),
             Command::new("list")
                 .short_flag('l')
@@ -67,10 +462,20 @@ fn main() -> anyhow::Result<()> {
                         .help("edit a to-do list")
                         .takes_value(true)
                         .value_name("LIST NAME"),
+                    Arg::new("notes")
+                        .short('n')
+                        .long("notes")
+                        .help("edit a to-do list's notes, shown above its items, in $EDITOR")
+                        .takes_value(true)
+                        .value_name("LIST NAME"),
+                    Arg::new("tiers")
+                        .short('t')
+                        .long("tiers")
+                        .help("group the lists into those with something overdue, something due this week, and the quiet ones"),
                 ])
                 .group(
                     ArgGroup::new("list_funcs")
-                        .args(&["add", "remove", "edit"]),
+                        .args(&["add", "remove", "edit", "notes"]),
                 ),
             Command::new("move")
                 .short_flag('m')
@@ -78,7 +483,7 @@ fn main() -> anyhow::Result<()> {
                 .arg(
                     Arg::new("from")
                         .help("index of item to move")
-                        .required(true)
+                        .required_unless_present("pick")
                         .takes_value(true)
                         .use_value_delimiter(true)
                         .require_value_delimiter(true),
@@ -86,10 +491,49 @@ fn main() -> anyhow::Result<()> {
                 .arg(
                     Arg::new("to")
                         .help("index at which to insert item")
+                        .required_unless_present("pick")
+                        .takes_value(true)
+                        .use_value_delimiter(true)
+                        .require_value_delimiter(true),
+                )
+                .arg(
+                    Arg::new("place")
+                        .long("place")
+                        .help("where to put the item relative to the one already at TO; without it, you're asked, or when not interactive the item is taken out and then inserted at TO")
+                        .takes_value(true)
+                        .value_name("PLACE")
+                        .possible_values(Place::NAMES),
+                )
+                .arg(
+                    Arg::new("pick")
+                        .long("pick")
+                        .help("choose the item, and the one to put it by, by typing part of their titles instead")
+                        .conflicts_with_all(&["from", "to"]),
+                ),
+            Command::new("copy")
+                .about("copy an item and everything under it")
+                .arg(
+                    Arg::new("from")
+                        .help("index of item to copy")
+                        .required(true)
+                        .takes_value(true)
+                        .use_value_delimiter(true)
+                        .require_value_delimiter(true),
+                )
+                .arg(
+                    Arg::new("to")
+                        .help("index at which to insert the copy")
                         .required(true)
                         .takes_value(true)
                         .use_value_delimiter(true)
                         .require_value_delimiter(true),
+                )
+                .arg(
+                    Arg::new("anchor")
+                        .long("anchor")
+                        .help("move the copy's dates so that its own date lands on this day (yyyy-mm-dd)")
+                        .takes_value(true)
+                        .value_name("DATE"),
                 ),
             Command::new("edit")
                 .short_flag('e')
@@ -97,256 +541,3702 @@ fn main() -> anyhow::Result<()> {
                 .arg(
                     Arg::new("index")
                         .help("index of item to edit")
+                        .required_unless_present("pick")
+                        .use_value_delimiter(true)
+                        .require_value_delimiter(true),
+                )
+                .arg(
+                    Arg::new("title")
+                        .long("title")
+                        .help("set the title instead of prompting")
+                        .takes_value(true)
+                        .value_name("TITLE")
+                        .forbid_empty_values(true),
+                )
+                .arg(
+                    Arg::new("date")
+                        .long("date")
+                        .help("set the date instead of prompting, as add --date reads it")
+                        .takes_value(true)
+                        .value_name("DATE"),
+                )
+                .arg(
+                    Arg::new("time")
+                        .long("time")
+                        .help("set the time of day instead of prompting, e.g. 17:30 or 5pm; without --date, on the item's date or today")
+                        .takes_value(true)
+                        .value_name("TIME"),
+                )
+                .arg(
+                    Arg::new("no-date")
+                        .long("no-date")
+                        .help("remove the item's date instead of prompting")
+                        .conflicts_with_all(&["date", "time", "deadline"]),
+                )
+                .arg(
+                    Arg::new("priority")
+                        .long("priority")
+                        .short('p')
+                        .help("set the priority instead of prompting: A-Z, high, medium, low, or none")
+                        .takes_value(true)
+                        .value_name("PRIORITY"),
+                )
+                .arg(
+                    Arg::new("tag")
+                        .long("tag")
+                        .short('t')
+                        .help("add a tag instead of prompting; repeat for more")
+                        .takes_value(true)
+                        .value_name("TAG")
+                        .multiple_occurrences(true),
+                )
+                .arg(
+                    Arg::new("untag")
+                        .long("untag")
+                        .help("remove a tag instead of prompting; repeat for more")
+                        .takes_value(true)
+                        .value_name("TAG")
+                        .multiple_occurrences(true),
+                )
+                .arg(
+                    Arg::new("deadline")
+                        .long("deadline")
+                        .help("make the item's date a hard deadline instead of prompting"),
+                )
+                .arg(
+                    Arg::new("soft")
+                        .long("soft")
+                        .help("make the item's date a soft target again instead of prompting")
+                        .conflicts_with("deadline"),
+                )
+                .arg(
+                    Arg::new("notes")
+                        .long("notes")
+                        .short('n')
+                        .help("edit the item's notes in $EDITOR instead")
+                        .conflicts_with_all(&[
+                            "priority", "tag", "untag", "deadline", "soft",
+                            "title", "date", "time", "no-date",
+                        ]),
+                )
+                .arg(
+                    Arg::new("pick")
+                        .long("pick")
+                        .help("choose the item by typing part of its title instead")
+                        .conflicts_with("index"),
+                )
+                .arg(
+                    Arg::new("no-prompt")
+                        .long("no-prompt")
+                        .help("fail rather than prompt when no change is given, as happens anyway when stdin isn't a terminal"),
+                ),
+            Command::new("snooze")
+                .about("push an item's date back by a while, or to a given date")
+                .long_about("Move an item's date later by a length of time such as 3h, 1d or 2w, keeping its time of day; an item without a date gets one that far from now. Items with a plain date only move by whole days. With --to, the item gets that date instead, as `later add --date` reads it.")
+                .arg(
+                    Arg::new("index")
+                        .help("index of the item to snooze")
                         .required(true)
                         .use_value_delimiter(true)
                         .require_value_delimiter(true),
+                )
+                .arg(
+                    Arg::new("by")
+                        .help("how much later, e.g. 3h, 1d, 2w")
+                        .value_name("DURATION"),
+                )
+                .arg(
+                    Arg::new("to")
+                        .long("to")
+                        .help("the new date, e.g. friday or 'tomorrow 9am'")
+                        .takes_value(true)
+                        .value_name("DATE")
+                        .conflicts_with("by"),
+                )
+                .group(
+                    ArgGroup::new("when")
+                        .args(&["by", "to"])
+                        .required(true),
                 ),
             Command::new("sort")
                 .short_flag('s')
-                .about("sort a list by date")
-        ])
-        .get_matches();
-
-    // find folder + file name
-    let todo_folder = if let Some(path) = dirs::data_local_dir() {
-        path.join("later")
-    } else {
-        // should never happen on common operating systems
-        bail!("Could not find standard local data directory.")
-    };
-    let todo_file = todo_folder.join("later.json");
-
-    // make the file and parent folders if they don't exist
-    std::fs::DirBuilder::new()
-        .recursive(true)
-        .create(todo_folder.clone())?;
-    let mut file = std::fs::OpenOptions::new()
-        .write(true)
-        .read(true)
-        .create(true)
-        .open(&todo_file)?;
-
-    let mut json = String::new();
-    file.read_to_string(&mut json).with_context(|| {
-        format!("Couldn't read to-do list file ({})", &todo_file.display())
-    })?;
-
-    // load existing lists or make a new one
-    let mut lists: HashMap<String, TodoList> = if json.is_empty() {
-        let mut m = HashMap::new();
-        m.insert(String::from(DEFAULT_LIST), TodoList::default());
-        println!("Generating new storage file in {}", todo_file.display());
-        save(&todo_file, &m)?;
-        m
-    } else {
-        serde_json::from_str(&json).with_context(|| {
-            format!("Couldn't parse to-do list file ({})", todo_file.display())
-        })?
-    };
-
-    // use list-name argument, otherwise use default list
-    let list_name = if args.is_present("list-name") {
-        args.value_of("list-name").unwrap()
-    } else {
-        if !lists.contains_key(DEFAULT_LIST) {
-            lists.insert(String::from(DEFAULT_LIST), TodoList::default());
-        }
-        DEFAULT_LIST
-    };
-
-    // get the active list struct
-    let active_list =
-        if let Some(list) = lists.get_mut(&String::from(list_name)) {
-            list
-        } else {
-            bail!("List '{}' not found!", list_name);
-        };
-
-    let mut stdout = std::io::stdout();
-    match args.subcommand() {
-        Some(("list", list_args)) => {
-            if list_args.is_present("add") {
-                // add new list
-                let (title, date) = match list_args.value_of_t::<String>("add")
-                {
-                    Ok(title) => (title, None),
-                    Err(_) => prompt_for_info(None)?,
-                };
-                if lists.contains_key(&title) {
-                    bail!("The list '{}' already exists", title);
-                }
-                lists.insert(
-                    title.clone(),
-                    TodoList::from_info(title.clone(), date),
-                );
-                save(&todo_file, &lists)?;
-                println!("added new to-do list: '{}'", title);
-            } else if list_args.is_present("remove") {
-                // remove list
-
-                let title: String = list_args.value_of_t_or_exit("remove");
-                if title == DEFAULT_LIST {
-                    bail!("You cannot remove the default to-do list!");
-                } else if !lists.contains_key(&title) {
-                    bail!(
-                        "The to-do list '{}' does not currently exist",
-                        title
-                    );
-                }
-
-                let mut rl = rustyline::Editor::<()>::new();
-                let confirm =
-                    rl.readline(&format!("Remove list '{}'? (y/N): ", title))?;
-                if confirm.to_lowercase() == "y" {
-                    lists.remove(&title);
-                    save(&todo_file, &lists)?;
-                    println!("removed to-do list: '{}'", title);
-                } else {
-                    bail!("Cancelled.");
-                }
-            } else if list_args.is_present("edit") {
-                // edit list
-                let title: String = list_args.value_of_t_or_exit("edit");
-                let removed_list = match lists.remove(&title) {
-                    Some(l) => l,
-                    None => {
-                        bail!("The list '{}' does not currently exist", title)
-                    }
-                };
-                let list_item = ListItem::List(removed_list);
-                let (new_title, new_date) = prompt_for_info(Some(&list_item))?;
-                if let ListItem::List(mut l) = list_item {
-                    l.title = new_title.clone();
-                    l.date = new_date;
-                    if lists.contains_key(&new_title) {
-                        bail!(
-                            "Weird, the list '{}' already exists. Edit reverted.",
-                            new_title
-                        );
-                    }
-                    lists.insert(new_title.clone(), l);
-                    save(&todo_file, &lists)?;
-                }
-            }
+                .about("sort a list by date, priority or when items were added")
+                .arg(
+                    Arg::new("by")
+                        .long("by")
+                        .help("what to sort by; priority sorts by date within each priority, and created puts the oldest items first")
+                        .takes_value(true)
+                        .value_name("KEY")
+                        .possible_values(SortBy::NAMES)
+                        .default_value("date"),
+                )
+                .arg(
+                    Arg::new("then")
+                        .long("then")
+                        .help("order of items with the same date (default from the config, or original)")
+                        .takes_value(true)
+                        .value_name("TIE BREAK")
+                        .possible_values(TieBreak::NAMES),
+                ),
+            Command::new("reset")
+                .about("mark everything in a list not done again, as for a checklist")
+                .arg(
+                    Arg::new("list")
+                        .help("list to reset (default: the current list)"),
+                )
+                .arg(
+                    Arg::new("every")
+                        .long("every")
+                        .help("how often the items recur, e.g. 1w; past dates move on by that much until they're upcoming")
+                        .takes_value(true)
+                        .value_name("PERIOD"),
+                ),
+            Command::new("clear")
+                .about("remove every done item in a list or sublist, at any depth")
+                .arg(
+                    Arg::new("target")
+                        .help("list to clear, or index of a sublist in the current list (default: the current list)"),
+                )
+                .arg(
+                    Arg::new("archive")
+                        .long("archive")
+                        .help("archive the items instead, so `later archive restore` can bring them back"),
+                ),
+            Command::new("prune")
+                .about("remove the unfinished items in a list whose dates are long past")
+                .arg(
+                    Arg::new("list")
+                        .help("list to prune (default: the current list)"),
+                )
+                .arg(
+                    Arg::new("overdue-older-than")
+                        .long("overdue-older-than")
+                        .help("how long ago an item's date must have been, e.g. 30d, 8w, 1y")
+                        .takes_value(true)
+                        .value_name("AGE")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("archive")
+                        .long("archive")
+                        .help("archive the items instead, so `later archive restore` can bring them back"),
+                ),
+            Command::new("split")
+                .about("break a sublist up into several, by month, tag or size")
+                .arg(
+                    Arg::new("index")
+                        .help("index of the sublist to split")
+                        .required(true)
+                        .takes_value(true)
+                        .forbid_empty_values(true)
+                        .use_value_delimiter(true)
+                        .require_value_delimiter(true),
+                )
+                .arg(
+                    Arg::new("by")
+                        .long("by")
+                        .help("date for one sublist per month, tag for one per first tag, or count N for runs of N items")
+                        .takes_value(true)
+                        .value_name("RULE")
+                        .min_values(1)
+                        .max_values(2)
+                        .required(true),
+                ),
+            Command::new("start")
+                .about("start tracking time spent on an item, stopping any other")
+                .arg(
+                    Arg::new("index")
+                        .help("index of the item to track")
+                        .required(true)
+                        .takes_value(true)
+                        .forbid_empty_values(true)
+                        .use_value_delimiter(true)
+                        .require_value_delimiter(true),
+                ),
+            Command::new("stop")
+                .about("stop tracking time on the item being tracked"),
+            Command::new("demo")
+                .about("fill a throwaway list with random sample items")
+                .args(vec![
+                    Arg::new("items")
+                        .long("items")
+                        .help("number of items to generate")
+                        .takes_value(true)
+                        .value_name("N")
+                        .default_value("20"),
+                    Arg::new("depth")
+                        .long("depth")
+                        .help("maximum nesting depth")
+                        .takes_value(true)
+                        .value_name("D")
+                        .default_value("3"),
+                    Arg::new("name")
+                        .long("name")
+                        .help("name of the list to create")
+                        .takes_value(true)
+                        .value_name("LIST NAME")
+                        .default_value("sample"),
+                    Arg::new("seed")
+                        .long("seed")
+                        .help("seed for reproducible output")
+                        .takes_value(true)
+                        .value_name("SEED"),
+                    Arg::new("file")
+                        .long("file")
+                        .help("write a separate data file instead")
+                        .takes_value(true)
+                        .value_name("PATH"),
+                ]),
+            Command::new("now")
+                .about("capture an item in the inbox list, no questions asked (and no undo)")
+                .arg(
+                    Arg::new("text")
+                        .help("text of the item")
+                        .required(true)
+                        .multiple_values(true),
+                ),
+            Command::new("handle-url")
+                .about("act on a later://add?list=...&title=...&due=... URL")
+                .arg(
+                    Arg::new("url")
+                        .help("URL to handle")
+                        .required_unless_present("install"),
+                )
+                .arg(
+                    Arg::new("install")
+                        .long("install")
+                        .help("register later as the handler for later:// URLs"),
+                ),
+            Command::new("import")
+                .about("add items to a list from another format")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("format of the file to import")
+                        .takes_value(true)
+                        .required(true)
+                        .possible_values(import::Format::NAMES),
+                )
+                .arg(
+                    Arg::new("path")
+                        .help("file (or directory, for maildirs) to import")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("map")
+                        .long("map")
+                        .help("TOML file saying which JSON fields hold the title, date, notes and children")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .required_if_eq("format", "json"),
+                ),
+            Command::new("export")
+                .about("write a list in another format")
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("format to write; ics has dated items as events, ics-todo as to-dos; ansi-html draws the list as in the terminal, in HTML")
+                        .takes_value(true)
+                        .default_value("markdown")
+                        .possible_values(export::Format::NAMES),
+                )
+                .arg(
+                    Arg::new("list")
+                        .help("list to export (default: the current list, or every list for ics)"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .help("write to a file instead of stdout")
+                        .takes_value(true)
+                        .value_name("FILE"),
+                ),
+            Command::new("native-host")
+                .about("serve a browser extension over native messaging")
+                .long_about("Run as a native messaging host for a companion browser extension, reading requests such as {\"action\": \"add\", \"list\": \"reading\", \"title\": \"...\", \"url\": \"...\"} on stdin. With --manifest, print the host manifest to install for a browser instead.")
+                .arg(
+                    Arg::new("manifest")
+                        .long("manifest")
+                        .help("print the host manifest for a browser")
+                        .takes_value(true)
+                        .value_name("BROWSER")
+                        .possible_values(["chrome", "chromium", "firefox"])
+                        .requires("extension-id"),
+                )
+                .arg(
+                    Arg::new("extension-id")
+                        .long("extension-id")
+                        .help("ID of the extension allowed to connect")
+                        .takes_value(true)
+                        .value_name("ID"),
+                ),
+            Command::new("serve")
+                .about("serve the lists over HTTP (calendar feed at /feed.ics, JSON API at /lists)")
+                .long_about("Serve the lists over HTTP: a calendar feed at /feed.ics, and a JSON API to list lists (GET /lists), get one (GET /lists/NAME) and add, change, remove and move items (POST /lists/NAME/items, PATCH and DELETE /lists/NAME/items/INDEX, POST /lists/NAME/items/INDEX/move). Changes wait for the command line to finish with the lists, and can be undone with `later undo`. With --web, / is a page showing every list, with checkboxes to mark items done.")
+                .args(vec![
+                    Arg::new("port")
+                        .long("port")
+                        .short('p')
+                        .help("port to listen on")
+                        .takes_value(true)
+                        .default_value("8080"),
+                    Arg::new("bind")
+                        .long("bind")
+                        .help("address to listen on")
+                        .takes_value(true)
+                        .default_value("127.0.0.1"),
+                    Arg::new("token")
+                        .long("token")
+                        .help("require this token, as Authorization: Bearer TOKEN or ?token=TOKEN")
+                        .takes_value(true)
+                        .value_name("TOKEN"),
+                    Arg::new("web")
+                        .long("web")
+                        .help("also serve a page at / showing every list, with checkboxes to mark items done"),
+                ]),
+            Command::new("notify")
+                .about("send alerts for items that are due")
+                .long_about("Send one alert for every item, in any list, that is due within the next few minutes (or overdue) and hasn't been announced yet, through the channels configured under [notify] in the config file. Meant to be run regularly, e.g. from cron.")
+                .args(vec![
+                    Arg::new("ahead")
+                        .long("ahead")
+                        .help("minutes ahead of the due time to alert")
+                        .takes_value(true)
+                        .value_name("MINUTES"),
+                    Arg::new("test")
+                        .long("test")
+                        .help("send a test message through every channel"),
+                    Arg::new("daemon")
+                        .long("daemon")
+                        .conflicts_with("test")
+                        .help("keep running, alerting as items come due and running the on_due commands the moment timed items fall due"),
+                ]),
+            Command::new("archive")
+                .about("look through, restore or purge archived items")
+                .subcommand_required(true)
+                .subcommands(vec![
+                    Command::new("list")
+                        .about("show archived items, oldest first")
+                        .arg(
+                            Arg::new("list")
+                                .long("list")
+                                .help("only those from this list")
+                                .takes_value(true)
+                                .value_name("LIST"),
+                        ),
+                    Command::new("restore")
+                        .about("put an archived item back where it was")
+                        .arg(
+                            Arg::new("id")
+                                .help("number of the archived item, as in `later archive list`")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::new("to")
+                                .long("to")
+                                .help("list to put it at the end of instead")
+                                .takes_value(true)
+                                .value_name("LIST"),
+                        ),
+                    Command::new("purge")
+                        .about("delete archived items for good")
+                        .arg(
+                            Arg::new("older-than")
+                                .long("older-than")
+                                .help("only those archived longer ago than this, e.g. 90d, 12w, 1y")
+                                .takes_value(true)
+                                .value_name("AGE")
+                                .required(true),
+                        ),
+                ]),
+            Command::new("triage")
+                .about("file the undated, untagged items in a list one by one, with suggested dates and lists"),
+            Command::new("gc")
+                .about("clear out items that are likely just clutter")
+                .arg(
+                    Arg::new("suggest")
+                        .long("suggest")
+                        .required(true)
+                        .help("list items done long ago, undated items untouched for months and empty sublists, then archive the ones picked"),
+                )
+                .arg(
+                    Arg::new("done-for")
+                        .long("done-for")
+                        .help("how long an item must have been done, e.g. 30d, 8w, 1y")
+                        .takes_value(true)
+                        .value_name("AGE")
+                        .default_value("30d"),
+                )
+                .arg(
+                    Arg::new("untouched-for")
+                        .long("untouched-for")
+                        .help("how long ago an undated item must have been added")
+                        .takes_value(true)
+                        .value_name("AGE")
+                        .default_value("90d"),
+                )
+                .arg(
+                    Arg::new("pick")
+                        .long("pick")
+                        .help("the suggestions to act on without asking, e.g. 1,3-5 or all")
+                        .takes_value(true)
+                        .value_name("NUMBERS"),
+                )
+                .arg(
+                    Arg::new("delete")
+                        .long("delete")
+                        .help("delete the picked items instead of archiving them"),
+                ),
+            Command::new("report")
+                .about("reports across all lists")
+                .args_conflicts_with_subcommands(true)
+                .arg(
+                    Arg::new("week")
+                        .long("week")
+                        .help("sum up the time tracked this week, by list and by tag"),
+                )
+                .subcommand(
+                    Command::new("slipped")
+                        .about("list items that keep being pushed back")
+                        .arg(
+                            Arg::new("min")
+                                .long("min")
+                                .help("how many times an item must have slipped")
+                                .takes_value(true)
+                                .value_name("COUNT")
+                                .default_value("2"),
+                        ),
+                ),
+            Command::new("search")
+                .about("find items by title across all lists")
+                .args(vec![
+                    Arg::new("query")
+                        .help("text the title contains, ignoring case")
+                        .required(true),
+                    Arg::new("regex")
+                        .long("regex")
+                        .help("treat the query as a regular expression"),
+                    Arg::new("list")
+                        .long("list")
+                        .help("only search this list")
+                        .takes_value(true)
+                        .value_name("LIST NAME"),
+                    Arg::new("apply")
+                        .long("apply")
+                        .help("then do this to every match, after showing them: done, remove, tag X or move LIST")
+                        .takes_value(true)
+                        .value_name("ACTION")
+                        .min_values(1)
+                        .max_values(2),
+                ]),
+            Command::new("rename-matching")
+                .about("rewrite part of every matching title in a list, showing the changes first")
+                .args(vec![
+                    Arg::new("pattern")
+                        .help("text to replace")
+                        .required(true),
+                    Arg::new("replacement")
+                        .help("text to put in its place; with --regex, $1 refers to a group")
+                        .required(true),
+                    Arg::new("regex")
+                        .long("regex")
+                        .help("treat the pattern as a regular expression"),
+                ]),
+            Command::new("restore")
+                .about("replace the lists with a backup, by default the most recent one that can be read")
+                .arg(
+                    Arg::new("backup")
+                        .help("which backup: 1 is the most recent")
+                        .takes_value(true)
+                        .value_name("N"),
+                ),
+            Command::new("undo")
+                .about("take back the last change to the lists"),
+            Command::new("redo")
+                .about("reapply the last change taken back by undo"),
+            Command::new("escalate")
+                .about("apply the config's overdue escalation rules to every list"),
+            Command::new("doctor")
+                .about("describe later's files, terminal and integrations, for bug reports"),
+            Command::new("completions")
+                .about("print a shell completion script, e.g. `source <(later completions bash)`")
+                .arg(
+                    Arg::new("shell")
+                        .help("shell to complete for")
+                        .required(true)
+                        .possible_values(["bash", "zsh", "fish", "elvish", "powershell"]),
+                ),
+            Command::new("complete")
+                .about("print list names or tags, for completion scripts")
+                .hide(true)
+                .arg(
+                    Arg::new("what")
+                        .required(true)
+                        .possible_values(["lists", "tags"]),
+                ),
+            Command::new("open-data")
+                .about("print where the lists are kept, and optionally open them")
+                .args(vec![
+                    Arg::new("folder")
+                        .long("folder")
+                        .help("also open the containing folder in the file manager"),
+                    Arg::new("edit")
+                        .long("edit")
+                        .help("also open the file itself in $EDITOR")
+                        .conflicts_with("folder"),
+                ]),
+            Command::new("edit-raw")
+                .about("edit the data file in $EDITOR, saving it only if it's still valid")
+                .long_about("Open a copy of the data file in $EDITOR. When the editor closes, the copy is checked and, if it holds valid lists, saved back in the usual layout (keeping a backup of the old version); if not, the error is shown with its line and you can edit again or give up, leaving the file as it was."),
+            Command::new("setup")
+                .about("choose where lists are kept, the default list, colors and confirmations"),
+            Command::new("init")
+                .about("make the lists file, or turn encryption on or off")
+                .long_about("Make the lists file if it doesn't exist yet. With --encrypt, the lists (with their backups, undo history, archive and sync state) are encrypted from then on with a passphrase, which is read from LATER_PASSPHRASE or asked for once per run; --decrypt turns that off again.")
+                .args(vec![
+                    Arg::new("encrypt")
+                        .long("encrypt")
+                        .help("encrypt the lists with a passphrase"),
+                    Arg::new("decrypt")
+                        .long("decrypt")
+                        .help("store the lists unencrypted again")
+                        .conflicts_with("encrypt"),
+                ]),
+            Command::new("agenda")
+                .about("show what's due across every list, day by day")
+                .arg(
+                    Arg::new("days")
+                        .long("days")
+                        .help("how many days ahead to look, today included; overdue items are always shown")
+                        .takes_value(true)
+                        .value_name("N")
+                        .default_value("7"),
+                ),
+            Command::new("motd")
+                .about("print today's agenda as a plain-text block")
+                .long_about("Print everything due today or overdue, across all lists, as a fixed-width block of plain text, e.g. for /etc/motd, a login script or a desktop widget.")
+                .args(vec![
+                    Arg::new("width")
+                        .long("width")
+                        .short('w')
+                        .help("width of the block in columns")
+                        .takes_value(true)
+                        .default_value("60"),
+                    Arg::new("output")
+                        .long("output")
+                        .short('o')
+                        .help("write to a file instead of stdout")
+                        .takes_value(true)
+                        .value_name("FILE"),
+                ]),
+            Command::new("sync")
+                .about("merge the lists with the remote copy")
+                .long_about("Merge the lists with the remote copy set up under [sync] in the config file. Lists changed on only one side since the last sync take that side's version; lists changed on both sides keep the local version until the conflict is resolved.")
+                .subcommands(vec![
+                    Command::new("status").about(
+                        "show unsynced changes on both sides and any conflicts",
+                    ),
+                    Command::new("resolve")
+                        .about("settle a conflict by keeping one version")
+                        .arg(
+                            Arg::new("list")
+                                .help("name of the conflicting list")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::new("keep")
+                                .long("keep")
+                                .help("which version to keep")
+                                .takes_value(true)
+                                .required(true)
+                                .possible_values(["local", "remote"]),
+                        ),
+                ]),
+            Command::new("template")
+                .about("save a sublist as a template, or stamp out a copy")
+                .subcommand_required(true)
+                .subcommands(vec![
+                    Command::new("save")
+                        .about("save an item and everything under it")
+                        .arg(
+                            Arg::new("index")
+                                .help("index of the item to save")
+                                .required(true)
+                                .use_value_delimiter(true)
+                                .require_value_delimiter(true),
+                        )
+                        .arg(
+                            Arg::new("name")
+                                .help("name to save it under")
+                                .required(true),
+                        ),
+                    Command::new("new")
+                        .about("add a fresh copy of a template, with its dates counted from today")
+                        .arg(
+                            Arg::new("name")
+                                .help("name of the template")
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::new("dest")
+                                .help("index to add it under (default: the end of the list)")
+                                .use_value_delimiter(true)
+                                .require_value_delimiter(true),
+                        )
+                        .arg(
+                            Arg::new("anchor")
+                                .long("anchor")
+                                .help("day that offsets like T-7d count from (yyyy-mm-dd; default: today)")
+                                .takes_value(true)
+                                .value_name("DATE"),
+                        ),
+                    Command::new("list").about("list the saved templates"),
+                ]),
+            Command::new("show")
+                .about("show a list, or everything about one item in it, including its notes")
+                .arg(
+                    Arg::new("index")
+                        .help("index of item to show")
+                        .use_value_delimiter(true)
+                        .require_value_delimiter(true),
+                )
+                .arg(
+                    Arg::new("as-of")
+                        .long("as-of")
+                        .help("show dates as they will look on another day (yyyy-mm-dd [hh:mm])")
+                        .takes_value(true)
+                        .value_name("DATE"),
+                )
+                .arg(
+                    Arg::new("tag")
+                        .long("tag")
+                        .help("only show items with this tag, the sublists they're in, and everything in sublists with it")
+                        .takes_value(true)
+                        .value_name("TAG")
+                        .conflicts_with("index"),
+                ),
+            Command::new("watch")
+                .about("keep the list on screen, redrawing it as it changes")
+                .args(vec![
+                    Arg::new("all")
+                        .long("all")
+                        .help("show every list, not just this one"),
+                    Arg::new("interval")
+                        .long("interval")
+                        .help("seconds between checks for changes")
+                        .takes_value(true)
+                        .value_name("SECONDS")
+                        .default_value("1"),
+                ]),
+            Command::new("countdown")
+                .about("count down to an item's date, live on one line")
+                .long_about("Show the time left until an item's date, updated every second until it passes or you quit with Ctrl-C. Plain dates count down to the end of the day. When output isn't a terminal, the time left is printed once.")
+                .args(vec![
+                    Arg::new("index")
+                        .help("index of the item to count down to")
+                        .required(true)
+                        .takes_value(true)
+                        .forbid_empty_values(true)
+                        .use_value_delimiter(true)
+                        .require_value_delimiter(true),
+                    Arg::new("big")
+                        .long("big")
+                        .help("draw the time left in large digits"),
+                ]),
+            Command::new("tx")
+                .about("run several commands, saving only if all succeed")
+                .long_about("Run several commands against the lists in memory and save once at the end, or not at all if any of them fails. Each step is a quoted command line as it would follow `later`, e.g. `later tx 'add milk' 'move 3 0' 'sort'`; steps without a list name use the list given to tx.")
+                .arg(
+                    Arg::new("steps")
+                        .help("commands to run, in order")
+                        .required(true)
+                        .multiple_values(true),
+                ),
+            Command::new("events")
+                .about("print changes to the lists as JSON lines")
+                .long_about("Print one line of JSON per item added, edited, marked done or reopened, or removed, e.g. {\"event\": \"done\", \"list\": \"to-do\", \"index\": [2], \"item\": {...}, \"at\": \"...\"}. Without --follow, every item is printed once as added, to start from; with it, nothing is printed until the lists change, and then only the changes.")
+                .args(vec![
+                    Arg::new("follow")
+                        .long("follow")
+                        .short('f')
+                        .help("keep running, printing changes as they're saved"),
+                    Arg::new("interval")
+                        .long("interval")
+                        .help("seconds between checks for changes")
+                        .takes_value(true)
+                        .value_name("SECONDS")
+                        .default_value("1"),
+                ]),
+            Command::new("status")
+                .about("print how many items are overdue or due today, across all lists")
+                .long_about("Print a one-line summary such as \"3 overdue, 2 due today\" across all lists, exiting with status 1 when anything is overdue, e.g. for a shell prompt."),
+            Command::new("batch")
+                .about("run commands read from stdin, saving only if all succeed")
+                .long_about("Read commands from stdin, one per line as it would follow `later`, e.g. `work add \"fix bug\" --date 2022/07/01`, and run them against the lists in memory, saving once at the end, or not at all if any of them fails. `add LIST TITLE DATE` is short for `LIST add TITLE --date DATE`. Blank lines and lines starting with # are skipped; lines without a list name use the list given to batch.")
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .help("only check that every line is a valid command"),
+                ),
+            Command::new("intro")
+                .about("learn how later works with a guided walkthrough"),
+        ])
+}
+
+// run one command line against the loaded lists
+fn run(
+    args: &ArgMatches,
+    lists: &mut HashMap<String, TodoList>,
+    config: &config::Config,
+    session: &Session,
+) -> anyhow::Result<()> {
+    let (todo_folder, todo_file) =
+        (&session.store.todo_folder, &session.store.todo_file);
+    let snapshot = session.snapshot;
+
+    if let Some(("init", init_args)) = args.subcommand() {
+        let encrypt = init_args.is_present("encrypt");
+        if !encrypt && !init_args.is_present("decrypt") {
+            // loading made the file if it was missing
+            println!("lists are kept in {}", todo_file.display());
+            return Ok(());
+        }
+        match (encrypt, crypt::is_on()) {
+            (true, true) => bail!("The lists are already encrypted"),
+            (false, false) => bail!("The lists aren't encrypted"),
+            _ => {}
+        }
+        if snapshot.is_some() {
+            println!(
+                "would {} {}",
+                if encrypt { "encrypt" } else { "decrypt" },
+                todo_file.display()
+            );
+            eprintln!("Dry run, nothing was saved.");
+            return Ok(());
+        }
+        if encrypt {
+            crypt::enable(&new_passphrase()?)?;
+        } else {
+            crypt::disable();
+        }
+        // saving first moves the old file into the backups, to be redone
+        save(todo_file, lists, config.general.backups)?;
+        reseal(todo_folder, todo_file, config.general.backups)?;
+        println!(
+            "{} {} and the files kept with it",
+            if encrypt { "encrypted" } else { "decrypted" },
+            todo_file.display()
+        );
+        return Ok(());
+    }
+
+    if let Some(("tx", tx_args)) = args.subcommand() {
+        let steps: Vec<(String, String)> = tx_args
+            .values_of("steps")
+            .unwrap()
+            .enumerate()
+            .map(|(n, step)| (format!("Step {}", n + 1), String::from(step)))
+            .collect();
+        transaction(
+            &steps,
+            args.value_of("list-name"),
+            lists,
+            config,
+            session,
+        )?;
+        if snapshot.is_none() {
+            println!("applied {} steps", steps.len());
+        }
+        return Ok(());
+    }
+
+    if let Some(("batch", batch_args)) = args.subcommand() {
+        let mut steps = Vec::new();
+        for (n, line) in std::io::stdin().lines().enumerate() {
+            let line = line.context("Couldn't read commands from stdin")?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            steps.push((format!("Line {}", n + 1), batch_line(line)?));
+        }
+        if batch_args.is_present("check") {
+            for (label, step) in &steps {
+                step_matches(label, step, None)?;
+            }
+            println!("{} commands look fine", steps.len());
+            return Ok(());
+        }
+        transaction(
+            &steps,
+            args.value_of("list-name"),
+            lists,
+            config,
+            session,
+        )?;
+        if snapshot.is_none() {
+            println!("applied {} commands", steps.len());
+        }
+        return Ok(());
+    }
+
+    // fast path: skip list resolution, prompts and rendering entirely
+    if let Some(("now", now_args)) = args.subcommand() {
+        let title = now_args
+            .values_of("text")
+            .unwrap()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let inbox = config.inbox();
+        lists
+            .entry(String::from(inbox))
+            .or_insert_with(|| TodoList::from_info(String::from(inbox), None))
+            .append(
+                &ItemPath::root(),
+                ListItem::Entry(TodoEntry::from_info(title, None)),
+            )?;
+        session.commit(lists)?;
+        return Ok(());
+    }
+
+    if let Some(("handle-url", url_args)) = args.subcommand() {
+        if url_args.is_present("install") {
+            return install_url_handler();
+        }
+        let url =
+            url_scheme::CaptureUrl::parse(url_args.value_of("url").unwrap())?;
+        match url.action.as_str() {
+            "add" => {
+                let list_name =
+                    url.get("list").unwrap_or(config.default_list());
+                let title = match url.get("title") {
+                    Some(title) => title.to_string(),
+                    None => bail!("The URL needs a title parameter"),
+                };
+                let date =
+                    url.get("due").map(url_scheme::parse_due).transpose()?;
+                let list = match lists.get_mut(list_name) {
+                    Some(list) => list,
+                    None => {
+                        bail!(Error::list_not_found(list_name, lists.keys()))
+                    }
+                };
+                list.append(
+                    &ItemPath::root(),
+                    ListItem::Entry(TodoEntry::from_info(title.clone(), date)),
+                )?;
+                session.commit(lists)?;
+                println!("added '{}' to '{}'", title, list_name);
+            }
+            other => bail!("Unknown URL action '{}' (expected 'add')", other),
+        }
+        return Ok(());
+    }
+
+    if let Some(("native-host", host_args)) = args.subcommand() {
+        if let Some(browser) = host_args.value_of("manifest") {
+            let manifest = native_host::manifest(
+                browser,
+                &std::env::current_exe()?,
+                host_args.value_of("extension-id").unwrap(),
+            )?;
+            println!("{}", serde_json::to_string_pretty(&manifest)?);
+            return Ok(());
+        }
+        if snapshot.is_some() {
+            // the diff would go to stdout, which is the browser's
+            bail!("native-host can't be run with --dry-run");
+        }
+        return native_host(session.store, config.default_list());
+    }
+
+    if let Some(("serve", serve_args)) = args.subcommand() {
+        let address = format!(
+            "{}:{}",
+            serve_args.value_of("bind").unwrap(),
+            serve_args.value_of("port").unwrap()
+        );
+        let token = serve_args.value_of("token").map(String::from);
+        if token.is_none() && serve_args.value_of("bind") != Some("127.0.0.1") {
+            eprintln!(
+                "Anyone who can reach {} can change the lists; consider \
+                 --token",
+                address
+            );
+        }
+        return server::Server::new(
+            session.store.clone(),
+            token,
+            serve_args.is_present("web"),
+        )
+        .run(&address);
+    }
+
+    if let Some(("notify", notify_args)) = args.subcommand() {
+        if notify_args.is_present("daemon") {
+            return notify_daemon(
+                todo_file,
+                &todo_folder.join("notified.json"),
+                &config.notify,
+                notify_args,
+            );
+        }
+        return notify(
+            &todo_folder.join("notified.json"),
+            lists,
+            &config.notify,
+            notify_args,
+        );
+    }
+
+    if let Some(("archive", archive_args)) = args.subcommand() {
+        let path = archive::path(todo_folder);
+        let mut archived = archive::read(&path)?;
+        match archive_args.subcommand() {
+            Some(("list", list_args)) => {
+                let only = list_args.value_of("list");
+                let shown: Vec<&archive::Archived> = archived
+                    .iter()
+                    .filter(|a| only.is_none_or(|list| a.list == list))
+                    .collect();
+                if shown.is_empty() {
+                    eprintln!("Nothing archived.");
+                }
+                archive::write_archived(&mut std::io::stdout(), &shown)?;
+            }
+            Some(("restore", restore_args)) => {
+                let id = restore_args.value_of("id").unwrap();
+                let id: u64 =
+                    id.trim_start_matches('#').parse().map_err(|_| {
+                        anyhow::anyhow!("Invalid archive number '{}'", id)
+                    })?;
+                let position = match archived.iter().position(|a| a.id == id) {
+                    Some(position) => position,
+                    None => bail!("Nothing is archived as #{}", id),
+                };
+                let entry = archived.remove(position);
+                let name = restore_args.value_of("to").unwrap_or(&entry.list);
+                let list = match lists.get_mut(name) {
+                    Some(list) => list,
+                    None => bail!(
+                        "List '{}' not found! (use --to to restore it to \
+                         another list){}",
+                        name,
+                        did_you_mean(&suggest_names(name, lists.keys()))
+                    ),
+                };
+                let title = entry.item.title().to_string();
+                // where it was if that spot is still there, else at the end
+                let index = ItemPath::from(entry.index.clone());
+                let (parent, last) = index.split_at(index.len() - 1);
+                let fits = match list.get(parent) {
+                    _ if parent.is_empty() => last[0] <= list.items().len(),
+                    Some(ListItem::List(sublist)) => {
+                        last[0] <= sublist.items().len()
+                    }
+                    // its sublist was emptied, and so became an entry again
+                    Some(ListItem::Entry(_)) => last[0] == 0,
+                    None => false,
+                };
+                if fits && !restore_args.is_present("to") {
+                    list.insert(&index, entry.item)?;
+                } else {
+                    list.append(&ItemPath::root(), entry.item)?;
+                }
+                session.commit(lists)?;
+                if snapshot.is_none() {
+                    archive::rewrite(&path, &archived)?;
+                }
+                println!("restored '{}' to '{}'", title, name);
+            }
+            Some(("purge", purge_args)) => {
+                let age = purge_args.value_of("older-than").unwrap();
+                let age = match natural::parse_period(age) {
+                    Some(age) => age,
+                    None => bail!("Invalid age '{}' (e.g. 90d, 12w, 1y)", age),
+                };
+                let cutoff = clock::now() - age;
+                let before = archived.len();
+                archived.retain(|a| a.at >= cutoff);
+                let purged = before - archived.len();
+                if snapshot.is_none() && purged > 0 {
+                    archive::rewrite(&path, &archived)?;
+                }
+                println!("purged {} archived items", purged);
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    if let Some(("gc", gc_args)) = args.subcommand() {
+        let mut thresholds = Vec::new();
+        for name in ["done-for", "untouched-for"] {
+            let age = gc_args.value_of(name).unwrap();
+            match natural::parse_period(age) {
+                Some(age) => thresholds.push(age),
+                None => bail!("Invalid age '{}' (e.g. 30d, 8w, 1y)", age),
+            }
+        }
+        let thresholds = gc::Thresholds {
+            done: thresholds[0],
+            untouched: thresholds[1],
+        };
+        let candidates = gc::suggest(lists, clock::now(), thresholds);
+        if candidates.is_empty() {
+            println!("Nothing to clear out.");
+            return Ok(());
+        }
+        gc::write_candidates(&mut std::io::stdout(), &candidates)?;
+        let delete = gc_args.is_present("delete");
+        let picks = match gc_args.value_of("pick") {
+            Some(picks) => gc::parse_picks(picks, candidates.len())?,
+            // without a terminal to ask at, the list is all there is
+            None if !std::io::stdin().is_terminal() => return Ok(()),
+            None => {
+                let mut rl = rustyline::Editor::<()>::new();
+                let answer = rl.readline(&format!(
+                    "{} which? (e.g. 1,3-5 or all; blank for none): ",
+                    if delete { "Delete" } else { "Archive" }
+                ))?;
+                gc::parse_picks(&answer, candidates.len())?
+            }
+        };
+        if picks.is_empty() {
+            println!("Nothing cleared out.");
+            return Ok(());
+        }
+        let mut archived = Vec::new();
+        // last first, so taking one out doesn't move the others
+        for &pick in picks.iter().rev() {
+            let candidate = &candidates[pick];
+            let list = lists.get_mut(&candidate.list).unwrap();
+            let item = list.remove(&candidate.index)?;
+            session.record(
+                webhook::Action::Remove,
+                &candidate.list,
+                &candidate.index,
+                &item,
+            );
+            if !delete {
+                let reason = match candidate.reason {
+                    gc::Reason::Done(_) => archive::Reason::Completed,
+                    _ => archive::Reason::Removed,
+                };
+                archived.push(archive::Archived::new(
+                    &candidate.list,
+                    &candidate.index,
+                    reason,
+                    item,
+                ));
+            }
+        }
+        archived.reverse();
+        session.archived.borrow_mut().extend(archived);
+        session.commit(lists)?;
+        println!(
+            "{} {} items",
+            if delete { "deleted" } else { "archived" },
+            picks.len()
+        );
+        return Ok(());
+    }
+
+    if let Some(("report", report_args)) = args.subcommand() {
+        if let Some(("slipped", slipped_args)) = report_args.subcommand() {
+            let min: usize = slipped_args.value_of_t_or_exit("min");
+            let slipped = report::slipped(lists, min);
+            report::write_slipped(&mut std::io::stdout(), &slipped)?;
+        } else if report_args.is_present("week") {
+            let monday = tracking::week_start(clock::today());
+            let from = chrono::Local
+                .from_local_datetime(&monday.and_hms(0, 0, 0))
+                .earliest()
+                .unwrap();
+            let totals = tracking::totals(lists, from, clock::now());
+            println!(
+                "Week of {}",
+                i18n::Lang::current().month_day(monday, false)
+            );
+            tracking::write_totals(&mut std::io::stdout(), &totals)?;
+        } else {
+            bail!(
+                "Choose a report: `later report slipped` or `later report \
+                 --week`"
+            );
+        }
+        return Ok(());
+    }
+
+    if let Some(("search", search_args)) = args.subcommand() {
+        let query = search::Query::new(
+            search_args.value_of("query").unwrap(),
+            search_args.is_present("regex"),
+        )?;
+        // searching every list is what leaving it out does anyway
+        let only = search_args
+            .value_of("list")
+            .filter(|name| *name != ALL_LISTS);
+        if let Some(name) = only.filter(|name| !lists.contains_key(*name)) {
+            bail!(Error::list_not_found(name, lists.keys()));
+        }
+        let action = match search_args.values_of("apply") {
+            Some(words) => {
+                Some(search::Action::parse(&words.collect::<Vec<_>>())?)
+            }
+            None => None,
+        };
+        if let Some(search::Action::Move(to)) = &action {
+            if !lists.contains_key(to) {
+                bail!(Error::list_not_found(to, lists.keys()));
+            }
+        }
+        let found = search::search(lists, &query, only);
+        if found.is_empty() {
+            eprintln!("No matches.");
+        }
+        search::write_matches(
+            &mut std::io::stdout(),
+            &found,
+            &RenderOptions::for_terminal(config)?,
+        )?;
+        let action = match action {
+            Some(action) => action,
+            None => return Ok(()),
+        };
+        let targets = search::targets(&found, &action);
+        if targets.is_empty() {
+            if !found.is_empty() {
+                println!("Nothing to change.");
+            }
+            return Ok(());
+        }
+
+        if config.general.confirm
+            && snapshot.is_none()
+            && std::io::stdin().is_terminal()
+        {
+            let mut rl = rustyline::Editor::<()>::new();
+            let confirm = rl.readline(&format!(
+                "{}? (y/N): ",
+                action.describe(targets.len(), false)
+            ))?;
+            if confirm.to_lowercase() != "y" {
+                bail!("Cancelled.");
+            }
+        }
+        apply_to_matches(lists, &targets, &action, session)?;
+        session.commit(lists)?;
+        if snapshot.is_none() {
+            println!("{}", action.describe(targets.len(), true));
+        }
+        return Ok(());
+    }
+
+    if let Some((command @ ("undo" | "redo"), _)) = args.subcommand() {
+        let path = todo_folder.join("history.json");
+        let mut history = history::History::load(&path)?;
+        let current = sync::snapshot(lists)?;
+        let (step, done) = match command {
+            "undo" => (history.undo(current), "undid"),
+            _ => (history.redo(current), "redid"),
+        };
+        let step = match step {
+            Some(step) => step,
+            None => bail!("Nothing to {}.", command),
+        };
+        *lists = sync::to_lists(step.lists)?;
+        // not through the session, which would add this to the history
+        store::commit(todo_file, lists, snapshot, config.general.backups)?;
+        if snapshot.is_none() {
+            history.save(&path)?;
+            println!(
+                "{} '{}' from {}",
+                done,
+                step.command,
+                step.at.format("%Y-%m-%d %H:%M")
+            );
+        }
+        return Ok(());
+    }
+
+    if let Some(("escalate", _)) = args.subcommand() {
+        let changed =
+            escalate::escalate(lists, &config.escalate.rules, clock::now());
+        session.commit(lists)?;
+        println!("escalated {} items", changed);
+        return Ok(());
+    }
+
+    if let Some(("export", export_args)) = args.subcommand() {
+        let format =
+            export::Format::from_name(export_args.value_of("format").unwrap())
+                .unwrap();
+        let name = export_args
+            .value_of("list")
+            .or_else(|| args.value_of("list-name"));
+        let mut exported = Vec::new();
+        match name {
+            // a calendar holds every list unless one is named
+            None | Some(ALL_LISTS) if format.is_calendar() => {
+                let lists: Vec<&TodoList> = lists.values().collect();
+                export::write_calendar(format, &lists, &mut exported)?;
+            }
+            Some(ALL_LISTS) => export::export(
+                format,
+                &all_lists(lists),
+                &RenderOptions::for_terminal(config)?,
+                &mut exported,
+            )?,
+            _ => {
+                let name = name.unwrap_or(config.default_list());
+                let list = match lists.get(name) {
+                    Some(list) => list,
+                    None => bail!(Error::list_not_found(name, lists.keys())),
+                };
+                export::export(
+                    format,
+                    list,
+                    &RenderOptions::for_terminal(config)?,
+                    &mut exported,
+                )?;
+            }
+        }
+        match export_args.value_of("output") {
+            Some(path) => std::fs::write(path, exported)
+                .with_context(|| format!("Couldn't write {}", path))?,
+            None => std::io::stdout().write_all(&exported)?,
+        }
+        return Ok(());
+    }
+
+    if let Some(("reset", reset_args)) = args.subcommand() {
+        let name = reset_args
+            .value_of("list")
+            .or_else(|| args.value_of("list-name"))
+            .unwrap_or(config.default_list());
+        let every = match reset_args.value_of("every") {
+            Some(every) => match natural::parse_period(every) {
+                // dates only move by whole days
+                Some(every) if every.num_seconds() % 86400 == 0 => Some(every),
+                _ => bail!("Invalid period '{}' (e.g. 1d, 2w, 1y)", every),
+            },
+            None => None,
+        };
+        let list = match lists.get_mut(name) {
+            Some(list) => list,
+            None => bail!(Error::list_not_found(name, lists.keys())),
+        };
+        let reopened = list.reset(every, clock::today());
+        session.commit(lists)?;
+        println!("reset '{}': {} items to do again", name, reopened);
+        return Ok(());
+    }
+
+    if let Some(("clear", clear_args)) = args.subcommand() {
+        let current =
+            args.value_of("list-name").unwrap_or(config.default_list());
+        let (name, index) = match clear_args.value_of("target") {
+            Some(target) if lists.contains_key(target) => {
+                (target, ItemPath::root())
+            }
+            Some(target) => match lists.get(current) {
+                Some(list) => {
+                    (current, IndexPath::parse(target)?.resolve(list)?)
+                }
+                None => bail!(Error::list_not_found(current, lists.keys())),
+            },
+            None => (current, ItemPath::root()),
+        };
+        let list = match lists.get_mut(name) {
+            Some(list) => list,
+            None => bail!(Error::list_not_found(name, lists.keys())),
+        };
+        let sublist = if index.is_empty() {
+            list
+        } else {
+            match list.get_mut(&index) {
+                Some(ListItem::List(sublist)) => sublist,
+                _ => bail!("There's no sublist at {} in '{}'", index, name),
+            }
+        };
+        let mut taken = sublist.take_where(&ListItem::is_done);
+        for (path, _) in &mut taken {
+            path.splice(0..0, index.iter().copied());
+        }
+        if taken.is_empty() {
+            println!("Nothing done to clear in '{}'.", name);
+            return Ok(());
+        }
+        let reason = archive::Reason::Completed;
+        remove_taken(clear_args, session, lists, name, taken, reason)?;
+        return Ok(());
+    }
+
+    if let Some(("prune", prune_args)) = args.subcommand() {
+        let name = prune_args
+            .value_of("list")
+            .or_else(|| args.value_of("list-name"))
+            .unwrap_or(config.default_list());
+        let age = prune_args.value_of("overdue-older-than").unwrap();
+        let age = match natural::parse_period(age) {
+            Some(age) => age,
+            None => bail!("Invalid age '{}' (e.g. 30d, 8w, 1y)", age),
+        };
+        let cutoff = (clock::now() - age).naive_local();
+        let list = match lists.get_mut(name) {
+            Some(list) => list,
+            None => bail!(Error::list_not_found(name, lists.keys())),
+        };
+        // sublists are only looked into, as what's in them may not be stale
+        let taken = list.take_where(&|item| {
+            matches!(item, ListItem::Entry(_))
+                && !item.is_done()
+                && item.date().is_some_and(|date| date.start() < cutoff)
+        });
+        if taken.is_empty() {
+            println!("Nothing in '{}' is that overdue.", name);
+            return Ok(());
+        }
+        let reason = archive::Reason::Removed;
+        remove_taken(prune_args, session, lists, name, taken, reason)?;
+        return Ok(());
+    }
+
+    if let Some(("agenda", agenda_args)) = args.subcommand() {
+        let days: i64 = agenda_args.value_of_t_or_exit("days");
+        let today = clock::today();
+        let entries = agenda::collect(lists, today, days);
+        agenda::write_agenda(
+            &mut std::io::stdout(),
+            &entries,
+            today,
+            &RenderOptions::for_terminal(config)?,
+        )?;
+        return Ok(());
+    }
+
+    if let Some(("events", events_args)) = args.subcommand() {
+        if events_args.is_present("follow") {
+            let interval: f64 = events_args.value_of_t_or_exit("interval");
+            return events::follow(
+                todo_file,
+                std::time::Duration::from_secs_f64(interval),
+            );
+        }
+        let events = events::diff(&HashMap::new(), lists);
+        events::write_events(&mut std::io::stdout(), &events)?;
+        return Ok(());
+    }
+
+    if let Some(("status", _)) = args.subcommand() {
+        let summary = status::Summary::of(lists);
+        let mut stdout = std::io::stdout();
+        status::write_summary(
+            &mut stdout,
+            &summary,
+            &RenderOptions::for_terminal(config)?,
+        )?;
+        if summary.overdue > 0 {
+            stdout.flush()?;
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(("motd", motd_args)) = args.subcommand() {
+        let width: usize = motd_args.value_of_t_or_exit("width");
+        let mut agenda = Vec::new();
+        motd::write_agenda(&mut agenda, lists, clock::today(), width)?;
+        match motd_args.value_of("output") {
+            Some(path) => std::fs::write(path, agenda)
+                .with_context(|| format!("Couldn't write {}", path))?,
+            None => std::io::stdout().write_all(&agenda)?,
+        }
+        return Ok(());
+    }
+
+    if let Some(("sync", sync_args)) = args.subcommand() {
+        let state_file = todo_folder.join("sync.json");
+        return match sync_args.subcommand() {
+            Some(("status", _)) => sync_status(&state_file, lists, config),
+            Some(("resolve", resolve_args)) => {
+                sync_resolve(&state_file, lists, resolve_args, session)
+            }
+            _ => sync(&state_file, lists, config, session),
+        };
+    }
+
+    if let Some(("stop", _)) = args.subcommand() {
+        let (name, index) = match tracking::running(lists) {
+            Some(running) => running,
+            None => bail!("Nothing is being tracked"),
+        };
+        let item = lists.get_mut(&name).unwrap().get_mut(&index).unwrap();
+        let spent = tracking::stop(item).unwrap();
+        let title = item.title().to_string();
+        session.commit(lists)?;
+        println!("stopped '{}' after {}", title, tracking::hours(spent));
+        return Ok(());
+    }
+
+    // use list-name argument, otherwise use default list
+    let list_name = if args.is_present("list-name") {
+        args.value_of("list-name").unwrap()
+    } else {
+        let name = config.default_list();
+        if !lists.contains_key(name) {
+            lists.insert(
+                String::from(name),
+                TodoList::from_info(String::from(name), None),
+            );
+        }
+        name
+    };
+    debug!(list = list_name, command = ?args.subcommand_name());
+
+    // a copy of everything, which only makes sense to look at
+    let mut everything = None;
+    if list_name == ALL_LISTS {
+        match args.subcommand_name() {
+            None | Some("show" | "sort") => {}
+            Some(command) => bail!(
+                "'{}' is read-only: it can be shown, sorted for viewing, \
+                 searched or exported, but not used with '{}'",
+                ALL_LISTS,
+                command
+            ),
+        }
+        everything = Some(all_lists(lists));
+    }
+
+    if everything.is_none() && !lists.contains_key(list_name) {
+        let missing = Error::list_not_found(list_name, lists.keys());
+        if !std::io::stdin().is_terminal() {
+            bail!(missing);
+        }
+        eprintln!("{}", missing);
+        let mut rl = rustyline::Editor::<()>::new();
+        let confirm =
+            rl.readline(&format!("Create list '{}'? (y/N): ", list_name))?;
+        if confirm.to_lowercase() != "y" {
+            bail!("Cancelled.");
+        }
+        lists.insert(
+            String::from(list_name),
+            TodoList::from_info(String::from(list_name), None),
+        );
+        session.commit(lists)?;
+    }
+
+    if let Some(("triage", _)) = args.subcommand() {
+        let pending = triage::pending(&lists[list_name]);
+        if pending.is_empty() {
+            println!("Nothing to triage in '{}'.", list_name);
+            return Ok(());
+        }
+        let today = clock::today();
+        let mut stdout = std::io::stdout();
+        // without a terminal to ask at, the suggestions are all there is
+        let ask = std::io::stdin().is_terminal();
+        let mut rl = rustyline::Editor::<()>::new();
+        let mut filings = Vec::new();
+        for (n, &i) in pending.iter().enumerate() {
+            let item = &lists[list_name].items()[i];
+            let suggestion = triage::suggest(item, lists, list_name, today);
+            triage::write_suggestion(
+                &mut stdout,
+                n + 1,
+                pending.len(),
+                item.title(),
+                &suggestion,
+            )?;
+            if !ask {
+                continue;
+            }
+            let answer = loop {
+                let text = rl.readline("> ")?;
+                match triage::parse_answer(&text, &suggestion) {
+                    Ok(answer) => break answer,
+                    Err(e) => eprintln!("{}", e),
+                }
+            };
+            println!();
+            match answer {
+                triage::Answer::File(filing) => {
+                    filings.push((i, suggestion, filing))
+                }
+                triage::Answer::Skip => {}
+                triage::Answer::Quit => break,
+            }
+        }
+        if filings.is_empty() {
+            if ask {
+                println!("Nothing filed.");
+            }
+            return Ok(());
+        }
+        let inbox = lists.get_mut(list_name).unwrap();
+        let mut moved = Vec::new();
+        // last first, so taking one out doesn't move the others
+        for (i, suggestion, filing) in filings.iter().rev() {
+            let index = ItemPath::from(vec![*i]);
+            let item = inbox.get_mut(&index).unwrap();
+            if let (true, Some((title, date))) = (filing.date, &suggestion.date)
+            {
+                item.set_title(title.clone());
+                item.reschedule(Some(*date), None);
+            }
+            for tag in &filing.tags {
+                item.add_tag(tag);
+            }
+            if let Some(to) = filing.list {
+                let mut item = inbox.remove(&index)?;
+                item.touch();
+                moved.push((&suggestion.lists[to], item));
+            }
+        }
+        for (to, item) in moved.into_iter().rev() {
+            lists.get_mut(to).unwrap().append(&ItemPath::root(), item)?;
+        }
+        session.commit(lists)?;
+        println!("filed {} items", filings.len());
+        return Ok(());
+    }
+
+    // get the active list struct
+    let active_list = match &mut everything {
+        Some(all) => all,
+        None => lists.get_mut(list_name).unwrap(),
+    };
+
+    let hints_file = todo_folder.join("hints.json");
+    let hints = hints::Hints::load(&hints_file, list_name)?;
+    let mut stdout = std::io::stdout();
+    let mut render_opts = RenderOptions::for_terminal(config)?;
+    render_opts.hints = args.is_present("hints");
+    match args.subcommand() {
+        Some(("watch", watch_args)) => {
+            let interval: f64 = watch_args.value_of_t_or_exit("interval");
+            let names = if watch_args.is_present("all") {
+                Vec::new()
+            } else {
+                vec![String::from(list_name)]
+            };
+            let layout = match args.value_of("format") {
+                Some("table") => watch::Layout::Table,
+                _ => watch::Layout::Tree,
+            };
+            return watch::run(
+                todo_file,
+                &names,
+                layout,
+                config,
+                std::time::Duration::from_secs_f64(interval),
+            );
+        }
+        Some(("countdown", countdown_args)) => {
+            let index =
+                index_arg(countdown_args, "index", &hints, active_list)?;
+            let item = match active_list.get(&index) {
+                Some(item) => item,
+                None => bail!(index_error(
+                    active_list,
+                    &index,
+                    false,
+                    &render_opts,
+                    anyhow::anyhow!("Invalid index!"),
+                )),
+            };
+            return countdown::run(
+                item,
+                countdown_args.is_present("big"),
+                &render_opts,
+            );
+        }
+        Some(("intro", _)) => {
+            return intro(lists, &render_opts, session);
+        }
+        Some(("demo", demo_args)) => {
+            let items: usize = demo_args.value_of_t_or_exit("items");
+            let depth: usize = demo_args.value_of_t_or_exit("depth");
+            let name: String = demo_args.value_of_t_or_exit("name");
+            let mut rng = match demo_args.value_of_t::<u64>("seed") {
+                Ok(seed) => demo::Rng::new(seed),
+                Err(_) => demo::Rng::from_time(),
+            };
+            let list = demo::generate(name.clone(), items, depth, &mut rng);
+            if let Some(path) = demo_args.value_of("file") {
+                let mut demo_lists = HashMap::new();
+                demo_lists.insert(name, list);
+                save(Path::new(path), &demo_lists, config.general.backups)?;
+                println!("wrote demo data to {}", path);
+                return Ok(());
+            }
+            if lists.contains_key(&name) {
+                bail!(
+                    "The list '{}' already exists (choose another with --name)",
+                    name
+                );
+            }
+            list.write_to(&mut stdout, 0, &render_opts)?;
+            lists.insert(name, list);
+            session.commit(lists)?;
+            return Ok(());
+        }
+        Some(("list", list_args)) => {
+            if list_args.is_present("add") {
+                // add new list
+                let (title, date) = match list_args.value_of_t::<String>("add")
+                {
+                    Ok(title) => (title, None),
+                    Err(_) => prompt_for_info(None)?,
+                };
+                if lists.contains_key(&title) {
+                    bail!("The list '{}' already exists", title);
+                }
+                if title == ALL_LISTS {
+                    bail!("'{}' is the name for every list at once", title);
+                }
+                lists.insert(
+                    title.clone(),
+                    TodoList::from_info(title.clone(), date),
+                );
+                session.commit(lists)?;
+                println!("added new to-do list: '{}'", title);
+            } else if list_args.is_present("remove") {
+                // remove list
+
+                let title: String = list_args.value_of_t_or_exit("remove");
+                if title == config.default_list() {
+                    bail!("You cannot remove the default to-do list!");
+                } else if !lists.contains_key(&title) {
+                    bail!(
+                        "The to-do list '{}' does not currently exist.{}",
+                        title,
+                        did_you_mean(&suggest_names(&title, lists.keys()))
+                    );
+                }
+
+                let mut rl = rustyline::Editor::<()>::new();
+                let confirmed = !config.general.confirm || {
+                    let answer = rl.readline(&format!(
+                        "Remove list '{}'? (y/N): ",
+                        title
+                    ))?;
+                    answer.to_lowercase() == "y"
+                };
+                if confirmed {
+                    lists.remove(&title);
+                    session.commit(lists)?;
+                    println!("removed to-do list: '{}'", title);
+                } else {
+                    bail!("Cancelled.");
+                }
+            } else if list_args.is_present("edit") {
+                // edit list
+                let title: String = list_args.value_of_t_or_exit("edit");
+                let removed_list = match lists.remove(&title) {
+                    Some(l) => l,
+                    None => {
+                        bail!(
+                            "The list '{}' does not currently exist.{}",
+                            title,
+                            did_you_mean(&suggest_names(&title, lists.keys()))
+                        )
+                    }
+                };
+                let list_item = ListItem::List(removed_list);
+                let (new_title, new_date) = prompt_for_info(Some(&list_item))?;
+                if let ListItem::List(mut l) = list_item {
+                    l.title = new_title.clone();
+                    l.date = new_date;
+                    if lists.contains_key(&new_title) {
+                        bail!(
+                            "Weird, the list '{}' already exists. Edit reverted.",
+                            new_title
+                        );
+                    }
+                    lists.insert(new_title.clone(), l);
+                    session.commit(lists)?;
+                }
+            } else if list_args.is_present("notes") {
+                let title: String = list_args.value_of_t_or_exit("notes");
+                let list = match lists.get_mut(&title) {
+                    Some(list) => list,
+                    None => bail!(Error::list_not_found(&title, lists.keys())),
+                };
+                let notes = edit_text(list.notes.as_deref().unwrap_or(""))?;
+                let notes = notes.trim_end();
+                list.notes = if notes.trim().is_empty() {
+                    None
+                } else {
+                    Some(String::from(notes))
+                };
+                list.modified_at = Some(clock::now());
+                session.commit(lists)?;
+            }
             if lists.len() == 1 {
                 eprintln!("No named lists exist currently. (Use `later list --add` to create one.)");
             } else {
                 // list the lists
                 let mut v: Vec<(&String, &TodoList)> = lists.iter().collect();
                 v.sort_by_key(|(title, _)| *title);
-                v.iter()
-                    .filter(|(title, _)| *title != DEFAULT_LIST)
-                    .map(
-                    |(_, list): &(&String, &TodoList)| -> std::io::Result<()> {
-                        list.write_header(&mut stdout)?;
-                        Ok(())
-                    },
-                ).collect::<std::io::Result<_>>()?;
+                let named: Vec<&TodoList> = v
+                    .iter()
+                    .filter(|(title, _)| *title != config.default_list())
+                    .map(|(_, list)| *list)
+                    .collect();
+                if list_args.is_present("tiers") {
+                    tiers::write_tiers(&mut stdout, &named, &render_opts)?;
+                } else {
+                    named.iter().try_for_each(|list| {
+                        list.write_header(&mut stdout, &render_opts)
+                    })?;
+                }
+            }
+            return Ok(());
+        }
+        Some(("add", add_args)) if add_args.is_present("stdin") => {
+            let target = add_args.value_of("stdin").unwrap_or(list_name);
+            let no_hints = hints::Hints::default();
+            let target_hints = if target == list_name {
+                &hints
+            } else {
+                &no_hints
+            };
+            let list = match lists.get_mut(target) {
+                Some(list) => list,
+                None => bail!(Error::list_not_found(target, lists.keys())),
+            };
+            let index = index_arg(add_args, "index", target_hints, list)?;
+            let mut added = 0;
+            for line in std::io::stdin().lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let (title, date) = split_inline_date(&line);
+                let date = match date {
+                    Some(date) => Some(date),
+                    None => config.default_date(target, clock::today())?,
+                };
+                list.append(
+                    &index,
+                    ListItem::Entry(TodoEntry::from_info(title, date)),
+                )
+                .map_err(|e| {
+                    index_error(list, &index, false, &render_opts, e)
+                })?;
+                added += 1;
+            }
+            session.commit(lists)?;
+            println!("added {} items to '{}'", added, target);
+            return Ok(());
+        }
+        Some(("add", add_args)) => {
+            let (name, mut index) = match (
+                add_args.value_of("name").or(add_args.value_of("title")),
+                add_args.is_present("index"),
+            ) {
+                (Some(name), true) => (
+                    Some(String::from(name)),
+                    index_arg(add_args, "index", &hints, active_list)?,
+                ),
+                (None, true) => {
+                    // a lone word here may be a title, so only positions
+                    // count as an index
+                    let pieces: Vec<&str> =
+                        add_args.values_of("index").unwrap().collect();
+                    let index = IndexPath::parse(&pieces.join(","))
+                        .ok()
+                        .and_then(|path| path.positions());
+                    if let Some(v) = index {
+                        (None, ItemPath::from(v))
+                    } else {
+                        let name_pieces: Vec<String> =
+                            add_args.values_of_t_or_exit("index");
+                        let name = name_pieces
+                            .into_iter()
+                            .reduce(|acc, item| acc + "," + &item)
+                            .unwrap();
+                        (Some(name), ItemPath::root())
+                    }
+                }
+                (name, false) => (name.map(String::from), ItemPath::root()),
+            };
+            let name = match add_args.value_of("template") {
+                Some(template) => {
+                    let template = config
+                        .templates
+                        .get(template)
+                        .map_or(template, String::as_str);
+                    Some(template::expand(template, clock::today())?)
+                }
+                None => name,
+            };
+            let (date, zone) = if add_args.is_present("no-date") {
+                (None, None)
+            } else if add_args.is_present("date") {
+                date_args(add_args, None, None)?
+            } else {
+                let default = config.default_date(list_name, clock::today())?;
+                date_args(add_args, default, None)?
+            };
+            let priority = match add_args.value_of("priority") {
+                Some(name) => Some(priority_arg(name)?),
+                None => None,
+            };
+            if name.is_none() {
+                check_can_prompt(add_args, "A title", "--title")?;
+            }
+            let (title, date) = match (name, date) {
+                (Some(s), date) => (s, date),
+                (None, Some(date)) => (prompt_for_title()?, Some(date)),
+                (None, None) if add_args.is_present("no-date") => {
+                    (prompt_for_title()?, None)
+                }
+                (None, None) => prompt_for_info(None)?,
+            };
+            if add_args.is_present("deadline") && date.is_none() {
+                bail!("A deadline needs a date (give one with --date)");
+            }
+            let mut entry = TodoEntry::from_info(title, date);
+            entry.deadline = add_args.is_present("deadline");
+            entry.priority = priority;
+            entry.tags = tag_args(add_args, "tag")?;
+            entry.zone = zone;
+            active_list.append(&index, ListItem::Entry(entry)).map_err(
+                |e| index_error(active_list, &index, false, &render_opts, e),
+            )?;
+            let siblings = match active_list.get(&index) {
+                Some(ListItem::List(sublist)) => sublist.items().len(),
+                _ => active_list.items().len(),
+            };
+            index.push(siblings - 1);
+            if let Some(added) = active_list.get(&index) {
+                session.record(webhook::Action::Add, list_name, &index, added);
+            }
+            session.commit(lists)?;
+        }
+        Some(("focus", focus_args)) => {
+            if focus_args.is_present("clear") {
+                active_list.clear_focus();
+            }
+            for index in focus_args.values_of("index").unwrap_or_default() {
+                let index = match hints.resolve(index) {
+                    Some(index) => ItemPath::from(index),
+                    None => IndexPath::parse(index)?.resolve(active_list)?,
+                };
+                match active_list.get_mut(&index) {
+                    Some(item) => item.set_focused(true),
+                    None => bail!(index_error(
+                        active_list,
+                        &index,
+                        false,
+                        &render_opts,
+                        anyhow::anyhow!("Invalid index!"),
+                    )),
+                }
+            }
+            session.commit(lists)?;
+        }
+        Some(("done", done_args)) => {
+            let index = pick_or_index_arg(
+                done_args,
+                "index",
+                "Item to mark: ",
+                &hints,
+                active_list,
+            )?;
+            let item = match active_list.get_mut(&index) {
+                Some(item) => item,
+                None => bail!(index_error(
+                    active_list,
+                    &index,
+                    false,
+                    &render_opts,
+                    anyhow::anyhow!("Invalid index!"),
+                )),
+            };
+            if done_args.is_present("reopen") {
+                item.set_completed(None);
+            } else {
+                item.set_completed(Some(clock::now()));
+                session.record(webhook::Action::Done, list_name, &index, item);
+            }
+            session.commit(lists)?;
+        }
+        Some(("rename-matching", rename_args)) => {
+            let renames = search::renames(
+                active_list,
+                rename_args.value_of("pattern").unwrap(),
+                rename_args.value_of("replacement").unwrap(),
+                rename_args.is_present("regex"),
+            )?;
+            if renames.is_empty() {
+                println!("No titles match.");
+                return Ok(());
+            }
+            search::write_renames(&mut stdout, &renames)?;
+            if config.general.confirm
+                && snapshot.is_none()
+                && std::io::stdin().is_terminal()
+            {
+                let mut rl = rustyline::Editor::<()>::new();
+                let confirm = rl.readline(&format!(
+                    "Rename {} items? (y/N): ",
+                    renames.len()
+                ))?;
+                if confirm.to_lowercase() != "y" {
+                    bail!("Cancelled.");
+                }
+            }
+            for rename in renames {
+                if let Some(item) = active_list.get_mut(&rename.index) {
+                    item.set_title(rename.new);
+                }
+            }
+            session.commit(lists)?;
+        }
+        Some(("remove", remove_args)) => {
+            let index = pick_or_index_arg(
+                remove_args,
+                "index",
+                "Item to remove: ",
+                &hints,
+                active_list,
+            )?;
+            let mut rl = rustyline::Editor::<()>::new();
+            let removed = active_list.remove(&index).map_err(|e| {
+                index_error(active_list, &index, false, &render_opts, e)
+            })?;
+            session.record(
+                webhook::Action::Remove,
+                list_name,
+                &index,
+                &removed,
+            );
+            if remove_args.is_present("archive") {
+                // nothing is lost, so there's nothing to confirm
+                let title = removed.title().to_string();
+                session.archived.borrow_mut().push(archive::Archived::new(
+                    list_name,
+                    &index,
+                    archive::Reason::Removed,
+                    removed,
+                ));
+                session.commit(lists)?;
+                println!("archived '{}'", title);
+            } else if !config.general.confirm
+                || match removed {
+                    ListItem::List(l) => {
+                        let confirm = rl.readline(&format!(
+                            "Remove sublist '{}'? (y/N): ",
+                            l.title
+                        ))?;
+                        confirm.to_lowercase() == "y"
+                    }
+                    ListItem::Entry(e) => {
+                        let confirm = rl.readline(&format!(
+                            "Remove entry '{}'? (Y/n): ",
+                            e.title
+                        ))?;
+                        confirm.to_lowercase() == "y" || confirm.is_empty()
+                    }
+                }
+            {
+                session.commit(lists)?;
+            } else {
+                bail!("Cancelled.");
+            }
+        }
+        Some(("move", move_args)) => {
+            let from_index = pick_or_index_arg(
+                move_args,
+                "from",
+                "Item to move: ",
+                &hints,
+                active_list,
+            )?;
+            let to_index = pick_or_index_arg(
+                move_args,
+                "to",
+                "Put it by: ",
+                &hints,
+                active_list,
+            )?;
+            let place = match move_args.value_of("place") {
+                Some(name) => Place::from_name(name),
+                None if std::io::stdin().is_terminal()
+                    && from_index != to_index =>
+                {
+                    match (
+                        active_list.get(&from_index),
+                        active_list.get(&to_index),
+                    ) {
+                        (Some(item), Some(target)) => {
+                            Some(ask_place(item.title(), target.title())?)
+                        }
+                        _ => None,
+                    }
+                }
+                None => None,
+            };
+            match place {
+                Some(Place::Swap) => {
+                    active_list.swap_items(&from_index, &to_index)?;
+                    session.commit(lists)?;
+                }
+                Some(place) => {
+                    if active_list.get(&to_index).is_none() {
+                        bail!(index_error(
+                            active_list,
+                            &to_index,
+                            false,
+                            &render_opts,
+                            anyhow::anyhow!("Invalid index!"),
+                        ));
+                    }
+                    if to_index.starts_with(&from_index) {
+                        if to_index == from_index {
+                            return Ok(());
+                        }
+                        bail!("Can't move an item into itself");
+                    }
+                    let mut item =
+                        active_list.remove(&from_index).map_err(|e| {
+                            index_error(
+                                active_list,
+                                &from_index,
+                                false,
+                                &render_opts,
+                                e,
+                            )
+                        })?;
+                    item.touch();
+                    let mut to_index = to_index.after_removal(&from_index);
+                    if place == Place::After {
+                        *to_index.last_mut().unwrap() += 1;
+                    }
+                    active_list.insert(&to_index, item)?;
+                    session.commit(lists)?;
+                }
+                None => {
+                    let mut item =
+                        active_list.remove(&from_index).map_err(|e| {
+                            index_error(
+                                active_list,
+                                &from_index,
+                                false,
+                                &render_opts,
+                                e,
+                            )
+                        })?;
+                    item.touch();
+                    active_list.insert(&to_index, item).map_err(|e| {
+                        index_error(
+                            active_list,
+                            &to_index,
+                            true,
+                            &render_opts,
+                            e,
+                        )
+                    })?;
+                    session.commit(lists)?;
+                }
+            }
+        }
+        Some(("copy", copy_args)) => {
+            let from_index = index_arg(copy_args, "from", &hints, active_list)?;
+            let to_index = index_arg(copy_args, "to", &hints, active_list)?;
+            let item = match active_list.get(&from_index) {
+                Some(item) => item,
+                None => bail!(index_error(
+                    active_list,
+                    &from_index,
+                    false,
+                    &render_opts,
+                    anyhow::anyhow!("Invalid index!"),
+                )),
+            };
+            let today = clock::today();
+            let anchor = match anchor_arg(copy_args)? {
+                Some(anchor) => anchor,
+                None => template::anchor_of(item, today),
+            };
+            let copy = template::copy(item, today, anchor);
+            active_list.insert(&to_index, copy).map_err(|e| {
+                index_error(active_list, &to_index, true, &render_opts, e)
+            })?;
+            session.commit(lists)?;
+        }
+        Some(("edit", edit_args)) if edit_args.is_present("notes") => {
+            let index = pick_or_index_arg(
+                edit_args,
+                "index",
+                "Item to edit: ",
+                &hints,
+                active_list,
+            )?;
+            let item = match active_list.get_mut(&index) {
+                Some(item) => item,
+                None => bail!(index_error(
+                    active_list,
+                    &index,
+                    false,
+                    &render_opts,
+                    anyhow::anyhow!("Invalid index!"),
+                )),
+            };
+            let notes = edit_text(item.notes().unwrap_or(""))?;
+            let notes = notes.trim_end();
+            item.set_notes(if notes.trim().is_empty() {
+                None
+            } else {
+                Some(String::from(notes))
+            });
+            session.commit(lists)?;
+        }
+        Some(("edit", edit_args))
+            if [
+                "priority", "tag", "untag", "deadline", "soft", "title",
+                "date", "time", "no-date",
+            ]
+            .iter()
+            .any(|arg| edit_args.is_present(arg)) =>
+        {
+            let index = pick_or_index_arg(
+                edit_args,
+                "index",
+                "Item to edit: ",
+                &hints,
+                active_list,
+            )?;
+            let priority = match edit_args.value_of("priority") {
+                Some("none") => Some(None),
+                Some(name) => Some(Some(priority_arg(name)?)),
+                None => None,
+            };
+            let tags = tag_args(edit_args, "tag")?;
+            let untags = tag_args(edit_args, "untag")?;
+            let item = match active_list.get_mut(&index) {
+                Some(item) => item,
+                None => bail!(index_error(
+                    active_list,
+                    &index,
+                    false,
+                    &render_opts,
+                    anyhow::anyhow!("Invalid index!"),
+                )),
+            };
+            if let Some(title) = edit_args.value_of("title") {
+                item.set_title(String::from(title));
+            }
+            if edit_args.is_present("no-date") {
+                item.reschedule(None, None);
+            } else if edit_args.is_present("time") {
+                let (date, zone) =
+                    date_args(edit_args, item.date(), item.zone())?;
+                item.reschedule(date, zone);
+            } else if edit_args.is_present("date") {
+                let (date, zone) = date_args(edit_args, None, None)?;
+                item.reschedule(date, zone);
+            }
+            if let Some(priority) = priority {
+                item.set_priority(priority);
+            }
+            for tag in &untags {
+                item.remove_tag(tag);
+            }
+            for tag in &tags {
+                item.add_tag(tag);
+            }
+            if edit_args.is_present("deadline") {
+                if item.date().is_none() {
+                    bail!("A deadline needs a date (give the item one first)");
+                }
+                item.set_deadline(true);
+            } else if edit_args.is_present("soft") {
+                item.set_deadline(false);
+            }
+            session.commit(lists)?;
+        }
+        Some(("edit", edit_args)) => {
+            check_can_prompt(
+                edit_args,
+                "A change",
+                "--title, --date, --time or the like",
+            )?;
+            let index = pick_or_index_arg(
+                edit_args,
+                "index",
+                "Item to edit: ",
+                &hints,
+                active_list,
+            )?;
+            let item = active_list.remove(&index).map_err(|e| {
+                index_error(active_list, &index, false, &render_opts, e)
+            })?;
+            let (new_title, new_date) = prompt_for_info(Some(&item))?;
+            match item {
+                ListItem::Entry(mut entry) => {
+                    entry.title = new_title;
+                    entry.reschedule(new_date);
+                    active_list.insert(&index, ListItem::Entry(entry))?;
+                }
+                ListItem::List(mut list) => {
+                    list.title = new_title;
+                    list.reschedule(new_date);
+                    active_list.insert(&index, ListItem::List(list))?;
+                }
+            }
+            session.commit(lists)?;
+        }
+        Some(("import", import_args)) => {
+            let format = import::Format::from_name(
+                import_args.value_of("format").unwrap(),
+            )
+            .unwrap();
+            let path = Path::new(import_args.value_of("path").unwrap());
+            let mapping = import_args.value_of("map").map(Path::new);
+            let items = import::import(format, path, mapping)?;
+            let count = items.len();
+            for item in items {
+                active_list.append(&ItemPath::root(), item)?;
+            }
+            session.commit(lists)?;
+            println!("imported {} items into '{}'", count, list_name);
+        }
+        Some(("template", template_args)) => {
+            let templates_file = todo_folder.join("templates.json");
+            let mut templates = template::load(&templates_file)?;
+            match template_args.subcommand() {
+                Some(("save", save_args)) => {
+                    let index =
+                        index_arg(save_args, "index", &hints, active_list)?;
+                    let name = save_args.value_of("name").unwrap();
+                    let item = match active_list.get(&index) {
+                        Some(item) => item,
+                        None => bail!(index_error(
+                            active_list,
+                            &index,
+                            false,
+                            &render_opts,
+                            anyhow::anyhow!("Invalid index!"),
+                        )),
+                    };
+                    let saved =
+                        template::Template::from_item(item, clock::today());
+                    let count = saved.count();
+                    templates.insert(String::from(name), saved);
+                    if snapshot.is_none() {
+                        template::save(&templates_file, &templates)?;
+                    }
+                    println!("saved {} items as template '{}'", count, name);
+                    return Ok(());
+                }
+                Some(("new", new_args)) => {
+                    let name = new_args.value_of("name").unwrap();
+                    let saved = match templates.get(name) {
+                        Some(saved) => saved,
+                        None => {
+                            let names: Vec<String> =
+                                templates.keys().cloned().collect();
+                            bail!(
+                                "No template called '{}'.{}",
+                                name,
+                                did_you_mean(&suggest_names(name, &names))
+                            )
+                        }
+                    };
+                    let dest =
+                        index_arg(new_args, "dest", &hints, active_list)?;
+                    active_list
+                        .append(
+                            &dest,
+                            saved.instantiate(
+                                anchor_arg(new_args)?
+                                    .unwrap_or_else(clock::today),
+                            ),
+                        )
+                        .map_err(|e| {
+                            index_error(
+                                active_list,
+                                &dest,
+                                false,
+                                &render_opts,
+                                e,
+                            )
+                        })?;
+                    session.commit(lists)?;
+                }
+                _ => {
+                    for (name, saved) in &templates {
+                        println!("{} ({} items)", name, saved.count());
+                    }
+                    return Ok(());
+                }
+            }
+        }
+        Some(("show", show_args)) => {
+            if let Some(as_of) = show_args.value_of("as-of") {
+                let as_of = url_scheme::parse_due(as_of).map_err(|_| {
+                    anyhow::anyhow!(
+                        "Invalid date '{}' (expected yyyy-mm-dd [hh:mm])",
+                        as_of
+                    )
+                })?;
+                let time = match as_of {
+                    DateMaybeTime::DateTime(time) => time,
+                    // keep the time of day, so "in 3 hours" still makes sense
+                    DateMaybeTime::Date(date) => chrono::Local
+                        .from_local_datetime(
+                            &date.and_time(clock::now().time()),
+                        )
+                        .earliest()
+                        .unwrap_or_else(clock::now),
+                };
+                clock::pin(Some(time));
+                let lang = i18n::Lang::current();
+                let date = clock::today();
+                println!(
+                    "{}",
+                    render_opts.style(Style::new().dimmed()).paint(format!(
+                        "as of {}, {}",
+                        lang.weekday(date.weekday()),
+                        lang.month_day(date, true)
+                    ))
+                );
+            }
+            if let Some(tag) = show_args.value_of("tag") {
+                render_opts.tag = Some(tag_arg(tag)?);
+            }
+            if show_args.is_present("index") {
+                let index = index_arg(show_args, "index", &hints, active_list)?;
+                match active_list.get(&index) {
+                    Some(item) => {
+                        show::write_details(&mut stdout, item, &render_opts)?
+                    }
+                    None => bail!(index_error(
+                        active_list,
+                        &index,
+                        false,
+                        &render_opts,
+                        anyhow::anyhow!("Invalid index!"),
+                    )),
+                }
+                return Ok(());
+            }
+        }
+        Some(("split", split_args)) => {
+            let index = index_arg(split_args, "index", &hints, active_list)?;
+            let by: Vec<&str> = split_args.values_of("by").unwrap().collect();
+            let size = match by.get(1) {
+                Some(size) => match size.parse() {
+                    Ok(size) => Some(size),
+                    Err(_) => bail!("Invalid count '{}'", size),
+                },
+                None => None,
+            };
+            let rule = match split::Rule::from_name(by[0], size) {
+                Some(rule) => rule,
+                None => bail!(
+                    "Invalid rule '{}' (expected date, tag or count N)",
+                    by.join(" ")
+                ),
+            };
+            match active_list.get(&index) {
+                Some(ListItem::List(_)) => {}
+                Some(ListItem::Entry(entry)) => {
+                    bail!(
+                        "'{}' isn't a sublist, so can't be split",
+                        entry.title
+                    )
+                }
+                None => bail!(index_error(
+                    active_list,
+                    &index,
+                    false,
+                    &render_opts,
+                    anyhow::anyhow!("Invalid index!"),
+                )),
+            }
+            let sublist = match active_list.remove(&index)? {
+                ListItem::List(sublist) => sublist,
+                ListItem::Entry(_) => unreachable!(),
+            };
+            let title = sublist.title.clone();
+            let parts = split::split(sublist, rule);
+            if parts.len() < 2 {
+                bail!("Everything in '{}' falls in the same part", title);
+            }
+            let count = parts.len();
+            for part in parts.into_iter().rev() {
+                active_list.insert(&index, ListItem::List(part))?;
+            }
+            session.commit(lists)?;
+            println!("split '{}' into {} sublists", title, count);
+        }
+        Some(("start", start_args)) => {
+            let index = index_arg(start_args, "index", &hints, active_list)?;
+            if active_list.get(&index).is_none() {
+                bail!(index_error(
+                    active_list,
+                    &index,
+                    false,
+                    &render_opts,
+                    anyhow::anyhow!("Invalid index!"),
+                ));
+            }
+            // one clock at a time
+            if let Some((name, running)) = tracking::running(lists) {
+                let item = lists.get_mut(&name).unwrap().get_mut(&running);
+                let item = item.unwrap();
+                if name == list_name && *running == *index {
+                    bail!("'{}' is already being tracked", item.title());
+                }
+                let spent = tracking::stop(item).unwrap();
+                println!(
+                    "stopped '{}' after {}",
+                    item.title(),
+                    tracking::hours(spent)
+                );
+            }
+            let item = lists.get_mut(list_name).unwrap().get_mut(&index);
+            let item = item.unwrap();
+            tracking::start(item);
+            println!("started '{}'", item.title());
+            session.commit(lists)?;
+        }
+        Some(("snooze", snooze_args)) => {
+            let index = index_arg(snooze_args, "index", &hints, active_list)?;
+            let item = match active_list.get_mut(&index) {
+                Some(item) => item,
+                None => bail!(index_error(
+                    active_list,
+                    &index,
+                    false,
+                    &render_opts,
+                    anyhow::anyhow!("Invalid index!"),
+                )),
+            };
+            let (date, zone) = match snooze_args.value_of("to") {
+                Some(text) => {
+                    match natural::parse_zoned(text, clock::today()) {
+                        Some(parsed) => parsed,
+                        None => bail!(Error::ParseDate {
+                            text: String::from(text),
+                        }),
+                    }
+                }
+                None => {
+                    let by = snooze_args.value_of("by").unwrap();
+                    let by = match natural::parse_period(by) {
+                        Some(by) => by,
+                        None => {
+                            bail!("Invalid duration '{}' (e.g. 3h, 1d, 2w)", by)
+                        }
+                    };
+                    match snoozed(item.date(), by, clock::now()) {
+                        Some(date) => (date, item.zone()),
+                        None => bail!(
+                            "'{}' has a date but no time, so it can only be \
+                             snoozed by whole days (or use --to)",
+                            item.title()
+                        ),
+                    }
+                }
+            };
+            item.reschedule(Some(date), zone);
+            session.commit(lists)?;
+        }
+        Some(("sort", sort_args)) => {
+            let tie_break = sort_args
+                .value_of("then")
+                .and_then(TieBreak::from_name)
+                .unwrap_or(config.sort.tie_break);
+            let by =
+                SortBy::from_name(sort_args.value_of("by").unwrap()).unwrap();
+            timings::time(timings::Phase::Sort, || {
+                active_list.sort(by, tie_break, config.display.roll_up_dates)
+            });
+            // the copy of everything is only shown sorted
+            if everything.is_none() {
+                session.commit(lists)?;
+            }
+        }
+        _ => {}
+    }
+    if session.deferred {
+        return Ok(());
+    }
+    if config.display.summary {
+        let summary = status::Summary::of(lists);
+        status::write_summary(&mut stdout, &summary, &render_opts)?;
+    }
+    let active_list = match &mut everything {
+        Some(all) => all,
+        None => lists.get_mut(&String::from(list_name)).unwrap(),
+    };
+    let started = std::time::Instant::now();
+    match args.value_of("format") {
+        Some("table") => {
+            table::write_table(active_list, &mut stdout, &render_opts)?
+        }
+        _ => {
+            active_list.write_focus(&mut stdout, &render_opts)?;
+            active_list.write_to(&mut stdout, 0, &render_opts)?;
+        }
+    }
+    timings::record(timings::Phase::Render, started.elapsed());
+    if render_opts.hints && args.value_of("format") != Some("table") {
+        hints::Hints::for_list(list_name, active_list).save(&hints_file)?;
+    }
+    Ok(())
+}
+
+fn notify(
+    sent_file: &Path,
+    lists: &HashMap<String, TodoList>,
+    config: &config::NotifyConfig,
+    args: &ArgMatches,
+) -> anyhow::Result<()> {
+    if args.is_present("test") {
+        let message = notify::Message {
+            title: String::from("later"),
+            body: String::from("Notifications are working."),
+        };
+        return notify::send_all(config, &message);
+    }
+    let ahead = match args.value_of("ahead") {
+        Some(_) => args.value_of_t_or_exit("ahead"),
+        None => config.ahead_minutes,
+    };
+    let due = notify::due_items(
+        lists,
+        clock::now(),
+        chrono::Duration::minutes(ahead),
+    );
+    let sent = notify::load_sent(sent_file)?;
+    let fresh: Vec<notify::Due> = due
+        .iter()
+        .filter(|item| !sent.contains(&item.key()))
+        .cloned()
+        .collect();
+    if fresh.is_empty() {
+        return Ok(());
+    }
+    let message = notify::Message::for_items(&fresh);
+    if args.is_present("dry-run") {
+        println!("{}\n{}", message.title, message.body);
+        println!("Dry run, nothing was sent.");
+        return Ok(());
+    }
+    notify::send_all(config, &message)?;
+    // forget items that have since been removed or rescheduled
+    let keys: Vec<String> = due.iter().map(notify::Due::key).collect();
+    notify::save_sent(sent_file, &keys)
+}
+
+// how long the daemon sleeps at most, so it notices changes to the lists
+const DAEMON_POLL: std::time::Duration = std::time::Duration::from_secs(30);
+
+// run as `notify` would every so often, and the on_due commands as timed
+// items fall due, until interrupted; items already due when it starts
+// don't set them off
+fn notify_daemon(
+    todo_file: &Path,
+    sent_file: &Path,
+    config: &config::NotifyConfig,
+    args: &ArgMatches,
+) -> anyhow::Result<()> {
+    let mut from = clock::now();
+    loop {
+        let lists = load(todo_file)?;
+        // a channel being down shouldn't stop the alarms
+        if let Err(e) = notify(sent_file, &lists, config, args) {
+            eprintln!("Error: {:#}", e);
+        }
+        let now = clock::now();
+        for alarm in notify::alarms_between(&lists, from, now) {
+            let tags: Vec<&str> =
+                alarm.tags.iter().map(String::as_str).collect();
+            let command = match config.on_due_command(&tags) {
+                Some(command) => command,
+                None => continue,
+            };
+            debug!(title = %alarm.title, command, "on due");
+            if args.is_present("dry-run") {
+                println!("Would run '{}' for {}", command, alarm.title);
+            } else if let Err(e) = notify::ring(command, &alarm) {
+                eprintln!("Error: {:#}", e);
+            }
+        }
+        from = now;
+        let wait = match notify::next_alarm(&lists, now) {
+            Some(at) => {
+                (at - now).to_std().unwrap_or_default().min(DAEMON_POLL)
             }
+            None => DAEMON_POLL,
+        };
+        std::thread::sleep(wait);
+    }
+}
+
+fn sync_backend(
+    config: &config::Config,
+) -> anyhow::Result<Box<dyn sync::Backend>> {
+    match &config.sync.remote {
+        Some(remote) => Ok(sync::backend(remote, &config.data_dir()?)),
+        None => bail!(
+            "Sync isn't set up (add a remote under [sync] in {})",
+            config::Config::default_path()
+                .map_or(String::from("the config file"), |p| p
+                    .display()
+                    .to_string())
+        ),
+    }
+}
+
+fn describe_change(change: &sync::Change) -> String {
+    match change {
+        sync::Change::Added => String::from("new list"),
+        sync::Change::Removed => String::from("deleted"),
+        sync::Change::Modified {
+            added: 0,
+            removed: 0,
+        } => String::from("rearranged"),
+        sync::Change::Modified { added, removed } => {
+            format!("{} added, {} removed", added, removed)
+        }
+    }
+}
+
+fn sync(
+    state_file: &Path,
+    lists: &mut HashMap<String, TodoList>,
+    config: &config::Config,
+    session: &Session,
+) -> anyhow::Result<()> {
+    let backend = sync_backend(config)?;
+    let mut state = sync::State::load(state_file)?;
+    let local = sync::snapshot(lists)?;
+    // a missing remote copy is a fresh start, not every list being deleted
+    let remote = match backend.fetch()? {
+        Some(data) => sync::parse(&data)?,
+        None => state.base.clone(),
+    };
+    let pushed = sync::changes(&state.base, &local).len();
+    let merge = sync::merge(&state.base, &local, &remote);
+    *lists = sync::to_lists(merge.lists.clone())?;
+    session.commit(lists)?;
+    if session.snapshot.is_some() {
+        return Ok(());
+    }
+    backend.push(&sync::to_remote(lists)?)?;
+
+    println!(
+        "synced with {}: {} lists pulled, {} pushed",
+        backend.describe(),
+        merge.pulled.len(),
+        pushed
+    );
+    for name in merge.conflicts.keys() {
+        println!(
+            "conflict: '{}' changed on both sides; keeping the local version \
+             (see `later sync resolve`)",
+            name
+        );
+    }
+    state.base = merge.lists;
+    state.conflicts.extend(merge.conflicts);
+    state.last_sync = Some(chrono::Utc::now());
+    state.save(state_file)
+}
+
+fn sync_status(
+    state_file: &Path,
+    lists: &HashMap<String, TodoList>,
+    config: &config::Config,
+) -> anyhow::Result<()> {
+    let backend = sync_backend(config)?;
+    let state = sync::State::load(state_file)?;
+    let bold = color::style(Style::new().bold());
+    println!("{} {}", bold.paint("Remote:"), backend.describe());
+    let last_sync = match state.last_sync {
+        Some(time) => format!(
+            "{} ({})",
+            time.with_timezone(&chrono::Local).format("%Y/%m/%d %H:%M"),
+            humanize::relative(
+                i18n::Lang::current(),
+                time - chrono::Utc::now()
+            )
+        ),
+        None => String::from("never"),
+    };
+    println!("{} {}", bold.paint("Last sync:"), last_sync);
+
+    let local = sync::changes(&state.base, &sync::snapshot(lists)?);
+    println!("{}", bold.paint("Local changes:"));
+    if local.is_empty() {
+        println!("  none");
+    }
+    for (name, change) in &local {
+        println!("  {}: {}", name, describe_change(change));
+    }
+
+    println!("{}", bold.paint("Remote changes:"));
+    let remote = match backend.fetch() {
+        Ok(Some(data)) => sync::changes(&state.base, &sync::parse(&data)?),
+        Ok(None) => {
+            println!("  nothing pushed yet");
+            Vec::new()
+        }
+        Err(e) => {
+            println!("  unknown, the remote is unreachable ({:#})", e);
+            Vec::new()
+        }
+    };
+    if remote.is_empty() && state.last_sync.is_some() {
+        println!("  none");
+    }
+    for (name, change) in &remote {
+        println!("  {}: {}", name, describe_change(change));
+    }
+
+    println!("{}", bold.paint("Conflicts:"));
+    let upcoming: Vec<&String> = local
+        .iter()
+        .filter(|(name, _)| remote.iter().any(|(other, _)| other == name))
+        .map(|(name, _)| name)
+        .collect();
+    if state.conflicts.is_empty() && upcoming.is_empty() {
+        println!("  none");
+    }
+    let mut unresolved: Vec<&String> = state.conflicts.keys().collect();
+    unresolved.sort();
+    for name in unresolved {
+        println!(
+            "  {}: unresolved (later sync resolve {} --keep local|remote)",
+            name, name
+        );
+    }
+    for name in upcoming {
+        println!("  {}: changed on both sides since the last sync", name);
+    }
+    Ok(())
+}
+
+fn sync_resolve(
+    state_file: &Path,
+    lists: &mut HashMap<String, TodoList>,
+    args: &ArgMatches,
+    session: &Session,
+) -> anyhow::Result<()> {
+    let name = args.value_of("list").unwrap();
+    let mut state = sync::State::load(state_file)?;
+    let remote = match state.conflicts.remove(name) {
+        Some(remote) => remote,
+        None => {
+            let names: Vec<String> = state.conflicts.keys().cloned().collect();
+            bail!(
+                "There is no conflict over '{}'.{}",
+                name,
+                did_you_mean(&suggest_names(name, names.iter()))
+            )
+        }
+    };
+    if args.value_of("keep") == Some("remote") {
+        lists.insert(String::from(name), serde_json::from_value(remote)?);
+        session.commit(lists)?;
+    }
+    if session.snapshot.is_none() {
+        state.save(state_file)?;
+    }
+    Ok(())
+}
+
+// where the lists live, and how changes to them get saved
+struct Session<'a> {
+    store: &'a store::Store,
+    snapshot: Option<&'a str>,
+    // inside a transaction, saving waits until every step has succeeded
+    deferred: bool,
+    command: &'a str,
+    // the lists as loaded, taken by the first save to record for undo
+    before: Cell<Option<sync::Snapshot>>,
+    // changes for the webhooks, sent once they're saved
+    events: RefCell<Vec<webhook::Event>>,
+    // items taken out for the archive, written once the lists are saved
+    archived: RefCell<Vec<archive::Archived>>,
+}
+
+impl Session<'_> {
+    fn record(
+        &self,
+        action: webhook::Action,
+        list: &str,
+        index: &[usize],
+        item: &ListItem,
+    ) {
+        if self.store.wants_events() {
+            let event = webhook::Event::new(action, list, index, item);
+            self.events.borrow_mut().push(event);
+        }
+    }
+
+    fn commit(
+        &self,
+        lists: &mut HashMap<String, TodoList>,
+    ) -> anyhow::Result<()> {
+        if self.deferred {
             return Ok(());
         }
-        Some(("add", add_args)) => {
-            let (name, mut index) = match (
-                add_args.is_present("name"),
-                add_args.is_present("index"),
-            ) {
-                (true, true) => {
-                    let name: String = add_args.value_of_t_or_exit("name");
-                    let index: Vec<usize> =
-                        add_args.values_of_t_or_exit("index");
-                    (Some(name), index)
-                }
-                (false, true) => {
-                    let index: Result<Vec<usize>, _> =
-                        add_args.values_of_t("index");
-                    if let Ok(v) = index {
-                        (None, v)
-                    } else {
-                        let name_pieces: Vec<String> =
-                            add_args.values_of_t_or_exit("index");
-                        let name = name_pieces
-                            .into_iter()
-                            .reduce(|acc, item| acc + "," + &item)
-                            .unwrap();
-                        (Some(name), Vec::new())
+        let change = store::Change {
+            before: self.before.take(),
+            events: self.events.take(),
+            archived: self.archived.take(),
+        };
+        let messages =
+            self.store
+                .commit(self.command, lists, change, self.snapshot)?;
+        for message in messages {
+            eprintln!("{}", message);
+        }
+        Ok(())
+    }
+}
+
+// commands that only touch the lists in memory, and so can be steps
+const TX_COMMANDS: &[&str] = &[
+    "add", "remove", "done", "focus", "move", "copy", "edit", "sort", "import",
+    "list", "now", "split", "reset", "snooze",
+];
+
+fn transaction(
+    steps: &[(String, String)],
+    list_name: Option<&str>,
+    lists: &mut HashMap<String, TodoList>,
+    config: &config::Config,
+    session: &Session,
+) -> anyhow::Result<()> {
+    let deferred = Session {
+        store: session.store,
+        snapshot: None,
+        deferred: true,
+        command: session.command,
+        before: Cell::new(None),
+        events: RefCell::new(Vec::new()),
+        archived: RefCell::new(Vec::new()),
+    };
+    // steps work on a copy, so a failure leaves nothing half-applied
+    let mut working = sync::to_lists(sync::snapshot(lists)?)?;
+    for (label, step) in steps {
+        let step_args = step_matches(label, step, list_name)?;
+        run(&step_args, &mut working, config, &deferred).with_context(
+            || format!("{} ('{}') failed, so nothing was saved", label, step),
+        )?;
+    }
+    *lists = working;
+    session.events.borrow_mut().extend(deferred.events.take());
+    session
+        .archived
+        .borrow_mut()
+        .extend(deferred.archived.take());
+    session.commit(lists)
+}
+
+// parse one step of a transaction, checking it's a command that can be
+// part of one; steps without a list name get `list_name`
+fn step_matches(
+    label: &str,
+    step: &str,
+    list_name: Option<&str>,
+) -> anyhow::Result<ArgMatches> {
+    let words = split_words(step)?;
+    let mut step_args = cli()
+        .try_get_matches_from(
+            std::iter::once("later").chain(words.iter().map(String::as_str)),
+        )
+        .with_context(|| format!("{} ('{}') is invalid", label, step))?;
+    if let (Some(name), None) = (list_name, step_args.value_of("list-name")) {
+        step_args = cli().get_matches_from(
+            ["later", name]
+                .into_iter()
+                .chain(words.iter().map(String::as_str)),
+        );
+    }
+    match step_args.subcommand_name() {
+        Some(command) if TX_COMMANDS.contains(&command) => Ok(step_args),
+        Some(command) => bail!(
+            "{} ('{}'): {} can't be part of a transaction (steps can be: {})",
+            label,
+            step,
+            command,
+            TX_COMMANDS.join(", ")
+        ),
+        None => bail!("{} ('{}') has no command", label, step),
+    }
+}
+
+// a line of `later batch`, with `add LIST TITLE DATE` spelled out as
+// `LIST add TITLE --date DATE`; plain `add` never takes three words, so the
+// two can't be confused
+fn batch_line(line: &str) -> anyhow::Result<String> {
+    let words = split_words(line)?;
+    match words.as_slice() {
+        [add, list, title, date]
+            if add == "add"
+                && !list.starts_with('-')
+                && !title.starts_with('-')
+                && !date.starts_with('-') =>
+        {
+            Ok(format!(
+                "{} add {} --date {}",
+                quote(list),
+                quote(title),
+                quote(date)
+            ))
+        }
+        _ => Ok(String::from(line)),
+    }
+}
+
+// a word as `split_words` reads it back
+fn quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
+// splits a step into words like a shell would, minus the expansions
+fn split_words(line: &str) -> anyhow::Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' | '"' => {
+                let current = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some('\\') if c == '"' => {
+                            current.extend(chars.next());
+                        }
+                        Some(other) => current.push(other),
+                        None => bail!("Unclosed quote in '{}'", line),
                     }
                 }
-                _ => (None, Vec::new()),
-            };
-            let (title, date) = match name {
-                Some(s) => (s, None),
-                None => prompt_for_info(None)?,
-            };
-            active_list.add_item(
-                ListItem::Entry(TodoEntry { title, date }),
-                &mut index.iter_mut(),
-            )?;
-            save(&todo_file, &lists)?;
+            }
+            '\\' => {
+                let current = word.get_or_insert_with(String::new);
+                current.extend(chars.next());
+            }
+            c => word.get_or_insert_with(String::new).push(c),
         }
-        Some(("remove", remove_args)) => {
-            let mut index: Vec<usize> =
-                remove_args.values_of_t_or_exit("index");
-            let mut rl = rustyline::Editor::<()>::new();
-            if match active_list.remove_item(&mut index.iter_mut())? {
-                ListItem::List(l) => {
-                    let confirm = rl.readline(&format!(
-                        "Remove sublist '{}'? (y/N): ",
-                        l.title
-                    ))?;
-                    confirm.to_lowercase() == "y"
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+const INTRO_LIST: &str = "sandbox";
+
+// walk through the basics by running real commands on a sandbox list
+fn intro(
+    lists: &mut HashMap<String, TodoList>,
+    render_opts: &RenderOptions,
+    session: &Session,
+) -> anyhow::Result<()> {
+    if lists.contains_key(INTRO_LIST) {
+        bail!(
+            "A list called '{}' already exists, remove it to rerun the intro",
+            INTRO_LIST
+        );
+    }
+    let mut rl = rustyline::Editor::<()>::new();
+    let mut stdout = std::io::stdout();
+    let step = |text: &str, command: &str| {
+        println!("\n{}", text);
+        println!("  {}", color::style(Style::new().bold()).paint(command));
+    };
+    let ask = |rl: &mut rustyline::Editor<()>, prompt: &str, default: &str| {
+        rl.readline_with_initial(prompt, (default, "")).map(|s| {
+            if s.is_empty() {
+                default.to_string()
+            } else {
+                s
+            }
+        })
+    };
+
+    println!(
+        "Welcome to later! This walkthrough uses a sandbox list called '{}'.",
+        INTRO_LIST
+    );
+    step(
+        "Lists are created with `list --add`. Let's make the sandbox:",
+        &format!("later list --add {}", INTRO_LIST),
+    );
+    lists.insert(
+        String::from(INTRO_LIST),
+        TodoList::from_info(String::from(INTRO_LIST), None),
+    );
+    let list = lists.get_mut(INTRO_LIST).unwrap();
+
+    step(
+        "Items are added with `add`, naming the list before the command:",
+        &format!("later {} add \"<title>\"", INTRO_LIST),
+    );
+    let first = ask(&mut rl, "title: ", "plan a trip")?;
+    list.append(
+        &ItemPath::root(),
+        ListItem::Entry(TodoEntry::from_info(first.clone(), None)),
+    )?;
+    list.write_to(&mut stdout, 0, render_opts)?;
+
+    step(
+        "Every item has an index. Giving `add` an index puts the new item \
+         inside that one, turning it into a sublist:",
+        &format!("later {} add 0 \"<title>\"", INTRO_LIST),
+    );
+    let nested = ask(&mut rl, "title: ", "book train tickets")?;
+    list.append(
+        &ItemPath::from(vec![0]),
+        ListItem::Entry(TodoEntry::from_info(nested, None)),
+    )?;
+    list.write_to(&mut stdout, 0, render_opts)?;
+
+    step(
+        "Without a title, `add` asks for one, plus an optional date and \
+         time. Deeper items are addressed by comma-separated paths like 0,1.",
+        &format!("later {} add 0", INTRO_LIST),
+    );
+    let (title, date) = prompt_for_info(None)?;
+    list.append(
+        &ItemPath::from(vec![0]),
+        ListItem::Entry(TodoEntry::from_info(title, date)),
+    )?;
+    list.write_to(&mut stdout, 0, render_opts)?;
+
+    step(
+        "Finally, `sort` orders every level of the list by date, with \
+         undated items last:",
+        &format!("later {} sort", INTRO_LIST),
+    );
+    list.sort(SortBy::Date, TieBreak::Original, false);
+    list.write_to(&mut stdout, 0, render_opts)?;
+
+    println!(
+        "\nThat's it! `later --help` lists everything else, such as `move`, \
+         `edit` and `remove`."
+    );
+    let keep =
+        rl.readline(&format!("Keep the '{}' list? (y/N): ", INTRO_LIST))?;
+    if keep.to_lowercase() == "y" {
+        session.commit(lists)?;
+    } else {
+        lists.remove(INTRO_LIST);
+    }
+    Ok(())
+}
+
+/// Where `move` puts an item when something is already at the target index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Place {
+    Before,
+    After,
+    Swap,
+}
+
+impl Place {
+    const NAMES: &'static [&'static str] = &["before", "after", "swap"];
+
+    fn from_name(name: &str) -> Option<Place> {
+        match name {
+            "before" | "b" => Some(Place::Before),
+            "after" | "a" => Some(Place::After),
+            "swap" | "s" => Some(Place::Swap),
+            _ => None,
+        }
+    }
+}
+
+fn ask_place(title: &str, target: &str) -> anyhow::Result<Place> {
+    let mut rl = rustyline::Editor::<()>::new();
+    loop {
+        let answer = rl.readline(&format!(
+            "'{}' is already there. Put '{}' (b)efore it, (a)fter it, or \
+             (s)wap them? [b]: ",
+            target, title
+        ))?;
+        match answer.trim().to_lowercase().as_str() {
+            "" => return Ok(Place::Before),
+            answer => {
+                if let Some(place) = Place::from_name(answer) {
+                    return Ok(place);
                 }
-                ListItem::Entry(e) => {
-                    let confirm = rl.readline(&format!(
-                        "Remove entry '{}'? (Y/n): ",
-                        e.title
-                    ))?;
-                    confirm.to_lowercase() == "y" || confirm == ""
+            }
+        }
+    }
+}
+
+// do what `search --apply` asked to each target, which come by list and
+// position
+fn apply_to_matches(
+    lists: &mut HashMap<String, TodoList>,
+    targets: &[(String, ItemPath)],
+    action: &search::Action,
+    session: &Session,
+) -> anyhow::Result<()> {
+    let mut moved = Vec::new();
+    // last first, so taking items out doesn't move the ones still to come
+    for (name, index) in targets.iter().rev() {
+        let list = lists.get_mut(name).unwrap();
+        match action {
+            search::Action::Done => {
+                if let Some(item) = list.get_mut(index) {
+                    item.set_completed(Some(clock::now()));
+                    session.record(webhook::Action::Done, name, index, item);
                 }
-            } {
-                save(&todo_file, &lists)?;
-            } else {
-                bail!("Cancelled.");
             }
+            search::Action::Tag(tag) => {
+                if let Some(item) = list.get_mut(index) {
+                    item.add_tag(tag);
+                }
+            }
+            search::Action::Remove => {
+                let item = list.remove(index)?;
+                session.record(webhook::Action::Remove, name, index, &item);
+            }
+            search::Action::Move(_) => moved.push(list.remove(index)?),
         }
-        Some(("move", move_args)) => {
-            let mut from_index: Vec<usize> =
-                move_args.values_of_t_or_exit("from");
-            let mut to_index: Vec<usize> = move_args.values_of_t_or_exit("to");
-            let item = active_list.remove_item(&mut from_index.iter_mut())?;
-            active_list.insert_item(item, &mut to_index.iter_mut())?;
-            save(&todo_file, &lists)?;
+    }
+    if let search::Action::Move(to) = action {
+        let list = lists.get_mut(to).unwrap();
+        for item in moved.into_iter().rev() {
+            list.append(&ItemPath::root(), item)?;
         }
-        Some(("edit", edit_args)) => {
-            let mut index: Vec<usize> = edit_args.values_of_t_or_exit("index");
-            let item = active_list.remove_item(&mut index.iter_mut())?;
-            let (new_title, new_date) = prompt_for_info(Some(&item))?;
-            match item {
-                ListItem::Entry(mut entry) => {
-                    entry.title = new_title;
-                    entry.date = new_date;
-                    active_list.insert_item(
-                        ListItem::Entry(entry),
-                        &mut index.iter_mut(),
-                    )?;
+    }
+    Ok(())
+}
+
+// a passphrase for `init --encrypt`, from the environment or typed twice
+fn new_passphrase() -> anyhow::Result<String> {
+    if let Ok(passphrase) = std::env::var(crypt::PASSPHRASE_VAR) {
+        return Ok(passphrase);
+    }
+    if !std::io::stdin().is_terminal() {
+        bail!(
+            "Set {} to the passphrase, or run this in a terminal to be asked \
+             for one",
+            crypt::PASSPHRASE_VAR
+        );
+    }
+    loop {
+        let passphrase = rpassword::prompt_password("New passphrase: ")?;
+        if passphrase.is_empty() {
+            continue;
+        }
+        if rpassword::prompt_password("Again: ")? == passphrase {
+            return Ok(passphrase);
+        }
+        eprintln!("Those don't match; try again.");
+    }
+}
+
+// writes the backups, undo history, archive, sync state, templates and
+// sent notifications again, sealed or not as saves now are
+fn reseal(
+    todo_folder: &Path,
+    todo_file: &Path,
+    backups: usize,
+) -> anyhow::Result<()> {
+    for n in backup::list(todo_file, backups) {
+        let path = backup::path(todo_file, n);
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Couldn't read {}", path.display()))?;
+        std::fs::write(&path, crypt::seal(&crypt::open(&text)?)?)
+            .with_context(|| format!("Couldn't write {}", path.display()))?;
+    }
+    let history = todo_folder.join("history.json");
+    if history.exists() {
+        history::History::load(&history)?.save(&history)?;
+    }
+    let archive = archive::path(todo_folder);
+    if archive.exists() {
+        archive::rewrite(&archive, &archive::read(&archive)?)?;
+    }
+    let state = todo_folder.join("sync.json");
+    if state.exists() {
+        sync::State::load(&state)?.save(&state)?;
+    }
+    let templates = todo_folder.join("templates.json");
+    if templates.exists() {
+        template::save(&templates, &template::load(&templates)?)?;
+    }
+    let sent = todo_folder.join("notified.json");
+    if sent.exists() {
+        notify::save_sent(&sent, &notify::load_sent(&sent)?)?;
+    }
+    Ok(())
+}
+
+// an index, by position or by title (see `IndexPath`), or a code from the
+// last `--hints` render of the list
+fn index_arg(
+    args: &ArgMatches,
+    name: &str,
+    hints: &hints::Hints,
+    list: &TodoList,
+) -> anyhow::Result<ItemPath> {
+    let segments: Vec<&str> =
+        args.values_of(name).unwrap_or_default().collect();
+    if let [segment] = segments.as_slice() {
+        if let Some(index) = hints.resolve(segment) {
+            return Ok(ItemPath::from(index));
+        }
+    }
+    Ok(IndexPath::parse(&segments.join(","))?.resolve(list)?)
+}
+
+// the item named by the `name` argument or, with `--pick`, the one chosen
+// by typing part of its title after `prompt`
+fn pick_or_index_arg(
+    args: &ArgMatches,
+    name: &str,
+    prompt: &str,
+    hints: &hints::Hints,
+    list: &TodoList,
+) -> anyhow::Result<ItemPath> {
+    if !args.is_present("pick") {
+        return index_arg(args, name, hints, list);
+    }
+    if !std::io::stdin().is_terminal() {
+        bail!("--pick needs a terminal to choose in");
+    }
+    match picker::pick(list, prompt)? {
+        Some(path) => Ok(ItemPath::from(path)),
+        None => bail!("No item matches."),
+    }
+}
+
+// saves the lists after `clear` or `prune` took `taken` out of list `name`,
+// archiving the items with `--archive`, and lists what went
+fn remove_taken(
+    args: &ArgMatches,
+    session: &Session,
+    lists: &mut HashMap<String, TodoList>,
+    name: &str,
+    taken: Vec<(Vec<usize>, ListItem)>,
+    reason: archive::Reason,
+) -> anyhow::Result<()> {
+    let dry_run = session.snapshot.is_some();
+    let archiving = args.is_present("archive");
+    let width = taken
+        .iter()
+        .map(|(path, _)| ItemPath::from(path.as_slice()).to_string().len())
+        .max()
+        .unwrap_or(0);
+    let summary: Vec<String> = taken
+        .iter()
+        .map(|(path, item)| {
+            let path = ItemPath::from(path.as_slice()).to_string();
+            format!("  {:<width$}  {}", path, item.title())
+        })
+        .collect();
+    for (path, item) in taken {
+        session.record(webhook::Action::Remove, name, &path, &item);
+        if archiving {
+            session
+                .archived
+                .borrow_mut()
+                .push(archive::Archived::new(name, &path, reason, item));
+        }
+    }
+    session.commit(lists)?;
+    println!("{}", summary.join("\n"));
+    println!(
+        "{} {} from '{}'",
+        match (dry_run, archiving) {
+            (true, true) => "would archive",
+            (true, false) => "would remove",
+            (false, true) => "archived",
+            (false, false) => "removed",
+        },
+        i18n::Lang::current().quantity(summary.len() as i64, i18n::Unit::Item),
+        name
+    );
+    Ok(())
+}
+
+// the date and zone `--date` and `--time` give, or `date` and `zone` if
+// neither is given: `--time` alone sets the time of day on `date`'s day,
+// in its zone, or on today
+fn date_args(
+    args: &ArgMatches,
+    date: Option<DateMaybeTime>,
+    zone: Option<chrono_tz::Tz>,
+) -> anyhow::Result<(Option<DateMaybeTime>, Option<chrono_tz::Tz>)> {
+    let (date, zone) = match args.value_of("date") {
+        Some(text) => match natural::parse_zoned(text, clock::today()) {
+            Some((date, zone)) => (Some(date), zone),
+            None => bail!(Error::ParseDate {
+                text: String::from(text),
+            }),
+        },
+        None => (date, zone),
+    };
+    let text = match args.value_of("time") {
+        Some(text) => text,
+        None => return Ok((date, zone)),
+    };
+    let time = match natural::parse_time(&text.trim().to_lowercase()) {
+        Some(time) => time,
+        None => bail!(
+            "Invalid time '{}' (e.g. {} or 5pm)",
+            text,
+            formats::time(chrono::NaiveTime::from_hms(17, 30, 0))
+        ),
+    };
+    let moment = match (date, zone) {
+        (Some(DateMaybeTime::DateTime(moment)), Some(zone)) => {
+            let day = moment.with_timezone(&zone).naive_local().date();
+            zone::at(day, time, zone)
+        }
+        (date, _) => {
+            let day =
+                date.map_or_else(clock::today, |date| date.start().date());
+            chrono::Local
+                .from_local_datetime(&day.and_time(time))
+                .earliest()
+        }
+    };
+    match moment {
+        Some(moment) => Ok((Some(DateMaybeTime::DateTime(moment)), zone)),
+        None => bail!("{} doesn't happen on that day here", text),
+    }
+}
+
+// before asking for what the command line left out, which a script can't
+// answer: `what` is what's missing, and `flags` how to give it instead
+fn check_can_prompt(
+    args: &ArgMatches,
+    what: &str,
+    flags: &str,
+) -> anyhow::Result<()> {
+    let reason = if args.is_present("no-prompt") {
+        "with --no-prompt"
+    } else if !std::io::stdin().is_terminal() {
+        "when stdin isn't a terminal"
+    } else {
+        return Ok(());
+    };
+    bail!("{} is needed {} (give it with {})", what, reason, flags)
+}
+
+// read the `--anchor` day for templates and copies, if one was given
+fn anchor_arg(args: &ArgMatches) -> anyhow::Result<Option<chrono::NaiveDate>> {
+    match args.value_of("anchor") {
+        Some(anchor) => chrono::NaiveDate::parse_from_str(anchor, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "Invalid anchor '{}' (expected yyyy-mm-dd)",
+                    anchor
+                )
+            }),
+        None => Ok(None),
+    }
+}
+
+fn priority_arg(name: &str) -> anyhow::Result<Priority> {
+    Priority::from_name(name).ok_or_else(|| {
+        anyhow::anyhow!(
+            "Invalid priority '{}' (expected A-Z, high, medium or low)",
+            name
+        )
+    })
+}
+
+fn tag_arg(text: &str) -> anyhow::Result<String> {
+    tag_name(text).ok_or_else(|| {
+        anyhow::anyhow!("Invalid tag '{}' (expected a word, e.g. work)", text)
+    })
+}
+
+// every tag given to a repeatable option, once each
+fn tag_args(args: &ArgMatches, name: &str) -> anyhow::Result<Vec<String>> {
+    let mut tags = Vec::new();
+    for text in args.values_of(name).unwrap_or_default() {
+        let tag = tag_arg(text)?;
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    Ok(tags)
+}
+
+fn index_error(
+    list: &TodoList,
+    index: &[usize],
+    allow_new: bool,
+    render_opts: &RenderOptions,
+    error: impl Into<anyhow::Error>,
+) -> anyhow::Error {
+    match list.explain_index(index, allow_new, render_opts) {
+        Some(explanation) => anyhow::anyhow!(explanation),
+        None => error.into(),
+    }
+}
+
+// open `text` in $VISUAL or $EDITOR (vi if neither is set) and return what
+// was saved
+fn edit_text(text: &str) -> anyhow::Result<String> {
+    let copy = scratch::Scratch::new("notes.txt", text)?;
+    run_editor(copy.path()).context("The notes weren't changed")?;
+    copy.read()
+}
+
+// edit a copy of the data file until it parses or the user gives up, then
+// save it through the usual path
+fn edit_raw(
+    todo_file: &Path,
+    dry_run: bool,
+    backups: usize,
+) -> anyhow::Result<()> {
+    let original = match std::fs::read_to_string(todo_file) {
+        // the copy is plain, kept private and removed however this ends
+        Ok(text) => crypt::open(&text)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(e).with_context(|| {
+                format!("Couldn't read {}", todo_file.display())
+            })
+        }
+    };
+    let copy = scratch::Scratch::new("later.json", &original)?;
+    let edited = loop {
+        let text = run_editor(copy.path()).and_then(|_| copy.read());
+        let text = match text {
+            Ok(text) => text,
+            Err(e) => return Err(e.context("The data file wasn't changed")),
+        };
+        match migrate::parse(&text) {
+            Ok(lists) => break (text, lists),
+            Err(e) => {
+                match e {
+                    migrate::ParseError::Json(e) => {
+                        eprintln!("{}", describe_json_error(&text, &e))
+                    }
+                    e => eprintln!("{}", e),
                 }
-                ListItem::List(mut list) => {
-                    list.title = new_title;
-                    list.date = new_date;
-                    active_list.insert_item(
-                        ListItem::List(list),
-                        &mut index.iter_mut(),
-                    )?;
+                let again = std::io::stdin().is_terminal()
+                    && rustyline::Editor::<()>::new()
+                        .readline("Edit again? (Y/n): ")?
+                        .to_lowercase()
+                        != "n";
+                if !again {
+                    bail!("The data file wasn't changed");
                 }
             }
-            save(&todo_file, &lists)?;
         }
-        Some(("sort", _sort_args)) => {
-            active_list.sort();
-            save(&todo_file, &lists)?;
+    };
+    drop(copy);
+    let (text, lists) = edited;
+    if text == original {
+        println!("(no changes)");
+        return Ok(());
+    }
+    if dry_run {
+        diff::write_diff(&mut std::io::stdout(), &original, &text)?;
+        eprintln!("Dry run, nothing was saved.");
+        return Ok(());
+    }
+    save(todo_file, &lists, backups)?;
+    println!("saved {} lists to {}", lists.len(), todo_file.display());
+    Ok(())
+}
+
+// a JSON error with the line it's on and a caret under the column, e.g.
+//   line 12, column 5: expected `,` or `}`
+//     12 |     "title" "milk"
+//        |             ^
+fn describe_json_error(text: &str, error: &serde_json::Error) -> String {
+    let (line, column) = (error.line(), error.column());
+    // serde_json's own message ends with the position, which is given first
+    let message = error.to_string();
+    let message = match message.rfind(" at line ") {
+        Some(end) => &message[..end],
+        None => &message,
+    };
+    let mut described =
+        format!("line {}, column {}: {}", line, column, message);
+    if let Some(source) = text.lines().nth(line.saturating_sub(1)) {
+        let gutter = line.to_string().len();
+        let pad: String = source
+            .chars()
+            .take(column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        described.push_str(&format!(
+            "\n  {} | {}\n  {} | {}^",
+            line,
+            source,
+            " ".repeat(gutter),
+            pad
+        ));
+    }
+    described
+}
+
+// open `path` in $VISUAL or $EDITOR, falling back to vi, and wait for it
+fn run_editor(path: &Path) -> anyhow::Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+    // through the shell, so editors given with arguments (`code -w`) work
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(path)
+        .status()
+        .with_context(|| format!("Couldn't run editor '{}'", editor))?;
+    if !status.success() {
+        bail!("Editor '{}' failed ({})", editor, status);
+    }
+    Ok(())
+}
+
+// show a folder in the desktop's file manager
+fn open_folder(folder: &Path) -> anyhow::Result<()> {
+    let opener = if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    let status = std::process::Command::new(opener)
+        .arg(folder)
+        .status()
+        .with_context(|| format!("Couldn't run {}", opener))?;
+    if !status.success() {
+        bail!("Couldn't open {} ({})", folder.display(), status);
+    }
+    Ok(())
+}
+
+// write a desktop entry for later:// and make it the default handler
+fn install_url_handler() -> anyhow::Result<()> {
+    let path = match url_scheme::desktop_entry_path() {
+        Some(path) => path,
+        None => bail!("Could not find standard data directory."),
+    };
+    let exe = std::env::current_exe()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, url_scheme::desktop_entry(&exe))?;
+    println!("wrote {}", path.display());
+    let registered = std::process::Command::new("xdg-mime")
+        .args([
+            "default",
+            url_scheme::DESKTOP_FILE,
+            &format!("x-scheme-handler/{}", url_scheme::SCHEME),
+        ])
+        .status();
+    match registered {
+        Ok(status) if status.success() => {
+            println!("registered as the handler for later:// URLs")
         }
-        _ => {}
+        _ => eprintln!(
+            "Couldn't run xdg-mime; register {} for x-scheme-handler/{} \
+             with your desktop environment instead.",
+            url_scheme::DESKTOP_FILE,
+            url_scheme::SCHEME
+        ),
+    }
+    Ok(())
+}
+
+// answer extension requests until the browser disconnects; like `serve`,
+// this skips the session, as the browser can keep the host running for as
+// long as it likes: rather than hold the lock and the lists loaded at the
+// start, each message reloads them, and each add locks and saves through
+// the store on its own
+fn native_host(store: &store::Store, default_list: &str) -> anyhow::Result<()> {
+    use native_host::{Message, Request, Response};
+    let (mut stdin, mut stdout) = (std::io::stdin(), std::io::stdout());
+    while let Some(message) = native_host::read_message(&mut stdin)? {
+        let message = match message {
+            Message::Request(message) => message,
+            Message::TooLarge(length) => {
+                let error = format!("Request too large ({} bytes)", length);
+                native_host::write_message(
+                    &mut stdout,
+                    &Response::error(error),
+                )?;
+                continue;
+            }
+        };
+        let response = match serde_json::from_slice(&message) {
+            Err(e) => Response::error(format!("Invalid request: {}", e)),
+            Ok(Request::Lists) => match load(&store.todo_file) {
+                Ok(lists) => {
+                    let mut names: Vec<String> =
+                        lists.keys().cloned().collect();
+                    names.sort();
+                    Response {
+                        lists: Some(names),
+                        ..Response::ok()
+                    }
+                }
+                Err(e) => Response::error(e.to_string()),
+            },
+            Ok(Request::Add { list, title, url }) => {
+                let list_name = list.as_deref().unwrap_or(default_list);
+                // the page's address goes in the notes, out of the way
+                let mut entry = TodoEntry::from_info(title, None);
+                entry.notes = url;
+                native_host_add(store, list_name, entry).map_or_else(
+                    |e| Response::error(format!("{:#}", e)),
+                    |_| Response::ok(),
+                )
+            }
+        };
+        native_host::write_message(&mut stdout, &response)?;
+    }
+    Ok(())
+}
+
+// adds the entry under the lock, saved as `later add` would save it
+fn native_host_add(
+    store: &store::Store,
+    list_name: &str,
+    entry: TodoEntry,
+) -> anyhow::Result<()> {
+    let _lock = lock::acquire(&store.todo_file)?;
+    let mut lists = load(&store.todo_file)?;
+    let mut change = store::Change::default();
+    if store.undo_depth > 0 {
+        change.before = Some(sync::snapshot(&lists)?);
+    }
+    if !lists.contains_key(list_name) {
+        return Err(Error::list_not_found(list_name, lists.keys()).into());
+    }
+    let list = lists.get_mut(list_name).unwrap();
+    list.append(&ItemPath::root(), ListItem::Entry(entry))?;
+    let index = [list.items().len() - 1];
+    if store.wants_events() {
+        let item = &list.items()[index[0]];
+        let event =
+            webhook::Event::new(webhook::Action::Add, list_name, &index, item);
+        change.events.push(event);
+    }
+    for message in store.commit("add", &mut lists, change, None)? {
+        eprintln!("{}", message);
     }
-    let active_list = lists.get_mut(&String::from(list_name)).unwrap();
-    active_list.write_to(&mut stdout, 0)?;
     Ok(())
 }
@@ -0,0 +1,136 @@
+//! The versions of the data file's layout, and how older ones are brought
+//! up to date as they're read.
+//!
+//! Version 0 is a JSON object of lists by name, as every release wrote
+//! before the layout carried a version. From version 1 on, the file is
+//! `{"version": N, "lists": {...}}`. To change the layout, bump
+//! [`VERSION`] and add a step to [`STEPS`] that turns a document of the
+//! previous version into the new one; files of any older version then go
+//! through every step after theirs on the way in, and are saved in the new
+//! layout the next time anything changes.
+
+use crate::TodoList;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// The version this release writes.
+pub const VERSION: u64 = 1;
+
+/// Each step takes a document from the version it's at in the list to the
+/// next one, so `STEPS[0]` turns version 0 into version 1.
+const STEPS: [fn(Value) -> Value; VERSION as usize] = [v0_to_v1];
+
+// the lists object becomes the `lists` field
+fn v0_to_v1(lists: Value) -> Value {
+    json!({ "version": 1, "lists": lists })
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParseError {
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// Written by a newer release, which may have put things in the file
+    /// that this one would lose.
+    #[error(
+        "data version {version} is from a newer version of later (this \
+         one reads up to {VERSION})"
+    )]
+    TooNew { version: u64 },
+}
+
+#[derive(Deserialize)]
+struct Header {
+    // a list can be called "version" in a version 0 file, so this is only a
+    // version if it's a number
+    #[serde(default)]
+    version: Option<Value>,
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    version: u64,
+    lists: &'a HashMap<String, TodoList>,
+}
+
+#[derive(Deserialize)]
+struct Stored {
+    lists: HashMap<String, TodoList>,
+}
+
+/// The version of the layout `json` is in.
+pub fn version_of(json: &str) -> serde_json::Result<u64> {
+    let header: Header = serde_json::from_str(json)?;
+    Ok(header.version.and_then(|v| v.as_u64()).unwrap_or(0))
+}
+
+/// The lists in `json`, upgrading it first if it's in an older layout.
+pub fn parse(json: &str) -> Result<HashMap<String, TodoList>, ParseError> {
+    let version = version_of(json)?;
+    if version > VERSION {
+        return Err(ParseError::TooNew { version });
+    }
+    let stored: Stored = if version == VERSION {
+        // straight from the text, so errors keep their line and column
+        serde_json::from_str(json)?
+    } else {
+        let document = STEPS[version as usize..]
+            .iter()
+            .fold(serde_json::from_str(json)?, |document, step| step(document));
+        serde_json::from_value(document)?
+    };
+    Ok(stored.lists)
+}
+
+/// The lists as the data file holds them, in the current layout.
+pub fn to_string(
+    lists: &HashMap<String, TodoList>,
+) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&Document {
+        version: VERSION,
+        lists,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn current_layout_round_trips(lists in testing::lists()) {
+            let json = to_string(&lists)?;
+            prop_assert_eq!(version_of(&json)?, VERSION);
+            prop_assert!(testing::same(&parse(&json)?, &lists));
+        }
+
+        #[test]
+        fn version_0_is_migrated(lists in testing::lists()) {
+            let json = serde_json::to_string_pretty(&lists)?;
+            prop_assert_eq!(version_of(&json)?, 0);
+            let migrated = parse(&json)?;
+            prop_assert!(testing::same(&migrated, &lists));
+            // and is saved in the current layout
+            let saved = to_string(&migrated)?;
+            prop_assert!(testing::same(&parse(&saved)?, &lists));
+        }
+    }
+
+    #[test]
+    fn list_called_version() {
+        let json = r#"{"version": {"title": "version", "list": []}}"#;
+        assert_eq!(version_of(json).unwrap(), 0);
+        assert!(parse(json).unwrap().contains_key("version"));
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let json = json!({ "version": VERSION + 1, "lists": {} }).to_string();
+        assert!(matches!(
+            parse(&json),
+            Err(ParseError::TooNew { version }) if version == VERSION + 1
+        ));
+    }
+}
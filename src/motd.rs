@@ -0,0 +1,119 @@
+//! Today's agenda as a small block of plain text, for `/etc/motd`, login
+//! scripts or desktop widgets like conky, with hard deadlines coming up in
+//! the next few days above it.
+
+use crate::i18n::Lang;
+use crate::table::fit;
+use crate::{
+    display_width, formats, DateMaybeTime, TodoList, DEADLINE_LEAD_DAYS,
+};
+use chrono::prelude::*;
+use std::collections::HashMap;
+use std::io::prelude::*;
+
+const GAP: &str = "  ";
+// list names give way to titles on narrow blocks
+const MAX_LIST_SHARE: usize = 4;
+
+struct Line {
+    // a hard deadline, listed apart
+    hard: bool,
+    // overdue items first, then timed ones, then the rest of the day
+    order: (u8, NaiveDate, Option<NaiveTime>),
+    when: String,
+    title: String,
+    list: String,
+}
+
+/// Writes every item not yet done that is due today or earlier, across all
+/// lists, with each line padded to exactly `width` columns and no escape
+/// codes. Hard deadlines are listed apart, first, from
+/// [`DEADLINE_LEAD_DAYS`] days before they fall.
+pub fn write_agenda(
+    out: &mut impl Write,
+    lists: &HashMap<String, TodoList>,
+    today: NaiveDate,
+    width: usize,
+) -> std::io::Result<()> {
+    let lang = Lang::current();
+    let mut lines: Vec<Line> = lists
+        .iter()
+        .flat_map(|(name, list)| {
+            list.items_with_paths()
+                .into_iter()
+                .filter(|(_, item)| !item.is_done())
+                .filter_map(move |(_, item)| {
+                    let hard = item.is_deadline();
+                    let (date, time) = match item.date()? {
+                        DateMaybeTime::Date(date) => (date, None),
+                        DateMaybeTime::DateTime(datetime) => (
+                            datetime.naive_local().date(),
+                            Some(datetime.time()),
+                        ),
+                    };
+                    let ahead = (date - today).num_days();
+                    let (rank, when) = match time {
+                        _ if ahead > 0 && !hard => return None,
+                        _ if ahead >= DEADLINE_LEAD_DAYS => return None,
+                        _ if ahead < 0 => (0, lang.overdue().to_string()),
+                        Some(time) if ahead == 0 => (1, formats::time(time)),
+                        None if ahead == 0 => (2, String::new()),
+                        // a deadline in the next few days
+                        _ if ahead == 1 => (3, lang.tomorrow().to_string()),
+                        _ => (3, lang.weekday(date.weekday()).to_string()),
+                    };
+                    Some(Line {
+                        hard,
+                        order: (rank, date, time),
+                        when,
+                        title: item.title().to_string(),
+                        list: name.clone(),
+                    })
+                })
+        })
+        .collect();
+    lines.sort_by(|a, b| a.order.cmp(&b.order).then(a.list.cmp(&b.list)));
+    let (deadlines, lines): (Vec<Line>, Vec<Line>) =
+        lines.into_iter().partition(|line| line.hard);
+
+    let header = format!(
+        "{}, {}",
+        lang.weekday(today.weekday()),
+        lang.month_day(today, false)
+    );
+    writeln!(out, "{}", fit(&header, width))?;
+    writeln!(out, "{}", "-".repeat(width))?;
+    if deadlines.is_empty() && lines.is_empty() {
+        return writeln!(out, "{}", fit(lang.nothing_due(), width));
+    }
+
+    let all = || deadlines.iter().chain(&lines);
+    let when_width = all().map(|l| display_width(&l.when)).max();
+    let when_width = when_width.unwrap_or(0);
+    let list_width = all()
+        .map(|l| display_width(&l.list))
+        .max()
+        .unwrap_or(0)
+        .min(width / MAX_LIST_SHARE);
+    let title_width = width
+        .saturating_sub(when_width + list_width + 2 * GAP.len())
+        .max(1);
+    if !deadlines.is_empty() {
+        writeln!(out, "{}", fit(lang.deadlines(), width))?;
+    }
+    for (i, line) in all().enumerate() {
+        if i > 0 && i == deadlines.len() {
+            writeln!(out, "{}", "-".repeat(width))?;
+        }
+        writeln!(
+            out,
+            "{}{}{}{}{}",
+            fit(&line.when, when_width),
+            GAP,
+            fit(&line.title, title_width),
+            GAP,
+            fit(&line.list, list_width)
+        )?;
+    }
+    Ok(())
+}
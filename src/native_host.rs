@@ -0,0 +1,147 @@
+//! The browser native messaging protocol: each message is UTF-8 JSON
+//! preceded by its length as a 32-bit native-endian integer, in both
+//! directions over stdin/stdout.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::io::prelude::*;
+use std::path::Path;
+
+pub const HOST_NAME: &str = "later";
+
+// browsers refuse messages to the host over 4GB, and from it over 1MB;
+// requests are far smaller, so the host takes no more than it sends
+const MAX_MESSAGE: u32 = 1024 * 1024;
+
+/// A request from the companion extension.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum Request {
+    /// Add an entry, e.g. a page to read later.
+    Add {
+        list: Option<String>,
+        title: String,
+        url: Option<String>,
+    },
+    /// Names of all lists, for the extension's list picker.
+    Lists,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lists: Option<Vec<String>>,
+}
+
+impl Response {
+    pub fn ok() -> Response {
+        Response {
+            ok: true,
+            error: None,
+            lists: None,
+        }
+    }
+
+    pub fn error(message: String) -> Response {
+        Response {
+            ok: false,
+            error: Some(message),
+            lists: None,
+        }
+    }
+}
+
+/// Whether these command-line arguments are how a browser launches a native
+/// host: Chrome passes the caller's origin, Firefox the manifest path and
+/// extension ID.
+pub fn is_browser_launch(args: &[String]) -> bool {
+    match args {
+        [origin, ..] if origin.starts_with("chrome-extension://") => true,
+        [manifest, _extension_id] => {
+            manifest.ends_with(".json") && Path::new(manifest).is_file()
+        }
+        _ => false,
+    }
+}
+
+/// A message as read, before it's parsed.
+#[derive(Debug)]
+pub enum Message {
+    Request(Vec<u8>),
+    /// A message over 1MB, skipped without reading it in, with its length.
+    TooLarge(u32),
+}
+
+/// Reads one message, or `None` once the browser closes the pipe.
+pub fn read_message(input: &mut impl Read) -> Result<Option<Message>> {
+    let mut length = [0u8; 4];
+    match input.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+            return Ok(None)
+        }
+        Err(e) => return Err(e.into()),
+    }
+    let length = u32::from_ne_bytes(length);
+    if length > MAX_MESSAGE {
+        // skipped, so the next message is read from its start
+        let skipped = std::io::copy(
+            &mut input.take(u64::from(length)),
+            &mut std::io::sink(),
+        )?;
+        if skipped < u64::from(length) {
+            return Ok(None);
+        }
+        return Ok(Some(Message::TooLarge(length)));
+    }
+    let mut message = vec![0u8; length as usize];
+    input.read_exact(&mut message)?;
+    Ok(Some(Message::Request(message)))
+}
+
+pub fn write_message(
+    output: &mut impl Write,
+    response: &Response,
+) -> Result<()> {
+    let json = serde_json::to_vec(response)?;
+    if json.len() > MAX_MESSAGE as usize {
+        bail!("Response too large for native messaging");
+    }
+    output.write_all(&(json.len() as u32).to_ne_bytes())?;
+    output.write_all(&json)?;
+    output.flush()?;
+    Ok(())
+}
+
+/// The host manifest that registers `exe` with a browser, allowing only the
+/// given extension to talk to it.
+pub fn manifest(
+    browser: &str,
+    exe: &Path,
+    extension_id: &str,
+) -> Result<serde_json::Value> {
+    let mut manifest = serde_json::json!({
+        "name": HOST_NAME,
+        "description": "later to-do lists",
+        "path": exe,
+        "type": "stdio",
+    });
+    match browser {
+        "chrome" | "chromium" => {
+            manifest["allowed_origins"] = serde_json::json!([format!(
+                "chrome-extension://{}/",
+                extension_id
+            )])
+        }
+        "firefox" => {
+            manifest["allowed_extensions"] = serde_json::json!([extension_id])
+        }
+        _ => {
+            bail!("Unknown browser '{}' (expected chrome or firefox)", browser)
+        }
+    }
+    Ok(manifest)
+}
@@ -0,0 +1,303 @@
+//! Dates as people type them: "tomorrow", "next friday", "in 3 days",
+//! "jan 15", optionally followed by a time such as "9:30" or "5pm". Only
+//! English words are understood, whatever the display language.
+
+use crate::{formats, zone, DateMaybeTime};
+use chrono::{prelude::*, Duration};
+use chrono_tz::Tz;
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+/// Examples for error messages.
+pub const EXAMPLES: &str =
+    "yyyy/mm/dd, tomorrow, next friday, in 3 days, jan 15";
+
+/// Reads `text` as a date, with an optional time, counting from `today`.
+pub fn parse(text: &str, today: NaiveDate) -> Option<DateMaybeTime> {
+    let (date, time) = parse_parts(text, today)?;
+    DateMaybeTime::from_parts(Some(date), time)
+}
+
+/// Like [`parse`], but also takes a time zone after the time, as in
+/// "friday 9:40 JST", reading the time as that zone's and returning the
+/// zone along with the date.
+pub fn parse_zoned(
+    text: &str,
+    today: NaiveDate,
+) -> Option<(DateMaybeTime, Option<Tz>)> {
+    match zone::split(text) {
+        (_, None) => parse(text, today).map(|date| (date, None)),
+        (rest, Some(zone)) => {
+            // a zone only means something with a time of day
+            let (date, time) = parse_parts(rest, today)?;
+            let moment = zone::at(date, time?, zone)?;
+            Some((DateMaybeTime::DateTime(moment), Some(zone)))
+        }
+    }
+}
+
+/// Like [`parse`], but keeps the date and time apart, so callers can tell
+/// whether a time was given.
+pub fn parse_parts(
+    text: &str,
+    today: NaiveDate,
+) -> Option<(NaiveDate, Option<NaiveTime>)> {
+    let text = text.trim().to_lowercase();
+    let mut words: Vec<&str> = text.split_whitespace().collect();
+    // "tomorrow at 5pm", "friday 9:30"
+    let time = match words.last().and_then(|word| parse_time(word)) {
+        Some(time) => {
+            words.pop();
+            if words.last() == Some(&"at") {
+                words.pop();
+            }
+            Some(time)
+        }
+        None => None,
+    };
+    let date = match words.as_slice() {
+        // a time alone means today
+        [] if time.is_some() => Some(today),
+        [word] => parse_word(word, today),
+        ["next", "week"] => Some(today + Duration::weeks(1)),
+        ["next", "month"] => add_months(today, 1),
+        ["next", "year"] => add_months(today, 12),
+        ["next", day] => weekday(day).map(|day| next_weekday(today, day)),
+        ["in", count, unit] => offset(today, count, unit),
+        [a, b] => month_day(a, b, None, today),
+        [a, b, year] => month_day(a, b, Some(year), today),
+        _ => None,
+    }?;
+    Some((date, time))
+}
+
+fn parse_word(word: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match word {
+        "today" | "tonight" => Some(today),
+        "tomorrow" => Some(today + Duration::days(1)),
+        "yesterday" => Some(today - Duration::days(1)),
+        _ => weekday(word)
+            .map(|day| next_weekday(today, day))
+            .or_else(|| formats::parse_date(word))
+            .or_else(|| NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()),
+    }
+}
+
+/// Reads a lowercase time of day: "9:30", "17:00", "5pm", "5:30am", or as
+/// the configured time format has it.
+pub fn parse_time(word: &str) -> Option<NaiveTime> {
+    if let Some(time) = formats::parse_time(word) {
+        return Some(time);
+    }
+    let (clock, pm) = match (word.strip_suffix("am"), word.strip_suffix("pm")) {
+        (Some(clock), _) => (clock, false),
+        (_, Some(clock)) => (clock, true),
+        _ => return None,
+    };
+    let (hour, minute): (u32, u32) = match clock.split_once(':') {
+        Some((hour, minute)) => (hour.parse().ok()?, minute.parse().ok()?),
+        None => (clock.parse().ok()?, 0),
+    };
+    if !(1..=12).contains(&hour) {
+        return None;
+    }
+    NaiveTime::from_hms_opt(hour % 12 + if pm { 12 } else { 0 }, minute, 0)
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    // chrono knows "fri" and "friday", but not "fr" or "thurs"
+    word.parse().ok()
+}
+
+// the first `day` after `today`, so "friday" on a Friday is a week away
+fn next_weekday(today: NaiveDate, day: Weekday) -> NaiveDate {
+    let ahead = (day.num_days_from_monday() + 7
+        - today.weekday().num_days_from_monday())
+        % 7;
+    today + Duration::days(if ahead == 0 { 7 } else { ahead as i64 })
+}
+
+// "in 3 days", "in a week", "in 2 months"
+fn offset(today: NaiveDate, count: &str, unit: &str) -> Option<NaiveDate> {
+    let count: i64 = match count {
+        "a" | "an" | "one" => 1,
+        count => count.parse().ok()?,
+    };
+    match unit.trim_end_matches('s') {
+        "day" => Some(today + Duration::days(count)),
+        "week" => Some(today + Duration::weeks(count)),
+        "month" => add_months(today, count),
+        "year" => add_months(today, count * 12),
+        _ => None,
+    }
+}
+
+// the same day of the month, or the last day of a shorter month
+fn add_months(date: NaiveDate, months: i64) -> Option<NaiveDate> {
+    let total = date.year() as i64 * 12 + date.month0() as i64 + months;
+    let (year, month) = (total.div_euclid(12) as i32, total.rem_euclid(12));
+    (1..=date.day())
+        .rev()
+        .find_map(|day| NaiveDate::from_ymd_opt(year, month as u32 + 1, day))
+}
+
+fn month(word: &str) -> Option<u32> {
+    if word.len() < 3 {
+        return None;
+    }
+    let i = MONTHS.iter().position(|name| name.starts_with(word))?;
+    Some(i as u32 + 1)
+}
+
+// "jan 15" or "15 jan", with an optional year; without one, the next time
+// that day comes round
+fn month_day(
+    a: &str,
+    b: &str,
+    year: Option<&str>,
+    today: NaiveDate,
+) -> Option<NaiveDate> {
+    let day = |s: &str| {
+        s.trim_end_matches(|c: char| c.is_ascii_alphabetic())
+            .parse::<u32>()
+            .ok()
+    };
+    let (month, day) = match (month(a), month(b)) {
+        (Some(month), None) => (month, day(b)?),
+        (None, Some(month)) => (month, day(a)?),
+        _ => return None,
+    };
+    match year {
+        Some(year) => NaiveDate::from_ymd_opt(year.parse().ok()?, month, day),
+        None => {
+            let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+            if date < today {
+                NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+            } else {
+                Some(date)
+            }
+        }
+    }
+}
+
+/// Reads a length of time such as `3h`, `90d`, `2w` or `1y`; a bare number
+/// is days.
+pub fn parse_period(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => text.split_at(i),
+        None => (text, "d"),
+    };
+    let n: i64 = number.parse().ok()?;
+    match unit.trim().to_lowercase().as_str() {
+        "h" | "hour" | "hours" => Some(Duration::hours(n)),
+        "d" | "day" | "days" => Some(Duration::days(n)),
+        "w" | "week" | "weeks" => Some(Duration::weeks(n)),
+        "y" | "year" | "years" => Some(Duration::days(365 * n)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn written_dates(date in testing::day(), today in testing::day()) {
+            for text in [date.format("%Y/%m/%d"), date.format("%Y-%m-%d")] {
+                let parsed = parse(&text.to_string(), today);
+                prop_assert_eq!(parsed, Some(DateMaybeTime::Date(date)));
+            }
+        }
+
+        #[test]
+        fn offsets(today in testing::day(), n in 0..1000i64) {
+            let days = format!("in {} days", n);
+            prop_assert_eq!(parse_parts(&days, today), Some((
+                today + Duration::days(n),
+                None,
+            )));
+            let weeks = format!("in {} weeks", n);
+            prop_assert_eq!(
+                parse_parts(&weeks, today).map(|(date, _)| date),
+                Some(today + Duration::weeks(n))
+            );
+            prop_assert_eq!(parse_period(&format!("{}d", n)), Some(
+                Duration::days(n)
+            ));
+        }
+
+        #[test]
+        fn weekdays_are_within_a_week(today in testing::day(), n in 0..7u32) {
+            let day = (0..n).fold(Weekday::Mon, |day, _| day.succ());
+            for name in [day.to_string(), format!("next {}", day)] {
+                let (date, _) = parse_parts(&name, today).unwrap();
+                prop_assert_eq!(date.weekday(), day);
+                prop_assert!((1..=7).contains(&(date - today).num_days()));
+            }
+        }
+
+        #[test]
+        fn month_and_day(date in testing::day(), today in testing::day()) {
+            let month = MONTHS[date.month0() as usize];
+            let day = date.day();
+            let with_year = format!("{} {} {}", &month[..3], day, date.year());
+            prop_assert_eq!(
+                parse_parts(&with_year, today),
+                Some((date, None))
+            );
+            // without a year, the next time it comes round, unless that's a
+            // Feb 29 that this year or the next doesn't have
+            let next = parse_parts(&format!("{} {}", day, month), today);
+            if let Some((next, _)) = next {
+                prop_assert_eq!(
+                    (next.month(), next.day()),
+                    (date.month(), day)
+                );
+                prop_assert!(next >= today);
+                prop_assert!(next.year() - today.year() <= 1);
+            } else {
+                prop_assert_eq!((date.month(), day), (2, 29));
+            }
+        }
+
+        #[test]
+        fn times(
+            hour in 0..24u32,
+            minute in 0..60u32,
+            today in testing::day(),
+        ) {
+            let time = NaiveTime::from_hms(hour, minute, 0);
+            let twelve = format!(
+                "{}:{:02}{}",
+                (hour + 11) % 12 + 1,
+                minute,
+                if hour < 12 { "am" } else { "pm" }
+            );
+            for text in [time.format("%H:%M").to_string(), twelve] {
+                prop_assert_eq!(parse_time(&text), Some(time));
+                let at = format!("tomorrow at {}", text);
+                prop_assert_eq!(
+                    parse_parts(&at, today),
+                    Some((today + Duration::days(1), Some(time)))
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Alerts for items coming due, sent through the channels configured under
+//! `[notify]` in the config file.
+
+use crate::config::{Channel, NotifyConfig};
+use crate::{crypt, DateMaybeTime, TodoList};
+use anyhow::{bail, Context, Result};
+use chrono::{prelude::*, Duration};
+use std::collections::HashMap;
+use std::io::prelude::*;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// An item that has come due (or is about to).
+#[derive(Debug, Clone)]
+pub struct Due {
+    pub list: String,
+    pub title: String,
+    pub date: DateMaybeTime,
+}
+
+impl Due {
+    /// Identifies the item across runs, so each is only announced once.
+    pub fn key(&self) -> String {
+        let date = match self.date {
+            DateMaybeTime::Date(date) => date.to_string(),
+            DateMaybeTime::DateTime(datetime) => datetime.to_rfc3339(),
+        };
+        format!("{}\t{}\t{}", self.list, self.title, date)
+    }
+}
+
+/// What gets sent: a short headline and one line per item.
+#[derive(Debug)]
+pub struct Message {
+    pub title: String,
+    pub body: String,
+}
+
+impl Message {
+    pub fn for_items(items: &[Due]) -> Message {
+        let title = match items.len() {
+            1 => String::from("later: 1 item due"),
+            n => format!("later: {} items due", n),
+        };
+        let body = items
+            .iter()
+            .map(|item| {
+                format!("{} ({}) - {}", item.title, item.list, item.date)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        Message { title, body }
+    }
+}
+
+// plain dates fall due at the start of the day
+fn due_at(date: &DateMaybeTime) -> DateTime<Local> {
+    match date {
+        DateMaybeTime::Date(date) => Local
+            .from_local_datetime(&date.and_hms(0, 0, 0))
+            .earliest()
+            .unwrap_or_else(crate::clock::now),
+        DateMaybeTime::DateTime(datetime) => *datetime,
+    }
+}
+
+/// Every dated item not yet done, in any list, that is due within `ahead`
+/// of `now` (overdue ones included), soonest first.
+pub fn due_items(
+    lists: &HashMap<String, TodoList>,
+    now: DateTime<Local>,
+    ahead: Duration,
+) -> Vec<Due> {
+    let mut items: Vec<Due> = lists
+        .iter()
+        .flat_map(|(name, list)| {
+            list.items_with_paths()
+                .into_iter()
+                .filter(|(_, item)| !item.is_done())
+                .filter_map(move |(_, item)| {
+                    Some(Due {
+                        list: name.clone(),
+                        title: item.title().to_string(),
+                        date: item.date()?,
+                    })
+                })
+        })
+        .filter(|item| due_at(&item.date) <= now + ahead)
+        .collect();
+    items.sort_by_key(|item| (due_at(&item.date), item.list.clone()));
+    items
+}
+
+/// A timed item falling due, for the `on_due` commands.
+#[derive(Debug, Clone)]
+pub struct Alarm {
+    pub list: String,
+    pub title: String,
+    pub at: DateTime<Local>,
+    /// The item's tags and those of the sublists it's in.
+    pub tags: Vec<String>,
+}
+
+/// Every timed item not yet done, in any list, that falls due after `from`
+/// and no later than `to`, soonest first.
+pub fn alarms_between(
+    lists: &HashMap<String, TodoList>,
+    from: DateTime<Local>,
+    to: DateTime<Local>,
+) -> Vec<Alarm> {
+    let mut alarms: Vec<Alarm> = lists
+        .iter()
+        .flat_map(|(name, list)| {
+            list.items_with_paths().into_iter().filter_map(
+                move |(path, item)| {
+                    let at = match item.date() {
+                        Some(DateMaybeTime::DateTime(at)) => at,
+                        _ => return None,
+                    };
+                    if item.is_done() || at <= from || at > to {
+                        return None;
+                    }
+                    let mut tags: Vec<String> = list
+                        .inherited_tags(&path)
+                        .into_iter()
+                        .map(String::from)
+                        .collect();
+                    tags.extend(item.tags().iter().cloned());
+                    Some(Alarm {
+                        list: name.clone(),
+                        title: item.title().to_string(),
+                        at,
+                        tags,
+                    })
+                },
+            )
+        })
+        .collect();
+    alarms.sort_by_key(|alarm| (alarm.at, alarm.list.clone()));
+    alarms
+}
+
+/// When the next timed item not yet done falls due after `from`.
+pub fn next_alarm(
+    lists: &HashMap<String, TodoList>,
+    from: DateTime<Local>,
+) -> Option<DateTime<Local>> {
+    lists
+        .values()
+        .flat_map(|list| list.items_with_paths())
+        .filter(|(_, item)| !item.is_done())
+        .filter_map(|(_, item)| match item.date() {
+            Some(DateMaybeTime::DateTime(at)) if at > from => Some(at),
+            _ => None,
+        })
+        .min()
+}
+
+/// Runs `command` for `alarm`, with the item in the `LATER_TITLE`,
+/// `LATER_LIST` and `LATER_DUE` environment variables, and waits for it.
+pub fn ring(command: &str, alarm: &Alarm) -> Result<()> {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("LATER_TITLE", &alarm.title)
+        .env("LATER_LIST", &alarm.list)
+        .env("LATER_DUE", alarm.at.to_rfc3339())
+        .stdin(Stdio::null())
+        .status()
+        .with_context(|| format!("command '{}'", command))?;
+    if !status.success() {
+        bail!("command '{}' failed ({})", command, status);
+    }
+    Ok(())
+}
+
+/// Keys of the items announced by earlier runs, stored next to the lists.
+pub fn load_sent(path: &Path) -> Result<Vec<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => {
+            serde_json::from_str(&crypt::open(&text)?).with_context(|| {
+                format!(
+                    "Couldn't parse notification state ({})",
+                    path.display()
+                )
+            })
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e).with_context(|| {
+            format!("Couldn't read notification state ({})", path.display())
+        }),
+    }
+}
+
+pub fn save_sent(path: &Path, keys: &[String]) -> Result<()> {
+    let text = crypt::seal(&serde_json::to_string_pretty(keys)?)?;
+    std::fs::write(path, text).with_context(|| {
+        format!("Couldn't write notification state ({})", path.display())
+    })
+}
+
+/// Sends `message` through every configured channel (or the desktop, if
+/// none are), trying them all before reporting any failures.
+pub fn send_all(config: &NotifyConfig, message: &Message) -> Result<()> {
+    let channels = if config.channels.is_empty() {
+        vec![Channel::Desktop]
+    } else {
+        config.channels.clone()
+    };
+    let failures: Vec<String> = channels
+        .iter()
+        .filter_map(|channel| send(channel, message).err())
+        .map(|e| format!("{:#}", e))
+        .collect();
+    if !failures.is_empty() {
+        bail!("Couldn't send notifications:\n  {}", failures.join("\n  "));
+    }
+    Ok(())
+}
+
+pub fn send(channel: &Channel, message: &Message) -> Result<()> {
+    match channel {
+        Channel::Desktop => desktop(message),
+        Channel::Command { command } => run_command(command, message),
+        Channel::Webhook { url, json } => {
+            let request = ureq::post(url).set("Title", &message.title);
+            let result = if *json {
+                let text = format!("*{}*\n{}", message.title, message.body);
+                request.set("Content-Type", "application/json").send_string(
+                    &serde_json::json!({ "text": text }).to_string(),
+                )
+            } else {
+                request.send_string(&message.body)
+            };
+            result.with_context(|| format!("webhook {}", url))?;
+            Ok(())
+        }
+    }
+}
+
+fn desktop(message: &Message) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {:?} with title {:?}",
+            message.body, message.title
+        );
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(script);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command
+            .arg("--app-name=later")
+            .arg(&message.title)
+            .arg(&message.body);
+        command
+    };
+    let status = command.status().context("desktop notification")?;
+    if !status.success() {
+        bail!("desktop notification failed ({})", status);
+    }
+    Ok(())
+}
+
+fn run_command(command: &str, message: &Message) -> Result<()> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("LATER_TITLE", &message.title)
+        .env("LATER_MESSAGE", &message.body)
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("command '{}'", command))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // the command may not read its input at all
+        let _ = writeln!(stdin, "{}\n{}", message.title, message.body);
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("command '{}' failed ({})", command, status);
+    }
+    Ok(())
+}
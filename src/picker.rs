@@ -0,0 +1,307 @@
+//! `--pick`: choosing an item by typing a few letters of its title rather
+//! than counting rows for its index. As you type, the items that match best
+//! are listed under the prompt; up and down move between them, and enter
+//! takes the highlighted one.
+//!
+//! Letters match in order but needn't be next to each other, so "grcr"
+//! finds "groceries", and each word typed has to match somewhere. Items
+//! are matched along with the titles of the sublists they're in, so "home
+//! clean" finds "Clean" in "Home".
+
+use crate::error::Result;
+use crate::{color, TodoList};
+use ansi_term::{Color, Style};
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{
+    Cmd, ConditionalEventHandler, Editor, Event, EventContext, EventHandler,
+    KeyCode, KeyEvent, Modifiers, Movement, RepeatCount,
+};
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use unicode_width::UnicodeWidthChar;
+
+// how many matches are listed at once
+const SHOWN: usize = 8;
+
+/// An item as it's offered: its index path, and its title after those of
+/// the sublists it's in, e.g. "Home / Clean the oven".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub path: Vec<usize>,
+    pub text: String,
+}
+
+impl Candidate {
+    /// The index, as the command line takes it.
+    pub fn index(&self) -> String {
+        let index: Vec<String> =
+            self.path.iter().map(usize::to_string).collect();
+        index.join(",")
+    }
+}
+
+/// Every item in `list`, in the order the tree draws them.
+pub fn candidates(list: &TodoList) -> Vec<Candidate> {
+    let mut titles: Vec<&str> = Vec::new();
+    list.flatten()
+        .map(|(path, item)| {
+            titles.truncate(path.len() - 1);
+            titles.push(item.title());
+            Candidate {
+                text: titles.join(" / "),
+                path,
+            }
+        })
+        .collect()
+}
+
+// how well `word` matches `text`, if it does: a point for every letter,
+// more for letters that follow on from the last one or start a word
+fn word_score(word: &[char], text: &[char]) -> Option<usize> {
+    let (first, rest) = word.split_first()?;
+    // the best of the matches starting at each place the first letter is
+    (0..text.len())
+        .filter(|&start| text[start] == *first)
+        .filter_map(|start| {
+            let starts_word =
+                |i: usize| i == 0 || !text[i - 1].is_alphanumeric();
+            let mut score = 1 + 2 * usize::from(starts_word(start));
+            let mut at = start;
+            for c in rest {
+                let next =
+                    at + 1 + text[at + 1..].iter().position(|t| t == c)?;
+                score += 1;
+                if next == at + 1 {
+                    score += 3;
+                } else if starts_word(next) {
+                    score += 2;
+                }
+                at = next;
+            }
+            Some(score)
+        })
+        .max()
+}
+
+/// How well `query` matches `text`, if every word of it does; case is
+/// ignored.
+pub fn score(query: &str, text: &str) -> Option<usize> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    query
+        .to_lowercase()
+        .split_whitespace()
+        .map(|word| {
+            let word: Vec<char> = word.chars().collect();
+            word_score(&word, &text)
+        })
+        .sum()
+}
+
+/// The candidates matching `query`, best first; those that match equally
+/// well, or everything when the query is empty, stay in tree order.
+pub fn rank<'a>(
+    query: &str,
+    candidates: &'a [Candidate],
+) -> Vec<&'a Candidate> {
+    let mut ranked: Vec<(usize, &Candidate)> = candidates
+        .iter()
+        .filter_map(|candidate| {
+            score(query, &candidate.text).map(|score| (score, candidate))
+        })
+        .collect();
+    ranked.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    ranked.into_iter().map(|(_, candidate)| candidate).collect()
+}
+
+// `text` cut to fit in `width` columns
+fn fit(text: &str, width: usize) -> String {
+    let mut used = 0;
+    let mut fitted = String::new();
+    for c in text.chars() {
+        used += c.width().unwrap_or(0);
+        if used + 1 > width {
+            fitted.push('…');
+            return fitted;
+        }
+        fitted.push(c);
+    }
+    fitted
+}
+
+struct Picker {
+    candidates: Vec<Candidate>,
+    // which of the matches shown is highlighted
+    selected: Arc<AtomicUsize>,
+    // how many matches are shown, so the highlight stays among them
+    shown: Arc<AtomicUsize>,
+    // the query the matches were last listed for, so that typing moves the
+    // highlight back to the best match
+    query: Mutex<String>,
+    // the line a move of the highlight is putting back, see `Step`
+    replaying: Arc<Mutex<Option<String>>>,
+}
+
+impl Picker {
+    fn matches(&self, query: &str) -> Vec<&Candidate> {
+        let mut matches = rank(query.trim(), &self.candidates);
+        matches.truncate(SHOWN);
+        matches
+    }
+}
+
+impl Hinter for Picker {
+    type Hint = String;
+
+    // the matches, listed under the line being typed
+    fn hint(
+        &self,
+        line: &str,
+        _pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> Option<String> {
+        let mut replaying = self.replaying.lock().unwrap();
+        match replaying.as_deref() {
+            // on the way to being put back, or put back as it was
+            Some(replayed) => {
+                if replayed == line {
+                    *replaying = None;
+                }
+                *self.query.lock().unwrap() = String::from(line);
+            }
+            None => {
+                let mut last = self.query.lock().unwrap();
+                if *last != line {
+                    *last = String::from(line);
+                    self.selected.store(0, Ordering::Relaxed);
+                }
+            }
+        }
+        let matches = self.matches(line);
+        self.shown.store(matches.len(), Ordering::Relaxed);
+        if matches.is_empty() {
+            return Some(String::from("\n  (no matches)"));
+        }
+        let width = terminal_size::terminal_size()
+            .map_or(80, |(terminal_size::Width(w), _)| w as usize);
+        let selected = self.selected.load(Ordering::Relaxed);
+        let hint = matches
+            .iter()
+            .enumerate()
+            .map(|(i, candidate)| {
+                let marker = if i == selected { '>' } else { ' ' };
+                let row = format!(
+                    "{} {}  {}",
+                    marker,
+                    candidate.index(),
+                    candidate.text
+                );
+                format!("\n{}", fit(&row, width))
+            })
+            .collect();
+        Some(hint)
+    }
+}
+
+impl Highlighter for Picker {
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        let lines: Vec<String> = hint
+            .split('\n')
+            .map(|line| {
+                if line.is_empty() {
+                    return String::new();
+                }
+                let style = if line.starts_with('>') {
+                    Color::Cyan.bold()
+                } else {
+                    Style::new().dimmed()
+                };
+                color::style(style).paint(line).to_string()
+            })
+            .collect();
+        Cow::Owned(lines.join("\n"))
+    }
+}
+
+impl Completer for Picker {
+    type Candidate = String;
+}
+
+impl Validator for Picker {}
+
+impl rustyline::Helper for Picker {}
+
+// moves the highlight up or down a row
+struct Step {
+    selected: Arc<AtomicUsize>,
+    shown: Arc<AtomicUsize>,
+    replaying: Arc<Mutex<Option<String>>>,
+    down: bool,
+}
+
+impl ConditionalEventHandler for Step {
+    fn handle(
+        &self,
+        _: &Event,
+        _: RepeatCount,
+        _: bool,
+        ctx: &EventContext,
+    ) -> Option<Cmd> {
+        let selected = self.selected.load(Ordering::Relaxed);
+        let last = self.shown.load(Ordering::Relaxed).saturating_sub(1);
+        let selected = if self.down {
+            (selected + 1).min(last)
+        } else {
+            selected.saturating_sub(1)
+        };
+        self.selected.store(selected, Ordering::Relaxed);
+        // the matches are only listed again when the line changes, so it's
+        // changed to itself; an empty line can't be, so it gets a space,
+        // which matching ignores
+        let (line, cmd) = match ctx.line() {
+            "" => (String::from(" "), Cmd::Insert(1, String::from(" "))),
+            line => (
+                String::from(line),
+                Cmd::Replace(Movement::WholeLine, Some(String::from(line))),
+            ),
+        };
+        *self.replaying.lock().unwrap() = Some(line);
+        Some(cmd)
+    }
+}
+
+/// Asks for an item in `list`, showing `prompt`. Returns its index path, or
+/// `None` if nothing matches what was typed.
+pub fn pick(list: &TodoList, prompt: &str) -> Result<Option<Vec<usize>>> {
+    let selected = Arc::new(AtomicUsize::new(0));
+    let shown = Arc::new(AtomicUsize::new(0));
+    let replaying = Arc::new(Mutex::new(None));
+    let picker = Picker {
+        candidates: candidates(list),
+        selected: Arc::clone(&selected),
+        shown: Arc::clone(&shown),
+        query: Mutex::new(String::new()),
+        replaying: Arc::clone(&replaying),
+    };
+    let mut rl = Editor::<Picker>::new();
+    for (code, down) in [(KeyCode::Up, false), (KeyCode::Down, true)] {
+        rl.bind_sequence(
+            KeyEvent(code, Modifiers::NONE),
+            EventHandler::Conditional(Box::new(Step {
+                selected: Arc::clone(&selected),
+                shown: Arc::clone(&shown),
+                replaying: Arc::clone(&replaying),
+                down,
+            })),
+        );
+    }
+    rl.set_helper(Some(picker));
+    let query = rl.readline(prompt)?;
+    let picker = rl.helper().unwrap();
+    let matches = picker.matches(&query);
+    let chosen = selected.load(Ordering::Relaxed).min(matches.len());
+    Ok(matches.get(chosen).map(|candidate| candidate.path.clone()))
+}
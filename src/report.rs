@@ -0,0 +1,78 @@
+//! Summaries drawn from the history kept on items.
+
+use crate::i18n::Lang;
+use crate::{humanize, TodoList};
+use chrono::Duration;
+use std::collections::HashMap;
+use std::io::prelude::*;
+
+/// An item that has been pushed back again and again.
+#[derive(Debug, Clone)]
+pub struct Slipped {
+    pub list: String,
+    pub index: Vec<usize>,
+    pub title: String,
+    /// How many times it was pushed back.
+    pub times: usize,
+    /// How far it was pushed back, all told.
+    pub total: Duration,
+}
+
+/// Every item not yet done that has been pushed back at least `min` times,
+/// across all lists, the worst first.
+pub fn slipped(lists: &HashMap<String, TodoList>, min: usize) -> Vec<Slipped> {
+    let mut found: Vec<Slipped> = lists
+        .iter()
+        .flat_map(|(name, list)| {
+            list.items_with_paths().into_iter().filter_map(
+                move |(index, item)| {
+                    let slips: Vec<Duration> = item
+                        .rescheduled()
+                        .iter()
+                        .filter_map(|r| r.slip())
+                        .collect();
+                    if item.is_done() || slips.len() < min.max(1) {
+                        return None;
+                    }
+                    Some(Slipped {
+                        list: name.clone(),
+                        index,
+                        title: item.title().to_string(),
+                        times: slips.len(),
+                        total: slips
+                            .into_iter()
+                            .fold(Duration::zero(), |a, b| a + b),
+                    })
+                },
+            )
+        })
+        .collect();
+    found.sort_by(|a, b| {
+        (b.times, b.total)
+            .cmp(&(a.times, a.total))
+            .then_with(|| (&a.list, &a.index).cmp(&(&b.list, &b.index)))
+    });
+    found
+}
+
+pub fn write_slipped(
+    out: &mut impl Write,
+    slipped: &[Slipped],
+) -> std::io::Result<()> {
+    let lang = Lang::current();
+    for item in slipped {
+        let index: Vec<String> =
+            item.index.iter().map(|i| i.to_string()).collect();
+        let total = humanize::amount(lang, item.total).unwrap_or_default();
+        writeln!(
+            out,
+            "↻{:<3} {:<18} {} ({} {})",
+            item.times,
+            total,
+            item.title,
+            item.list,
+            index.join(","),
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,253 @@
+//! A sync remote in an S3-compatible bucket (AWS, MinIO, R2, ...), for
+//! when object storage is the only thing the machines share.
+//!
+//! Credentials come from the usual `AWS_ACCESS_KEY_ID`,
+//! `AWS_SECRET_ACCESS_KEY` and optional `AWS_SESSION_TOKEN` variables, and
+//! requests are signed with AWS Signature Version 4. Pushes are
+//! conditional on the object's ETag from the last fetch, so two machines
+//! syncing at once can't silently overwrite each other.
+
+use crate::sync::Backend;
+use anyhow::{bail, Context, Result};
+use chrono::prelude::*;
+use ring::{digest, hmac};
+use std::cell::RefCell;
+
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// `later.json` under `prefix` in `bucket`.
+pub struct S3 {
+    bucket: String,
+    prefix: String,
+    region: String,
+    endpoint: Option<String>,
+    // the ETag seen at the last fetch or push, or `Some(None)` when there
+    // was no object yet, so the next push must create it; `None` when
+    // there's nothing to check against
+    fetched: RefCell<Option<Option<String>>>,
+}
+
+struct Credentials {
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+}
+
+impl Credentials {
+    fn from_env() -> Result<Credentials> {
+        let var = |name: &str| {
+            std::env::var(name).ok().filter(|value| !value.is_empty())
+        };
+        match (var("AWS_ACCESS_KEY_ID"), var("AWS_SECRET_ACCESS_KEY")) {
+            (Some(access_key), Some(secret_key)) => Ok(Credentials {
+                access_key,
+                secret_key,
+                session_token: var("AWS_SESSION_TOKEN"),
+            }),
+            _ => bail!(
+                "S3 sync needs AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY \
+                 set"
+            ),
+        }
+    }
+}
+
+impl S3 {
+    /// Without `region`, `AWS_REGION` or `AWS_DEFAULT_REGION` is used, then
+    /// `us-east-1`. Without `endpoint`, AWS itself.
+    pub fn new(
+        bucket: &str,
+        prefix: Option<&str>,
+        region: Option<&str>,
+        endpoint: Option<&str>,
+    ) -> S3 {
+        let region = region
+            .map(String::from)
+            .or_else(|| std::env::var("AWS_REGION").ok())
+            .or_else(|| std::env::var("AWS_DEFAULT_REGION").ok())
+            .filter(|region| !region.is_empty())
+            .unwrap_or_else(|| String::from(DEFAULT_REGION));
+        // "lists" and "lists/" both mean the folder
+        let prefix = match prefix.map(|p| p.trim_matches('/')) {
+            Some("") | None => String::new(),
+            Some(prefix) => format!("{}/", prefix),
+        };
+        S3 {
+            bucket: String::from(bucket),
+            prefix,
+            region,
+            endpoint: endpoint.map(|e| String::from(e.trim_end_matches('/'))),
+            fetched: RefCell::new(None),
+        }
+    }
+
+    fn key(&self) -> String {
+        format!("{}later.json", self.prefix)
+    }
+
+    // AWS takes the bucket as part of the host name; other services are
+    // usually addressed with the bucket as the first part of the path
+    fn url(&self) -> (String, String, String) {
+        let path = format!("/{}", uri_encode(&self.key(), false));
+        match &self.endpoint {
+            Some(endpoint) => {
+                let host = endpoint
+                    .split_once("://")
+                    .map_or(endpoint.as_str(), |(_, rest)| rest)
+                    .to_string();
+                let path =
+                    format!("/{}{}", uri_encode(&self.bucket, true), path);
+                (format!("{}{}", endpoint, path), host, path)
+            }
+            None => {
+                let host =
+                    format!("{}.s3.{}.amazonaws.com", self.bucket, self.region);
+                (format!("https://{}{}", host, path), host, path)
+            }
+        }
+    }
+
+    // a request with the Signature Version 4 headers for `body`
+    fn request(&self, method: &str, body: &[u8]) -> Result<ureq::Request> {
+        let credentials = Credentials::from_env()?;
+        let (url, host, path) = self.url();
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex(digest::digest(&digest::SHA256, body).as_ref());
+
+        let mut headers = vec![
+            ("host", host),
+            ("x-amz-content-sha256", payload_hash.clone()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &credentials.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+            .collect();
+        let signed_headers: Vec<&str> =
+            headers.iter().map(|(name, _)| *name).collect();
+        let signed_headers = signed_headers.join(";");
+        let canonical_request = format!(
+            "{}\n{}\n\n{}\n{}\n{}",
+            method, path, canonical_headers, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(
+                digest::digest(&digest::SHA256, canonical_request.as_bytes())
+                    .as_ref()
+            )
+        );
+        let key = [self.region.as_str(), "s3", "aws4_request"].iter().fold(
+            hmac_sha256(
+                format!("AWS4{}", credentials.secret_key).as_bytes(),
+                date.as_bytes(),
+            ),
+            |key, part| hmac_sha256(&key, part.as_bytes()),
+        );
+        let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+        let mut request = ureq::request(method, &url);
+        // ureq sets Host itself, from the same URL
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host")
+        {
+            request = request.set(name, value);
+        }
+        Ok(request.set(
+            "Authorization",
+            &format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, \
+                 Signature={}",
+                credentials.access_key, scope, signed_headers, signature
+            ),
+        ))
+    }
+}
+
+impl Backend for S3 {
+    fn describe(&self) -> String {
+        format!("s3://{}/{}", self.bucket, self.key())
+    }
+
+    fn fetch(&self) -> Result<Option<String>> {
+        let response = match self.request("GET", b"")?.call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => {
+                *self.fetched.borrow_mut() = Some(None);
+                return Ok(None);
+            }
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Couldn't fetch {}", self.describe())
+                })
+            }
+        };
+        let etag = response.header("ETag").map(String::from);
+        let data = response
+            .into_string()
+            .with_context(|| format!("Couldn't read {}", self.describe()))?;
+        *self.fetched.borrow_mut() = etag.map(Some);
+        Ok(Some(data))
+    }
+
+    fn push(&self, data: &str) -> Result<()> {
+        let mut request = self
+            .request("PUT", data.as_bytes())?
+            .set("Content-Type", "application/json");
+        // only over what was fetched, or into an empty spot if nothing was
+        request = match &*self.fetched.borrow() {
+            Some(Some(etag)) => request.set("If-Match", etag),
+            Some(None) => request.set("If-None-Match", "*"),
+            None => request,
+        };
+        match request.send_string(data) {
+            Ok(response) => {
+                let etag = response.header("ETag").map(String::from);
+                *self.fetched.borrow_mut() = etag.map(Some);
+                Ok(())
+            }
+            Err(ureq::Error::Status(409 | 412, _)) => bail!(
+                "{} changed since it was fetched, so nothing was pushed; run \
+                 `later sync` again to merge the new version",
+                self.describe()
+            ),
+            Err(e) => Err(e).with_context(|| {
+                format!("Couldn't push to {}", self.describe())
+            }),
+        }
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&key, data).as_ref().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// percent-encodes everything but unreserved characters, and slashes too
+// when `slashes` is set, as Signature Version 4 wants
+fn uri_encode(text: &str, slashes: bool) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'-'
+            | b'.'
+            | b'_'
+            | b'~' => (b as char).to_string(),
+            b'/' if !slashes => String::from("/"),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
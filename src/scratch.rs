@@ -0,0 +1,85 @@
+//! Private copies of text for an editor to work on: each in a folder of
+//! its own under the temp directory, readable only by the user, and
+//! removed along with the folder when dropped, as the text may be lists
+//! that are otherwise encrypted.
+
+use anyhow::{Context, Result};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::fs::{DirBuilder, OpenOptions};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+// tries at an unused folder name before giving up
+const ATTEMPTS: usize = 8;
+
+/// A file holding a copy of some text, gone once this is dropped.
+#[derive(Debug)]
+pub struct Scratch {
+    folder: PathBuf,
+    path: PathBuf,
+}
+
+impl Scratch {
+    /// Writes `text` to a new file called `name`. The folder and the file
+    /// are both made afresh, so nothing planted at either path is followed.
+    pub fn new(name: &str, text: &str) -> Result<Scratch> {
+        let folder = make_folder()?;
+        let path = folder.join(name);
+        // the folder is removed again if the file can't be written
+        let scratch = Scratch { folder, path };
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(&scratch.path)
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .with_context(|| {
+                format!("Couldn't write {}", scratch.path.display())
+            })?;
+        Ok(scratch)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The text as it is now.
+    pub fn read(&self) -> Result<String> {
+        std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Couldn't read {}", self.path.display()))
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.folder);
+    }
+}
+
+// a folder only the user can get into, under a name no one could guess
+fn make_folder() -> Result<PathBuf> {
+    let mut builder = DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    let temp = std::env::temp_dir();
+    for _ in 0..ATTEMPTS {
+        let mut random = [0u8; 8];
+        SystemRandom::new()
+            .fill(&mut random)
+            .map_err(|_| anyhow::anyhow!("No random numbers"))?;
+        let name: String =
+            random.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let folder = temp.join(format!("later-{}", name));
+        match builder.create(&folder) {
+            Ok(()) => return Ok(folder),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Couldn't create {}", folder.display())
+                })
+            }
+        }
+    }
+    anyhow::bail!("Couldn't find a free folder in {}", temp.display())
+}
@@ -0,0 +1,252 @@
+//! `later search`: titles matching a substring or regex, across every list
+//! and sublist, each with the list and index path needed to act on it, and
+//! with `--apply`, something done to all of them at once; and
+//! `later rename-matching`, which rewrites them.
+
+use crate::TodoList;
+use crate::{color, date_text, tag_name, ItemPath, ListItem, RenderOptions};
+use ansi_term::{Color, Style};
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::prelude::*;
+
+/// What a title has to contain.
+pub enum Query {
+    /// Case-insensitive.
+    Substring(String),
+    Regex(Regex),
+}
+
+impl Query {
+    pub fn new(text: &str, regex: bool) -> Result<Query> {
+        if regex {
+            let regex = Regex::new(text)
+                .with_context(|| format!("Invalid regex '{}'", text))?;
+            Ok(Query::Regex(regex))
+        } else {
+            Ok(Query::Substring(text.to_lowercase()))
+        }
+    }
+
+    pub fn matches(&self, title: &str) -> bool {
+        match self {
+            Query::Substring(text) => title.to_lowercase().contains(text),
+            Query::Regex(regex) => regex.is_match(title),
+        }
+    }
+}
+
+pub struct Match<'a> {
+    pub list: &'a str,
+    pub index: Vec<usize>,
+    pub item: &'a ListItem,
+}
+
+/// Every item whose title matches, in `only` if given, else in every list,
+/// by list name and then position.
+pub fn search<'a>(
+    lists: &'a HashMap<String, TodoList>,
+    query: &Query,
+    only: Option<&str>,
+) -> Vec<Match<'a>> {
+    let mut found: Vec<Match> = lists
+        .iter()
+        .filter(|(name, _)| only.is_none_or(|only| only == name.as_str()))
+        .flat_map(|(name, list)| {
+            list.items_with_paths()
+                .into_iter()
+                .filter(|(_, item)| query.matches(item.title()))
+                .map(move |(index, item)| Match {
+                    list: name,
+                    index,
+                    item,
+                })
+        })
+        .collect();
+    found.sort_by(|a, b| (a.list, &a.index).cmp(&(b.list, &b.index)));
+    found
+}
+
+/// What `search --apply` does to every match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Done,
+    Remove,
+    /// Adds the tag.
+    Tag(String),
+    /// Moves the item to the end of the named list.
+    Move(String),
+}
+
+impl Action {
+    /// Reads `done`, `remove`, `tag X` or `move LIST`.
+    pub fn parse(words: &[&str]) -> Result<Action> {
+        match words {
+            ["done"] => Ok(Action::Done),
+            ["remove"] => Ok(Action::Remove),
+            ["tag", tag] => match tag_name(tag) {
+                Some(tag) => Ok(Action::Tag(tag)),
+                None => bail!("Invalid tag '{}'", tag),
+            },
+            ["move", list] => Ok(Action::Move(String::from(*list))),
+            _ => bail!(
+                "Invalid action '{}' (expected done, remove, tag X or move \
+                 LIST)",
+                words.join(" ")
+            ),
+        }
+    }
+
+    /// What's about to happen, e.g. "Tag 3 items with #work", or with
+    /// `done` what happened, e.g. "tagged 3 items with #work".
+    pub fn describe(&self, count: usize, done: bool) -> String {
+        let items = match count {
+            1 => String::from("1 item"),
+            n => format!("{} items", n),
+        };
+        let (verb, rest) = match self {
+            Action::Done => (["Mark", "marked"], format!("{} as done", items)),
+            Action::Remove => (["Remove", "removed"], items),
+            Action::Tag(tag) => {
+                (["Tag", "tagged"], format!("{} with #{}", items, tag))
+            }
+            Action::Move(list) => {
+                (["Move", "moved"], format!("{} to '{}'", items, list))
+            }
+        };
+        format!("{} {}", verb[done as usize], rest)
+    }
+
+    // whether acting on `item`, in `list`, would change anything
+    fn changes(&self, list: &str, item: &ListItem) -> bool {
+        match self {
+            Action::Done => !item.is_done(),
+            Action::Remove => true,
+            Action::Tag(tag) => !item.has_tag(tag),
+            Action::Move(to) => to != list,
+        }
+    }
+
+    // whether the items inside a sublist go along with it
+    fn takes_whole(&self) -> bool {
+        matches!(self, Action::Remove | Action::Move(_))
+    }
+}
+
+/// The matches `action` would change, by list name and then position.
+/// When it removes or moves items, matches inside another match are left
+/// out, since they go with it.
+pub fn targets(matches: &[Match], action: &Action) -> Vec<(String, ItemPath)> {
+    let mut targets: Vec<(String, ItemPath)> = Vec::new();
+    for found in matches {
+        let inside = action.takes_whole()
+            && targets.iter().any(|(list, index)| {
+                list == found.list && found.index.starts_with(index)
+            });
+        if !inside && action.changes(found.list, found.item) {
+            targets.push((
+                String::from(found.list),
+                ItemPath::from(found.index.clone()),
+            ));
+        }
+    }
+    targets
+}
+
+/// A title that `rename-matching` would change.
+pub struct Rename {
+    pub index: Vec<usize>,
+    pub old: String,
+    pub new: String,
+}
+
+/// The renames that replacing `pattern` with `replacement` makes in `list`.
+/// Without `regex`, the pattern is plain text (matched case-sensitively);
+/// with it, `$1` and `${name}` in the replacement refer to its groups.
+pub fn renames(
+    list: &TodoList,
+    pattern: &str,
+    replacement: &str,
+    regex: bool,
+) -> Result<Vec<Rename>> {
+    let (pattern, replacement) = if regex {
+        let pattern = Regex::new(pattern)
+            .with_context(|| format!("Invalid regex '{}'", pattern))?;
+        (pattern, String::from(replacement))
+    } else {
+        // `$` means nothing in a plain replacement
+        let pattern = Regex::new(&regex::escape(pattern)).unwrap();
+        (pattern, replacement.replace('$', "$$"))
+    };
+    Ok(list
+        .items_with_paths()
+        .into_iter()
+        .filter_map(|(index, item)| {
+            let new = pattern.replace_all(item.title(), replacement.as_str());
+            (new != item.title()).then(|| Rename {
+                index,
+                old: item.title().to_string(),
+                new: new.into_owned(),
+            })
+        })
+        .collect())
+}
+
+pub fn write_renames(
+    out: &mut impl Write,
+    renames: &[Rename],
+) -> std::io::Result<()> {
+    for rename in renames {
+        let index: Vec<String> =
+            rename.index.iter().map(|i| i.to_string()).collect();
+        writeln!(
+            out,
+            "{}  {} → {}",
+            color::style(Color::Cyan.normal()).paint(index.join(",")),
+            color::style(Style::new().dimmed()).paint(&rename.old),
+            rename.new
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes one match per line: `work: 2,1,0  title (date)`.
+pub fn write_matches(
+    out: &mut impl Write,
+    matches: &[Match],
+    opts: &RenderOptions,
+) -> std::io::Result<()> {
+    for found in matches {
+        let index: Vec<String> =
+            found.index.iter().map(|i| i.to_string()).collect();
+        let done = found.item.is_done();
+        write!(
+            out,
+            "{}  {}",
+            opts.style(Color::Cyan.normal()).paint(format!(
+                "{}: {}",
+                found.list,
+                index.join(",")
+            )),
+            opts.title_style(Style::new(), done)
+                .paint(found.item.title())
+        )?;
+        if let Some(date) = found.item.date() {
+            let date_string = format!(
+                "({})",
+                date_text(
+                    &date,
+                    found.item.zone(),
+                    found.item.is_deadline(),
+                    done,
+                    opts.palette
+                )
+            );
+            let style = opts.date_style(&date, found.item.is_deadline(), done);
+            write!(out, " {}", style.paint(date_string))?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
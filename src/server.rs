@@ -0,0 +1,639 @@
+//! A small HTTP server over the data file, for clients that can't run the
+//! command line (calendar apps, phones, dashboards): a calendar feed at
+//! `/feed.ics`, and a JSON API for the lists.
+//!
+//! - `GET /lists`: each list's name, with how many items it has and how
+//!   many of those aren't done
+//! - `GET /lists/NAME`: the list as the data file holds it
+//! - `POST /lists/NAME/items`: adds `{"title": ..., "date": ..., "under":
+//!   ..., "priority": ..., "tags": [...]}`, where only the title is needed
+//!   and the date is read as `later add --date` reads it
+//! - `PATCH /lists/NAME/items/INDEX`: changes any of `title`, `date` (`null`
+//!   to clear it), `done` and `priority`
+//! - `DELETE /lists/NAME/items/INDEX`: removes the item
+//! - `POST /lists/NAME/items/INDEX/move`: moves it to the end of `{"list":
+//!   ..., "under": ...}`, both optional, so `{"list": "done"}` files it in
+//!   another list
+//!
+//! Names and indexes are percent-encoded in the path, and an index is
+//! anything the command line takes, e.g. `2,0` or `groceries%2Fmilk`. Each
+//! change is made under the [`lock`](crate::lock), so the command line can
+//! be used at the same time, and is saved as the command line saves its
+//! own: it can be taken back with `later undo`, and sets off the webhooks
+//! and the git auto-commit. Bodies are sent as `application/json`, and
+//! changes from another site's pages are refused.
+//!
+//! With `--web`, `/` is also a [`dashboard`] of every list.
+
+pub mod dashboard;
+
+use crate::index_path::IndexPath;
+use crate::store::{Change, Store};
+use crate::webhook::{Action, Event};
+use crate::{
+    clock, ics, load, lock, natural, sync, tag_name, url_scheme, Error,
+    ItemPath, ListItem, Priority, TodoEntry, TodoList,
+};
+use anyhow::Result;
+use serde::{Deserialize, Deserializer};
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::{prelude::*, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+// the most a request may send, which is plenty for an item
+const MAX_BODY: usize = 64 * 1024;
+
+// and for the request line and headers, which are read a line at a time
+const MAX_HEAD: u64 = 8 * 1024;
+
+// requests are served one at a time, so a client that stops sending
+// mustn't hold up the rest for long
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+// how long a change waits for the command line to finish with the lists
+const LOCK_WAIT: Duration = Duration::from_secs(5);
+
+/// What a handler sends back.
+pub struct Response {
+    pub status: &'static str,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(content_type: &'static str, body: Vec<u8>) -> Response {
+        Response {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+
+    pub fn json(status: &'static str, value: &serde_json::Value) -> Response {
+        Response {
+            status,
+            content_type: "application/json",
+            body: format!("{}\n", value).into_bytes(),
+        }
+    }
+
+    /// An API error, as `{"error": message}`.
+    pub fn json_error(status: &'static str, message: &str) -> Response {
+        Response::json(status, &json!({ "error": message }))
+    }
+}
+
+// for the many ways a request can be wrong
+fn bad_request(message: impl ToString) -> Response {
+    Response::json_error("400 Bad Request", &message.to_string())
+}
+
+/// An incoming request, reduced to what the handlers look at.
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// The `Authorization` header.
+    pub authorization: Option<String>,
+    /// The `Content-Type` header, without any parameters.
+    pub content_type: Option<String>,
+    /// The `Origin` and `Host` headers, which tell a page's requests from
+    /// the server's own.
+    pub origin: Option<String>,
+    pub host: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    // any page the user visits can have the browser send requests here,
+    // even on 127.0.0.1, so changes are refused from another origin; a
+    // page can't send a JSON body without a CORS check this server never
+    // passes, so bodies must be JSON too, for browsers that leave the
+    // origin out
+    fn refuse_cross_site(&self) -> Option<Response> {
+        let foreign = match (&self.origin, &self.host) {
+            (Some(origin), Some(host)) => {
+                origin.split_once("://").map(|(_, origin)| origin)
+                    != Some(host.as_str())
+            }
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        if foreign {
+            return Some(Response::json_error(
+                "403 Forbidden",
+                "Changes can't be made from another site",
+            ));
+        }
+        let json = self.content_type.as_deref() == Some("application/json");
+        if !self.body.is_empty() && !json {
+            return Some(Response::json_error(
+                "415 Unsupported Media Type",
+                "The body must be sent as application/json",
+            ));
+        }
+        None
+    }
+}
+
+pub struct Server {
+    store: Store,
+    // needed on every request if set
+    token: Option<String>,
+    // whether to serve the dashboard
+    web: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewItem {
+    title: String,
+    date: Option<String>,
+    /// Where to add it, if not at the top level.
+    under: Option<String>,
+    priority: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Changes {
+    title: Option<String>,
+    // missing leaves the date alone, `null` takes it away
+    #[serde(default, deserialize_with = "present")]
+    date: Option<Option<String>>,
+    done: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    priority: Option<Option<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Destination {
+    list: Option<String>,
+    under: Option<String>,
+}
+
+// a field that was given, even as null
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+// a handler's reply: `Ok` once it has changed the lists, to be saved,
+// `Err` if it turned the request down
+type Handled = std::result::Result<Response, Response>;
+
+// the changes a handler made, for the webhooks
+type Events = Vec<Event>;
+
+impl Server {
+    pub fn new(store: Store, token: Option<String>, web: bool) -> Server {
+        Server { store, token, web }
+    }
+
+    /// Serves requests one at a time until the process is stopped.
+    pub fn run(&self, address: &str) -> Result<()> {
+        let listener = TcpListener::bind(address)?;
+        println!("listening on http://{}", listener.local_addr()?);
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(e) = self.handle_connection(stream) {
+                        warn!("request failed: {:#}", e);
+                    }
+                }
+                Err(e) => warn!("connection failed: {}", e),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_connection(&self, mut stream: TcpStream) -> Result<()> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let mut reader = BufReader::new((&stream).take(MAX_HEAD));
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let (mut length, mut authorization) = (0, None);
+        let (mut content_type, mut origin, mut host) = (None, None, None);
+        let mut ended = false;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 {
+                break;
+            }
+            if header.trim().is_empty() {
+                ended = true;
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                let value = value.trim();
+                match name.trim().to_lowercase().as_str() {
+                    "content-length" => length = value.parse().unwrap_or(0),
+                    "authorization" => authorization = Some(value.to_string()),
+                    "content-type" => {
+                        let media_type = value.split(';').next().unwrap_or("");
+                        content_type = Some(media_type.trim().to_lowercase())
+                    }
+                    "origin" => origin = Some(value.to_string()),
+                    "host" => host = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+        }
+        let response = if !ended && reader.get_ref().limit() == 0 {
+            Response::json_error(
+                "431 Request Header Fields Too Large",
+                "Request headers too big",
+            )
+        } else if length > MAX_BODY {
+            Response::json_error("413 Payload Too Large", "Request too big")
+        } else {
+            // let the body through, past what's already buffered
+            reader.get_mut().set_limit(length as u64);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body)?;
+            let mut parts = request_line.split_whitespace();
+            let request = Request {
+                method: parts.next().unwrap_or("").to_string(),
+                path: parts.next().unwrap_or("/").to_string(),
+                authorization,
+                content_type,
+                origin,
+                host,
+                body,
+            };
+            debug!(method = %request.method, path = %request.path);
+            self.route(&request).unwrap_or_else(|e| {
+                Response::json_error(
+                    "500 Internal Server Error",
+                    &format!("{:#}", e),
+                )
+            })
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n",
+            response.status,
+            response.content_type,
+            response.body.len()
+        )?;
+        stream.write_all(&response.body)?;
+        Ok(())
+    }
+
+    // as a bearer token, or `?token=` for calendar apps, which can't send
+    // headers
+    fn is_authorized(&self, request: &Request, query: &str) -> bool {
+        let token = match &self.token {
+            Some(token) => token,
+            None => return true,
+        };
+        let given = request
+            .authorization
+            .as_deref()
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .map(String::from)
+            .or_else(|| {
+                query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix("token="))
+                    .and_then(|value| url_scheme::percent_decode(value).ok())
+            });
+        given.is_some_and(|given| {
+            ring::constant_time::verify_slices_are_equal(
+                given.as_bytes(),
+                token.as_bytes(),
+            )
+            .is_ok()
+        })
+    }
+
+    fn route(&self, request: &Request) -> Result<Response> {
+        let (path, query) =
+            request.path.split_once('?').unwrap_or((&request.path, ""));
+        if request.method != "GET" {
+            if let Some(refused) = request.refuse_cross_site() {
+                return Ok(refused);
+            }
+        }
+        if !self.is_authorized(request, query) {
+            return Ok(Response::json_error(
+                "401 Unauthorized",
+                "Missing or wrong token",
+            ));
+        }
+        let segments: Vec<String> = match path
+            .trim_matches('/')
+            .split('/')
+            .map(url_scheme::percent_decode)
+            .collect()
+        {
+            Ok(segments) => segments,
+            Err(e) => return Ok(bad_request(format!("{:#}", e))),
+        };
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        let body = &request.body;
+        Ok(match (request.method.as_str(), segments.as_slice()) {
+            ("GET", [""]) if self.web => {
+                let lists = load(&self.store.todo_file)?;
+                Response::new(
+                    "text/html; charset=utf-8",
+                    dashboard::render(&lists).into_bytes(),
+                )
+            }
+            ("GET", ["feed.ics"]) => {
+                // reloaded per request, so subscribers always see the latest
+                let lists = load(&self.store.todo_file)?;
+                let mut body = Vec::new();
+                let lists: Vec<&TodoList> = lists.values().collect();
+                ics::write_calendar(&mut body, &lists, ics::Component::Event)?;
+                Response::new("text/calendar; charset=utf-8", body)
+            }
+            ("GET", ["lists"]) => {
+                let lists = load(&self.store.todo_file)?;
+                let mut names: Vec<&String> = lists.keys().collect();
+                names.sort();
+                let summary: Vec<serde_json::Value> = names
+                    .into_iter()
+                    .map(|name| {
+                        let items = lists[name].items_with_paths();
+                        let open =
+                            items.iter().filter(|(_, i)| !i.is_done()).count();
+                        json!({
+                            "name": name,
+                            "items": items.len(),
+                            "open": open,
+                        })
+                    })
+                    .collect();
+                Response::json("200 OK", &json!(summary))
+            }
+            ("GET", ["lists", name]) => {
+                let lists = load(&self.store.todo_file)?;
+                match lists.get(*name) {
+                    Some(list) => {
+                        Response::json("200 OK", &serde_json::to_value(list)?)
+                    }
+                    None => not_found(name, &lists),
+                }
+            }
+            ("POST", ["lists", name, "items"]) => self
+                .change("add", |lists, events| {
+                    add(lists, events, name, parse(body)?)
+                })?,
+            ("PATCH", ["lists", name, "items", index]) => self
+                .change("edit", |lists, events| {
+                    edit(lists, events, name, index, parse(body)?)
+                })?,
+            ("DELETE", ["lists", name, "items", index]) => {
+                self.change("remove", |lists, events| {
+                    let list = list_mut(lists, name)?;
+                    let path = resolve(list, index)?;
+                    let removed = list.remove(&path).map_err(bad_request)?;
+                    events.push(Event::new(
+                        Action::Remove,
+                        name,
+                        &path,
+                        &removed,
+                    ));
+                    Ok(Response::json(
+                        "200 OK",
+                        &json!({ "removed": removed.title() }),
+                    ))
+                })?
+            }
+            ("POST", ["lists", name, "items", index, "move"]) => self
+                .change("move", |lists, _| {
+                    move_item(lists, name, index, parse(body)?)
+                })?,
+            ("GET", _) => Response::json_error("404 Not Found", "Not found"),
+            _ => Response::json_error(
+                "405 Method Not Allowed",
+                "Method not allowed",
+            ),
+        })
+    }
+
+    // loads the lists under the lock, has `handle` change them and saves
+    // them if it succeeds, as the command line would save `command`
+    fn change(
+        &self,
+        command: &str,
+        handle: impl FnOnce(&mut HashMap<String, TodoList>, &mut Events) -> Handled,
+    ) -> Result<Response> {
+        let started = Instant::now();
+        let _lock = loop {
+            if let Some(lock) = lock::try_acquire(&self.store.todo_file)? {
+                break lock;
+            }
+            if started.elapsed() > LOCK_WAIT {
+                return Ok(Response::json_error(
+                    "503 Service Unavailable",
+                    "The lists are busy; try again in a moment",
+                ));
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        };
+        let mut lists = load(&self.store.todo_file)?;
+        let before = sync::snapshot(&lists)?;
+        let mut events = Vec::new();
+        let response = match handle(&mut lists, &mut events) {
+            Ok(response) => response,
+            Err(response) => return Ok(response),
+        };
+        let change = Change {
+            before: Some(before).filter(|_| self.store.undo_depth > 0),
+            events,
+            archived: Vec::new(),
+        };
+        for message in self.store.commit(command, &mut lists, change, None)? {
+            warn!("{}", message);
+        }
+        Ok(response)
+    }
+}
+
+fn add(
+    lists: &mut HashMap<String, TodoList>,
+    events: &mut Events,
+    name: &str,
+    new: NewItem,
+) -> Handled {
+    let list = list_mut(lists, name)?;
+    let under = match &new.under {
+        Some(index) => resolve(list, index)?,
+        None => ItemPath::root(),
+    };
+    let (date, zone) = match new.date.as_deref().map(date) {
+        Some(parsed) => parsed.map(|(date, zone)| (Some(date), zone))?,
+        None => (None, None),
+    };
+    let mut entry = TodoEntry::from_info(new.title, date);
+    entry.zone = zone;
+    entry.priority = new.priority.as_deref().map(priority).transpose()?;
+    for tag in &new.tags {
+        match tag_name(tag) {
+            Some(tag) if !entry.tags.contains(&tag) => entry.tags.push(tag),
+            Some(_) => {}
+            None => return Err(bad_request(format!("Invalid tag '{}'", tag))),
+        }
+    }
+    list.append(&under, ListItem::Entry(entry))
+        .map_err(bad_request)?;
+    let mut index = under;
+    let siblings = match list.get(&index) {
+        Some(ListItem::List(sublist)) => sublist.items().len(),
+        _ => list.items().len(),
+    };
+    index.push(siblings - 1);
+    if let Some(item) = list.get(&index) {
+        events.push(Event::new(Action::Add, name, &index, item));
+    }
+    Ok(Response::json(
+        "201 Created",
+        &json!({ "list": name, "index": index.to_string() }),
+    ))
+}
+
+fn edit(
+    lists: &mut HashMap<String, TodoList>,
+    events: &mut Events,
+    name: &str,
+    index: &str,
+    changes: Changes,
+) -> Handled {
+    let list = list_mut(lists, name)?;
+    let path = resolve(list, index)?;
+    let priority = match changes.priority {
+        Some(Some(name)) => Some(Some(priority(&name)?)),
+        Some(None) => Some(None),
+        None => None,
+    };
+    let date = match changes.date {
+        Some(Some(text)) => Some(date(&text).map(|(d, z)| (Some(d), z))?),
+        Some(None) => Some((None, None)),
+        None => None,
+    };
+    let item = list.get_mut(&path).unwrap();
+    if let Some(title) = changes.title {
+        item.set_title(title);
+    }
+    if let Some((date, zone)) = date {
+        item.reschedule(date, zone);
+    }
+    if let Some(priority) = priority {
+        item.set_priority(priority);
+    }
+    match changes.done {
+        Some(true) if !item.is_done() => {
+            item.set_completed(Some(clock::now()));
+            events.push(Event::new(Action::Done, name, &path, item));
+        }
+        Some(false) => item.set_completed(None),
+        _ => {}
+    }
+    let item = serde_json::to_value(&*item).map_err(bad_request)?;
+    Ok(Response::json("200 OK", &item))
+}
+
+fn move_item(
+    lists: &mut HashMap<String, TodoList>,
+    name: &str,
+    index: &str,
+    to: Destination,
+) -> Handled {
+    let target = to.list.as_deref().unwrap_or(name);
+    let from = resolve(list_mut(lists, name)?, index)?;
+    let under = match &to.under {
+        Some(under) => resolve(list_mut(lists, target)?, under)?,
+        None => {
+            list_mut(lists, target)?;
+            ItemPath::root()
+        }
+    };
+    let within = target == name;
+    if within && under.starts_with(&from) {
+        return Err(bad_request("Can't move an item into itself"));
+    }
+    let mut item = list_mut(lists, name)?.remove(&from).map_err(bad_request)?;
+    item.touch();
+    let under = if within {
+        under.after_removal(&from)
+    } else {
+        under
+    };
+    let list = list_mut(lists, target)?;
+    list.append(&under, item).map_err(bad_request)?;
+    let mut index = under;
+    let siblings = match list.get(&index) {
+        Some(ListItem::List(sublist)) => sublist.items().len(),
+        _ => list.items().len(),
+    };
+    index.push(siblings - 1);
+    Ok(Response::json(
+        "200 OK",
+        &json!({ "list": target, "index": index.to_string() }),
+    ))
+}
+
+fn parse<'a, T: Deserialize<'a>>(body: &'a [u8]) -> Result<T, Response> {
+    serde_json::from_slice(body)
+        .map_err(|e| bad_request(format!("Invalid request body: {}", e)))
+}
+
+fn not_found(name: &str, lists: &HashMap<String, TodoList>) -> Response {
+    let error = Error::list_not_found(name, lists.keys());
+    Response::json_error("404 Not Found", &error.to_string())
+}
+
+fn list_mut<'a>(
+    lists: &'a mut HashMap<String, TodoList>,
+    name: &str,
+) -> Result<&'a mut TodoList, Response> {
+    if !lists.contains_key(name) {
+        return Err(not_found(name, lists));
+    }
+    Ok(lists.get_mut(name).unwrap())
+}
+
+// the item at `index`, which must be there
+fn resolve(list: &TodoList, index: &str) -> Result<ItemPath, Response> {
+    let path = IndexPath::parse(index)
+        .and_then(|index| index.resolve(list))
+        .map_err(|e| Response::json_error("404 Not Found", &e.to_string()))?;
+    match list.get(&path) {
+        Some(_) => Ok(path),
+        None => Err(Response::json_error(
+            "404 Not Found",
+            &Error::NoItem { index: path }.to_string(),
+        )),
+    }
+}
+
+fn date(
+    text: &str,
+) -> Result<(crate::DateMaybeTime, Option<chrono_tz::Tz>), Response> {
+    natural::parse_zoned(text, clock::today()).ok_or_else(|| {
+        bad_request(Error::ParseDate {
+            text: String::from(text),
+        })
+    })
+}
+
+fn priority(name: &str) -> Result<Priority, Response> {
+    Priority::from_name(name).ok_or_else(|| {
+        bad_request(format!(
+            "Invalid priority '{}' (A-Z, high, medium or low)",
+            name
+        ))
+    })
+}
@@ -0,0 +1,147 @@
+//! The page `later serve --web` serves at `/`: every list, with dates
+//! colored by how soon they're due and a checkbox on each item that marks
+//! it done (or not) through the JSON API. The page is drawn on the server
+//! and reloads itself every minute, so a tablet on the wall stays current
+//! with nothing but a browser.
+
+use crate::export::ansi_html::escape;
+use crate::{date_text, ListItem, Palette, TodoList, Urgency};
+use std::collections::HashMap;
+
+// how often the page reloads, in seconds
+const REFRESH: u32 = 60;
+
+const STYLE: &str = "\
+body { font-family: sans-serif; background: #1e1e1e; color: #d4d4d4;
+       margin: 1em; }
+main { display: flex; flex-wrap: wrap; gap: 1em; align-items: flex-start; }
+section { background: #2a2a2a; border-radius: 6px; padding: 0.5em 1em;
+          min-width: 16em; }
+h2 { font-size: 1.1em; margin: 0.4em 0; }
+ul { list-style: none; padding-left: 1.2em; margin: 0; }
+section > ul { padding-left: 0; }
+li { margin: 0.3em 0; }
+label { cursor: pointer; }
+.date { font-size: 0.9em; margin-left: 0.4em; }
+.overdue { color: #f14c4c; font-weight: bold; }
+.soon { color: #e5e510; }
+.later { color: #23d18b; }
+.done { opacity: 0.5; text-decoration: line-through; }
+.tag { color: #c586c0; font-size: 0.9em; margin-left: 0.4em; }
+";
+
+// sends the checkbox's state as `done`, with the token the page was
+// opened with, then reloads to show the lists as they are now
+const SCRIPT: &str = "\
+const token = new URLSearchParams(location.search).get('token');
+for (const box of document.querySelectorAll('input[data-index]')) {
+  box.addEventListener('change', async () => {
+    box.disabled = true;
+    const headers = { 'Content-Type': 'application/json' };
+    if (token) headers.Authorization = 'Bearer ' + token;
+    const url = '/lists/' + encodeURIComponent(box.dataset.list) +
+      '/items/' + box.dataset.index;
+    const response = await fetch(url, {
+      method: 'PATCH',
+      headers,
+      body: JSON.stringify({ done: box.checked }),
+    });
+    if (!response.ok) alert((await response.json()).error);
+    location.reload();
+  });
+}
+";
+
+fn urgency_class(urgency: Urgency) -> &'static str {
+    match urgency {
+        Urgency::Overdue => "overdue",
+        Urgency::Soon => "soon",
+        Urgency::Later => "later",
+    }
+}
+
+fn write_item(html: &mut String, list: &str, path: &[usize], item: &ListItem) {
+    let done = item.is_done();
+    let index: Vec<String> = path.iter().map(usize::to_string).collect();
+    html.push_str("<li><label><input type=\"checkbox\" data-list=\"");
+    escape(list, html);
+    html.push_str(&format!("\" data-index=\"{}\"", index.join(",")));
+    if done {
+        html.push_str(" checked");
+    }
+    html.push_str(if done {
+        "> <span class=\"done\">"
+    } else {
+        "> <span>"
+    });
+    escape(item.title(), html);
+    html.push_str("</span></label>");
+    if let Some(date) = item.date() {
+        let class = match item.urgency() {
+            Some(urgency) if !done => urgency_class(urgency),
+            _ => "done",
+        };
+        html.push_str(&format!("<span class=\"date {}\">", class));
+        let text = date_text(
+            &date,
+            item.zone(),
+            item.is_deadline(),
+            done,
+            Palette::Default,
+        );
+        escape(&text, html);
+        html.push_str("</span>");
+    }
+    for tag in item.tags() {
+        html.push_str("<span class=\"tag\">#");
+        escape(tag, html);
+        html.push_str("</span>");
+    }
+    if let ListItem::List(sublist) = item {
+        write_items(html, list, path, sublist);
+    }
+    html.push_str("</li>\n");
+}
+
+fn write_items(
+    html: &mut String,
+    list: &str,
+    path: &[usize],
+    items: &TodoList,
+) {
+    if items.items().is_empty() {
+        return;
+    }
+    html.push_str("<ul>\n");
+    for (i, item) in items.items().iter().enumerate() {
+        let mut path = path.to_vec();
+        path.push(i);
+        write_item(html, list, &path, item);
+    }
+    html.push_str("</ul>\n");
+}
+
+/// The dashboard, with the lists in name order.
+pub fn render(lists: &HashMap<String, TodoList>) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, \
+         initial-scale=1\">\n<meta http-equiv=\"refresh\" content=\"{}\">\n\
+         <title>later</title>\n<style>\n{}</style>\n</head>\n<body>\n<main>\n",
+        REFRESH, STYLE
+    );
+    let mut names: Vec<&String> = lists.keys().collect();
+    names.sort();
+    for name in names {
+        html.push_str("<section>\n<h2>");
+        escape(name, &mut html);
+        html.push_str("</h2>\n");
+        write_items(&mut html, name, &[], &lists[name]);
+        html.push_str("</section>\n");
+    }
+    html.push_str(&format!(
+        "</main>\n<script>\n{}</script>\n</body>\n</html>\n",
+        SCRIPT
+    ));
+    html
+}
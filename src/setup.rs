@@ -0,0 +1,106 @@
+//! The questions asked on first run (and by `later setup`), whose answers
+//! become the config file.
+
+use crate::config::Config;
+use anyhow::Result;
+use rustyline::Editor;
+use std::path::{Path, PathBuf};
+
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+// greys for each depth, for those who'd rather not have cyan and blue
+const MUTED: [&str; 2] = ["244", "240"];
+
+/// Whether this looks like the very first run: no config file and no lists.
+pub fn is_first_run(config_path: &Path, todo_file: &Path) -> bool {
+    !config_path.exists() && !todo_file.exists()
+}
+
+// asks `question`, returning `default` for an empty answer
+fn ask(rl: &mut Editor<()>, question: &str, default: &str) -> Result<String> {
+    let answer = rl.readline(&format!("{} [{}]: ", question, default))?;
+    let answer = answer.trim();
+    Ok(String::from(if answer.is_empty() {
+        default
+    } else {
+        answer
+    }))
+}
+
+// asks until the answer is one of `choices`
+fn choose(
+    rl: &mut Editor<()>,
+    question: &str,
+    choices: &[&str],
+    default: usize,
+) -> Result<usize> {
+    let question = format!("{} ({})", question, choices.join("/"));
+    loop {
+        let answer = ask(rl, &question, choices[default])?.to_lowercase();
+        match choices.iter().position(|choice| *choice == answer) {
+            Some(i) => return Ok(i),
+            None => eprintln!("Please answer {}.", choices.join(" or ")),
+        }
+    }
+}
+
+/// Walks through the main settings, starting from `config`, and returns
+/// the updated config. Nothing is written.
+pub fn run(config: &Config, default_data_dir: &Path) -> Result<Config> {
+    let mut rl = Editor::<()>::new();
+    let mut config = config.clone();
+    println!("Setting up later. Press enter to keep the suggestion.");
+
+    let current = config.data_dir()?;
+    let data_dir = ask(
+        &mut rl,
+        "Where should your lists be kept?",
+        &current.display().to_string(),
+    )?;
+    let data_dir = PathBuf::from(data_dir);
+    config.general.data_dir = if data_dir == default_data_dir {
+        None
+    } else {
+        Some(data_dir)
+    };
+
+    let default_list = ask(
+        &mut rl,
+        "What should the default list be called?",
+        config.default_list(),
+    )?;
+    config.general.default_list = if default_list == crate::DEFAULT_LIST {
+        None
+    } else {
+        Some(default_list)
+    };
+
+    let markers = choose(
+        &mut rl,
+        "Mark items with",
+        &["numbers", "bullets"],
+        usize::from(!config.display.bullets.is_empty()),
+    )?;
+    config.display.bullets = match markers {
+        0 => Vec::new(),
+        _ => BULLETS.iter().map(|b| b.to_string()).collect(),
+    };
+    let colors = choose(
+        &mut rl,
+        "Marker colors",
+        &["default", "muted"],
+        usize::from(!config.display.bullet_colors.is_empty()),
+    )?;
+    config.display.bullet_colors = match colors {
+        0 => Vec::new(),
+        _ => MUTED.iter().map(|c| c.to_string()).collect(),
+    };
+
+    let confirm = choose(
+        &mut rl,
+        "Ask before removing things?",
+        &["y", "n"],
+        usize::from(!config.general.confirm),
+    )?;
+    config.general.confirm = confirm == 0;
+    Ok(config)
+}
@@ -0,0 +1,120 @@
+//! `later show`: everything about one item, where the tree has room for a
+//! single line.
+
+use crate::{
+    clock, date_text, tracking, DateMaybeTime, ListItem, RenderOptions,
+};
+use ansi_term::Style;
+use chrono::Duration;
+use std::io::prelude::*;
+
+const TIMESTAMP: &str = "%Y-%m-%d %H:%M";
+
+// e.g. "2026-11-03 09:15 (November 03, 09:15am; in 2 weeks 3 days)"
+fn when(date: &DateMaybeTime, item: &ListItem, opts: &RenderOptions) -> String {
+    let absolute = date.start().format(match date {
+        DateMaybeTime::Date(_) => "%Y-%m-%d",
+        DateMaybeTime::DateTime(_) => TIMESTAMP,
+    });
+    let done = item.is_done();
+    let hard = item.is_deadline();
+    let relative = opts.date_style(date, hard, done).paint(date_text(
+        date,
+        item.zone(),
+        hard,
+        done,
+        opts.palette,
+    ));
+    format!("{} ({})", absolute, relative)
+}
+
+/// Writes the item's title, then its other fields as labelled lines (for a
+/// sublist, how many items it holds and which falls due next), then its
+/// notes in full.
+pub fn write_details(
+    out: &mut impl Write,
+    item: &ListItem,
+    opts: &RenderOptions,
+) -> std::io::Result<()> {
+    let mut fields: Vec<(&str, String)> = Vec::new();
+    if let Some(date) = item.date() {
+        fields.push(("due", when(&date, item, opts)));
+    }
+    if let Some(priority) = item.priority() {
+        fields.push(("priority", priority.to_string()));
+    }
+    if !item.tags().is_empty() {
+        let tags: Vec<String> =
+            item.tags().iter().map(|tag| format!("#{}", tag)).collect();
+        fields.push(("tags", tags.join(" ")));
+    }
+    if let Some(created) = item.created_at() {
+        fields.push(("created", created.format(TIMESTAMP).to_string()));
+    }
+    // only worth a line once it differs from when the item was added
+    if let Some(modified) =
+        item.modified_at().filter(|&m| Some(m) != item.created_at())
+    {
+        fields.push(("modified", modified.format(TIMESTAMP).to_string()));
+    }
+    if let Some(completed) = item.completed_at() {
+        fields.push(("done", completed.format(TIMESTAMP).to_string()));
+    }
+    let slips = item.rescheduled().iter().filter(|r| r.slip().is_some());
+    match slips.count() {
+        0 => {}
+        n => fields.push(("slipped", format!("{} times", n))),
+    }
+    if item.is_focused() {
+        fields.push(("focus", String::from("pinned")));
+    }
+    let now = clock::now();
+    let tracked = item.tracked().iter().fold(Duration::zero(), |sum, i| {
+        sum + (i.end.unwrap_or(now) - i.start)
+    });
+    if tracked > Duration::zero() {
+        fields.push(("tracked", tracking::hours(tracked)));
+    }
+    if let ListItem::List(list) = item {
+        let all = list.items_with_paths();
+        let finished = all.iter().filter(|(_, item)| item.is_done()).count();
+        fields.push((
+            "items",
+            format!(
+                "{} ({} in all, {} done)",
+                list.items().len(),
+                all.len(),
+                finished
+            ),
+        ));
+        // the soonest thing still to do inside it
+        let next = all
+            .iter()
+            .filter(|(_, item)| !item.is_done())
+            .filter_map(|(_, item)| item.date().map(|date| (date, *item)))
+            .min_by_key(|(date, _)| date.start());
+        if let Some((date, next)) = next {
+            fields.push((
+                "next",
+                format!("{}: {}", next.title(), when(&date, next, opts)),
+            ));
+        }
+    }
+
+    let heading = opts.style(match item {
+        ListItem::List(_) => Style::new().bold().underline(),
+        ListItem::Entry(_) => Style::new().bold(),
+    });
+    writeln!(out, "{}", heading.paint(item.title()))?;
+    let width = fields.iter().map(|(label, _)| label.len()).max();
+    let dimmed = opts.style(Style::new().dimmed());
+    for (label, value) in &fields {
+        let label = format!("{:<w$}", label, w = width.unwrap_or(0));
+        writeln!(out, "{}  {}", dimmed.paint(label), value)?;
+    }
+    if let Some(notes) = item.notes() {
+        writeln!(out)?;
+        writeln!(out, "{}", notes.trim_end())?;
+    }
+    Ok(())
+}
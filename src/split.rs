@@ -0,0 +1,126 @@
+//! `later split`: breaking an oversized sublist up into several smaller
+//! ones, by month, by tag or into runs of so many items.
+
+use crate::i18n::Lang;
+use crate::{ListItem, TodoList};
+use chrono::prelude::*;
+
+/// How items are shared out among the new sublists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// One sublist per month of the items' dates, undated items last.
+    Date,
+    /// One sublist per item's first tag, untagged items last.
+    Tag,
+    /// Runs of this many items.
+    Count(usize),
+}
+
+impl Rule {
+    pub const NAMES: &'static [&'static str] = &["date", "tag", "count"];
+
+    /// Reads `date`, `tag` or `count` with its size.
+    pub fn from_name(name: &str, size: Option<usize>) -> Option<Rule> {
+        match (name, size) {
+            ("date", None) => Some(Rule::Date),
+            ("tag", None) => Some(Rule::Tag),
+            ("count", Some(size)) if size > 0 => Some(Rule::Count(size)),
+            _ => None,
+        }
+    }
+}
+
+// which part an item goes in; parts come out in the order of their keys
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Key {
+    Month(i32, u32),
+    // tags keep the order they first turn up in
+    Tag(usize, String),
+    Run(usize),
+    Rest,
+}
+
+impl Key {
+    fn label(&self, rule: Rule, lang: Lang, runs: usize) -> String {
+        match self {
+            Key::Month(year, month) => {
+                format!("{} {}", lang.month(*month), year)
+            }
+            Key::Tag(_, tag) => format!("#{}", tag),
+            Key::Run(n) => format!("{}/{}", n + 1, runs),
+            Key::Rest if rule == Rule::Date => String::from("no date"),
+            Key::Rest => String::from("untagged"),
+        }
+    }
+}
+
+/// Shares out the items of `list` by `rule`, keeping their order within
+/// each part. Every part keeps the list's date, notes, tags and so on,
+/// with what it holds added to the title, e.g. "Reading (March 2024)".
+pub fn split(list: TodoList, rule: Rule) -> Vec<TodoList> {
+    let mut tags: Vec<String> = Vec::new();
+    let keyed: Vec<(Key, ListItem)> = list
+        .list
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let key = match rule {
+                Rule::Date => match item.date() {
+                    Some(date) => {
+                        let day = date.start().date();
+                        Key::Month(day.year(), day.month())
+                    }
+                    None => Key::Rest,
+                },
+                Rule::Tag => match item.tags().first() {
+                    Some(tag) => {
+                        let n = tags.iter().position(|t| t == tag);
+                        let n = n.unwrap_or_else(|| {
+                            tags.push(tag.clone());
+                            tags.len() - 1
+                        });
+                        Key::Tag(n, tag.clone())
+                    }
+                    None => Key::Rest,
+                },
+                Rule::Count(size) => Key::Run(i / size),
+            };
+            (key, item)
+        })
+        .collect();
+    let runs = match rule {
+        Rule::Count(size) => keyed.len().div_ceil(size),
+        _ => 0,
+    };
+
+    let mut keys: Vec<Key> = keyed.iter().map(|(key, _)| key.clone()).collect();
+    keys.sort();
+    keys.dedup();
+    let lang = Lang::current();
+    let mut parts: Vec<TodoList> = keys
+        .iter()
+        .map(|key| TodoList {
+            title: format!("{} ({})", list.title, key.label(rule, lang, runs)),
+            date: list.date,
+            created_at: list.created_at,
+            modified_at: list.modified_at,
+            notes: list.notes.clone(),
+            completed_at: list.completed_at,
+            rescheduled: list.rescheduled.clone(),
+            deadline: list.deadline,
+            focused: list.focused,
+            priority: list.priority,
+            tags: list.tags.clone(),
+            zone: list.zone,
+            tracked: Vec::new(),
+            list: Vec::new(),
+        })
+        .collect();
+    // time spent on the list as a whole stays with its first part
+    parts[0].tracked = list.tracked;
+    for (key, item) in keyed {
+        let part = keys.binary_search(&key).unwrap();
+        parts[part].list.push(item);
+    }
+    parts
+}
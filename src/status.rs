@@ -0,0 +1,63 @@
+//! The one-line due summary, e.g. "3 overdue, 2 due today": all that
+//! `later status` prints, and shown above the list when `display.summary`
+//! is on.
+
+use crate::{clock, RenderOptions, TodoList, Urgency};
+use std::collections::HashMap;
+use std::io::prelude::*;
+
+/// How many items not yet done are past their date, or due later today,
+/// across every list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub overdue: usize,
+    pub today: usize,
+}
+
+impl Summary {
+    pub fn of(lists: &HashMap<String, TodoList>) -> Summary {
+        let today = clock::today();
+        let mut summary = Summary {
+            overdue: 0,
+            today: 0,
+        };
+        for list in lists.values() {
+            for (_, item) in list.items_with_paths() {
+                let date = match item.date() {
+                    Some(date) if !item.is_done() => date,
+                    _ => continue,
+                };
+                if date.urgency() == Urgency::Overdue {
+                    summary.overdue += 1;
+                } else if date.start().date() == today {
+                    summary.today += 1;
+                }
+            }
+        }
+        summary
+    }
+}
+
+/// Writes the summary on a line of its own, colored like the dates it
+/// counts; "nothing due today" when there's nothing to count.
+pub fn write_summary(
+    out: &mut impl Write,
+    summary: &Summary,
+    opts: &RenderOptions,
+) -> std::io::Result<()> {
+    let mut parts = Vec::new();
+    if summary.overdue > 0 {
+        let style = opts.style(opts.theme.urgency(Urgency::Overdue));
+        let text = format!("{} overdue", summary.overdue);
+        parts.push(style.paint(text).to_string());
+    }
+    if summary.today > 0 {
+        let style = opts.style(opts.theme.urgency(Urgency::Soon));
+        let text = format!("{} due today", summary.today);
+        parts.push(style.paint(text).to_string());
+    }
+    if parts.is_empty() {
+        parts.push(String::from("nothing due today"));
+    }
+    writeln!(out, "{}", parts.join(", "))
+}
@@ -0,0 +1,115 @@
+//! Saving changed lists along with everything a change sets off: the
+//! configured limits, the undo history, the archive, the git auto-commit
+//! and the webhooks. The command line and `later serve` both save through
+//! here, so a change is handled the same whichever made it.
+
+use crate::config::{Config, Limit, Webhook};
+use crate::history::History;
+use crate::{archive, diff, git, limits, save, sync, webhook, TodoList};
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Where the lists live, and what the config asks of each change to them.
+#[derive(Debug, Clone)]
+pub struct Store {
+    pub todo_folder: PathBuf,
+    pub todo_file: PathBuf,
+    pub undo_depth: usize,
+    /// How many old versions of the lists file to keep.
+    pub backups: usize,
+    limits: BTreeMap<String, Limit>,
+    webhooks: Vec<Webhook>,
+    // commit the lists file to git after saving
+    auto_commit: bool,
+}
+
+/// What a change did besides changing the lists, kept until they're saved.
+#[derive(Debug, Default)]
+pub struct Change {
+    /// The lists as they were, for `later undo`.
+    pub before: Option<sync::Snapshot>,
+    /// Sent to the webhooks once the lists are saved.
+    pub events: Vec<webhook::Event>,
+    /// Items taken out, written to the archive once the lists are saved.
+    pub archived: Vec<archive::Archived>,
+}
+
+impl Store {
+    pub fn new(todo_file: &Path, config: &Config) -> Store {
+        Store {
+            todo_folder: todo_file
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+            todo_file: todo_file.to_path_buf(),
+            undo_depth: config.general.undo_depth,
+            backups: config.general.backups,
+            limits: config.limits.clone(),
+            webhooks: config.webhooks.clone(),
+            auto_commit: config.sync.auto_commit,
+        }
+    }
+
+    /// Whether any webhooks want events, which are only worth making then.
+    pub fn wants_events(&self) -> bool {
+        !self.webhooks.is_empty()
+    }
+
+    /// Saves `lists` as changed by `command`, or for a dry run shows how
+    /// they differ from `snapshot`. The lists are saved before the archive,
+    /// git and the webhooks are seen to, so their failures come back as
+    /// messages, along with notices of anything the limits took out.
+    pub fn commit(
+        &self,
+        command: &str,
+        lists: &mut HashMap<String, TodoList>,
+        change: Change,
+        snapshot: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let mut messages = limits::enforce(lists, &self.limits);
+        if snapshot.is_some() {
+            commit(&self.todo_file, lists, snapshot, self.backups)?;
+            return Ok(messages);
+        }
+        if let Some(before) = change.before {
+            let path = self.todo_folder.join("history.json");
+            let mut history = History::load(&path)?;
+            history.record(command, before, self.undo_depth);
+            history.save(&path)?;
+        }
+        save(&self.todo_file, lists, self.backups)?;
+        archive::append(&archive::path(&self.todo_folder), change.archived)?;
+        if self.auto_commit {
+            let message = format!("later {}", command);
+            if let Err(e) = git::commit(&self.todo_folder, &message) {
+                messages.push(format!("Couldn't commit the lists: {:#}", e));
+            }
+        }
+        for event in &change.events {
+            messages.extend(webhook::fire(&self.webhooks, event));
+        }
+        Ok(messages)
+    }
+}
+
+/// Saves the lists, or for a dry run shows how they differ from the
+/// snapshot, without the rest of what [`Store::commit`] does.
+pub fn commit(
+    todo_file: &Path,
+    lists: &HashMap<String, TodoList>,
+    snapshot: Option<&str>,
+    backups: usize,
+) -> Result<()> {
+    match snapshot {
+        Some(before) => {
+            let after = serde_json::to_string_pretty(lists)?;
+            if !diff::write_diff(&mut std::io::stdout(), before, &after)? {
+                println!("(no changes)");
+            }
+            eprintln!("Dry run, nothing was saved.");
+            Ok(())
+        }
+        None => Ok(save(todo_file, lists, backups)?),
+    }
+}
@@ -0,0 +1,274 @@
+//! Keeping the lists in step across machines through a shared remote copy.
+//!
+//! Each sync is a three-way merge, one whole list at a time, against the
+//! copy both sides agreed on last time (the base). A list changed on only
+//! one side takes that side's version; a list changed on both is a conflict,
+//! which keeps the local version and holds on to the remote one until it's
+//! resolved.
+
+use crate::config::Remote;
+use crate::{crypt, migrate, TodoList};
+use anyhow::{Context, Result};
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Lists by name, in their serialized form, so that versions of a list can
+/// be compared without caring what's inside.
+pub type Snapshot = HashMap<String, Value>;
+
+/// Somewhere a copy of the lists can be read from and written to.
+pub trait Backend {
+    /// A short description for messages, e.g. "directory /mnt/share".
+    fn describe(&self) -> String;
+    /// The remote copy, or `None` if nothing has been pushed yet.
+    fn fetch(&self) -> Result<Option<String>>;
+    /// Replaces the remote copy. Backends that can tell refuse if it
+    /// changed after the last `fetch`.
+    fn push(&self, data: &str) -> Result<()>;
+}
+
+/// The backend for `remote`; a git remote goes through a repository in
+/// `data_dir`.
+pub fn backend(remote: &Remote, data_dir: &Path) -> Box<dyn Backend> {
+    match remote {
+        Remote::Directory { path } => Box::new(Directory::new(path)),
+        Remote::S3 {
+            bucket,
+            prefix,
+            region,
+            endpoint,
+        } => Box::new(crate::s3::S3::new(
+            bucket,
+            prefix.as_deref(),
+            region.as_deref(),
+            endpoint.as_deref(),
+        )),
+        Remote::Git { url, branch } => Box::new(crate::git::Git::new(
+            data_dir,
+            url,
+            branch.as_deref().unwrap_or("main"),
+        )),
+    }
+}
+
+/// A `later.json` in a directory that is shared by other means.
+pub struct Directory {
+    path: PathBuf,
+}
+
+impl Directory {
+    pub fn new(path: &Path) -> Directory {
+        Directory {
+            path: crate::config::expand_home(path),
+        }
+    }
+
+    fn file(&self) -> PathBuf {
+        self.path.join("later.json")
+    }
+}
+
+impl Backend for Directory {
+    fn describe(&self) -> String {
+        format!("directory {}", self.path.display())
+    }
+
+    fn fetch(&self) -> Result<Option<String>> {
+        match std::fs::read_to_string(self.file()) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| {
+                format!("Couldn't read {}", self.file().display())
+            }),
+        }
+    }
+
+    fn push(&self, data: &str) -> Result<()> {
+        std::fs::create_dir_all(&self.path).with_context(|| {
+            format!("Couldn't create {}", self.path.display())
+        })?;
+        // write next to the target and rename, so readers on other machines
+        // never see half a file
+        let temp = self.path.join(".later.json.tmp");
+        std::fs::write(&temp, data)
+            .and_then(|_| std::fs::rename(&temp, self.file()))
+            .with_context(|| {
+                format!("Couldn't write {}", self.file().display())
+            })
+    }
+}
+
+/// What this machine remembers between syncs, kept next to the lists.
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct State {
+    pub last_sync: Option<DateTime<Utc>>,
+    /// The lists as of the last sync, on both sides.
+    pub base: Snapshot,
+    /// Remote versions of lists that were changed on both sides, waiting
+    /// for `later sync resolve`.
+    pub conflicts: Snapshot,
+}
+
+impl State {
+    pub fn load(path: &Path) -> Result<State> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&crypt::open(&text)?)
+                .with_context(|| {
+                    format!("Couldn't parse sync state ({})", path.display())
+                }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Ok(State::default())
+            }
+            Err(e) => Err(e).with_context(|| {
+                format!("Couldn't read sync state ({})", path.display())
+            }),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let text = crypt::seal(&serde_json::to_string_pretty(self)?)?;
+        std::fs::write(path, text).with_context(|| {
+            format!("Couldn't write sync state ({})", path.display())
+        })
+    }
+}
+
+pub fn snapshot(lists: &HashMap<String, TodoList>) -> Result<Snapshot> {
+    lists
+        .iter()
+        .map(|(name, list)| Ok((name.clone(), serde_json::to_value(list)?)))
+        .collect()
+}
+
+/// Parses a remote copy, which has the same layout as the local file, and
+/// is upgraded the same way if an older version of later pushed it, or
+/// opened if it's encrypted.
+pub fn parse(data: &str) -> Result<Snapshot> {
+    let lists = migrate::parse(&crypt::open(data)?)
+        .context("Couldn't parse the remote copy")?;
+    snapshot(&lists)
+}
+
+/// The lists as a remote copy, encrypted if the local file is.
+pub fn to_remote(lists: &HashMap<String, TodoList>) -> Result<String> {
+    Ok(crypt::seal(&migrate::to_string(lists)?)?)
+}
+
+pub fn to_lists(snapshot: Snapshot) -> Result<HashMap<String, TodoList>> {
+    snapshot
+        .into_iter()
+        .map(|(name, value)| Ok((name, serde_json::from_value(value)?)))
+        .collect()
+}
+
+/// How one list differs from its base version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    /// Counts of items that appeared and disappeared; both are zero when
+    /// items were only reordered or nested differently.
+    Modified {
+        added: usize,
+        removed: usize,
+    },
+}
+
+/// Every list in `current` that differs from `base`, by name.
+pub fn changes(base: &Snapshot, current: &Snapshot) -> Vec<(String, Change)> {
+    let names: BTreeSet<&String> = base.keys().chain(current.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let change = match (base.get(name), current.get(name)) {
+                (None, Some(_)) => Change::Added,
+                (Some(_), None) => Change::Removed,
+                (Some(old), Some(new)) if old != new => {
+                    let (old, new) = (items(old), items(new));
+                    Change::Modified {
+                        added: count_missing(&new, &old),
+                        removed: count_missing(&old, &new),
+                    }
+                }
+                _ => return None,
+            };
+            Some((name.clone(), change))
+        })
+        .collect()
+}
+
+// every entry and sublist, flattened to its title and date
+fn items(list: &Value) -> Vec<String> {
+    let mut found = Vec::new();
+    for item in list["list"].as_array().into_iter().flatten() {
+        let (kind, inner) = match item.as_object().and_then(|o| o.iter().next())
+        {
+            Some(pair) => pair,
+            None => continue,
+        };
+        found.push(format!("{}\t{}\t{}", kind, inner["title"], inner["date"]));
+        if kind == "List" {
+            found.extend(items(inner));
+        }
+    }
+    found
+}
+
+// how many of `items` have no counterpart in `other`, duplicates included
+fn count_missing(items: &[String], other: &[String]) -> usize {
+    let mut other = other.to_vec();
+    items
+        .iter()
+        .filter(|item| match other.iter().position(|o| o == *item) {
+            Some(i) => {
+                other.swap_remove(i);
+                false
+            }
+            None => true,
+        })
+        .count()
+}
+
+/// The outcome of merging both sides of a sync.
+#[derive(Debug, Default)]
+pub struct Merge {
+    pub lists: Snapshot,
+    /// Names of lists taken from the remote side, including deletions.
+    pub pulled: Vec<String>,
+    /// Remote versions of lists changed on both sides.
+    pub conflicts: Snapshot,
+}
+
+pub fn merge(base: &Snapshot, local: &Snapshot, remote: &Snapshot) -> Merge {
+    let mut merge = Merge {
+        lists: local.clone(),
+        ..Merge::default()
+    };
+    for (name, _) in changes(base, remote) {
+        let remote_version = remote.get(name.as_str());
+        if local.get(&name) == base.get(&name) {
+            match remote_version {
+                Some(list) => merge.lists.insert(name.clone(), list.clone()),
+                None => merge.lists.remove(&name),
+            };
+            merge.pulled.push(name);
+        } else if local.get(&name) != remote_version {
+            // deleted on one side and edited on the other keeps the edits
+            match (local.get(&name), remote_version) {
+                (None, Some(list)) => {
+                    merge.lists.insert(name.clone(), list.clone());
+                    merge.pulled.push(name);
+                }
+                (Some(_), Some(list)) => {
+                    merge.conflicts.insert(name, list.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+    merge
+}
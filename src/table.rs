@@ -0,0 +1,208 @@
+use crate::{date_text, display_width, ListItem, RenderOptions, TodoList};
+use ansi_term::Style;
+use std::io::prelude::*;
+use unicode_width::UnicodeWidthChar;
+
+const GAP: &str = "  ";
+// titles never shrink below this, even if it means overflowing
+const MIN_TITLE: usize = 10;
+
+struct Row<'a> {
+    index: String,
+    depth: usize,
+    item: &'a ListItem,
+    due: String,
+    tags: String,
+}
+
+fn status(item: &ListItem) -> &'static str {
+    if item.is_done() {
+        "done"
+    } else {
+        "open"
+    }
+}
+
+// `text` padded out to `width` columns once it's been painted
+fn pad(text: &str, width: usize) -> String {
+    " ".repeat(width.saturating_sub(display_width(text)))
+}
+
+fn collect_rows<'a>(
+    list: &'a TodoList,
+    prefix: &str,
+    depth: usize,
+    opts: &RenderOptions,
+    rows: &mut Vec<Row<'a>>,
+) {
+    opts.within(list, || collect_items(list, prefix, depth, opts, rows))
+}
+
+fn collect_items<'a>(
+    list: &'a TodoList,
+    prefix: &str,
+    depth: usize,
+    opts: &RenderOptions,
+    rows: &mut Vec<Row<'a>>,
+) {
+    for (i, item) in list.list.iter().enumerate() {
+        if !opts.shows(item) {
+            continue;
+        }
+        let index = if prefix.is_empty() {
+            i.to_string()
+        } else {
+            format!("{},{}", prefix, i)
+        };
+        rows.push(Row {
+            index: index.clone(),
+            depth,
+            item,
+            due: item
+                .date()
+                .map(|d| {
+                    date_text(
+                        &d,
+                        item.zone(),
+                        item.is_deadline(),
+                        item.is_done(),
+                        opts.palette,
+                    )
+                })
+                .unwrap_or_default(),
+            tags: item
+                .tags()
+                .iter()
+                .map(|tag| format!("#{}", tag))
+                .collect::<Vec<_>>()
+                .join(" "),
+        });
+        if let ListItem::List(sublist) = item {
+            collect_rows(sublist, &index, depth + 1, opts, rows);
+        }
+    }
+}
+
+// cuts `s` down to at most `width` columns and pads it out to exactly that,
+// never splitting a wide character
+pub(crate) fn fit(s: &str, width: usize) -> String {
+    let mut fitted = String::new();
+    let mut used = 0;
+    if display_width(s) <= width {
+        fitted.push_str(s);
+        used = display_width(s);
+    } else {
+        for c in s.chars() {
+            let w = c.width().unwrap_or(0);
+            if used + w + 1 > width {
+                break;
+            }
+            fitted.push(c);
+            used += w;
+        }
+        fitted.push('…');
+        used += 1;
+    }
+    fitted.push_str(&" ".repeat(width.saturating_sub(used)));
+    fitted
+}
+
+/// Writes the list as aligned columns, one row per item with its full index
+/// path, which suits flat or shallow lists better than the tree.
+pub fn write_table(
+    list: &TodoList,
+    out: &mut impl Write,
+    opts: &RenderOptions,
+) -> std::io::Result<()> {
+    let mut rows = Vec::new();
+    collect_rows(list, "", 0, opts, &mut rows);
+
+    let index_width = rows
+        .iter()
+        .map(|r| r.index.len())
+        .chain(std::iter::once(1))
+        .max()
+        .unwrap();
+    let due_width = rows
+        .iter()
+        .map(|r| display_width(&r.due))
+        .max()
+        .filter(|&w| w > 0)
+        .map(|w| w.max("DUE".len()));
+    let tags_width = rows
+        .iter()
+        .map(|r| display_width(&r.tags))
+        .chain(std::iter::once("TAGS".len()))
+        .max()
+        .unwrap();
+    let status_width = "STATUS".len();
+    let title_of =
+        |r: &Row| format!("{}{}", "  ".repeat(r.depth), r.item.title());
+    let mut title_width = rows
+        .iter()
+        .map(|r| display_width(&title_of(r)))
+        .chain(std::iter::once(5))
+        .max()
+        .unwrap();
+    if let Some(width) = opts.width {
+        let rest = index_width
+            + GAP.len()
+            + due_width.map_or(0, |w| w + GAP.len())
+            + tags_width
+            + GAP.len()
+            + status_width
+            + GAP.len();
+        title_width =
+            title_width.min(width.saturating_sub(rest).max(MIN_TITLE));
+    }
+
+    let heading = opts.style(Style::new().bold().underline());
+    write!(
+        out,
+        "{}{}",
+        heading.paint(format!("{:<iw$}", "#", iw = index_width)),
+        GAP
+    )?;
+    write!(out, "{}", heading.paint(fit("TITLE", title_width)))?;
+    if let Some(width) = due_width {
+        write!(out, "{}{}", GAP, heading.paint(fit("DUE", width)))?;
+    }
+    write!(out, "{}{}", GAP, heading.paint(fit("TAGS", tags_width)))?;
+    write!(out, "{}{}", GAP, heading.paint("STATUS"))?;
+    writeln!(out)?;
+
+    for row in &rows {
+        let padded = fit(&title_of(row), title_width);
+        let style = match row.item {
+            ListItem::List(_) => Style::new().underline(),
+            ListItem::Entry(_) => Style::new(),
+        };
+        let done = row.item.is_done();
+        let title = opts.title_style(style, done).paint(padded);
+        write!(out, "{:<iw$}{}{}", row.index, GAP, title, iw = index_width)?;
+        if let Some(width) = due_width {
+            let style = match row.item.date() {
+                Some(datemaybe) => {
+                    opts.date_style(&datemaybe, row.item.is_deadline(), done)
+                }
+                None => Style::new(),
+            };
+            let padding = pad(&row.due, width);
+            write!(out, "{}{}{}", GAP, style.paint(&row.due), padding)?;
+        }
+        let tag_style = if done {
+            Style::new().dimmed()
+        } else {
+            opts.theme.tag
+        };
+        let tags = if row.tags.is_empty() {
+            String::new()
+        } else {
+            opts.style(tag_style).paint(&row.tags).to_string()
+        };
+        write!(out, "{}{}{}", GAP, tags, pad(&row.tags, tags_width))?;
+        write!(out, "{}{}", GAP, status(row.item))?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
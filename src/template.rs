@@ -0,0 +1,264 @@
+//! Templates: titles such as "Review {week} metrics", whose variables are
+//! filled in when the item is created, and whole saved subtrees (a release
+//! checklist, say) that can be stamped out again as fresh sublists.
+
+use crate::i18n::Lang;
+use crate::{crypt, DateMaybeTime, ListItem, TodoEntry, TodoList};
+use anyhow::{bail, Context, Result};
+use chrono::{prelude::*, Duration};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Variables a title template can use, as of `date`.
+pub const VARIABLES: &[&str] =
+    &["date", "day", "weekday", "week", "month", "year"];
+
+fn variable(name: &str, date: NaiveDate) -> Option<String> {
+    let lang = Lang::current();
+    Some(match name {
+        "date" => date.format("%Y/%m/%d").to_string(),
+        "day" => date.day().to_string(),
+        "weekday" => lang.weekday(date.weekday()).to_string(),
+        // ISO 8601 week number, as calendars show it
+        "week" => date.iso_week().week().to_string(),
+        "month" => lang.month(date.month()).to_string(),
+        "year" => date.year().to_string(),
+        _ => return None,
+    })
+}
+
+/// Replaces each `{variable}` in `template`; `{{` and `}}` stand for
+/// literal braces.
+pub fn expand(template: &str, date: NaiveDate) -> Result<String> {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let name: String =
+                    chars.by_ref().take_while(|&c| c != '}').collect();
+                match variable(name.trim(), date) {
+                    Some(value) => out.push_str(&value),
+                    None => bail!(
+                        "Unknown template variable '{{{}}}' (available: {})",
+                        name,
+                        VARIABLES.join(", ")
+                    ),
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+/// A saved item and everything under it, with dates kept as offsets from
+/// an anchor day so that each copy lands relative to when it's made.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Template {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<Offset>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Template>,
+}
+
+/// A date as a number of days from the anchor, plus a time of day for
+/// timed items. Written as `T-7d`, `T+1w` or `T` (the anchor day itself),
+/// optionally followed by a time, e.g. `T-1d 09:00`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Offset {
+    pub days: i64,
+    pub time: Option<NaiveTime>,
+}
+
+impl std::fmt::Display for Offset {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.days {
+            0 => write!(f, "T")?,
+            days => write!(f, "T{:+}d", days)?,
+        }
+        match self.time {
+            Some(time) => write!(f, " {}", time.format("%H:%M")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::str::FromStr for Offset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Offset> {
+        let invalid =
+            || anyhow::anyhow!("Invalid offset '{}' (expected e.g. T-7d)", s);
+        let (day, time) = match s.trim().split_once(' ') {
+            Some((day, time)) => (
+                day,
+                Some(
+                    NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                        .map_err(|_| invalid())?,
+                ),
+            ),
+            None => (s.trim(), None),
+        };
+        let rest = day.strip_prefix('T').ok_or_else(invalid)?;
+        let days = if rest.is_empty() {
+            0
+        } else {
+            let (count, unit) = rest.split_at(rest.len() - 1);
+            if !count.starts_with(['+', '-']) {
+                return Err(invalid());
+            }
+            let count: i64 = count.parse().map_err(|_| invalid())?;
+            match unit {
+                "d" => count,
+                "w" => count * 7,
+                _ => return Err(invalid()),
+            }
+        };
+        Ok(Offset { days, time })
+    }
+}
+
+impl TryFrom<String> for Offset {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Offset> {
+        s.parse()
+    }
+}
+
+impl From<Offset> for String {
+    fn from(offset: Offset) -> String {
+        offset.to_string()
+    }
+}
+
+impl Offset {
+    pub fn between(anchor: NaiveDate, date: DateMaybeTime) -> Offset {
+        let (day, time) = match date {
+            DateMaybeTime::Date(date) => (date, None),
+            DateMaybeTime::DateTime(datetime) => {
+                (datetime.naive_local().date(), Some(datetime.time()))
+            }
+        };
+        Offset {
+            days: day.signed_duration_since(anchor).num_days(),
+            time,
+        }
+    }
+
+    pub fn from(&self, anchor: NaiveDate) -> Option<DateMaybeTime> {
+        let day = anchor + Duration::days(self.days);
+        match self.time {
+            None => Some(DateMaybeTime::Date(day)),
+            Some(time) => Local
+                .from_local_datetime(&day.and_time(time))
+                .earliest()
+                .map(DateMaybeTime::DateTime),
+        }
+    }
+}
+
+impl Template {
+    /// Captures `item`, measuring dates from its own date, or from `today`
+    /// if it has none.
+    pub fn from_item(item: &ListItem, today: NaiveDate) -> Template {
+        Template::capture(item, anchor_of(item, today))
+    }
+
+    fn capture(item: &ListItem, anchor: NaiveDate) -> Template {
+        let children = match item {
+            ListItem::Entry(_) => Vec::new(),
+            ListItem::List(list) => list
+                .list
+                .iter()
+                .map(|child| Template::capture(child, anchor))
+                .collect(),
+        };
+        let notes = item.notes().map(String::from);
+        Template {
+            title: item.title().to_string(),
+            offset: item.date().map(|date| Offset::between(anchor, date)),
+            notes,
+            children,
+        }
+    }
+
+    /// A fresh copy of the saved items, with dates measured from `anchor`.
+    pub fn instantiate(&self, anchor: NaiveDate) -> ListItem {
+        let date = self.offset.and_then(|offset| offset.from(anchor));
+        if self.children.is_empty() {
+            let mut entry = TodoEntry::from_info(self.title.clone(), date);
+            entry.notes = self.notes.clone();
+            return ListItem::Entry(entry);
+        }
+        let mut list = TodoList::from_info(self.title.clone(), date);
+        list.notes = self.notes.clone();
+        list.list = self
+            .children
+            .iter()
+            .map(|child| child.instantiate(anchor))
+            .collect();
+        ListItem::List(list)
+    }
+
+    /// How many items the template makes, itself included.
+    pub fn count(&self) -> usize {
+        1 + self.children.iter().map(Template::count).sum::<usize>()
+    }
+}
+
+/// The day that dates under `item` are measured from: its own date, or
+/// `today` if it has none.
+pub fn anchor_of(item: &ListItem, today: NaiveDate) -> NaiveDate {
+    match item.date() {
+        Some(DateMaybeTime::Date(date)) => date,
+        Some(DateMaybeTime::DateTime(datetime)) => {
+            datetime.naive_local().date()
+        }
+        None => today,
+    }
+}
+
+/// A copy of `item` and everything under it, moved so that its own date
+/// (or `today`, if it has none) lands on `anchor`.
+pub fn copy(item: &ListItem, today: NaiveDate, anchor: NaiveDate) -> ListItem {
+    Template::from_item(item, today).instantiate(anchor)
+}
+
+/// Saved templates by name, from `templates.json` next to the lists.
+pub fn load(path: &Path) -> Result<BTreeMap<String, Template>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => {
+            serde_json::from_str(&crypt::open(&text)?).with_context(|| {
+                format!("Couldn't parse templates ({})", path.display())
+            })
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Ok(BTreeMap::new())
+        }
+        Err(e) => Err(e).with_context(|| {
+            format!("Couldn't read templates ({})", path.display())
+        }),
+    }
+}
+
+pub fn save(path: &Path, templates: &BTreeMap<String, Template>) -> Result<()> {
+    let text = crypt::seal(&serde_json::to_string_pretty(templates)?)?;
+    std::fs::write(path, text).with_context(|| {
+        format!("Couldn't write templates ({})", path.display())
+    })
+}
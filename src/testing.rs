@@ -0,0 +1,296 @@
+//! Property testing for code built on later, with the `testing` feature:
+//! [`proptest`] strategies for lists of any shape, an in-memory [`Store`]
+//! that applies [`Op`]s the way the commands do, and checks for what
+//! should hold after any of them, e.g.
+//!
+//! ```ignore
+//! use later::testing::{self, Op, Store};
+//!
+//! proptest! {
+//!     #[test]
+//!     fn moves_keep_items(list in testing::list(), ops in testing::ops(20)) {
+//!         let mut store = Store::with_list("l", list);
+//!         let before = testing::titles(&store.lists["l"]);
+//!         for op in &ops {
+//!             if let Op::Move { .. } = op {
+//!                 let _ = store.run("l", op);
+//!             }
+//!         }
+//!         prop_assert_eq!(testing::titles(&store.lists["l"]), before);
+//!         prop_assert!(testing::invariants(&store.lists["l"]).is_ok());
+//!     }
+//! }
+//! ```
+
+use crate::error::{Error, Result};
+use crate::{
+    DateMaybeTime, ItemPath, ListItem, SortBy, TieBreak, TodoEntry, TodoList,
+};
+use chrono::prelude::*;
+use chrono::Duration;
+use proptest::collection::{hash_map, vec};
+use proptest::option;
+use proptest::prelude::*;
+use std::collections::HashMap;
+
+/// Days from 2020 through 2029.
+pub fn day() -> impl Strategy<Value = NaiveDate> {
+    let first = NaiveDate::from_ymd(2020, 1, 1);
+    (0..3653i64).prop_map(move |days| first + Duration::days(days))
+}
+
+/// Dates from 2020 through 2029, with or without a time of day.
+pub fn date() -> impl Strategy<Value = DateMaybeTime> {
+    let first = NaiveDate::from_ymd(2020, 1, 1);
+    prop_oneof![
+        day().prop_map(DateMaybeTime::Date),
+        (0..3653 * 1440i64).prop_map(move |minutes| {
+            let at = first.and_hms(0, 0, 0) + Duration::minutes(minutes);
+            DateMaybeTime::DateTime(
+                Local
+                    .from_local_datetime(&at)
+                    .earliest()
+                    .unwrap_or_else(|| Local.from_utc_datetime(&at)),
+            )
+        }),
+    ]
+}
+
+/// Titles of one to three short words.
+pub fn title() -> impl Strategy<Value = String> {
+    "[a-z]{1,8}( [a-z]{1,8}){0,2}"
+}
+
+/// Entries, some done and some with a date.
+pub fn entry() -> impl Strategy<Value = TodoEntry> {
+    (title(), option::of(date()), any::<bool>()).prop_map(
+        |(title, date, done)| {
+            let mut entry = TodoEntry::from_info(title, date);
+            if done {
+                entry.completed_at = entry.created_at;
+            }
+            entry
+        },
+    )
+}
+
+/// Entries and sublists nested up to four deep. Sublists always hold
+/// something, as they do in the lists themselves.
+pub fn item() -> impl Strategy<Value = ListItem> {
+    entry()
+        .prop_map(ListItem::Entry)
+        .prop_recursive(4, 48, 4, |inner| {
+            (title(), option::of(date()), vec(inner, 1..5)).prop_map(
+                |(title, date, items)| {
+                    let mut sublist = TodoList::from_info(title, date);
+                    sublist.list = items;
+                    ListItem::List(sublist)
+                },
+            )
+        })
+}
+
+/// A list of up to eight top-level items.
+pub fn list() -> impl Strategy<Value = TodoList> {
+    (title(), vec(item(), 0..8)).prop_map(|(title, items)| {
+        let mut list = TodoList::from_info(title, None);
+        list.list = items;
+        list
+    })
+}
+
+/// One to three lists by name, as in the data file.
+pub fn lists() -> impl Strategy<Value = HashMap<String, TodoList>> {
+    hash_map("[a-z]{1,6}", list(), 1..4)
+}
+
+/// A change to one list, as made by a command. Paths are random, so many
+/// lead nowhere, and running those should fail without changing anything.
+#[derive(Debug, Clone)]
+pub enum Op {
+    /// `later add`: a new entry at the end of the sublist at `under`.
+    Add {
+        under: ItemPath,
+        title: String,
+        date: Option<DateMaybeTime>,
+    },
+    /// `later remove`.
+    Remove { index: ItemPath },
+    /// `later move`, putting the item before the one at `to`.
+    Move { from: ItemPath, to: ItemPath },
+    /// `later sort`.
+    Sort { by: SortBy, tie_break: TieBreak },
+}
+
+fn path(min: usize) -> impl Strategy<Value = ItemPath> {
+    vec(0..6usize, min..4).prop_map(ItemPath::from)
+}
+
+pub fn op() -> impl Strategy<Value = Op> {
+    let sort_by = prop_oneof![
+        Just(SortBy::Date),
+        Just(SortBy::Priority),
+        Just(SortBy::Created),
+    ];
+    let tie_break = prop_oneof![
+        Just(TieBreak::Original),
+        Just(TieBreak::Title),
+        Just(TieBreak::Created),
+    ];
+    prop_oneof![
+        (path(0), title(), option::of(date()))
+            .prop_map(|(under, title, date)| Op::Add { under, title, date }),
+        path(1).prop_map(|index| Op::Remove { index }),
+        (path(1), path(1)).prop_map(|(from, to)| Op::Move { from, to }),
+        (sort_by, tie_break)
+            .prop_map(|(by, tie_break)| Op::Sort { by, tie_break }),
+    ]
+}
+
+/// Up to `max` changes in a row.
+pub fn ops(max: usize) -> impl Strategy<Value = Vec<Op>> {
+    vec(op(), 0..=max)
+}
+
+/// Lists kept in memory instead of in a file.
+#[derive(Debug, Default)]
+pub struct Store {
+    pub lists: HashMap<String, TodoList>,
+}
+
+impl Store {
+    pub fn new(lists: HashMap<String, TodoList>) -> Store {
+        Store { lists }
+    }
+
+    pub fn with_list(name: &str, list: TodoList) -> Store {
+        Store::new(HashMap::from([(String::from(name), list)]))
+    }
+
+    /// Makes the change to the list called `name`.
+    pub fn run(&mut self, name: &str, op: &Op) -> Result<()> {
+        let list = match self.lists.get_mut(name) {
+            Some(list) => list,
+            None => return Err(Error::list_not_found(name, self.lists.keys())),
+        };
+        match op {
+            Op::Add { under, title, date } => {
+                let entry = TodoEntry::from_info(title.clone(), *date);
+                list.append(under, ListItem::Entry(entry))
+            }
+            Op::Remove { index } => list.remove(index).map(|_| ()),
+            Op::Move { from, to } => {
+                // as `later move` does, moving an item onto itself does
+                // nothing
+                if to == from {
+                    return Ok(());
+                }
+                if to.starts_with(from) {
+                    return Err(Error::NoItem { index: to.clone() });
+                }
+                for index in [from, to] {
+                    if list.get(index).is_none() {
+                        return Err(Error::NoItem {
+                            index: index.clone(),
+                        });
+                    }
+                }
+                let mut item = list.remove(from)?;
+                item.touch();
+                list.insert(&to.clone().after_removal(from), item)
+            }
+            Op::Sort { by, tie_break } => {
+                list.sort(*by, *tie_break, false);
+                Ok(())
+            }
+        }
+    }
+
+    /// The lists after saving and loading them again, which should be the
+    /// same as they are now; see [`same`].
+    pub fn round_trip(&self) -> serde_json::Result<HashMap<String, TodoList>> {
+        serde_json::from_str(&serde_json::to_string_pretty(&self.lists)?)
+    }
+}
+
+/// Whether two sets of lists hold the same things, as they'd be saved.
+pub fn same(
+    a: &HashMap<String, TodoList>,
+    b: &HashMap<String, TodoList>,
+) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// Every title in the list, sorted, to compare what's in two lists
+/// regardless of order.
+pub fn titles(list: &TodoList) -> Vec<String> {
+    let mut titles: Vec<String> = list
+        .items_with_paths()
+        .into_iter()
+        .map(|(_, item)| item.title().to_string())
+        .collect();
+    titles.sort();
+    titles
+}
+
+/// What holds for every list however it was changed: no sublist is left
+/// empty. The error says where it doesn't hold.
+pub fn invariants(list: &TodoList) -> std::result::Result<(), String> {
+    for (path, item) in list.items_with_paths() {
+        if let ListItem::List(sublist) = item {
+            if sublist.list.is_empty() {
+                return Err(format!(
+                    "the sublist at {} is empty",
+                    ItemPath::from(path)
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn ops_keep_invariants(list in list(), ops in ops(20)) {
+            let mut store = Store::with_list("l", list);
+            for op in &ops {
+                let _ = store.run("l", op);
+                prop_assert!(invariants(&store.lists["l"]).is_ok());
+            }
+            prop_assert!(same(&store.round_trip()?, &store.lists));
+        }
+
+        #[test]
+        fn failed_ops_change_nothing(list in list(), ops in ops(20)) {
+            let mut store = Store::with_list("l", list);
+            for op in &ops {
+                let before = store.round_trip()?;
+                if store.run("l", op).is_err() {
+                    prop_assert!(same(&before, &store.lists));
+                }
+            }
+        }
+
+        #[test]
+        fn moves_and_sorts_keep_items(list in list(), ops in ops(20)) {
+            let mut store = Store::with_list("l", list);
+            let before = titles(&store.lists["l"]);
+            for op in &ops {
+                if let Op::Move { .. } | Op::Sort { .. } = op {
+                    let _ = store.run("l", op);
+                }
+            }
+            prop_assert_eq!(titles(&store.lists["l"]), before);
+        }
+
+        #[test]
+        fn lists_round_trip(lists in lists()) {
+            let store = Store::new(lists);
+            prop_assert!(same(&store.round_trip()?, &store.lists));
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! The colors and styles of a listing: built-in themes, picked with
+//! `name` under `[theme]` in the config file, and any of their styles
+//! replaced one at a time, e.g. `overdue = "bold #ff5f00"`.
+
+use crate::config::{parse_style, ThemeConfig};
+use crate::{Palette, Priority, Urgency};
+use ansi_term::{Color, Style};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// The themes later comes with.
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Builtin {
+    /// The terminal's own red, yellow and green for dates, or the
+    /// palette's colors.
+    #[default]
+    Default,
+    /// Ethan Schoonover's Solarized accents, which read on its light and
+    /// dark backgrounds alike.
+    Solarized,
+    /// No colors, only bold, underline and dimming, for monochrome
+    /// terminals and printouts.
+    Mono,
+}
+
+// Solarized's accent colors
+const RED: Color = Color::RGB(220, 50, 47);
+const ORANGE: Color = Color::RGB(203, 75, 22);
+const YELLOW: Color = Color::RGB(181, 137, 0);
+const GREEN: Color = Color::RGB(133, 153, 0);
+const CYAN: Color = Color::RGB(42, 161, 152);
+const BLUE: Color = Color::RGB(38, 139, 210);
+const VIOLET: Color = Color::RGB(108, 113, 196);
+
+/// Every style a listing is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// Dates that have passed.
+    pub overdue: Style,
+    /// Dates within the next day.
+    pub today: Style,
+    /// Dates further off.
+    pub future: Style,
+    /// The `0)` before entries, unless `display.bullet_colors` says
+    /// otherwise.
+    pub entry_marker: Style,
+    /// The `0--->` before sublists.
+    pub list_marker: Style,
+    pub entry_title: Style,
+    pub list_title: Style,
+    /// Added to the title's style once an item is done.
+    pub done_title: Style,
+    pub tag: Style,
+    // the badges of priorities A, B and C
+    priorities: [Style; 3],
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::builtin(Builtin::Default, Palette::Default)
+    }
+}
+
+impl Theme {
+    /// One of the built-in themes. The default theme takes its date, tag
+    /// and priority colors from `palette`.
+    pub fn builtin(builtin: Builtin, palette: Palette) -> Theme {
+        let priority =
+            |letter| Priority::from_name(letter).unwrap().style(palette);
+        let base = Theme {
+            overdue: palette.urgency_style(Urgency::Overdue),
+            today: palette.urgency_style(Urgency::Soon),
+            future: palette.urgency_style(Urgency::Later),
+            entry_marker: Color::Cyan.normal(),
+            list_marker: Color::Blue.normal(),
+            entry_title: Style::new(),
+            list_title: Style::new().underline(),
+            done_title: Style::new().dimmed().strikethrough(),
+            tag: palette.tag_style(),
+            priorities: [priority("A"), priority("B"), priority("C")],
+        };
+        match builtin {
+            Builtin::Default => base,
+            Builtin::Solarized => Theme {
+                overdue: RED.normal(),
+                today: YELLOW.normal(),
+                future: GREEN.normal(),
+                entry_marker: CYAN.normal(),
+                list_marker: BLUE.normal(),
+                tag: VIOLET.normal(),
+                priorities: [RED.bold(), ORANGE.bold(), CYAN.bold()],
+                ..base
+            },
+            Builtin::Mono => Theme {
+                overdue: Style::new().bold(),
+                today: Style::new().underline(),
+                future: Style::new(),
+                entry_marker: Style::new(),
+                list_marker: Style::new().bold(),
+                tag: Style::new().italic(),
+                priorities: [Style::new().bold(); 3],
+                ..base
+            },
+        }
+    }
+
+    /// The theme `config` describes: its built-in theme with any styles
+    /// it gives in place of that theme's.
+    pub fn from_config(
+        config: &ThemeConfig,
+        palette: Palette,
+    ) -> Result<Theme> {
+        let mut theme = Theme::builtin(config.name, palette);
+        let overrides = [
+            ("overdue", &config.overdue, &mut theme.overdue),
+            ("today", &config.today, &mut theme.today),
+            ("future", &config.future, &mut theme.future),
+            (
+                "entry_marker",
+                &config.entry_marker,
+                &mut theme.entry_marker,
+            ),
+            ("list_marker", &config.list_marker, &mut theme.list_marker),
+            ("entry_title", &config.entry_title, &mut theme.entry_title),
+            ("list_title", &config.list_title, &mut theme.list_title),
+            ("done_title", &config.done_title, &mut theme.done_title),
+            ("tag", &config.tag, &mut theme.tag),
+        ];
+        for (key, given, style) in overrides {
+            if let Some(given) = given {
+                *style = parse_style(given).with_context(|| {
+                    format!("Invalid theme.{} in config", key)
+                })?;
+            }
+        }
+        Ok(theme)
+    }
+
+    pub fn urgency(&self, urgency: Urgency) -> Style {
+        match urgency {
+            Urgency::Overdue => self.overdue,
+            Urgency::Soon => self.today,
+            Urgency::Later => self.future,
+        }
+    }
+
+    /// The style of a priority's badge; those below C are dimmed.
+    pub fn priority(&self, priority: Priority) -> Style {
+        match priority.letter() {
+            'A' => self.priorities[0],
+            'B' => self.priorities[1],
+            'C' => self.priorities[2],
+            _ => Style::new().dimmed(),
+        }
+    }
+
+    /// `base` with the done style added to it.
+    pub fn done(&self, base: Style) -> Style {
+        let done = self.done_title;
+        Style {
+            foreground: done.foreground.or(base.foreground),
+            background: done.background.or(base.background),
+            is_bold: base.is_bold || done.is_bold,
+            is_dimmed: base.is_dimmed || done.is_dimmed,
+            is_italic: base.is_italic || done.is_italic,
+            is_underline: base.is_underline || done.is_underline,
+            is_blink: base.is_blink || done.is_blink,
+            is_reverse: base.is_reverse || done.is_reverse,
+            is_hidden: base.is_hidden || done.is_hidden,
+            is_strikethrough: base.is_strikethrough || done.is_strikethrough,
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! `later list --tiers`: the lists grouped by where attention is needed,
+//! those with something overdue first, then those with something due this
+//! week, then the quiet ones.
+
+use crate::{clock, RenderOptions, TodoList, Urgency};
+use ansi_term::Style;
+use chrono::prelude::*;
+use std::io::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Tier {
+    Overdue,
+    /// Something not done falls due in the next seven days.
+    ThisWeek,
+    Quiet,
+}
+
+impl Tier {
+    /// Which tier a list is in, going by it and everything in it that isn't
+    /// done.
+    pub fn of(list: &TodoList, today: NaiveDate) -> Tier {
+        let own = (list.completed_at.is_none()).then_some(list.date).flatten();
+        let dates = list
+            .items_with_paths()
+            .into_iter()
+            .filter(|(_, item)| !item.is_done())
+            .filter_map(|(_, item)| item.date())
+            .chain(own);
+        let mut tier = Tier::Quiet;
+        for date in dates {
+            if date.urgency() == Urgency::Overdue {
+                return Tier::Overdue;
+            }
+            if (date.start().date() - today).num_days() < 7 {
+                tier = Tier::ThisWeek;
+            }
+        }
+        tier
+    }
+
+    fn heading(self) -> &'static str {
+        match self {
+            Tier::Overdue => "Has overdue",
+            Tier::ThisWeek => "Due this week",
+            Tier::Quiet => "Quiet",
+        }
+    }
+
+    fn style(self, opts: &RenderOptions) -> Style {
+        let style = match self {
+            Tier::Overdue => opts.theme.urgency(Urgency::Overdue),
+            Tier::ThisWeek => opts.theme.urgency(Urgency::Soon),
+            Tier::Quiet => Style::new().dimmed(),
+        };
+        opts.style(style.bold())
+    }
+}
+
+/// Writes the lists' headers under a heading for each tier, leaving out
+/// tiers with no lists in them. Within a tier, lists keep the order given.
+pub fn write_tiers(
+    out: &mut impl Write,
+    lists: &[&TodoList],
+    opts: &RenderOptions,
+) -> std::io::Result<()> {
+    let today = clock::today();
+    let mut tiered: Vec<(Tier, &TodoList)> = lists
+        .iter()
+        .map(|&list| (Tier::of(list, today), list))
+        .collect();
+    // sort_by_key is stable
+    tiered.sort_by_key(|(tier, _)| *tier);
+    let mut current = None;
+    for (tier, list) in tiered {
+        if current != Some(tier) {
+            if current.is_some() {
+                writeln!(out)?;
+            }
+            writeln!(out, "{}", tier.style(opts).paint(tier.heading()))?;
+            current = Some(tier);
+        }
+        list.write_header(out, opts)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,119 @@
+//! How long each phase of a command took, shown with `later -v` along with
+//! a warning for any phase over its budget, so slowdowns show up as the
+//! lists grow.
+
+use crate::config::BudgetConfig;
+use std::io::prelude::*;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Reading the data file.
+    Load,
+    /// Turning it into lists.
+    Parse,
+    Sort,
+    /// Drawing the list at the end of a command.
+    Render,
+    /// Writing the lists back, backups included.
+    Save,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 5] = [
+        Phase::Load,
+        Phase::Parse,
+        Phase::Sort,
+        Phase::Render,
+        Phase::Save,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Load => "load",
+            Phase::Parse => "parse",
+            Phase::Sort => "sort",
+            Phase::Render => "render",
+            Phase::Save => "save",
+        }
+    }
+
+    /// The budget when the config doesn't set one.
+    pub fn default_budget(self) -> Duration {
+        Duration::from_millis(match self {
+            Phase::Load => 20,
+            Phase::Parse => 50,
+            Phase::Sort => 20,
+            Phase::Render => 50,
+            Phase::Save => 100,
+        })
+    }
+}
+
+// time spent in each phase so far, and how many times it ran, in the order
+// of `Phase::ALL`
+static SPENT: Mutex<[(Duration, u32); 5]> =
+    Mutex::new([(Duration::ZERO, 0); 5]);
+
+/// Runs `f`, counting the time it takes towards `phase`.
+pub fn time<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    record(phase, start.elapsed());
+    result
+}
+
+pub fn record(phase: Phase, elapsed: Duration) {
+    let i = Phase::ALL.iter().position(|&p| p == phase).unwrap();
+    let mut spent = SPENT.lock().unwrap();
+    spent[i].0 += elapsed;
+    spent[i].1 += 1;
+}
+
+/// Every phase that ran, with the time it took all told and how many times
+/// it ran.
+pub fn spent() -> Vec<(Phase, Duration, u32)> {
+    let spent = SPENT.lock().unwrap();
+    Phase::ALL
+        .iter()
+        .zip(spent.iter())
+        .filter(|(_, (_, runs))| *runs > 0)
+        .map(|(&phase, &(elapsed, runs))| (phase, elapsed, runs))
+        .collect()
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Writes a line per phase that ran, then one warning per phase that went
+/// over budget.
+pub fn write_report(
+    out: &mut impl Write,
+    budgets: &BudgetConfig,
+) -> std::io::Result<()> {
+    let spent = spent();
+    for &(phase, elapsed, runs) in &spent {
+        write!(out, "{:<7}{:>10}", phase.name(), millis(elapsed))?;
+        if runs > 1 {
+            write!(out, " ({} times)", runs)?;
+        }
+        writeln!(out)?;
+    }
+    for &(phase, elapsed, _) in &spent {
+        let budget = budgets.budget(phase);
+        if elapsed > budget {
+            writeln!(
+                out,
+                "warning: {} took {}, over its budget of {} (budgets.{} in \
+                 the config)",
+                phase.name(),
+                millis(elapsed),
+                millis(budget),
+                phase.name()
+            )?;
+        }
+    }
+    Ok(())
+}
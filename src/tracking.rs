@@ -0,0 +1,160 @@
+//! Time tracking: `later start` opens an interval on an item and
+//! `later stop` closes it, and `later report --week` adds the intervals up
+//! by list and by tag.
+
+use crate::{clock, ListItem, TodoList};
+use chrono::prelude::*;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::prelude::*;
+
+/// A stretch of time spent on an item; the one still going has no end.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Hash)]
+pub struct Interval {
+    pub start: DateTime<Local>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<DateTime<Local>>,
+}
+
+impl Interval {
+    /// How much of the interval falls between `from` and `to`, counting one
+    /// still going as running until now.
+    pub fn within(
+        &self,
+        from: DateTime<Local>,
+        to: DateTime<Local>,
+    ) -> Duration {
+        let end = self.end.unwrap_or_else(clock::now).min(to);
+        let start = self.start.max(from);
+        (end - start).max(Duration::zero())
+    }
+}
+
+/// Where the item being tracked is, if any: its list and position.
+pub fn running(
+    lists: &HashMap<String, TodoList>,
+) -> Option<(String, Vec<usize>)> {
+    let mut names: Vec<&String> = lists.keys().collect();
+    names.sort();
+    names.into_iter().find_map(|name| {
+        lists[name]
+            .items_with_paths()
+            .into_iter()
+            .find(|(_, item)| is_running(item))
+            .map(|(path, _)| (name.clone(), path))
+    })
+}
+
+pub fn is_running(item: &ListItem) -> bool {
+    item.tracked().last().is_some_and(|i| i.end.is_none())
+}
+
+/// Opens an interval on `item` from now.
+pub fn start(item: &mut ListItem) {
+    item.tracked_mut().push(Interval {
+        start: clock::now(),
+        end: None,
+    });
+}
+
+/// Closes the interval open on `item`, returning how long it ran.
+pub fn stop(item: &mut ListItem) -> Option<Duration> {
+    let now = clock::now();
+    match item.tracked_mut().last_mut() {
+        Some(interval) if interval.end.is_none() => {
+            interval.end = Some(now);
+            Some(now - interval.start)
+        }
+        _ => None,
+    }
+}
+
+/// The start of the week `today` is in, taking weeks to start on Monday.
+pub fn week_start(today: NaiveDate) -> NaiveDate {
+    today - Duration::days(today.weekday().num_days_from_monday() as i64)
+}
+
+/// Time tracked between two moments, all told, by list and by tag.
+#[derive(Debug)]
+pub struct Totals {
+    pub total: Duration,
+    pub lists: BTreeMap<String, Duration>,
+    /// An item's time counts towards its own tags and those of the
+    /// sublists it's in.
+    pub tags: BTreeMap<String, Duration>,
+}
+
+pub fn totals(
+    lists: &HashMap<String, TodoList>,
+    from: DateTime<Local>,
+    to: DateTime<Local>,
+) -> Totals {
+    let mut totals = Totals {
+        total: Duration::zero(),
+        lists: BTreeMap::new(),
+        tags: BTreeMap::new(),
+    };
+    for (name, list) in lists {
+        for (path, item) in list.items_with_paths() {
+            let spent = item
+                .tracked()
+                .iter()
+                .fold(Duration::zero(), |a, i| a + i.within(from, to));
+            if spent <= Duration::zero() {
+                continue;
+            }
+            totals.total = totals.total + spent;
+            add(&mut totals.lists, name, spent);
+            let mut tags: Vec<&str> = list.inherited_tags(&path);
+            tags.extend(item.tags().iter().map(String::as_str));
+            tags.sort_unstable();
+            tags.dedup();
+            for tag in tags {
+                add(&mut totals.tags, tag, spent);
+            }
+        }
+    }
+    totals
+}
+
+fn add(to: &mut BTreeMap<String, Duration>, key: &str, spent: Duration) {
+    let sum = to.entry(String::from(key)).or_insert_with(Duration::zero);
+    *sum = *sum + spent;
+}
+
+/// A duration as hours and minutes, e.g. "12:05".
+pub fn hours(duration: Duration) -> String {
+    let minutes = duration.num_minutes();
+    format!("{}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// Writes the total, then one line per list and per tag, most time first.
+pub fn write_totals(
+    out: &mut impl Write,
+    totals: &Totals,
+) -> std::io::Result<()> {
+    writeln!(out, "{:>7}  total", hours(totals.total))?;
+    let mut rows: Vec<(String, Duration)> = totals
+        .lists
+        .iter()
+        .map(|(name, spent)| (name.clone(), *spent))
+        .collect();
+    rows.sort_by_key(|(_, spent)| std::cmp::Reverse(*spent));
+    let mut tags: Vec<(String, Duration)> = totals
+        .tags
+        .iter()
+        .map(|(tag, spent)| (format!("#{}", tag), *spent))
+        .collect();
+    tags.sort_by_key(|(_, spent)| std::cmp::Reverse(*spent));
+    for (heading, rows) in [("Lists", rows), ("Tags", tags)] {
+        if rows.is_empty() {
+            continue;
+        }
+        writeln!(out, "{}", heading)?;
+        for (name, spent) in rows {
+            writeln!(out, "{:>7}  {}", hours(spent), name)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,218 @@
+//! `later triage`: goes through the undated, untagged items in the inbox
+//! (the default list, or the one named), offering for each a date read
+//! from the end of its title and the lists that share words with it, so it
+//! can be filed in a keystroke or two.
+
+use crate::{natural, tag_name, DateMaybeTime, ListItem, TodoList};
+use anyhow::{bail, Result};
+use chrono::prelude::*;
+use std::collections::HashMap;
+use std::io::prelude::*;
+
+// too common to say anything about where an item belongs
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "with", "from", "about", "this", "that", "into",
+    "out", "get", "some",
+];
+
+// at most this many lists are offered
+const MAX_LISTS: usize = 3;
+
+/// What's offered for one item.
+#[derive(Debug, Clone, Default)]
+pub struct Suggestion {
+    /// A date at the end of the title, with what the title is without it.
+    pub date: Option<(String, DateMaybeTime)>,
+    /// Lists sharing words with the title, most in common first.
+    pub lists: Vec<String>,
+}
+
+/// The positions of the top-level items still to be filed: not done, with
+/// no date and no tags.
+pub fn pending(inbox: &TodoList) -> Vec<usize> {
+    inbox
+        .items()
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| {
+            !item.is_done() && item.date().is_none() && item.tags().is_empty()
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn keywords(text: &str) -> Vec<String> {
+    let mut words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|w| w.chars().count() >= 3 && !STOP_WORDS.contains(&w.as_str()))
+        .collect();
+    words.sort();
+    words.dedup();
+    words
+}
+
+/// The longest run of words at the end of `title` that reads as a date,
+/// e.g. "pay rent by next friday" gives "pay rent" and next Friday. A
+/// trailing "by", "on", "at" or "due" goes with the date, and a title that
+/// is nothing but a date has none.
+pub fn date_in_title(
+    title: &str,
+    today: NaiveDate,
+) -> Option<(String, DateMaybeTime)> {
+    let words: Vec<&str> = title.split_whitespace().collect();
+    (1..words.len().min(5)).rev().find_map(|n| {
+        let (rest, tail) = words.split_at(words.len() - n);
+        let date = natural::parse(&tail.join(" "), today)?;
+        let rest = match rest {
+            [rest @ .., word]
+                if ["by", "on", "at", "due"]
+                    .contains(&word.to_lowercase().as_str()) =>
+            {
+                rest
+            }
+            rest => rest,
+        };
+        (!rest.is_empty()).then(|| (rest.join(" "), date))
+    })
+}
+
+/// The lists other than `inbox` that share words with `title`, going by
+/// their names first and then the titles in them.
+pub fn matching_lists(
+    title: &str,
+    lists: &HashMap<String, TodoList>,
+    inbox: &str,
+) -> Vec<String> {
+    let wanted = keywords(title);
+    let mut scored: Vec<(usize, &String)> = lists
+        .iter()
+        .filter(|(name, _)| name.as_str() != inbox)
+        .map(|(name, list)| {
+            let in_name = keywords(name);
+            let in_items: Vec<String> = list
+                .items_with_paths()
+                .into_iter()
+                .flat_map(|(_, item)| keywords(item.title()))
+                .collect();
+            let score = wanted
+                .iter()
+                .map(|word| {
+                    if in_name.contains(word) {
+                        3
+                    } else if in_items.contains(word) {
+                        1
+                    } else {
+                        0
+                    }
+                })
+                .sum();
+            (score, name)
+        })
+        .filter(|(score, _)| *score > 0)
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+    scored
+        .into_iter()
+        .take(MAX_LISTS)
+        .map(|(_, name)| name.clone())
+        .collect()
+}
+
+pub fn suggest(
+    item: &ListItem,
+    lists: &HashMap<String, TodoList>,
+    inbox: &str,
+    today: NaiveDate,
+) -> Suggestion {
+    let date = date_in_title(item.title(), today);
+    // the date's words say nothing about where it goes
+    let title = date.as_ref().map_or(item.title(), |(rest, _)| rest);
+    Suggestion {
+        lists: matching_lists(title, lists, inbox),
+        date,
+    }
+}
+
+/// What to do with an item, from an answer such as "d1 #errand".
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filing {
+    /// Take the suggested date, and the title without it.
+    pub date: bool,
+    /// Move to this one of the suggested lists.
+    pub list: Option<usize>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    File(Filing),
+    Skip,
+    /// Stop, keeping what was filed so far.
+    Quit,
+}
+
+/// Reads an answer: `d` for the date, a number for a list, and `#tag`s, in
+/// any order and with or without spaces between the keys; `s` or nothing
+/// skips the item and `q` stops.
+pub fn parse_answer(text: &str, suggestion: &Suggestion) -> Result<Answer> {
+    let text = text.trim();
+    match text.to_lowercase().as_str() {
+        "" | "s" => return Ok(Answer::Skip),
+        "q" => return Ok(Answer::Quit),
+        _ => {}
+    }
+    let mut filing = Filing::default();
+    for word in text.split_whitespace() {
+        if word.starts_with('#') {
+            match tag_name(word) {
+                Some(tag) if !filing.tags.contains(&tag) => {
+                    filing.tags.push(tag)
+                }
+                Some(_) => {}
+                None => bail!("Invalid tag '{}'", word),
+            }
+            continue;
+        }
+        for key in word.chars() {
+            match key.to_digit(10) {
+                _ if key.eq_ignore_ascii_case(&'d') => {
+                    if suggestion.date.is_none() {
+                        bail!("There's no date to take");
+                    }
+                    filing.date = true;
+                }
+                Some(n) if n >= 1 && (n as usize) <= suggestion.lists.len() => {
+                    if filing.list.is_some() {
+                        bail!("An item can only go to one list");
+                    }
+                    filing.list = Some(n as usize - 1);
+                }
+                _ => bail!(
+                    "Invalid choice '{}' (d, a list's number, #tag, s or q)",
+                    key
+                ),
+            }
+        }
+    }
+    Ok(Answer::File(filing))
+}
+
+/// Writes the item's title, counted as `n` of `total`, and the choices
+/// offered for it.
+pub fn write_suggestion(
+    out: &mut impl Write,
+    n: usize,
+    total: usize,
+    title: &str,
+    suggestion: &Suggestion,
+) -> std::io::Result<()> {
+    writeln!(out, "({}/{}) {}", n, total, title)?;
+    if let Some((rest, date)) = &suggestion.date {
+        writeln!(out, "  d  date it {}, as '{}'", date, rest)?;
+    }
+    for (i, list) in suggestion.lists.iter().enumerate() {
+        writeln!(out, "  {}  move to '{}'", i + 1, list)?;
+    }
+    writeln!(out, "     #tag to tag it, s to skip, q to stop")
+}
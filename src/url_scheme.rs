@@ -0,0 +1,102 @@
+use crate::DateMaybeTime;
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, NaiveTime};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const SCHEME: &str = "later";
+pub const DESKTOP_FILE: &str = "later-url-handler.desktop";
+
+/// A parsed `later://<action>?key=value&...` URL.
+#[derive(Debug)]
+pub struct CaptureUrl {
+    pub action: String,
+    pub params: HashMap<String, String>,
+}
+
+pub(crate) fn percent_decode(s: &str) -> Result<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        match b {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex: Vec<u8> = iter.by_ref().take(2).collect();
+                let hex = std::str::from_utf8(&hex).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(decoded) if hex.len() == 2 => bytes.push(decoded),
+                    _ => bail!("Invalid percent-encoding in URL: %{}", hex),
+                }
+            }
+            _ => bytes.push(b),
+        }
+    }
+    String::from_utf8(bytes).context("URL doesn't decode to valid UTF-8")
+}
+
+impl CaptureUrl {
+    pub fn parse(url: &str) -> Result<CaptureUrl> {
+        let rest = match url.split_once("://") {
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case(SCHEME) => rest,
+            _ => bail!("Not a {}:// URL: {}", SCHEME, url),
+        };
+        let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let mut params = HashMap::new();
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            params.insert(percent_decode(key)?, percent_decode(value)?);
+        }
+        Ok(CaptureUrl {
+            action: action.trim_end_matches('/').to_lowercase(),
+            params,
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.params
+            .get(key)
+            .map(|v| v.as_str())
+            .filter(|v| !v.is_empty())
+    }
+}
+
+/// Parses a `due` parameter: a `yyyy-mm-dd` or `yyyy/mm/dd` date, with an
+/// optional `hh:mm` time after a space or `T`.
+pub fn parse_due(due: &str) -> Result<DateMaybeTime> {
+    let (date, time) = match due.split_once([' ', 'T']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (due, None),
+    };
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y/%m/%d"))
+        .with_context(|| format!("Invalid due date '{}'", due))?;
+    let time = match time {
+        Some(t) => Some(
+            NaiveTime::parse_from_str(t, "%H:%M")
+                .with_context(|| format!("Invalid due time '{}'", t))?,
+        ),
+        None => None,
+    };
+    Ok(DateMaybeTime::from_parts(Some(date), time).unwrap())
+}
+
+/// Contents of a freedesktop entry registering `exe` for `later://` URLs.
+pub fn desktop_entry(exe: &Path) -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=later\n\
+         Comment=Capture to-do items from later:// links\n\
+         Exec=\"{}\" handle-url %u\n\
+         Terminal=false\n\
+         NoDisplay=true\n\
+         MimeType=x-scheme-handler/{};\n",
+        exe.display(),
+        SCHEME
+    )
+}
+
+/// Where [`desktop_entry`] gets installed for the current user.
+pub fn desktop_entry_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("applications").join(DESKTOP_FILE))
+}
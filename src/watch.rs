@@ -0,0 +1,121 @@
+//! Keeping a rendering of the lists on screen while they change.
+
+use crate::{clock, config, load, table, RenderOptions, TodoList};
+use anyhow::Result;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::prelude::*;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tracing::debug;
+
+/// How a list is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layout {
+    Tree,
+    Table,
+}
+
+/// Rendered lists, reused for as long as nothing that shows up in them has
+/// changed: their contents, the terminal width, or the current minute
+/// (which moves "in 5 minutes" along). Bullets and colors are taken to stay
+/// the same for the cache's lifetime.
+#[derive(Debug, Default)]
+pub struct RenderCache {
+    entries: HashMap<String, (u64, Vec<u8>)>,
+}
+
+impl RenderCache {
+    pub fn new() -> RenderCache {
+        RenderCache::default()
+    }
+
+    /// The rendering of `list`, drawn afresh only if the cached one is out
+    /// of date.
+    pub fn render(
+        &mut self,
+        name: &str,
+        list: &TodoList,
+        layout: Layout,
+        opts: &RenderOptions,
+    ) -> std::io::Result<&[u8]> {
+        let mut hasher = DefaultHasher::new();
+        list.hash(&mut hasher);
+        layout.hash(&mut hasher);
+        opts.width.hash(&mut hasher);
+        (clock::now().timestamp() / 60).hash(&mut hasher);
+        let key = hasher.finish();
+
+        let fresh = matches!(self.entries.get(name), Some((k, _)) if *k == key);
+        if !fresh {
+            let mut out = Vec::new();
+            match layout {
+                Layout::Tree => list.write_to(&mut out, 0, opts)?,
+                Layout::Table => table::write_table(list, &mut out, opts)?,
+            }
+            self.entries.insert(name.to_string(), (key, out));
+        }
+        debug!(list = name, cached = fresh, "render");
+        Ok(&self.entries[name].1)
+    }
+
+    /// Forgets lists that no longer exist.
+    pub fn retain(&mut self, names: &[&String]) {
+        self.entries.retain(|name, _| names.contains(&name));
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Redraws the named lists (or all of them, when `names` is empty) whenever
+/// the file changes, a minute passes or the terminal is resized, until
+/// interrupted.
+pub fn run(
+    todo_file: &Path,
+    names: &[String],
+    layout: Layout,
+    config: &config::Config,
+    interval: Duration,
+) -> Result<()> {
+    let mut cache = RenderCache::new();
+    let mut last = None;
+    let mut lists = HashMap::new();
+    let mut stdout = std::io::stdout();
+    loop {
+        let opts = RenderOptions::for_terminal(config)?;
+        let modified = modified(todo_file);
+        let minute = clock::now().timestamp() / 60;
+        let state = Some((modified, minute, opts.width));
+        if state != last {
+            if last.map(|(m, _, _)| m) != Some(modified) {
+                lists = load(todo_file)?;
+            }
+            last = state;
+
+            let mut shown: Vec<&String> = match names {
+                [] => lists.keys().collect(),
+                names => {
+                    names.iter().filter(|n| lists.contains_key(*n)).collect()
+                }
+            };
+            shown.sort();
+            cache.retain(&shown);
+            // clear the screen and go home
+            let mut screen = b"\x1b[2J\x1b[H".to_vec();
+            for name in &shown {
+                screen.extend_from_slice(cache.render(
+                    name,
+                    &lists[*name],
+                    layout,
+                    &opts,
+                )?);
+            }
+            stdout.write_all(&screen)?;
+            stdout.flush()?;
+        }
+        std::thread::sleep(interval);
+    }
+}
@@ -0,0 +1,73 @@
+//! Outgoing webhooks: a JSON POST for every item added, marked done or
+//! removed, so home dashboards, chat bots and other automations can react
+//! to changes as they're saved.
+
+use crate::config::Webhook;
+use crate::ListItem;
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// a slow endpoint shouldn't hold up the command for long
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A kind of change that webhooks can be fired on.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Add,
+    Done,
+    Remove,
+}
+
+/// One change, as sent: `{"event": "done", "list": "to-do", "index": [2],
+/// "item": {...}, "at": "..."}`, with the item as it's stored in the file.
+#[derive(Serialize, Debug, Clone)]
+pub struct Event {
+    #[serde(rename = "event")]
+    pub action: Action,
+    pub list: String,
+    pub index: Vec<usize>,
+    pub item: serde_json::Value,
+    pub at: DateTime<Local>,
+}
+
+impl Event {
+    pub fn new(
+        action: Action,
+        list: &str,
+        index: &[usize],
+        item: &ListItem,
+    ) -> Event {
+        Event {
+            action,
+            list: String::from(list),
+            index: index.to_vec(),
+            item: serde_json::to_value(item).unwrap_or_default(),
+            at: crate::clock::now(),
+        }
+    }
+}
+
+/// Posts the event to every webhook that wants it. The change is already
+/// saved by then, so failures are returned as messages rather than errors.
+pub fn fire(hooks: &[Webhook], event: &Event) -> Vec<String> {
+    let body = match serde_json::to_string(event) {
+        Ok(body) => body,
+        Err(e) => return vec![format!("Couldn't encode webhook event: {}", e)],
+    };
+    hooks
+        .iter()
+        .filter(|hook| {
+            hook.events.is_empty() || hook.events.contains(&event.action)
+        })
+        .filter_map(|hook| {
+            ureq::post(&hook.url)
+                .timeout(TIMEOUT)
+                .set("Content-Type", "application/json")
+                .send_string(&body)
+                .err()
+                .map(|e| format!("Webhook {} failed: {}", hook.url, e))
+        })
+        .collect()
+}
@@ -0,0 +1,107 @@
+//! Time zones for items that happen somewhere else, such as a flight's
+//! departure: typed as `09:40 JST` or `09:40 Asia/Tokyo`, kept with the
+//! item, and shown in that zone next to the local time.
+
+use crate::formats;
+use crate::i18n::Lang;
+use chrono::prelude::*;
+use chrono_tz::Tz;
+
+// common abbreviations, each standing for a zone that uses it; the zone
+// then decides whether daylight saving applies on the day
+const ABBREVIATIONS: &[(&str, Tz)] = &[
+    ("UTC", Tz::UTC),
+    ("GMT", Tz::Europe__London),
+    ("BST", Tz::Europe__London),
+    ("WET", Tz::Europe__Lisbon),
+    ("CET", Tz::Europe__Paris),
+    ("CEST", Tz::Europe__Paris),
+    ("EET", Tz::Europe__Athens),
+    ("EEST", Tz::Europe__Athens),
+    ("MSK", Tz::Europe__Moscow),
+    ("IST", Tz::Asia__Kolkata),
+    ("ICT", Tz::Asia__Bangkok),
+    ("SGT", Tz::Asia__Singapore),
+    ("HKT", Tz::Asia__Hong_Kong),
+    ("JST", Tz::Asia__Tokyo),
+    ("KST", Tz::Asia__Seoul),
+    ("AEST", Tz::Australia__Sydney),
+    ("AEDT", Tz::Australia__Sydney),
+    ("NZST", Tz::Pacific__Auckland),
+    ("NZDT", Tz::Pacific__Auckland),
+    ("EST", Tz::America__New_York),
+    ("EDT", Tz::America__New_York),
+    ("CST", Tz::America__Chicago),
+    ("CDT", Tz::America__Chicago),
+    ("MST", Tz::America__Denver),
+    ("MDT", Tz::America__Denver),
+    ("PST", Tz::America__Los_Angeles),
+    ("PDT", Tz::America__Los_Angeles),
+];
+
+/// Reads a zone name such as `Asia/Tokyo`, or an abbreviation such as
+/// `JST`.
+pub fn parse(name: &str) -> Option<Tz> {
+    let name = name.trim();
+    ABBREVIATIONS
+        .iter()
+        .find(|(abbreviation, _)| abbreviation.eq_ignore_ascii_case(name))
+        .map(|(_, zone)| *zone)
+        .or_else(|| name.parse().ok())
+}
+
+/// Splits a zone off the end of `text`, as in "friday 9:40 JST".
+pub fn split(text: &str) -> (&str, Option<Tz>) {
+    let text = text.trim();
+    match text.rsplit_once(char::is_whitespace) {
+        Some((rest, last)) => match parse(last) {
+            Some(zone) => (rest.trim_end(), Some(zone)),
+            None => (text, None),
+        },
+        None => (text, None),
+    }
+}
+
+/// The moment it's `time` on `date` in `zone`, as local time.
+pub fn at(
+    date: NaiveDate,
+    time: NaiveTime,
+    zone: Tz,
+) -> Option<DateTime<Local>> {
+    zone.from_local_datetime(&date.and_time(time))
+        .earliest()
+        .map(|moment| moment.with_timezone(&Local))
+}
+
+/// The time in `zone` at `moment`, e.g. "09:40 JST", with the weekday too
+/// when it's a different day there; nothing if the zone keeps local time
+/// then anyway.
+pub fn label(moment: DateTime<Local>, zone: Tz) -> Option<String> {
+    let there = moment.with_timezone(&zone);
+    if there.offset().fix() == moment.offset().fix() {
+        return None;
+    }
+    let day = if there.naive_local().date() == moment.naive_local().date() {
+        String::new()
+    } else {
+        format!("{} ", Lang::current().weekday(there.weekday()))
+    };
+    let abbreviation = there.format("%Z").to_string();
+    // zones without an abbreviation of their own show an offset such as
+    // "+04", so their name goes alongside
+    if abbreviation.starts_with(['+', '-']) {
+        return Some(format!(
+            "{}{} {} ({})",
+            day,
+            formats::time(there.time()),
+            abbreviation,
+            zone.name()
+        ));
+    }
+    Some(format!(
+        "{}{} {}",
+        day,
+        formats::time(there.time()),
+        abbreviation
+    ))
+}